@@ -0,0 +1,162 @@
+//! 轴对齐包围盒模块
+//!
+//! 提供由三个坐标轴区间组成的轴对齐包围盒(AABB)，用于加速结构的快速剔除
+
+use super::interval::{self, Interval};
+use super::ray::Ray;
+use super::rtweekend::Real;
+use super::vec3::Point3;
+
+/// 轴对齐包围盒
+///
+/// # Fields
+/// - x: x轴方向的区间
+/// - y: y轴方向的区间
+/// - z: z轴方向的区间
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+/// 空包围盒常量，不包含任何点
+pub const EMPTY: Aabb = Aabb {
+    x: interval::EMPTY,
+    y: interval::EMPTY,
+    z: interval::EMPTY,
+};
+
+/// 全空间包围盒常量
+pub const UNIVERSE: Aabb = Aabb {
+    x: interval::UNIVERSE,
+    y: interval::UNIVERSE,
+    z: interval::UNIVERSE,
+};
+
+impl Aabb {
+    /// 由三个轴向区间创建包围盒
+    ///
+    /// 过薄的轴会被略微加厚，避免与轴对齐平面相交时出现数值问题
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        let mut bbox = Self { x, y, z };
+        bbox.pad_to_minimums();
+        bbox
+    }
+
+    /// 由两个对角点创建包围盒(顺序任意)
+    ///
+    /// # Arguments
+    /// * `a` - 第一个角点
+    /// * `b` - 第二个角点
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self::new(
+            Interval::new(a.x().min(b.x()), a.x().max(b.x())),
+            Interval::new(a.y().min(b.y()), a.y().max(b.y())),
+            Interval::new(a.z().min(b.z()), a.z().max(b.z())),
+        )
+    }
+
+    /// 创建恰好包含两个包围盒的包围盒
+    pub fn surrounding(box0: &Aabb, box1: &Aabb) -> Self {
+        Self {
+            x: Interval::enclosing(&box0.x, &box1.x),
+            y: Interval::enclosing(&box0.y, &box1.y),
+            z: Interval::enclosing(&box0.z, &box1.z),
+        }
+    }
+
+    /// 获取第n个坐标轴的区间(0:x, 1:y, 2:z)
+    pub fn axis_interval(&self, n: usize) -> &Interval {
+        match n {
+            1 => &self.y,
+            2 => &self.z,
+            _ => &self.x,
+        }
+    }
+
+    /// 使用slab方法检测光线是否与包围盒相交
+    ///
+    /// # Arguments
+    /// * `r` - 入射光线
+    /// * `ray_t` - 光线参数有效范围
+    ///
+    /// # Returns
+    /// 如果光线在有效范围内穿过包围盒返回true
+    pub fn hit(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.hit_interval(r, ray_t).is_some()
+    }
+
+    /// 求光线在包围盒内的参数区间
+    ///
+    /// # Arguments
+    /// * `r` - 入射光线
+    /// * `ray_t` - 光线参数有效范围
+    ///
+    /// # Returns
+    /// 返回光线进出包围盒的参数区间与`ray_t`的交集，不相交时返回None
+    pub fn hit_interval(&self, r: &Ray, ray_t: &Interval) -> Option<Interval> {
+        let ray_orig = r.origin();
+        let ray_dir = r.direction();
+        let mut t_min = ray_t.min;
+        let mut t_max = ray_t.max;
+
+        for axis in 0..3 {
+            let ax = self.axis_interval(axis);
+            let adinv = 1.0 / ray_dir[axis];
+
+            let t0 = (ax.min - ray_orig[axis]) * adinv;
+            let t1 = (ax.max - ray_orig[axis]) * adinv;
+
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            if t0 > t_min {
+                t_min = t0;
+            }
+            if t1 < t_max {
+                t_max = t1;
+            }
+
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some(Interval::new(t_min, t_max))
+    }
+
+    /// 返回包围盒最长轴的索引(0:x, 1:y, 2:z)
+    pub fn longest_axis(&self) -> usize {
+        if self.x.size() > self.y.size() {
+            if self.x.size() > self.z.size() { 0 } else { 2 }
+        } else if self.y.size() > self.z.size() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// 包围盒中心点
+    pub fn center(&self) -> Point3 {
+        Point3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
+    /// 包围盒表面积，空包围盒为0
+    pub fn surface_area(&self) -> Real {
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        if dx < 0.0 || dy < 0.0 || dz < 0.0 {
+            return 0.0;
+        }
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    /// 确保每个轴的宽度不小于最小值
+    fn pad_to_minimums(&mut self) {
+        let delta: Real = 0.0001;
+        if self.x.size() < delta { self.x = self.x.expand(delta); }
+        if self.y.size() < delta { self.y = self.y.expand(delta); }
+        if self.z.size() < delta { self.z = self.z.expand(delta); }
+    }
+}
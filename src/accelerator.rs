@@ -0,0 +1,76 @@
+//! 加速结构模块
+//!
+//! 提供各种空间加速结构(BVH、kd树等)共同实现的接口，
+//! 便于在同一场景上替换和比较不同的加速结构
+
+use super::hittable::Hittable;
+use super::hittable_list::HittableList;
+use super::rtweekend::Real;
+use std::sync::Arc;
+
+/// 加速结构的结构统计
+///
+/// 与`RenderStats::nodes_per_ray`(渲染时每条光线平均访问的节点数)配合，
+/// 可用于比较不同构建参数得到的树
+///
+/// # Fields
+/// - primitive_count: 物体数量
+/// - node_count: 节点数量
+/// - leaf_count: 叶节点数量
+/// - max_depth: 最深叶节点的深度，根节点深度为0
+/// - leaf_references: 所有叶节点引用的物体数之和，kd树中跨越多个叶节点的物体重复计数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub primitive_count: usize,
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub max_depth: usize,
+    pub leaf_references: usize,
+}
+
+impl Stats {
+    /// 叶节点平均包含的物体数
+    ///
+    /// # Returns
+    /// 返回 leaf_references / leaf_count，没有叶节点时返回0
+    pub fn average_leaf_size(&self) -> Real {
+        if self.leaf_count == 0 {
+            0.0
+        } else {
+            self.leaf_references as Real / self.leaf_count as Real
+        }
+    }
+}
+
+/// 加速结构抽象接口
+///
+/// 加速结构本身也是可命中物体，构建后可以直接代替物体列表参与渲染
+pub trait Accelerator: Hittable {
+    /// 以默认选项为一组物体构建加速结构
+    ///
+    /// # Arguments
+    /// * `objects` - 参与构建的物体
+    fn build(objects: Vec<Arc<dyn Hittable>>) -> Self
+    where
+        Self: Sized;
+
+    /// 以默认选项为物体列表构建加速结构，嵌套列表会先被展开
+    ///
+    /// # Arguments
+    /// * `list` - 物体列表
+    fn from_list(list: &HittableList) -> Self
+    where
+        Self: Sized,
+    {
+        Self::build(list.flatten().into_objects())
+    }
+
+    /// 加速结构包含的物体数量
+    fn primitive_count(&self) -> usize;
+
+    /// 节点数量
+    fn node_count(&self) -> usize;
+
+    /// 树的结构统计
+    fn stats(&self) -> Stats;
+}
@@ -0,0 +1,103 @@
+//! 背景模块
+//!
+//! 定义光线未命中任何物体时返回的背景颜色
+
+use std::sync::Arc;
+
+use super::color::Color;
+use super::ray::Ray;
+use super::sphere;
+use super::texture::Texture;
+use super::vec3;
+
+/// 场景背景
+///
+/// # Variants
+/// - Solid: 纯色背景
+/// - Gradient: 沿竖直方向从`bottom`渐变到`top`的双色背景
+/// - Texture: 以光线方向的球面坐标(与球体的UV映射相同)采样的纹理环境
+#[derive(Clone)]
+pub enum Background {
+    Solid(Color),
+    Gradient { bottom: Color, top: Color },
+    Texture(Arc<dyn Texture>),
+}
+
+impl Default for Background {
+    /// 默认背景：白色到天蓝色的天空渐变
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Background {
+    /// 计算未命中光线的背景颜色
+    ///
+    /// # Arguments
+    /// * `r` - 未命中任何物体的光线
+    pub fn value(&self, r: &Ray) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let unit_direction = vec3::unit_vector(r.direction());  // 归一化光线方向
+                let a = 0.5 * (unit_direction.y() + 1.0);  // 计算垂直方向的混合系数
+                (1.0 - a) * *bottom + a * *top
+            }
+            Background::Texture(texture) => {
+                let unit_direction = vec3::unit_vector(r.direction());
+                let (u, v) = sphere::get_sphere_uv(unit_direction);
+                texture.value(u, v, unit_direction)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::{Point3, Vec3};
+
+    fn ray_towards(direction: Vec3) -> Ray {
+        Ray::new(Point3::default(), direction)
+    }
+
+    #[test]
+    fn gradient_blends_from_bottom_to_top() {
+        let bottom = Color::new(1.0, 0.5, 0.0);
+        let top = Color::new(0.1, 0.2, 0.8);
+        let sky = Background::Gradient { bottom, top };
+
+        assert!((sky.value(&ray_towards(Vec3::new(0.0, 3.0, 0.0))) - top).near_zero());
+        assert!((sky.value(&ray_towards(Vec3::new(0.0, -0.5, 0.0))) - bottom).near_zero());
+        let horizon = sky.value(&ray_towards(Vec3::new(1.0, 0.0, 0.0)));
+        assert!((horizon - 0.5 * (bottom + top)).near_zero());
+    }
+
+    #[test]
+    fn default_background_is_the_classic_sky() {
+        let sky = Background::default();
+        assert!((sky.value(&ray_towards(Vec3::new(0.0, 1.0, 0.0))) - Color::new(0.5, 0.7, 1.0)).near_zero());
+        assert!((sky.value(&ray_towards(Vec3::new(0.0, -1.0, 0.0))) - Color::new(1.0, 1.0, 1.0)).near_zero());
+    }
+
+    #[test]
+    fn solid_texture_background_is_the_same_in_every_direction() {
+        use crate::rtweekend;
+        use crate::texture::SolidColor;
+        let color = Color::new(0.2, 0.4, 0.6);
+        let env = Background::Texture(Arc::new(SolidColor::new(color)));
+
+        rtweekend::seed_random(847);
+        let axes = [
+            Vec3::new(1.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0),
+        ];
+        for direction in axes.into_iter().chain((0..100).map(|_| vec3::random_unit_vector() * 5.0)) {
+            assert_eq!(env.value(&ray_towards(direction)).e, color.e, "direction {}", direction);
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! Bézier曲面片模块
+//!
+//! 提供双三次Bézier曲面片。构造时把曲面片细分为若干子片，利用Bézier曲面的凸包性质
+//! 为每个子片求出保守的包围盒；求交时对光线穿过的子片用牛顿迭代求解精确交点
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::{self, Aabb};
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 每个参数方向上的子片数量
+const SUBDIVISIONS: usize = 8;
+
+/// 牛顿迭代的最大次数
+const NEWTON_ITERATIONS: usize = 12;
+
+/// 双三次Bézier曲面片
+///
+/// 曲面为 S(u, v) = Σᵢ Σⱼ Bᵢ(u) Bⱼ(v) P[i][j]，Bᵢ为三次Bernstein基函数，u, v ∈ [0, 1]
+///
+/// # Fields
+/// - control: 4×4控制点，第一个下标对应u方向
+/// - cells: 各子片的参数范围(u0, v0)及其包围盒
+/// - mat: 材质
+/// - bbox: 整个曲面片的包围盒
+pub struct BezierPatch {
+    control: [[Point3; 4]; 4],
+    cells: Vec<(Real, Real, Aabb)>,
+    mat: Arc<dyn Material + Send + Sync>,
+    bbox: Aabb,
+}
+
+impl BezierPatch {
+    /// 创建新的Bézier曲面片
+    ///
+    /// # Arguments
+    /// * `control` - 4×4控制点，`control[i][j]`对应u方向第i个、v方向第j个
+    /// * `material` - 材质
+    pub fn new(control: [[Point3; 4]; 4], material: Arc<dyn Material + Send + Sync>) -> Self {
+        let step = 1.0 / SUBDIVISIONS as Real;
+        let mut cells = Vec::with_capacity(SUBDIVISIONS * SUBDIVISIONS);
+        let mut bbox = aabb::EMPTY;
+
+        for a in 0..SUBDIVISIONS {
+            let (u0, u1) = (a as Real * step, (a + 1) as Real * step);
+            // 先在u方向截取每一列曲线
+            let cols: [[Point3; 4]; 4] = std::array::from_fn(|j| {
+                sub_curve([control[0][j], control[1][j], control[2][j], control[3][j]], u0, u1)
+            });
+
+            for b in 0..SUBDIVISIONS {
+                let (v0, v1) = (b as Real * step, (b + 1) as Real * step);
+                // 再在v方向截取，得到子片的控制点，其凸包包含整个子片
+                let cell_box = (0..4)
+                    .flat_map(|i| sub_curve(cols.map(|col| col[i]), v0, v1))
+                    .fold(aabb::EMPTY, |acc, p| Aabb::surrounding(&acc, &Aabb::from_points(p, p)));
+                bbox = Aabb::surrounding(&bbox, &cell_box);
+                cells.push((u0, v0, cell_box));
+            }
+        }
+
+        Self {
+            control,
+            cells,
+            mat: material,
+            bbox,
+        }
+    }
+
+    /// 计算曲面上的点及两个偏导数
+    ///
+    /// # Returns
+    /// 返回(S(u,v), ∂S/∂u, ∂S/∂v)
+    fn evaluate(&self, u: Real, v: Real) -> (Point3, Vec3, Vec3) {
+        let (bu, du) = bernstein(u);
+        let (bv, dv) = bernstein(v);
+
+        let mut p = Vec3::default();
+        let mut su = Vec3::default();
+        let mut sv = Vec3::default();
+        for i in 0..4 {
+            for j in 0..4 {
+                let c = self.control[i][j];
+                p += bu[i] * bv[j] * c;
+                su += du[i] * bv[j] * c;
+                sv += bu[i] * dv[j] * c;
+            }
+        }
+        (p, su, sv)
+    }
+
+    /// 用牛顿迭代求解 S(u, v) = O + tD
+    ///
+    /// # Returns
+    /// 收敛时返回(t, u, v)
+    fn newton(&self, r: &Ray, mut t: Real, mut u: Real, mut v: Real) -> Option<(Real, Real, Real)> {
+        let d = r.direction();
+        let tolerance = rtweekend::NEAR_ZERO_EPSILON * (1.0 + r.origin().length());
+
+        for _ in 0..NEWTON_ITERATIONS {
+            let (p, su, sv) = self.evaluate(u, v);
+            let f = p - r.at(t);
+            if f.length() < tolerance {
+                return Some((t, u, v));
+            }
+
+            // 雅可比矩阵的列为 [Su, Sv, -D]，用克拉默法则求解 J Δ = -F
+            let det = vec3::dot(su, vec3::cross(sv, -d));
+            if det.abs() < 1e-14 {
+                return None;
+            }
+            let du = vec3::dot(-f, vec3::cross(sv, -d)) / det;
+            let dv = vec3::dot(su, vec3::cross(-f, -d)) / det;
+            let dt = vec3::dot(su, vec3::cross(sv, -f)) / det;
+
+            u += du;
+            v += dv;
+            t += dt;
+        }
+        None
+    }
+}
+
+impl Hittable for BezierPatch {
+    /// 实现曲面片的光线命中检测
+    ///
+    /// 对光线穿过包围盒的每个子片，以子片中心和光线进入包围盒的位置为初值做牛顿迭代，
+    /// 收敛且参数落在子片附近的解中取最近的一个
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+
+        let step = 1.0 / SUBDIVISIONS as Real;
+        // 允许解略微越出子片，避免子片接缝处漏掉交点
+        let slack = 0.1 * step;
+
+        let mut closest = ray_t.max;
+        let mut nearest = None;
+        for &(u0, v0, cell_box) in &self.cells {
+            let Some(span) = cell_box.hit_interval(r, &Interval::new(ray_t.min, closest)) else {
+                continue;
+            };
+            let Some((t, u, v)) = self.newton(r, span.min, u0 + 0.5 * step, v0 + 0.5 * step) else {
+                continue;
+            };
+            let in_cell = (u0 - slack..=u0 + step + slack).contains(&u)
+                && (v0 - slack..=v0 + step + slack).contains(&v);
+            if in_cell
+                && (0.0..=1.0).contains(&u)
+                && (0.0..=1.0).contains(&v)
+                && ray_t.surrounds(t)
+                && t < closest
+            {
+                closest = t;
+                nearest = Some((u, v));
+            }
+        }
+        let Some((u, v)) = nearest else {
+            return false;
+        };
+
+        let (_, su, sv) = self.evaluate(u, v);
+        let mut normal = vec3::cross(su, sv);
+        if normal.near_zero() {
+            // 退化点(如控制点重合的极点)处用相邻参数的法线代替
+            let (_, su, sv) = self.evaluate(u + (0.5 - u) * 1e-3, v + (0.5 - v) * 1e-3);
+            normal = vec3::cross(su, sv);
+        }
+
+        hit_record.t = closest;
+        hit_record.p = r.at(closest);
+        hit_record.u = u;
+        hit_record.v = v;
+        hit_record.tangent = if su.near_zero() { Vec3::default() } else { vec3::unit_vector(su) };
+        hit_record.set_face_normal(r, vec3::unit_vector(normal));
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// 三次Bernstein基函数及其导数
+///
+/// # Returns
+/// 返回([B₀..B₃](t), [B₀'..B₃'](t))
+pub fn bernstein(t: Real) -> ([Real; 4], [Real; 4]) {
+    let s = 1.0 - t;
+    (
+        [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t],
+        [-3.0 * s * s, 3.0 * s * s - 6.0 * t * s, 6.0 * t * s - 3.0 * t * t, 3.0 * t * t],
+    )
+}
+
+/// 在参数t处用de Casteljau算法分割三次Bézier曲线
+///
+/// # Returns
+/// 返回(左半段[0,t]的控制点, 右半段[t,1]的控制点)
+pub fn split_curve(p: [Point3; 4], t: Real) -> ([Point3; 4], [Point3; 4]) {
+    let lerp = |a: Point3, b: Point3| vec3::lerp(a, b, t);
+    let p01 = lerp(p[0], p[1]);
+    let p12 = lerp(p[1], p[2]);
+    let p23 = lerp(p[2], p[3]);
+    let p012 = lerp(p01, p12);
+    let p123 = lerp(p12, p23);
+    let mid = lerp(p012, p123);
+    ([p[0], p01, p012, mid], [mid, p123, p23, p[3]])
+}
+
+/// 截取三次Bézier曲线在参数区间[t0, t1]上的一段
+fn sub_curve(p: [Point3; 4], t0: Real, t1: Real) -> [Point3; 4] {
+    let (left, _) = split_curve(p, t1);
+    if t0 <= 0.0 {
+        return left;
+    }
+    let (_, right) = split_curve(left, t0 / t1);
+    right
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    /// 覆盖xz平面上[0,2]²的曲面片，内部四个控制点抬高到`bump`
+    fn patch(bump: Real) -> BezierPatch {
+        let control = std::array::from_fn(|i| {
+            std::array::from_fn(|j| {
+                let inner = (1..=2).contains(&i) && (1..=2).contains(&j);
+                Point3::new(2.0 * i as Real / 3.0, if inner { bump } else { 0.0 }, 2.0 * j as Real / 3.0)
+            })
+        });
+        BezierPatch::new(control, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))
+    }
+
+    /// 命中时检查法线为单位长度且朝向光线来的一侧
+    fn cast(patch: &BezierPatch, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        let r = Ray::new(origin, direction);
+        if !patch.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return None;
+        }
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!(vec3::dot(rec.normal, direction) < 0.0);
+        Some(rec)
+    }
+
+    #[test]
+    fn flat_patch_is_hit_at_the_expected_parameters() {
+        let rec = cast(&patch(0.0), Point3::new(0.5, 5.0, 1.5), Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-4);
+        assert!((rec.u - 0.25).abs() < 1e-3 && (rec.v - 0.75).abs() < 1e-3);
+        // Su × Sv指向-y，从上方看是反面
+        assert!(!rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn dome_is_hit_at_its_bernstein_height() {
+        // 中心处内部控制点的权重为(3/8 + 3/8)² = 9/16
+        let top = 9.0 / 16.0;
+        let rec = cast(&patch(1.0), Point3::new(1.0, 5.0, 1.0), Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((rec.t - (5.0 - top)).abs() < 1e-4);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-4);
+        // 斜着射向穹顶
+        let rec = cast(&patch(1.0), Point3::new(-3.0, 5.0, 1.0), Vec3::new(4.0, -5.0, 0.0)).unwrap();
+        assert!(rec.p.y() > 0.0 && rec.p.y() <= top + 1e-4);
+    }
+
+    #[test]
+    fn rays_outside_the_patch_miss() {
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        assert!(cast(&patch(1.0), Point3::new(2.5, 5.0, 1.0), down).is_none());
+        assert!(cast(&patch(1.0), Point3::new(-1.0, 0.7, 1.0), Vec3::new(1.0, 0.0, 0.0)).is_none());
+        assert!(cast(&patch(1.0), Point3::new(1.0, 5.0, 1.0), -down).is_none());
+    }
+
+    #[test]
+    fn ray_from_under_the_dome_hits_it_from_below() {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let rec = cast(&patch(1.0), Point3::new(1.0, 0.0, 1.0), up).unwrap();
+        assert!((rec.t - 9.0 / 16.0).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal + up).length() < 1e-4);
+        // 起点就在曲面上时不会在原处再次命中
+        assert!(cast(&patch(1.0), rec.p, up).is_none());
+    }
+}
@@ -0,0 +1,65 @@
+//! 长方体模块
+//!
+//! 提供由六个四边形组成的轴对齐长方体
+
+use super::vec3::{Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::hittable_list::HittableList;
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::quad::Quad;
+use std::sync::Arc;
+
+/// 轴对齐长方体
+///
+/// # Fields
+/// - sides: 六个面，每个面的法线都朝外
+pub struct Box3 {
+    sides: HittableList,
+}
+
+impl Box3 {
+    /// 由两个相对的角点创建长方体
+    ///
+    /// # Arguments
+    /// * `a` - 一个角点
+    /// * `b` - 与`a`相对的角点(各分量大小顺序任意)
+    /// * `material` - 六个面共用的材质
+    pub fn new(a: Point3, b: Point3, material: Arc<dyn Material + Send + Sync>) -> Self {
+        let mut sides = HittableList::default();
+
+        let min = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+        let max = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+
+        let dx = Vec3::new(max.x() - min.x(), 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y() - min.y(), 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z() - min.z());
+
+        let mat = || Arc::clone(&material);
+        sides.add(Arc::new(Quad::new(Point3::new(min.x(), min.y(), max.z()), dx, dy, mat()))); // 前
+        sides.add(Arc::new(Quad::new(Point3::new(max.x(), min.y(), max.z()), -dz, dy, mat()))); // 右
+        sides.add(Arc::new(Quad::new(Point3::new(max.x(), min.y(), min.z()), -dx, dy, mat()))); // 后
+        sides.add(Arc::new(Quad::new(Point3::new(min.x(), min.y(), min.z()), dz, dy, mat()))); // 左
+        sides.add(Arc::new(Quad::new(Point3::new(min.x(), max.y(), max.z()), dx, -dz, mat()))); // 顶
+        sides.add(Arc::new(Quad::new(Point3::new(min.x(), min.y(), min.z()), dx, dz, mat()))); // 底
+
+        Self { sides }
+    }
+}
+
+impl Hittable for Box3 {
+    /// 返回六个面中最近的命中
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        self.sides.hit(r, ray_t, hit_record)
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.sides.hit_any(r, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.sides.bounding_box()
+    }
+}
@@ -0,0 +1,1262 @@
+//! 层次包围盒(BVH)模块
+//!
+//! 提供按深度优先顺序存放在连续数组中的二叉BVH，支持按中位数划分、
+//! 分桶表面积启发式(SAH)和按Morton码并行构建(LBVH)三种方式，把逐个求交的线性开销降为对数级
+
+use super::ray::Ray;
+use super::accelerator::{Accelerator, Stats};
+use super::hittable::{HitRecord, Hittable};
+use super::hittable_list::HittableList;
+use super::interval::Interval;
+use super::aabb::{self, Aabb};
+use super::vec3::Point3;
+use super::packet::RayPacket;
+use super::stats;
+use super::rtweekend::Real;
+use crossbeam::scope;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 遍历栈的容量
+const STACK_SIZE: usize = 64;
+
+/// SAH和Morton码划分可能得到不平衡的树，超过此深度后改用中位数划分，保证树深不超过遍历栈的容量
+const MAX_UNBALANCED_DEPTH: usize = 32;
+
+/// Morton码每个坐标轴的位数
+const MORTON_BITS: u32 = 21;
+
+/// 访问一个内部节点相对于一次图元求交的开销，用于SAH代价估计
+const TRAVERSAL_COST: Real = 0.125;
+
+/// BVH缓存文件头标识
+const CACHE_MAGIC: &[u8; 4] = b"RTBV";
+
+/// 划分策略
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitMethod {
+    /// 沿图元中心分布最长的轴，按中心的中位数把图元分为数量相等的两半
+    Median,
+    /// 分桶表面积启发式：在三个轴上各把图元中心范围均分为`bins`个桶，
+    /// 选取 遍历开销 + Σ(子节点表面积 / 父节点表面积 × 子节点图元数) 最小的划分
+    Sah { bins: usize },
+    /// 线性BVH(LBVH)：按图元中心的Morton码排序，各内部节点的范围和划分位置
+    /// 由相邻Morton码的公共前缀长度独立求出，可多线程并行构建，适合百万级图元的场景。
+    /// 划分相当于空间中点划分，树的质量略低于SAH
+    Morton,
+}
+
+/// BVH节点
+///
+/// 左子节点紧跟在父节点之后，只需记录右子节点的位置
+///
+/// # Fields
+/// - bbox: 节点包围盒
+/// - offset: 叶节点为第一个图元在图元数组中的索引，内部节点为右子节点的索引
+/// - count: 叶节点的图元数量，内部节点为0
+/// - axis: 内部节点的划分轴，遍历时据此先访问离光线起点较近的子节点
+#[derive(Clone, Copy, Debug)]
+struct BvhNode {
+    bbox: Aabb,
+    offset: usize,
+    count: usize,
+    axis: usize,
+}
+
+/// 构建时使用的图元信息
+///
+/// # Fields
+/// - index: 图元在输入列表中的索引
+/// - bbox: 图元包围盒
+/// - centroid: 包围盒中心
+#[derive(Clone, Copy)]
+struct BuildPrimitive {
+    index: usize,
+    bbox: Aabb,
+    centroid: Point3,
+}
+
+/// BVH构建器
+///
+/// # Fields
+/// - objects: 参与构建的物体
+/// - split_method: 划分策略，默认为中位数划分
+/// - max_leaf_size: 叶节点最多包含的图元数，默认为4
+pub struct BvhBuilder {
+    objects: Vec<Arc<dyn Hittable>>,
+    split_method: SplitMethod,
+    max_leaf_size: usize,
+}
+
+impl BvhBuilder {
+    /// 创建新的构建器
+    ///
+    /// # Arguments
+    /// * `objects` - 参与构建的物体
+    pub fn new(objects: Vec<Arc<dyn Hittable>>) -> Self {
+        Self {
+            objects,
+            split_method: SplitMethod::Median,
+            max_leaf_size: 4,
+        }
+    }
+
+    /// 设置划分策略
+    pub fn with_split_method(mut self, split_method: SplitMethod) -> Self {
+        self.split_method = split_method;
+        self
+    }
+
+    /// 设置叶节点最多包含的图元数(至少为1)
+    pub fn with_max_leaf_size(mut self, max_leaf_size: usize) -> Self {
+        self.max_leaf_size = max_leaf_size.max(1);
+        self
+    }
+
+    /// 构建BVH
+    pub fn build(self) -> Bvh {
+        let mut bounds = vec![aabb::EMPTY; self.objects.len()];
+        parallel_for_each(&mut bounds, |index, bbox| *bbox = self.objects[index].bounding_box());
+        let (tree, order) = self.build_tree(&bounds);
+
+        // 按叶节点顺序重排物体，使每个叶节点的图元在数组中连续
+        let objects = order.iter().map(|&index| Arc::clone(&self.objects[index])).collect();
+        Bvh { objects, order, tree }
+    }
+
+    /// 只根据图元包围盒构建节点数组，构建器中的物体不参与
+    ///
+    /// 供自行保存图元的结构使用，如三角网格按三角面构建的底层BVH
+    ///
+    /// # Arguments
+    /// * `bounds` - 各图元的包围盒
+    ///
+    /// # Returns
+    /// 返回(节点数组, 按叶节点顺序排列的图元原始索引)，图元需按该顺序重排后才能与节点对应
+    pub(crate) fn build_tree(&self, bounds: &[Aabb]) -> (BvhTree, Vec<usize>) {
+        let mut primitives = vec![
+            BuildPrimitive { index: 0, bbox: aabb::EMPTY, centroid: Point3::default() };
+            bounds.len()
+        ];
+        parallel_for_each(&mut primitives, |index, primitive| {
+            let bbox = bounds[index];
+            *primitive = BuildPrimitive { index, bbox, centroid: bbox.center() };
+        });
+
+        let mut nodes = Vec::with_capacity(2 * primitives.len());
+        if !primitives.is_empty() {
+            if self.split_method == SplitMethod::Morton {
+                self.build_morton(&mut primitives, &mut nodes);
+            } else {
+                self.build_recursive(&mut primitives, 0, 0, &mut nodes);
+            }
+        }
+
+        let order = primitives.iter().map(|p| p.index).collect();
+        (BvhTree { nodes }, order)
+    }
+
+    /// 递归构建`primitives`对应的子树，返回子树根节点的索引
+    ///
+    /// # Arguments
+    /// * `primitives` - 子树包含的图元，构建后按叶节点顺序重排
+    /// * `first` - `primitives`中第一个图元在整个图元数组中的位置
+    /// * `depth` - 当前深度
+    /// * `nodes` - 节点数组
+    fn build_recursive(
+        &self,
+        primitives: &mut [BuildPrimitive],
+        first: usize,
+        depth: usize,
+        nodes: &mut Vec<BvhNode>,
+    ) -> usize {
+        let bbox = primitives.iter().fold(aabb::EMPTY, |acc, p| Aabb::surrounding(&acc, &p.bbox));
+        let node_index = nodes.len();
+        let leaf = BvhNode { bbox, offset: first, count: primitives.len(), axis: 0 };
+        nodes.push(leaf);
+
+        if primitives.len() <= 1 {
+            return node_index;
+        }
+
+        let centroid_bounds = primitives
+            .iter()
+            .fold(aabb::EMPTY, |acc, p| Aabb::surrounding(&acc, &Aabb::from_points(p.centroid, p.centroid)));
+        let axis = centroid_bounds.longest_axis();
+        // 所有中心重合时无法划分
+        if centroid_bounds.axis_interval(axis).size() <= 0.0 {
+            return node_index;
+        }
+
+        let split = match self.split_method {
+            SplitMethod::Sah { bins } if depth < MAX_UNBALANCED_DEPTH => {
+                match sah_split(primitives, &bbox, &centroid_bounds, bins.max(2)) {
+                    Some((axis, mid, cost)) => {
+                        // 划分代价不低于直接做成叶节点时，图元不多就不再划分
+                        if cost >= primitives.len() as Real && primitives.len() <= self.max_leaf_size {
+                            return node_index;
+                        }
+                        Some((axis, mid))
+                    }
+                    None => None,
+                }
+            }
+            _ => None,
+        };
+        if split.is_none() && primitives.len() <= self.max_leaf_size {
+            return node_index;
+        }
+        let (axis, mid) = split.unwrap_or_else(|| {
+            let mid = primitives.len() / 2;
+            primitives.select_nth_unstable_by(mid, |a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+            (axis, mid)
+        });
+
+        let (left, right) = primitives.split_at_mut(mid);
+        self.build_recursive(left, first, depth + 1, nodes);
+        let right_index = self.build_recursive(right, first + mid, depth + 1, nodes);
+        nodes[node_index] = BvhNode { bbox, offset: right_index, count: 0, axis };
+        node_index
+    }
+}
+
+impl BvhBuilder {
+    /// 按Morton码构建LBVH(Karras 2012)
+    ///
+    /// 图元按(Morton码, 原始索引)排序后，n个图元对应n-1个内部节点，
+    /// 内部节点i的范围以i为一端，其另一端和划分位置只依赖相邻键的公共前缀长度，
+    /// 因此所有内部节点可以并行求出。之后自顶向下转换为深度优先的节点数组并合并包围盒
+    fn build_morton(&self, primitives: &mut [BuildPrimitive], nodes: &mut Vec<BvhNode>) {
+        let centroid_bounds = primitives
+            .iter()
+            .fold(aabb::EMPTY, |acc, p| Aabb::surrounding(&acc, &Aabb::from_points(p.centroid, p.centroid)));
+
+        let mut codes = vec![0u64; primitives.len()];
+        parallel_for_each(&mut codes, |i, code| {
+            *code = morton_code(&centroid_bounds, primitives[i].centroid);
+        });
+        let mut order: Vec<(u64, usize)> = codes.into_iter().zip(0..).collect();
+        order.sort_unstable();
+        let sorted: Vec<BuildPrimitive> = order.iter().map(|&(_, i)| primitives[i]).collect();
+        primitives.copy_from_slice(&sorted);
+        let keys: Vec<u64> = order.iter().map(|&(code, _)| code).collect();
+
+        let mut splits = vec![0usize; keys.len() - 1];
+        parallel_for_each(&mut splits, |i, split| {
+            *split = karras_split(&keys, i);
+        });
+
+        self.flatten_morton(primitives, &splits, 0, 0, 0, nodes);
+    }
+
+    /// 把LBVH内部节点转换为深度优先的节点数组，返回子树根节点的索引
+    ///
+    /// # Arguments
+    /// * `primitives` - 子树包含的图元(按Morton码排好序)
+    /// * `splits` - 各内部节点的划分位置，左子树覆盖到该位置为止
+    /// * `internal` - 子树根对应的内部节点编号
+    /// * `first` - `primitives`中第一个图元在整个图元数组中的位置
+    /// * `depth` - 当前深度
+    /// * `nodes` - 节点数组
+    fn flatten_morton(
+        &self,
+        primitives: &mut [BuildPrimitive],
+        splits: &[usize],
+        internal: usize,
+        first: usize,
+        depth: usize,
+        nodes: &mut Vec<BvhNode>,
+    ) -> usize {
+        if primitives.len() <= self.max_leaf_size || depth >= MAX_UNBALANCED_DEPTH {
+            return self.build_recursive(primitives, first, depth, nodes);
+        }
+
+        let node_index = nodes.len();
+        nodes.push(BvhNode { bbox: aabb::EMPTY, offset: 0, count: 0, axis: 0 });
+
+        // 左子树为[first, split]，右子树为[split + 1, last]，子内部节点编号分别为split和split + 1
+        let split = splits[internal];
+        let (left, right) = primitives.split_at_mut(split + 1 - first);
+        let left_index = self.flatten_morton(left, splits, split, first, depth + 1, nodes);
+        let right_index = self.flatten_morton(right, splits, split + 1, split + 1, depth + 1, nodes);
+
+        let (left_box, right_box) = (nodes[left_index].bbox, nodes[right_index].bbox);
+        // 左子树在最高的不同位对应的轴上位于右子树下方，取两者中心相差最大的轴作为划分轴，
+        // 保证遍历时先访问近处子节点的判断成立
+        let (left_center, right_center) = (left_box.center(), right_box.center());
+        let axis = (0..3)
+            .max_by(|&a, &b| (right_center[a] - left_center[a]).total_cmp(&(right_center[b] - left_center[b])))
+            .unwrap_or(0);
+        nodes[node_index] = BvhNode {
+            bbox: Aabb::surrounding(&left_box, &right_box),
+            offset: right_index,
+            count: 0,
+            axis,
+        };
+        node_index
+    }
+}
+
+/// 把点的坐标在包围盒内归一化后交错各轴的位，得到63位Morton码
+fn morton_code(bounds: &Aabb, p: Point3) -> u64 {
+    let scale = ((1u64 << MORTON_BITS) - 1) as Real;
+    let quantize = |axis: usize| {
+        let interval = bounds.axis_interval(axis);
+        let size = interval.size();
+        let t = if size > 0.0 { ((p[axis] - interval.min) / size).clamp(0.0, 1.0) } else { 0.5 };
+        expand_bits((t * scale) as u64)
+    };
+    (quantize(0) << 2) | (quantize(1) << 1) | quantize(2)
+}
+
+/// 把21位整数的各位之间插入两个0
+fn expand_bits(v: u64) -> u64 {
+    let mut x = v & 0x1f_ffff;
+    x = (x | (x << 32)) & 0x001f_0000_0000_ffff;
+    x = (x | (x << 16)) & 0x001f_0000_ff00_00ff;
+    x = (x | (x << 8)) & 0x100f_00f0_0f00_f00f;
+    x = (x | (x << 4)) & 0x10c3_0c30_c30c_30c3;
+    x = (x | (x << 2)) & 0x1249_2492_4924_9249;
+    x
+}
+
+/// 求LBVH内部节点i的划分位置
+///
+/// δ(i, j)为第i、j个键的公共前缀长度，Morton码相同时以索引作为键的低位区分。
+/// 先由两侧邻居的δ确定节点范围延伸的方向d，再用倍增和二分求出范围的另一端j，
+/// 最后在范围内二分找到与i的公共前缀长度大于δ(i, j)的最远位置
+fn karras_split(keys: &[u64], i: usize) -> usize {
+    let n = keys.len() as i64;
+    let i = i as i64;
+    let delta = |j: i64| -> i64 {
+        if j < 0 || j >= n {
+            return -1;
+        }
+        let (a, b) = (keys[i as usize], keys[j as usize]);
+        if a == b {
+            64 + ((i ^ j) as u64).leading_zeros() as i64
+        } else {
+            (a ^ b).leading_zeros() as i64
+        }
+    };
+
+    let d = if delta(i + 1) > delta(i - 1) { 1 } else { -1 };
+    let delta_min = delta(i - d);
+    let mut l_max = 2;
+    while delta(i + l_max * d) > delta_min {
+        l_max *= 2;
+    }
+    let mut l = 0;
+    let mut t = l_max / 2;
+    while t >= 1 {
+        if delta(i + (l + t) * d) > delta_min {
+            l += t;
+        }
+        t /= 2;
+    }
+    let j = i + l * d;
+
+    let delta_node = delta(j);
+    let mut s = 0;
+    let mut t = l;
+    loop {
+        t = (t + 1) / 2;
+        if delta(i + (s + t) * d) > delta_node {
+            s += t;
+        }
+        if t <= 1 {
+            break;
+        }
+    }
+    (i + s * d + d.min(0)) as usize
+}
+
+/// 多线程对切片的每个元素执行`f(索引, 元素)`，切片按线程数均分
+fn parallel_for_each<T: Send>(data: &mut [T], f: impl Fn(usize, &mut T) + Sync) {
+    let chunk_size = data.len().div_ceil(num_cpus::get()).max(1);
+    let f = &f;
+    scope(|s| {
+        for (k, chunk) in data.chunks_mut(chunk_size).enumerate() {
+            s.spawn(move |_| {
+                for (i, item) in chunk.iter_mut().enumerate() {
+                    f(k * chunk_size + i, item);
+                }
+            });
+        }
+    })
+    .unwrap();
+}
+
+/// 读取一个小端u64
+pub(crate) fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    input.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// 分桶SAH划分
+///
+/// 在三个轴上分别把图元按中心分桶，扫描桶之间的所有划分位置，
+/// 选出代价最低者并按它重排图元
+///
+/// # Returns
+/// 返回(划分轴, 左半部分的图元数, 以图元求交次数计的代价)，所有轴都无法划分时返回None
+fn sah_split(
+    primitives: &mut [BuildPrimitive],
+    bbox: &Aabb,
+    centroid_bounds: &Aabb,
+    bins: usize,
+) -> Option<(usize, usize, Real)> {
+    let parent_area = bbox.surface_area();
+    let bin_of = |p: &BuildPrimitive, axis: usize| {
+        let extent = centroid_bounds.axis_interval(axis);
+        let offset = (p.centroid[axis] - extent.min) / extent.size();
+        ((offset * bins as Real) as usize).min(bins - 1)
+    };
+
+    let mut best: Option<(usize, usize, Real)> = None;
+    for axis in 0..3 {
+        if centroid_bounds.axis_interval(axis).size() <= 0.0 {
+            continue;
+        }
+
+        let mut counts = vec![0usize; bins];
+        let mut boxes = vec![aabb::EMPTY; bins];
+        for p in primitives.iter() {
+            let b = bin_of(p, axis);
+            counts[b] += 1;
+            boxes[b] = Aabb::surrounding(&boxes[b], &p.bbox);
+        }
+
+        // 从右往左累积，right_area[i]、right_count[i]为桶i..bins的并
+        let mut right_area = vec![0.0; bins];
+        let mut right_count = vec![0usize; bins];
+        let (mut acc_box, mut acc_count) = (aabb::EMPTY, 0);
+        for i in (1..bins).rev() {
+            acc_box = Aabb::surrounding(&acc_box, &boxes[i]);
+            acc_count += counts[i];
+            right_area[i] = acc_box.surface_area();
+            right_count[i] = acc_count;
+        }
+
+        let (mut acc_box, mut acc_count) = (aabb::EMPTY, 0);
+        for i in 0..bins - 1 {
+            acc_box = Aabb::surrounding(&acc_box, &boxes[i]);
+            acc_count += counts[i];
+            if acc_count == 0 || right_count[i + 1] == 0 {
+                continue;
+            }
+            let cost = TRAVERSAL_COST
+                + (acc_box.surface_area() * acc_count as Real
+                    + right_area[i + 1] * right_count[i + 1] as Real)
+                    / parent_area;
+            if best.is_none_or(|(_, _, c)| cost < c) {
+                best = Some((axis, i, cost));
+            }
+        }
+    }
+
+    let (axis, split_bin, cost) = best?;
+    // 把桶号不超过split_bin的图元移到前面
+    let mut mid = 0;
+    for i in 0..primitives.len() {
+        if bin_of(&primitives[i], axis) <= split_bin {
+            primitives.swap(i, mid);
+            mid += 1;
+        }
+    }
+    Some((axis, mid, cost))
+}
+
+/// 与图元类型无关的BVH节点数组
+///
+/// 图元由使用者按构建时给出的顺序自行保存，遍历时通过回调对叶节点中的图元求交。
+/// 物体级的`Bvh`和三角网格内部的底层BVH共用这一结构
+///
+/// # Fields
+/// - nodes: 深度优先顺序的节点数组，根节点位于索引0
+pub(crate) struct BvhTree {
+    nodes: Vec<BvhNode>,
+}
+
+impl BvhTree {
+    /// 节点数量
+    pub(crate) fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// 整棵树的包围盒
+    pub(crate) fn bounding_box(&self) -> Aabb {
+        self.nodes.first().map_or(aabb::EMPTY, |root| root.bbox)
+    }
+
+    /// 求光线在`ray_t`范围内的最近交点
+    ///
+    /// # Arguments
+    /// * `hit_primitive` - 对给定索引的图元在给定范围内求交，命中时返回交点的t
+    ///
+    /// # Returns
+    /// 命中时返回最近交点的t
+    pub(crate) fn closest_hit(
+        &self,
+        r: &Ray,
+        ray_t: &Interval,
+        hit_primitive: impl FnMut(usize, &Interval) -> Option<Real>,
+    ) -> Option<Real> {
+        self.traverse(r, ray_t, false, hit_primitive)
+    }
+
+    /// 判断光线在`ray_t`范围内是否命中任一图元，找到第一个交点即返回
+    ///
+    /// # Arguments
+    /// * `hit_primitive` - 判断给定索引的图元在给定范围内是否被命中
+    pub(crate) fn any_hit(&self, r: &Ray, ray_t: &Interval, mut hit_primitive: impl FnMut(usize, &Interval) -> bool) -> bool {
+        self.traverse(r, ray_t, true, |index, interval| hit_primitive(index, interval).then_some(interval.max))
+            .is_some()
+    }
+
+    /// 遍历与光线相交的节点
+    ///
+    /// 用栈代替递归遍历，内部节点按划分轴上光线方向的正负先访问近处的子节点，
+    /// 已找到的最近交点用于剔除更远的节点
+    ///
+    /// # Arguments
+    /// * `any` - 为true时找到第一个交点即停止
+    /// * `hit_primitive` - 对给定索引的图元在给定范围内求交，命中时返回交点的t
+    fn traverse(
+        &self,
+        r: &Ray,
+        ray_t: &Interval,
+        any: bool,
+        mut hit_primitive: impl FnMut(usize, &Interval) -> Option<Real>,
+    ) -> Option<Real> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut nearest = None;
+        let mut closest = ray_t.max;
+        let direction = r.direction();
+
+        let mut stack = [0usize; STACK_SIZE];
+        let mut top = 1;
+        while top > 0 {
+            top -= 1;
+            let node = &self.nodes[stack[top]];
+            stats::count_node_visit();
+            if !node.bbox.hit(r, &Interval::new(ray_t.min, closest)) {
+                continue;
+            }
+
+            if node.count > 0 {
+                for index in node.offset..node.offset + node.count {
+                    stats::count_intersection_test();
+                    if let Some(t) = hit_primitive(index, &Interval::new(ray_t.min, closest)) {
+                        if any {
+                            return Some(t);
+                        }
+                        closest = t;
+                        nearest = Some(t);
+                    }
+                }
+            } else {
+                // 后入栈的先访问
+                let left = stack[top] + 1;
+                let (near, far) = if direction[node.axis] < 0.0 { (node.offset, left) } else { (left, node.offset) };
+                stack[top] = far;
+                stack[top + 1] = near;
+                top += 2;
+            }
+        }
+        nearest
+    }
+
+    /// 遍历与光线包中任一光线相交的节点
+    ///
+    /// 每个节点对仍在进行的光线一起做包围盒测试，只有全部光线都错过时才剔除该节点；
+    /// 内部节点按第一条穿过它的光线的方向决定访问顺序，光线方向相近时与逐条遍历的顺序一致
+    ///
+    /// # Arguments
+    /// * `any` - 为true时每条光线找到第一个交点即停止
+    /// * `hit_primitive` - 对给定光线和图元在给定范围内求交，参数依次为光线序号、图元索引和范围，
+    ///   命中时返回交点的t
+    ///
+    /// # Returns
+    /// 第i位为1表示第i条光线命中了图元
+    pub(crate) fn traverse_packet<const N: usize>(
+        &self,
+        packet: &RayPacket<N>,
+        any: bool,
+        mut hit_primitive: impl FnMut(usize, usize, &Interval) -> Option<Real>,
+    ) -> u32 {
+        if self.nodes.is_empty() {
+            return 0;
+        }
+
+        let mut closest: [Real; N] = std::array::from_fn(|lane| packet.ray_t(lane).max);
+        let mut active: u32 = if N == 32 { u32::MAX } else { (1 << N) - 1 };
+        let mut hit_mask = 0;
+
+        let mut stack = [0usize; STACK_SIZE];
+        let mut top = 1;
+        while top > 0 {
+            top -= 1;
+            let node = &self.nodes[stack[top]];
+            stats::count_node_visit();
+            let mask = packet.hit_mask(&node.bbox, &closest) & active;
+            if mask == 0 {
+                continue;
+            }
+
+            if node.count > 0 {
+                for index in node.offset..node.offset + node.count {
+                    let mut lanes = mask & active;
+                    while lanes != 0 {
+                        let lane = lanes.trailing_zeros() as usize;
+                        lanes &= lanes - 1;
+                        stats::count_intersection_test();
+                        let interval = Interval::new(packet.ray_t(lane).min, closest[lane]);
+                        if let Some(t) = hit_primitive(lane, index, &interval) {
+                            closest[lane] = t;
+                            hit_mask |= 1 << lane;
+                            if any {
+                                active &= !(1 << lane);
+                            }
+                        }
+                    }
+                }
+                if active == 0 {
+                    break;
+                }
+            } else {
+                let lane = mask.trailing_zeros() as usize;
+                let left = stack[top] + 1;
+                let (near, far) = if packet.ray(lane).direction()[node.axis] < 0.0 {
+                    (node.offset, left)
+                } else {
+                    (left, node.offset)
+                };
+                stack[top] = far;
+                stack[top + 1] = near;
+                top += 2;
+            }
+        }
+        hit_mask
+    }
+
+    /// 统计叶节点数量、最大深度和叶节点引用的图元数，其余字段为0
+    pub(crate) fn stats(&self) -> Stats {
+        let mut stats = Stats { node_count: self.nodes.len(), ..Stats::default() };
+        let mut depth = vec![0usize; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.count > 0 {
+                stats.leaf_count += 1;
+                stats.leaf_references += node.count;
+                stats.max_depth = stats.max_depth.max(depth[index]);
+            } else {
+                depth[index + 1] = depth[index] + 1;
+                depth[node.offset] = depth[index] + 1;
+            }
+        }
+        stats
+    }
+
+    /// 写入树的结构(不含包围盒)
+    ///
+    /// 格式(小端)：节点数(u64)，随后每个节点的offset、count、axis(各u64)
+    pub(crate) fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(&(self.nodes.len() as u64).to_le_bytes())?;
+        for node in &self.nodes {
+            for n in [node.offset, node.count, node.axis] {
+                out.write_all(&(n as u64).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 读取`write`写入的树结构，之后需调用`refit`计算包围盒
+    ///
+    /// # Arguments
+    /// * `primitive_count` - 图元数量，用于检查叶节点的范围
+    ///
+    /// # Returns
+    /// 节点引用越界或树深超过遍历栈容量时返回`InvalidData`错误
+    pub(crate) fn read(input: &mut impl Read, primitive_count: usize) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("bvh cache: {}", msg));
+        let node_count = read_u64(input)? as usize;
+        if node_count > 2 * primitive_count {
+            return Err(invalid("too many nodes"));
+        }
+
+        let mut nodes = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            let [offset, count, axis] = [read_u64(input)?, read_u64(input)?, read_u64(input)?].map(|n| n as usize);
+            nodes.push(BvhNode { bbox: aabb::EMPTY, offset, count, axis });
+        }
+
+        // 子节点必须位于父节点之后，据此可以顺序求出各节点的深度
+        let mut depth = vec![0usize; node_count];
+        for (index, node) in nodes.iter().enumerate() {
+            if depth[index] + 1 >= STACK_SIZE {
+                return Err(invalid("tree too deep"));
+            }
+            if node.count > 0 {
+                if node.offset.checked_add(node.count).is_none_or(|end| end > primitive_count) {
+                    return Err(invalid("leaf out of range"));
+                }
+            } else if node.axis > 2 || node.offset <= index + 1 || node.offset >= node_count {
+                return Err(invalid("bad interior node"));
+            } else {
+                for child in [index + 1, node.offset] {
+                    depth[child] = depth[child].max(depth[index] + 1);
+                }
+            }
+        }
+        Ok(Self { nodes })
+    }
+
+    /// 保持树的结构不变，按图元新的包围盒自底向上重新计算各节点的包围盒
+    ///
+    /// 子节点总是位于父节点之后，逆序遍历节点数组即可保证先更新子节点
+    ///
+    /// # Arguments
+    /// * `bounds` - 给定索引的图元当前的包围盒
+    pub(crate) fn refit(&mut self, bounds: impl Fn(usize) -> Aabb) {
+        for index in (0..self.nodes.len()).rev() {
+            let node = self.nodes[index];
+            self.nodes[index].bbox = if node.count > 0 {
+                (node.offset..node.offset + node.count).fold(aabb::EMPTY, |acc, i| Aabb::surrounding(&acc, &bounds(i)))
+            } else {
+                Aabb::surrounding(&self.nodes[index + 1].bbox, &self.nodes[node.offset].bbox)
+            };
+        }
+    }
+}
+
+/// 层次包围盒
+///
+/// 图元可以是共享同一物体的`Instance`：网格等物体内部各自带有底层BVH(BLAS)，
+/// 以实例为图元构建的`Bvh`即为顶层BVH(TLAS)，光线在进入实例时才变换到物体空间，
+/// 大量重复物体(树林、人群)不需要展开成各自的三角形
+///
+/// # Fields
+/// - objects: 按叶节点顺序排列的物体
+/// - order: 各物体在构建时输入列表中的索引
+/// - tree: 节点数组
+pub struct Bvh {
+    objects: Vec<Arc<dyn Hittable>>,
+    order: Vec<usize>,
+    tree: BvhTree,
+}
+impl Bvh {
+    /// 以默认选项(中位数划分)为物体列表构建BVH
+    ///
+    /// 列表会先被展开，嵌套列表中的物体直接成为BVH的图元
+    ///
+    /// # Arguments
+    /// * `list` - 物体列表
+    pub fn new(list: &HittableList) -> Self {
+        Self::from_list(list)
+    }
+
+    /// 按物体当前的包围盒更新节点包围盒，不改变树的结构
+    ///
+    /// 适用于物体自身的包围盒发生变化的情况。开销与节点数成正比，远低于重新构建
+    pub fn refit(&mut self) {
+        let objects = &self.objects;
+        self.tree.refit(|index| objects[index].bounding_box());
+    }
+
+    /// 求光线包中每条光线的最近交点
+    ///
+    /// 结果与逐条调用`hit`相同，方向相近的光线(如同一像素块的主光线)共享节点的读取和剔除
+    ///
+    /// # Arguments
+    /// * `packet` - 光线包
+    /// * `records` - 各光线的命中记录，只有命中的光线对应的记录会被写入
+    ///
+    /// # Returns
+    /// 各光线是否命中
+    pub fn hit_packet<const N: usize>(&self, packet: &RayPacket<N>, records: &mut [HitRecord; N]) -> [bool; N] {
+        let mut temp_rec = HitRecord::default();
+        let mask = self.tree.traverse_packet(packet, false, |lane, index, interval| {
+            if !self.objects[index].hit(packet.ray(lane), interval, &mut temp_rec) {
+                return None;
+            }
+            records[lane] = temp_rec.clone();
+            Some(temp_rec.t)
+        });
+        std::array::from_fn(|lane| mask & (1 << lane) != 0)
+    }
+
+    /// 判断光线包中每条光线是否被遮挡，用于成批的阴影光线
+    ///
+    /// # Arguments
+    /// * `packet` - 光线包，各光线的参数上限通常为到光源采样点的距离
+    ///
+    /// # Returns
+    /// 各光线在其参数范围内是否命中任一物体
+    pub fn hit_any_packet<const N: usize>(&self, packet: &RayPacket<N>) -> [bool; N] {
+        let mask = self.tree.traverse_packet(packet, true, |lane, index, interval| {
+            self.objects[index].hit_any(packet.ray(lane), interval).then_some(interval.max)
+        });
+        std::array::from_fn(|lane| mask & (1 << lane) != 0)
+    }
+
+    /// 把BVH的结构写入缓存文件，下次渲染同一场景时可用`load_cache`跳过构建
+    ///
+    /// 格式(小端)：文件头"RTBV"，物体数(u64)，树结构，随后为各叶节点顺序位置上物体的输入索引(各u64)。
+    /// 物体本身不写入文件
+    pub fn save_cache(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(CACHE_MAGIC)?;
+        out.write_all(&(self.objects.len() as u64).to_le_bytes())?;
+        self.tree.write(&mut out)?;
+        for &index in &self.order {
+            out.write_all(&(index as u64).to_le_bytes())?;
+        }
+        out.flush()
+    }
+
+    /// 从缓存文件恢复BVH
+    ///
+    /// 节点包围盒按物体当前的包围盒重新计算，物体略有变化时结果仍然正确，只是树的质量可能下降
+    ///
+    /// # Arguments
+    /// * `path` - `save_cache`写入的文件
+    /// * `objects` - 物体，顺序和数量须与构建时的输入相同(`from_list`的输入为展开后的列表)
+    ///
+    /// # Returns
+    /// 文件头不匹配、物体数量不一致或数据损坏时返回`InvalidData`错误
+    pub fn load_cache(path: impl AsRef<Path>, objects: &[Arc<dyn Hittable>]) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("bvh cache: {}", msg));
+        let mut input = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != CACHE_MAGIC {
+            return Err(invalid("not a bvh cache file"));
+        }
+        if read_u64(&mut input)? as usize != objects.len() {
+            return Err(invalid("object count mismatch"));
+        }
+        let tree = BvhTree::read(&mut input, objects.len())?;
+
+        let mut seen = vec![false; objects.len()];
+        let mut order = Vec::with_capacity(objects.len());
+        for _ in 0..objects.len() {
+            let index = read_u64(&mut input)? as usize;
+            if index >= objects.len() || std::mem::replace(&mut seen[index], true) {
+                return Err(invalid("bad object index"));
+            }
+            order.push(index);
+        }
+
+        let objects = order.iter().map(|&index| Arc::clone(&objects[index])).collect();
+        let mut bvh = Self { objects, order, tree };
+        bvh.refit();
+        Ok(bvh)
+    }
+
+    /// 用新一帧的物体替换原有物体并更新节点包围盒
+    ///
+    /// 动画中每帧只改变物体的变换时，用新的实例调用此方法即可代替重新构建。
+    /// 物体移动幅度较大时节点之间的重叠增多，遍历效率逐渐下降，此时应重新构建
+    ///
+    /// # Arguments
+    /// * `objects` - 新的物体，顺序和数量须与构建时的输入相同(`from_list`的输入为展开后的列表)
+    ///
+    /// # Note
+    /// 物体数量与构建时不同会panic
+    pub fn refit_with(&mut self, objects: &[Arc<dyn Hittable>]) {
+        assert_eq!(objects.len(), self.objects.len(), "物体数量与构建时不同");
+        self.objects = self.order.iter().map(|&index| Arc::clone(&objects[index])).collect();
+        self.refit();
+    }
+}
+
+impl Accelerator for Bvh {
+    fn build(objects: Vec<Arc<dyn Hittable>>) -> Self {
+        BvhBuilder::new(objects).build()
+    }
+
+    fn primitive_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.tree.node_count()
+    }
+
+    fn stats(&self) -> Stats {
+        Stats { primitive_count: self.objects.len(), ..self.tree.stats() }
+    }
+}
+
+impl Hittable for Bvh {
+    /// 实现BVH的光线命中检测
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let mut temp_rec = HitRecord::default();
+        self.tree
+            .closest_hit(r, ray_t, |index, interval| {
+                if !self.objects[index].hit(r, interval, &mut temp_rec) {
+                    return None;
+                }
+                *hit_record = temp_rec.clone();
+                Some(temp_rec.t)
+            })
+            .is_some()
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.tree.any_hit(r, ray_t, |index, interval| self.objects[index].hit_any(r, interval))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.tree.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::instance::{Instance, Translate};
+    use crate::interval;
+    use crate::material::{Lambertian, Material};
+    use crate::mesh::TriangleMesh;
+    use crate::packet::RayPacket;
+    use crate::quad::Quad;
+    use crate::rtweekend;
+    use crate::sphere::Sphere;
+    use crate::transform::Transform;
+    use crate::vec3::Vec3;
+    use std::fs;
+
+    /// 随机散布的小球，固定种子保证每次相同
+    fn scattered_spheres(count: usize) -> Vec<Arc<dyn Hittable>> {
+        rtweekend::seed_random(823);
+        let mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        (0..count)
+            .map(|_| Arc::new(Sphere::new(Vec3::random_range(-10.0, 10.0), 0.5, mat.clone())) as Arc<dyn Hittable>)
+            .collect()
+    }
+
+    fn gray() -> Arc<dyn Material + Send + Sync> {
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+    }
+
+    /// 随机小球，加上中心重合的同心球、完全相同的球和没有厚度的共面四边形
+    fn mixed_objects(spheres: usize) -> Vec<Arc<dyn Hittable>> {
+        let mut objects = scattered_spheres(spheres);
+        for k in 1..=6 {
+            objects.push(Arc::new(Sphere::new(Point3::new(3.0, -2.0, 1.0), 0.4 * k as Real, gray())));
+        }
+        for _ in 0..3 {
+            objects.push(Arc::new(Sphere::new(Point3::new(-6.0, 4.0, 0.0), 1.0, gray())));
+        }
+        let (x, y) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        objects.push(Arc::new(Quad::new(Point3::new(-8.0, -8.0, 0.0), 16.0 * x, 16.0 * y, gray())));
+        objects.push(Arc::new(Quad::new(Point3::new(-2.0, -2.0, 0.0), 4.0 * x, 4.0 * y, gray())));
+        objects
+    }
+
+    /// 固定种子的随机光线，起点覆盖物体所在的整个范围，部分光线参数范围有限
+    fn random_rays(count: usize) -> Vec<(Ray, Interval)> {
+        rtweekend::seed_random(1038);
+        (0..count)
+            .map(|k| {
+                let r = Ray::new(Vec3::random_range(-15.0, 15.0), Vec3::random_range(-1.0, 1.0));
+                let t_max = if k % 3 == 0 { 8.0 } else { rtweekend::INFINITY };
+                (r, Interval::new(rtweekend::HIT_EPSILON, t_max))
+            })
+            .collect()
+    }
+
+    /// 在随机光线上比较两个物体的`hit`和`hit_any`，返回命中的光线数
+    fn assert_same_hits(expected: &dyn Hittable, actual: &dyn Hittable, rays: usize) -> usize {
+        let mut hits = 0;
+        for (k, (r, ray_t)) in random_rays(rays).iter().enumerate() {
+            let (mut a, mut b) = (HitRecord::default(), HitRecord::default());
+            let hit = expected.hit(r, ray_t, &mut a);
+            assert_eq!(hit, actual.hit(r, ray_t, &mut b), "ray {}", k);
+            if hit {
+                assert_eq!(a.t, b.t, "ray {}", k);
+                hits += 1;
+            }
+            assert_eq!(hit, actual.hit_any(r, ray_t), "ray {}", k);
+        }
+        hits
+    }
+
+    /// 物体列表，作为求交结果的参照
+    fn list_of(objects: &[Arc<dyn Hittable>]) -> HittableList {
+        let mut list = HittableList::default();
+        for object in objects {
+            list.add(Arc::clone(object));
+        }
+        list
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rtbv-{}-{}.bin", std::process::id(), name))
+    }
+
+    fn tree_bytes(tree: &BvhTree) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        tree.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn tree_write_read_round_trip() {
+        let objects = scattered_spheres(50);
+        let bvh = BvhBuilder::new(objects.clone()).with_max_leaf_size(2).build();
+        let bytes = tree_bytes(&bvh.tree);
+
+        let tree = BvhTree::read(&mut bytes.as_slice(), objects.len()).unwrap();
+        assert_eq!(tree.node_count(), bvh.tree.node_count());
+        for (a, b) in tree.nodes.iter().zip(&bvh.tree.nodes) {
+            assert_eq!((a.offset, a.count, a.axis), (b.offset, b.count, b.axis));
+        }
+        assert_eq!(tree_bytes(&tree), bytes);
+    }
+
+    #[test]
+    fn loaded_cache_gives_the_same_hits() {
+        let objects = scattered_spheres(50);
+        let bvh = BvhBuilder::new(objects.clone()).build();
+        let path = temp_path("round-trip");
+        bvh.save_cache(&path).unwrap();
+        let loaded = Bvh::load_cache(&path, &objects);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.order, bvh.order);
+
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+        for _ in 0..200 {
+            let r = Ray::new(Vec3::random_range(-15.0, 15.0), Vec3::random_range(-1.0, 1.0));
+            let (mut a, mut b) = (HitRecord::default(), HitRecord::default());
+            assert_eq!(bvh.hit(&r, &ray_t, &mut a), loaded.hit(&r, &ray_t, &mut b));
+            assert_eq!(a.t, b.t);
+        }
+    }
+
+    #[test]
+    fn truncated_or_corrupt_cache_is_rejected() {
+        let objects = scattered_spheres(20);
+        let bvh = BvhBuilder::new(objects.clone()).build();
+        let path = temp_path("truncated");
+        bvh.save_cache(&path).unwrap();
+        let full = fs::read(&path).unwrap();
+
+        // 文件截断在头部、树结构或索引表中的任意位置都返回错误
+        for len in [0, 3, 4, 11, 12, 20, full.len() / 2, full.len() - 8, full.len() - 1] {
+            fs::write(&path, &full[..len]).unwrap();
+            assert!(Bvh::load_cache(&path, &objects).is_err(), "len = {}", len);
+        }
+
+        // 索引表中出现重复的物体
+        let mut corrupt = full.clone();
+        let n = corrupt.len();
+        let (last, prev) = (n - 8, n - 16);
+        corrupt.copy_within(prev..last, last);
+        fs::write(&path, &corrupt).unwrap();
+        assert_eq!(Bvh::load_cache(&path, &objects).err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+
+        // 物体数量与缓存不符
+        fs::write(&path, &full).unwrap();
+        assert!(Bvh::load_cache(&path, &objects[1..]).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn sah_builds_match_the_plain_list() {
+        let objects = mixed_objects(300);
+        let list = list_of(&objects);
+        for bins in [2, 16] {
+            for leaf in [1, 4] {
+                let bvh = BvhBuilder::new(objects.clone())
+                    .with_split_method(SplitMethod::Sah { bins })
+                    .with_max_leaf_size(leaf)
+                    .build();
+                let stats = bvh.stats();
+                assert_eq!(stats.leaf_references, objects.len());
+                assert_eq!(stats.node_count, 2 * stats.leaf_count - 1);
+                assert!(stats.max_depth < STACK_SIZE);
+                assert!(assert_same_hits(&list, &bvh, 2000) > 200, "bins {} leaf {}", bins, leaf);
+            }
+        }
+
+        // 所有中心重合时没有可用的SAH划分，退回按数量对半划分，每个物体仍只出现一次
+        let concentric: Vec<Arc<dyn Hittable>> = (1..=20)
+            .map(|k| Arc::new(Sphere::new(Point3::default(), 0.5 * k as Real, gray())) as Arc<dyn Hittable>)
+            .collect();
+        let bvh = BvhBuilder::new(concentric.clone()).with_split_method(SplitMethod::Sah { bins: 2 }).with_max_leaf_size(1).build();
+        let stats = bvh.stats();
+        assert_eq!(stats.leaf_references, concentric.len());
+        assert!(stats.max_depth <= MAX_UNBALANCED_DEPTH);
+        assert!(assert_same_hits(&list_of(&concentric), &bvh, 500) > 100);
+    }
+
+    #[test]
+    fn morton_builds_match_the_plain_list() {
+        let objects = mixed_objects(300);
+        for leaf in [1, 4] {
+            let bvh = BvhBuilder::new(objects.clone()).with_split_method(SplitMethod::Morton).with_max_leaf_size(leaf).build();
+            let stats = bvh.stats();
+            assert_eq!(stats.leaf_references, objects.len());
+            assert_eq!(stats.node_count, 2 * stats.leaf_count - 1);
+            assert!(stats.max_depth < STACK_SIZE);
+            assert!(assert_same_hits(&list_of(&objects), &bvh, 2000) > 200, "leaf {}", leaf);
+        }
+
+        // 中心重合时Morton码全部相同，只靠原始索引区分
+        let concentric: Vec<Arc<dyn Hittable>> = (1..=20)
+            .map(|k| Arc::new(Sphere::new(Point3::new(1.0, 2.0, 3.0), 0.5 * k as Real, gray())) as Arc<dyn Hittable>)
+            .collect();
+        let bvh = BvhBuilder::new(concentric.clone()).with_split_method(SplitMethod::Morton).with_max_leaf_size(1).build();
+        assert_eq!(bvh.stats().leaf_references, concentric.len());
+        assert!(bvh.stats().max_depth < STACK_SIZE);
+        assert!(assert_same_hits(&list_of(&concentric), &bvh, 500) > 100);
+    }
+
+    /// 逐条光线与整个光线包的结果必须一致；`ray_t`为空区间的车道必须未命中且记录保持不变
+    fn assert_packet_matches_single_rays<const N: usize>(bvh: &Bvh, rays: [Ray; N], ray_t: [Interval; N]) -> usize {
+        let packet = RayPacket::new(rays, ray_t);
+        let mut records: [HitRecord; N] = std::array::from_fn(|_| HitRecord { t: -1.0, ..HitRecord::default() });
+        let hits = bvh.hit_packet(&packet, &mut records);
+        let occluded = bvh.hit_any_packet(&packet);
+        for lane in 0..N {
+            let mut rec = HitRecord::default();
+            let hit = bvh.hit(&rays[lane], &ray_t[lane], &mut rec);
+            assert_eq!(hits[lane], hit, "lane {}", lane);
+            assert_eq!(occluded[lane], bvh.hit_any(&rays[lane], &ray_t[lane]), "lane {}", lane);
+            if hit {
+                assert_eq!(records[lane].t, rec.t, "lane {}", lane);
+            } else {
+                assert_eq!(records[lane].t, -1.0, "lane {}", lane);
+            }
+        }
+        hits.iter().filter(|&&hit| hit).count()
+    }
+
+    #[test]
+    fn packet_queries_match_single_rays() {
+        let bvh = BvhBuilder::new(mixed_objects(300)).build();
+        // 方向随机，同一包内的光线方向符号各不相同
+        let rays = random_rays(32 * 20);
+        let mut hits = 0;
+        for chunk in rays.chunks_exact(8) {
+            hits += assert_packet_matches_single_rays::<8>(&bvh, std::array::from_fn(|k| chunk[k].0), std::array::from_fn(|k| chunk[k].1));
+        }
+        for chunk in rays.chunks_exact(32) {
+            assert_packet_matches_single_rays::<32>(&bvh, std::array::from_fn(|k| chunk[k].0), std::array::from_fn(|k| chunk[k].1));
+        }
+        assert!(hits > 60);
+
+        // 只填了5条光线的包，其余车道用空区间填充，且填充光线本身会命中物体
+        let toward_center = Ray::new(Point3::new(0.0, 0.0, 20.0), Vec3::new(0.0, 0.0, -1.0));
+        for chunk in rays.chunks_exact(5).take(20) {
+            let lane_rays = std::array::from_fn(|k| chunk.get(k).map_or(toward_center, |(r, _)| *r));
+            let lane_t = std::array::from_fn(|k| chunk.get(k).map_or(interval::EMPTY, |(_, t)| *t));
+            assert_packet_matches_single_rays::<8>(&bvh, lane_rays, lane_t);
+        }
+    }
+
+    #[test]
+    fn refit_after_moving_objects_matches_a_fresh_build() {
+        let objects = mixed_objects(200);
+        let mut bvh = BvhBuilder::new(objects.clone()).with_split_method(SplitMethod::Sah { bins: 8 }).build();
+        let before = bvh.stats();
+
+        // 新一帧：每个物体平移一段随机距离，部分物体移出原来的包围盒
+        rtweekend::seed_random(1043);
+        let moved: Vec<Arc<dyn Hittable>> = objects
+            .iter()
+            .map(|object| Arc::new(Translate::new(Arc::clone(object), Vec3::random_range(-3.0, 3.0))) as Arc<dyn Hittable>)
+            .collect();
+        bvh.refit_with(&moved);
+        assert_eq!(bvh.stats(), before);
+        let rebuilt = BvhBuilder::new(moved.clone()).with_split_method(SplitMethod::Sah { bins: 8 }).build();
+        assert!(assert_same_hits(&rebuilt, &bvh, 2000) > 200);
+        assert_same_hits(&list_of(&moved), &bvh, 2000);
+
+        // 换回原来的物体后结果仍与物体列表一致
+        bvh.refit_with(&objects);
+        bvh.refit();
+        assert_same_hits(&list_of(&objects), &bvh, 2000);
+    }
+
+    /// z=0平面上覆盖[0,1]²的4×4网格，共32个三角形
+    fn grid_mesh() -> TriangleMesh {
+        let n = 4;
+        let positions = (0..=n)
+            .flat_map(|j| (0..=n).map(move |i| Point3::new(i as Real / n as Real, j as Real / n as Real, 0.0)))
+            .collect();
+        let indices: Vec<[usize; 3]> = (0..n)
+            .flat_map(|j| (0..n).map(move |i| j * (n + 1) + i))
+            .flat_map(|k| [[k, k + 1, k + n + 2], [k, k + n + 2, k + n + 1]])
+            .collect();
+        TriangleMesh::from_indexed(positions, &indices, gray())
+    }
+
+    #[test]
+    fn top_level_bvh_over_two_instances_of_one_mesh() {
+        let mesh: Arc<dyn Hittable> = Arc::new(grid_mesh());
+        // 一个平移到x=5；另一个绕y轴转90°后落在x=-5处的yz平面上，z范围为[-1,0]
+        let shifted = Transform::translate(Vec3::new(5.0, 0.0, 0.0));
+        let turned = Transform::rotate(Vec3::new(0.0, 1.0, 0.0), 90.0).then(&Transform::translate(Vec3::new(-5.0, 0.0, 0.0)));
+        let instances: Vec<Arc<dyn Hittable>> =
+            vec![Arc::new(Instance::new(Arc::clone(&mesh), shifted)), Arc::new(Instance::new(Arc::clone(&mesh), turned))];
+        let tlas = BvhBuilder::new(instances.clone()).with_max_leaf_size(1).build();
+        assert_eq!(tlas.stats().leaf_count, 2);
+
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+        let mut rec = HitRecord::default();
+        let down = Ray::new(Point3::new(5.3, 0.6, 10.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(tlas.hit(&down, &ray_t, &mut rec));
+        assert!((rec.t - 10.0).abs() < 1e-4);
+        assert!((rec.p - Point3::new(5.3, 0.6, 0.0)).length() < 1e-4);
+        assert!((rec.normal.z().abs() - 1.0).abs() < 1e-4);
+
+        // 沿-x方向：先经过第一个实例所在的x范围但不在其平面上，再命中第二个实例
+        let across = Ray::new(Point3::new(10.0, 0.3, -0.7), Vec3::new(-1.0, 0.0, 0.0));
+        assert!(tlas.hit(&across, &ray_t, &mut rec));
+        assert!((rec.t - 15.0).abs() < 1e-4);
+        assert!((rec.normal.x().abs() - 1.0).abs() < 1e-4);
+        assert!(tlas.hit_any(&across, &ray_t));
+
+        // 从两个实例之间穿过
+        let between = Ray::new(Point3::new(0.0, 0.5, 10.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!tlas.hit(&between, &ray_t, &mut rec));
+        assert!(!tlas.hit_any(&between, &ray_t));
+
+        assert_same_hits(&list_of(&instances), &tlas, 2000);
+    }
+
+    #[test]
+    fn stats_of_small_median_trees() {
+        let row = |count: usize| -> Vec<Arc<dyn Hittable>> {
+            (0..count)
+                .map(|i| Arc::new(Sphere::new(Point3::new(3.0 * i as Real, 0.0, 0.0), 1.0, gray())) as Arc<dyn Hittable>)
+                .collect()
+        };
+        let stats = |count: usize, leaf: usize| BvhBuilder::new(row(count)).with_max_leaf_size(leaf).build().stats();
+
+        // 4个物体对半划分两次：完全平衡的二叉树
+        let expected = Stats { primitive_count: 4, node_count: 7, leaf_count: 4, max_depth: 2, leaf_references: 4 };
+        assert_eq!(stats(4, 1), expected);
+        // 5个物体先分成2+3，3再分成1+2
+        let expected = Stats { primitive_count: 5, node_count: 9, leaf_count: 5, max_depth: 3, leaf_references: 5 };
+        assert_eq!(stats(5, 1), expected);
+        // 叶节点最多4个物体时只划分一次
+        let five = stats(5, 4);
+        assert_eq!(five, Stats { primitive_count: 5, node_count: 3, leaf_count: 2, max_depth: 1, leaf_references: 5 });
+        assert_eq!(five.average_leaf_size(), 2.5);
+        // 只有一个物体时根节点就是叶节点
+        assert_eq!(stats(1, 4), Stats { primitive_count: 1, node_count: 1, leaf_count: 1, max_depth: 0, leaf_references: 1 });
+    }
+}
@@ -2,16 +2,96 @@
 //!
 //! 提供Camera结构体用于配置渲染参数和生成光线
 
-use super::rtweekend;
-use super::color::Color;
+use super::rtweekend::{self, Real};
+use super::aabb::Aabb;
+use super::background::Background;
+use super::color::{self, Color, EncodeOptions};
 use super::hittable::{HitRecord, Hittable};
 use super::ray::Ray;
 use super::interval::Interval;
+use super::material::Material;
 use super::vec3::{self, Point3, Vec3};
 
+use super::checkpoint::Checkpoint;
+use super::stats::{self, RenderStats};
+
 use crossbeam::scope;
-use std::io::{self, Write};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 散景(光圈)形状
+///
+/// # Variants
+/// - Circle: 圆形光圈，产生圆形散景
+/// - Polygon: 正多边形光圈，blades为光圈叶片数(至少3)，rotation为旋转角度(角度制)
+#[derive(Clone, Copy, Debug, Default)]
+pub enum BokehShape {
+    #[default]
+    Circle,
+    Polygon { blades: u32, rotation: Real },
+}
+
+/// 多线程渲染的任务划分方式
+///
+/// # Variants
+/// - Bands: 按扫描线把图像平均分成与线程数相同的水平条带
+/// - Tiles: 把图像划分为 size×size 像素的图块，线程从共享计数器依次领取，
+///   负载不均的场景下各线程更均衡
+#[derive(Clone, Copy, Debug)]
+pub enum RenderSchedule {
+    Bands,
+    Tiles { size: usize },
+}
+
+impl Default for RenderSchedule {
+    fn default() -> Self {
+        RenderSchedule::Tiles { size: 16 }
+    }
+}
+
+/// 像素重建滤波器
+///
+/// 子像素内的采样偏移按滤波器形状的分布抽取(重要性采样)，因此各采样权重相同，
+/// 仍按等权平均累积
+///
+/// # Variants
+/// - Box: 盒式滤波，在子像素内均匀抖动
+/// - Tent: 帐篷(三角)滤波，支撑范围为中心两侧各一个子像素
+/// - Gaussian: 高斯滤波，sigma为以子像素边长为单位的标准差
+#[derive(Clone, Copy, Debug, Default)]
+pub enum PixelFilter {
+    #[default]
+    Box,
+    Tent,
+    Gaussian { sigma: Real },
+}
+
+/// 单个像素的采样结果
+///
+/// # Fields
+/// - color: 所有采样的平均颜色
+/// - std_error: 均值标准误差，取三个通道的平均值
+/// - foreground: 只计入主光线命中物体的采样、再除以总采样数的颜色(预乘alpha)
+/// - coverage: 主光线命中物体的采样比例
+#[derive(Clone, Copy, Debug, Default)]
+struct PixelSample {
+    color: Color,
+    std_error: Real,
+    foreground: Color,
+    coverage: Real,
+}
+
+/// 图像中的一个矩形区域[x0, x1) × [y0, y1)
+#[derive(Clone, Copy, Debug)]
+struct Tile {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+}
 
 /// 相机结构体，包含渲染场景所需的所有参数
 /// 
@@ -20,18 +100,34 @@ use std::sync::{Arc, Mutex};
 /// - image_width: 图像宽度(像素)
 /// - samples_per_pixel: 每个像素的采样次数
 /// - max_depth: 光线最大反弹次数
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Camera {
-    pub aspect_ratio: f64,  // 图像宽高比（宽度/高度）
+    pub aspect_ratio: Real,  // 图像宽高比（宽度/高度）
     pub image_width: i32,   // 渲染图像宽度（像素数）
     pub samples_per_pixel: usize,  // 每个像素的采样次数
-    pub max_depth: i32,     // 光线最大反弹次数
-    pub vfov: f64,          // 垂直视野角度
+    pub max_depth: u32,     // 光线最大反弹次数
+    pub vfov: Real,          // 垂直视野角度
     pub lookfrom: Point3,   // 相机位置原点
     pub lookat: Point3,     // 相机瞄准点
     pub vup: Vec3,          // 相机上方向向量
-    pub defocus_angle: f64, // 散景模糊角度
-    pub focus_dist: f64,    // 对焦距离
+    pub defocus_angle: Real, // 散景模糊角度
+    pub focus_dist: Real,    // 对焦距离
+    pub bokeh: BokehShape,   // 散景光圈形状
+    pub supersample: u32,    // 超采样倍数，每个像素拆分为 n×n 个子像素
+    pub background: Background, // 光线未命中时的背景
+    pub checkpoint_path: Option<PathBuf>, // 检查点文件路径，None表示不保存
+    pub checkpoint_interval: usize, // 每累积多少轮采样保存一次检查点
+    pub firefly_clamp: Option<Real>, // 单个采样每通道的亮度上限，None表示不限制
+    pub schedule: RenderSchedule, // 多线程渲染的任务划分方式
+    pub hit_epsilon: Real,   // 求交的最小参数t，按场景尺度调整以避免阴影痤疮或漏光
+    pub edge_boost: Option<Real>, // 启用深度/法线预通道时，边缘像素的最大采样倍数
+    pub seed: u64,           // 全局随机数种子，与像素坐标和采样序号一起决定每个采样的随机序列
+    pub distortion_k1: Real, // 径向畸变二次项系数，正值使边缘光线向外偏(画面呈桶形)，负值呈枕形
+    pub distortion_k2: Real, // 径向畸变四次项系数
+    pub filter: PixelFilter, // 像素重建滤波器
+    pub material_override: Option<Arc<dyn Material + Send + Sync>>, // 设置后所有物体都使用该材质渲染，便于单独检查几何
+    pub shutter_open: Real,  // 快门打开时刻
+    pub shutter_close: Real, // 快门关闭时刻，大于shutter_open时每条光线在两者之间随机取时刻，产生运动模糊
     image_height: i32,      // 渲染图像高度
     center: Point3,         // 相机中心位置
     pixel00_loc: Point3,    // 像素(0,0)的位置
@@ -42,6 +138,9 @@ pub struct Camera {
     w: Vec3,                // 相机前向轴
     defocus_disk_u: Vec3,   // 散景圆盘水平轴
     defocus_disk_v: Vec3,   // 散景圆盘垂直轴
+    viewport_center: Point3, // 视口中心位置
+    viewport_half_height: Real, // 视口高度的一半，用于归一化畸变半径
+    temporal_frame: usize,  // accumulate_into已累积的帧数，用于错开各帧的随机序列
 }
 
 impl Default for Camera {
@@ -57,8 +156,24 @@ impl Default for Camera {
             vup: Vec3::new(0.0, 1.0, 0.0),
             defocus_angle: 0.0,
             focus_dist: 10.0,
+            bokeh: BokehShape::default(),
             samples_per_pixel: 4,
             max_depth: 10,
+            supersample: 1,
+            background: Background::default(),
+            checkpoint_path: None,
+            checkpoint_interval: 10,
+            firefly_clamp: None,
+            schedule: RenderSchedule::default(),
+            hit_epsilon: rtweekend::HIT_EPSILON,
+            edge_boost: None,
+            seed: 0,
+            distortion_k1: 0.0,
+            distortion_k2: 0.0,
+            filter: PixelFilter::default(),
+            material_override: None,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
             center: Point3::default(),
             pixel00_loc: Point3::default(),
             pixel_delta_u: Vec3::default(),
@@ -68,11 +183,44 @@ impl Default for Camera {
             w: Vec3::default(),
             defocus_disk_u: Vec3::default(),
             defocus_disk_v: Vec3::default(),
+            viewport_center: Point3::default(),
+            viewport_half_height: 0.0,
+            temporal_frame: 0,
         }
     }
 }
 
 impl Camera {
+    /// 自动取景，使整个包围盒落在画面内
+    ///
+    /// 保持当前观察方向不变，把`lookat`移到包围盒中心，并沿观察方向后退，
+    /// 距离取包围球半径除以水平、垂直半视角中较小者的正弦，保证包围球完全可见；
+    /// 同时把`focus_dist`设为该距离。包围盒为空、无界或退化为一个点时不做任何修改
+    ///
+    /// # Arguments
+    /// * `world_bbox` - 场景包围盒，通常为`world.bounding_box()`
+    pub fn frame_scene(&mut self, world_bbox: &Aabb) {
+        let extent = Vec3::new(world_bbox.x.size(), world_bbox.y.size(), world_bbox.z.size());
+        let radius = 0.5 * extent.length();
+        if !(0..3).all(|k| extent[k].is_finite() && extent[k] >= 0.0) || radius <= 0.0 {
+            return;
+        }
+
+        let mut view_dir = self.lookat - self.lookfrom;
+        if view_dir.near_zero() {
+            view_dir = Vec3::new(0.0, 0.0, -1.0);
+        }
+        let view_dir = vec3::unit_vector(view_dir);
+
+        let half_vfov = rtweekend::degrees_to_radians(self.vfov) / 2.0;
+        let half_hfov = (half_vfov.tan() * self.aspect_ratio).atan();
+        let distance = radius / half_vfov.min(half_hfov).sin();
+
+        self.lookat = world_bbox.center();
+        self.lookfrom = self.lookat - distance * view_dir;
+        self.focus_dist = distance;
+    }
+
     /// 渲染场景到标准输出(PPM格式)
     /// 
     /// # Arguments
@@ -85,133 +233,573 @@ impl Camera {
     pub fn render(&mut self, world: &dyn Hittable) {
         self.initialize();
 
-        println!("P3\n{} {}\n255", self.image_width, self.image_height);
+        let budget = self.sample_budget(world);
+        let linear = self.render_serial(world, &budget);
+        let pixels = color::encode(&linear, &EncodeOptions::default());
         let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+        color::write_ppm(&mut out, self.image_width as usize, self.image_height as usize, &pixels).unwrap();
+
+        eprintln!("\nDone.");
+    }
 
-        for j in 0..self.image_height {
+    /// 在当前线程中逐行渲染到线性颜色缓冲区，调用前需先`initialize`
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    /// * `budget` - 每像素采样次数，由`sample_budget`给出
+    ///
+    /// # Returns
+    /// 返回按行优先排列的线性颜色，长度为 宽 * 高
+    fn render_serial(&self, world: &dyn Hittable, budget: &[usize]) -> Vec<Color> {
+        let width = self.image_width as usize;
+        let mut linear = Vec::with_capacity((self.image_width * self.image_height) as usize);
+        for j in 0..self.image_height as usize {
             // eprintln!("\rScanlines remaining: {}", self.image_height - j);
-            for i in 0..self.image_width {
-                let mut pixel_color = Color::default();
-                for _ in 0..self.samples_per_pixel {
-                    let r = self.get_ray(i, j);
-                    // pixel_color += self.ray_color(&r, world);
-                    pixel_color += Self::ray_color(&r, self.max_depth, world);
-                }
-                pixel_color.write_color(&mut stdout.lock(), self.samples_per_pixel).unwrap();
+            for i in 0..width {
+                linear.push(self.sample_pixel(i, j, 0, budget[j * width + i], world).color);
             }
         }
+        linear
+    }
+
+    /// 多线程渲染场景到标准输出(PPM格式)
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    pub fn render_multi_thread(&mut self, world: &dyn Hittable) {
+        let pixels = self.render_to_buffer(world);
+
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+        color::write_ppm(&mut out, self.image_width as usize, self.image_height as usize, &pixels).unwrap();
 
         eprintln!("\nDone.");
     }
 
-   pub fn render_multi_thread(&mut self, world: &dyn Hittable) {
+    /// 多线程渲染场景到内存缓冲区
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    ///
+    /// # Returns
+    /// 返回按行优先排列的RGB8像素数据，长度为 宽 * 高 * 3
+    pub fn render_to_buffer(&mut self, world: &dyn Hittable) -> Vec<u8> {
+        let linear = self.render_linear(world);
+        color::encode(&linear, &EncodeOptions::default())
+    }
+
+    /// 多线程渲染场景到线性颜色缓冲区
+    ///
+    /// 结果已按采样数归一化，但没有经过曝光、色调映射、gamma和量化，
+    /// 可用`color::encode`以不同参数反复编码而无需重新渲染
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    ///
+    /// # Returns
+    /// 返回按行优先排列的线性颜色，长度为 宽 * 高
+    pub fn render_linear(&mut self, world: &dyn Hittable) -> Vec<Color> {
+        self.initialize();
+
+        let width = self.image_width as usize;
+        let budget = self.sample_budget(world);
+        self.render_parallel(|i, j| self.sample_pixel(i, j, 0, budget[j * width + i], world).color)
+    }
+
+    /// 多线程渲染场景，同时收集渲染统计
+    ///
+    /// 每个像素使用Welford在线算法按通道统计采样方差，
+    /// 噪声取所有像素均值标准误差(三个通道的平均)的平均值；
+    /// 光线和求交计数在各线程内累加，每个像素结束时随结果取出并汇总
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    ///
+    /// # Returns
+    /// 返回(RGB8像素数据, 渲染统计)
+    pub fn render_with_stats(&mut self, world: &dyn Hittable) -> (Vec<u8>, RenderStats) {
+        self.initialize();
+
+        let width = self.image_width as usize;
+        let budget = self.sample_budget(world);
+        let results = self.render_parallel(|i, j| {
+            stats::take();
+            let pixel = self.sample_pixel(i, j, 0, budget[j * width + i], world);
+            let mut pixel_stats = stats::take();
+            pixel_stats.noise = pixel.std_error;
+            (pixel.color, pixel_stats)
+        });
+
+        let mut linear = Vec::with_capacity(results.len());
+        let mut total = RenderStats::default();
+        for (pixel_color, pixel_stats) in &results {
+            linear.push(*pixel_color);
+            total += *pixel_stats;
+        }
+        if !results.is_empty() {
+            total.noise /= results.len() as Real;
+        }
+
+        (color::encode(&linear, &EncodeOptions::default()), total)
+    }
+
+    /// 多线程渲染场景到线性浮点缓冲区
+    ///
+    /// 结果不做色调映射、gamma校正和截断，自发光表面等大于1的亮度被完整保留，
+    /// 可配合`color::save_hdr`输出供外部合成或色调映射
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    ///
+    /// # Returns
+    /// 返回按行优先排列的线性RGB数据，长度为 宽 * 高 * 3
+    #[allow(clippy::unnecessary_cast)] // 单精度模式下Real为f32
+    pub fn render_hdr(&mut self, world: &dyn Hittable) -> Vec<f32> {
+        self.render_linear(world)
+            .iter()
+            .flat_map(|c| [c.x() as f32, c.y() as f32, c.z() as f32])
+            .collect()
+    }
+
+    /// 计算每个像素在主渲染通道中的采样次数
+    ///
+    /// 未设置`edge_boost`时所有像素均为`samples_per_pixel`。设置后先对每个像素
+    /// 中心发射一条主光线，记录深度和法线，根据与相邻像素的不连续程度得到
+    /// [0,1]的边缘强度，边缘像素的采样次数最多提高到`edge_boost`倍
+    ///
+    /// # Returns
+    /// 返回按行优先排列的每像素采样次数
+    pub fn sample_budget(&mut self, world: &dyn Hittable) -> Vec<usize> {
         self.initialize();
 
         let width = self.image_width as usize;
         let height = self.image_height as usize;
-        let samples_per_pixel = self.samples_per_pixel;
-        let max_depth = self.max_depth;
+        let Some(boost) = self.edge_boost else {
+            return vec![self.samples_per_pixel; width * height];
+        };
 
-        println!("P3\n{} {}\n255", width, height);
+        // 预通道：每个像素一条无抖动的主光线
+        let gbuffer = self.render_parallel(|i, j| {
+            let mut rec = HitRecord::default();
+            world
+                .hit(&self.center_ray(i, j), &Interval::new(self.hit_epsilon, rtweekend::INFINITY), &mut rec)
+                .then_some((rec.t, rec.normal))
+        });
 
-        // 这里一次性创建 Arc<Mutex<>>，所有线程共享
-        let pixels = Arc::new(Mutex::new(vec![0u8; width * height * 3]));
+        let edge_between = |a: &Option<(Real, Vec3)>, b: &Option<(Real, Vec3)>| -> Real {
+            match (a, b) {
+                (None, None) => 0.0,
+                (Some((t0, n0)), Some((t1, n1))) => {
+                    // 相对深度差或法线差异在阈值区间内线性过渡为边缘，
+                    // 曲面上相邻像素的平缓变化不计入
+                    let ramp = |x: Real, lo: Real, hi: Real| ((x - lo) / (hi - lo)).clamp(0.0, 1.0);
+                    let depth_edge = ramp((t0 - t1).abs() / t0.max(*t1), 0.05, 0.25);
+                    let normal_edge = ramp(1.0 - vec3::dot(*n0, *n1), 0.05, 0.3);
+                    depth_edge.max(normal_edge)
+                }
+                _ => 1.0,  // 轮廓：一侧命中物体，另一侧是背景
+            }
+        };
+
+        let mut budget = Vec::with_capacity(width * height);
+        for j in 0..height {
+            for i in 0..width {
+                let here = &gbuffer[j * width + i];
+                let mut edge: Real = 0.0;
+                if i > 0 { edge = edge.max(edge_between(here, &gbuffer[j * width + i - 1])); }
+                if i + 1 < width { edge = edge.max(edge_between(here, &gbuffer[j * width + i + 1])); }
+                if j > 0 { edge = edge.max(edge_between(here, &gbuffer[(j - 1) * width + i])); }
+                if j + 1 < height { edge = edge.max(edge_between(here, &gbuffer[(j + 1) * width + i])); }
 
+                let scale = 1.0 + (boost.max(1.0) - 1.0) * edge;
+                budget.push((self.samples_per_pixel as Real * scale).round() as usize);
+            }
+        }
+        budget
+    }
+
+    /// 长曝光渲染：依次渲染多个场景(如物体处于不同位置的各帧)并求平均
+    ///
+    /// 与运动模糊不同，物体的位置由调用者逐帧给出，而不是在快门时间内插值，
+    /// 适合渲染光轨等效果
+    ///
+    /// # Arguments
+    /// * `scenes` - 每个快门时刻的场景
+    ///
+    /// # Returns
+    /// 返回平均后的RGB8像素数据；没有场景时返回全黑图像
+    pub fn render_long_exposure(&mut self, scenes: &[&dyn Hittable]) -> Vec<u8> {
+        self.initialize();
+
+        let pixel_count = (self.image_width * self.image_height) as usize;
+        let mut exposure = vec![Color::default(); pixel_count];
+        for (k, world) in scenes.iter().enumerate() {
+            // 每帧使用不同的采样序号，避免各帧噪声完全相关
+            let first = k * self.samples_per_pixel;
+            let frame = self.render_parallel(|i, j| self.sample_pixel(i, j, first, self.samples_per_pixel, *world).color);
+            for (acc, c) in exposure.iter_mut().zip(&frame) {
+                *acc += *c;
+            }
+        }
+
+        let scale = 1.0 / scenes.len().max(1) as Real;
+        for c in exposure.iter_mut() {
+            *c *= scale;
+        }
+        color::encode(&exposure, &EncodeOptions::default())
+    }
+
+    /// 多线程渲染带alpha通道的图像，用于与其他图像合成
+    ///
+    /// alpha为主光线采样中命中物体的比例，轮廓处因多次采样而自然抗锯齿；
+    /// 颜色为预乘alpha的结果，即看到背景的采样贡献为0
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    ///
+    /// # Returns
+    /// 返回按行优先排列的RGBA8像素数据，长度为 宽 * 高 * 4
+    pub fn render_rgba(&mut self, world: &dyn Hittable) -> Vec<u8> {
+        self.initialize();
+
+        let width = self.image_width as usize;
+        let budget = self.sample_budget(world);
+        let results = self.render_parallel(|i, j| self.sample_pixel(i, j, 0, budget[j * width + i], world));
+
+        let foreground: Vec<Color> = results.iter().map(|pixel| pixel.foreground).collect();
+        let rgb = color::encode(&foreground, &EncodeOptions::default());
+
+        let mut pixels = Vec::with_capacity(results.len() * 4);
+        for (rgb, pixel) in rgb.chunks_exact(3).zip(&results) {
+            pixels.extend_from_slice(rgb);
+            pixels.push((pixel.coverage.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+        pixels
+    }
+
+    /// 渲染一帧并与已有的线性缓冲区混合，用于渐进式或时域累积预览
+    ///
+    /// 混合后 prior = prior * (1 - blend) + 本帧 * blend。`prior`的长度与当前图像
+    /// 像素数不一致时(首帧或分辨率改变)被重置为本帧结果。每次调用使用不同的
+    /// 采样序号，连续累积时各帧噪声互不相关
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    /// * `prior` - 之前累积的线性颜色缓冲区，混合结果写回其中
+    /// * `blend` - 本帧的权重，0表示保持prior不变，1表示完全使用本帧
+    pub fn accumulate_into(&mut self, world: &dyn Hittable, prior: &mut Vec<Color>, blend: Real) {
+        self.initialize();
+
+        let first = self.temporal_frame * self.samples_per_pixel;
+        self.temporal_frame += 1;
+        let frame = self.render_parallel(|i, j| self.sample_pixel(i, j, first, self.samples_per_pixel, world).color);
+
+        if prior.len() != frame.len() {
+            *prior = frame;
+            return;
+        }
+        for (acc, c) in prior.iter_mut().zip(&frame) {
+            *acc = vec3::lerp(*acc, *c, blend);
+        }
+    }
+
+    /// 在限定时间内渐进渲染场景，超时后返回已收敛的部分结果
+    ///
+    /// 每一轮为所有像素各增加一个采样，直到达到`samples_per_pixel`或超过时限。
+    /// 时限在每个像素开始采样前检查，超时后剩余像素不再采样；每个像素按自己
+    /// 实际得到的采样数归一化，一个采样都没有的像素为黑色
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    /// * `deadline` - 从调用开始计算的渲染时限
+    ///
+    /// # Returns
+    /// 返回RGB8像素数据
+    pub fn render_with_deadline(&mut self, world: &dyn Hittable, deadline: Duration) -> Vec<u8> {
+        let start = Instant::now();
+        self.initialize();
+
+        let pixel_count = (self.image_width * self.image_height) as usize;
+        let mut sums = vec![Color::default(); pixel_count];
+        let mut counts = vec![0usize; pixel_count];
+        for pass in 0..self.samples_per_pixel {
+            if start.elapsed() >= deadline {
+                break;
+            }
+            let samples = self.render_parallel(|i, j| {
+                (start.elapsed() < deadline).then(|| self.sample_pixel(i, j, pass, 1, world).color)
+            });
+            for ((sum, count), sample) in sums.iter_mut().zip(counts.iter_mut()).zip(samples) {
+                if let Some(c) = sample {
+                    *sum += c;
+                    *count += 1;
+                }
+            }
+        }
+
+        let linear: Vec<Color> = sums.iter().zip(&counts).map(|(c, &n)| *c / n.max(1) as Real).collect();
+        color::encode(&linear, &EncodeOptions::default())
+    }
+
+    /// 以累积缓冲区方式渲染场景，并按`checkpoint_interval`定期保存检查点
+    ///
+    /// 每一轮为所有像素各增加一个采样，直到达到`samples_per_pixel`。
+    /// 设置了`checkpoint_path`时，每隔`checkpoint_interval`轮以及渲染结束时
+    /// 将累积状态写入该文件，之后可用`resume_from`继续渲染。
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    ///
+    /// # Returns
+    /// 返回RGB8像素数据，写检查点失败时返回错误
+    pub fn render_checkpointed(&mut self, world: &dyn Hittable) -> io::Result<Vec<u8>> {
+        self.initialize();
+
+        let mut checkpoint = Checkpoint::new(self.image_width as usize, self.image_height as usize);
+        self.accumulate(world, &mut checkpoint)?;
+        Ok(checkpoint.to_rgb8())
+    }
+
+    /// 从检查点文件恢复渲染，继续累积采样直到`samples_per_pixel`
+    ///
+    /// # Arguments
+    /// * `world` - 包含要渲染物体的Hittable对象
+    /// * `path` - 检查点文件路径
+    ///
+    /// # Returns
+    /// 返回RGB8像素数据；文件无法读取或尺寸与相机不一致时返回错误
+    pub fn resume_from(&mut self, world: &dyn Hittable, path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        self.initialize();
+
+        let mut checkpoint = Checkpoint::load(path)?;
+        if checkpoint.width != self.image_width as usize || checkpoint.height != self.image_height as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "checkpoint is {}x{}, camera is {}x{}",
+                    checkpoint.width, checkpoint.height, self.image_width, self.image_height
+                ),
+            ));
+        }
+
+        self.accumulate(world, &mut checkpoint)?;
+        Ok(checkpoint.to_rgb8())
+    }
+
+    /// 逐轮向检查点累积采样，按间隔保存
+    fn accumulate(&self, world: &dyn Hittable, checkpoint: &mut Checkpoint) -> io::Result<()> {
+        let mut passes_since_save = 0;
+        while checkpoint.samples < self.samples_per_pixel {
+            let first = checkpoint.samples;
+            let pass = self.render_parallel(|i, j| self.sample_pixel(i, j, first, 1, world).color);
+            checkpoint.add_pass(&pass);
+            passes_since_save += 1;
+
+            if let Some(path) = &self.checkpoint_path
+                && self.checkpoint_interval > 0
+                && passes_since_save >= self.checkpoint_interval
+            {
+                checkpoint.save(path)?;
+                passes_since_save = 0;
+            }
+        }
+
+        if let Some(path) = &self.checkpoint_path {
+            checkpoint.save(path)?;
+        }
+        Ok(())
+    }
+
+    /// 多线程计算每个像素的值，按`schedule`划分任务
+    ///
+    /// 随机数在每个采样开始前按像素坐标重新设置(见`sample_pixel`)，
+    /// 因此结果与任务划分方式和线程调度顺序都无关
+    ///
+    /// # Arguments
+    /// * `pixel_fn` - 根据像素坐标(i, j)计算像素值的函数
+    ///
+    /// # Returns
+    /// 返回按行优先排列的像素值
+    fn render_parallel<T, F>(&self, pixel_fn: F) -> Vec<T>
+    where
+        T: Clone + Default + Send,
+        F: Fn(usize, usize) -> T + Sync,
+    {
+        let width = self.image_width as usize;
+        let height = self.image_height as usize;
         let thread_count = num_cpus::get();
-        let rows_per_thread = height / thread_count + 1;
 
-        // 克隆一个不可变引用 self 用于多线程
-        let cam_ref = &*self; 
+        let tiles = match self.schedule {
+            RenderSchedule::Bands => {
+                let rows_per_thread = height / thread_count + 1;
+                (0..thread_count)
+                    .map(|k| Tile {
+                        x0: 0,
+                        y0: (k * rows_per_thread).min(height),
+                        x1: width,
+                        y1: ((k + 1) * rows_per_thread).min(height),
+                    })
+                    .collect()
+            }
+            RenderSchedule::Tiles { size } => Self::split_tiles(width, height, size.max(1)),
+        };
 
-        eprintln!("\rScanlines row: {} {}", width,  self.image_height);
+        Self::render_tiles(width, height, &tiles, thread_count, &pixel_fn)
+    }
+
+    /// 把图像划分为 size×size 的图块(边缘图块可能更小)，按行优先排列
+    fn split_tiles(width: usize, height: usize, size: usize) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for y0 in (0..height).step_by(size) {
+            for x0 in (0..width).step_by(size) {
+                tiles.push(Tile {
+                    x0,
+                    y0,
+                    x1: (x0 + size).min(width),
+                    y1: (y0 + size).min(height),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// 多个工作线程从共享计数器领取图块，直到所有图块完成
+    fn render_tiles<T, F>(width: usize, height: usize, tiles: &[Tile], thread_count: usize, pixel_fn: &F) -> Vec<T>
+    where
+        T: Clone + Default + Send,
+        F: Fn(usize, usize) -> T + Sync,
+    {
+        // 这里一次性创建 Arc<Mutex<>>，所有线程共享
+        let pixels = Arc::new(Mutex::new(vec![T::default(); width * height]));
+        let next_tile = AtomicUsize::new(0);
+        let next_tile = &next_tile;
 
         scope(|s| {
-            for thread_idx in 0..thread_count {
+            for _ in 0..thread_count {
                 let pixels = Arc::clone(&pixels);
-                let world = world;
-                let cam = cam_ref;
-
-                let start_row = thread_idx * rows_per_thread;
-                let end_row = ((thread_idx + 1) * rows_per_thread).min(height);
-
-                // s.spawn(move |_| {
-                //     for j in start_row..end_row {
-                //         // eprintln!("\rScanlines remaining: {}", height - j);
-                //         for i in 0..width {
-                //             let mut pixel_color = Color::default();
-                //             for _ in 0..samples_per_pixel {
-                //                 let r = cam.get_ray(i as i32, j as i32);
-                //                 pixel_color += Self::ray_color(&r, max_depth, world);
-                //             }
-                //             let scale = 1.0 / samples_per_pixel as f64;
-                //             pixel_color *= scale;
-                //             let ir = (pixel_color.x().sqrt() * 255.999) as u8;
-                //             let ig = (pixel_color.y().sqrt() * 255.999) as u8;
-                //             let ib = (pixel_color.z().sqrt() * 255.999) as u8;
-
-                //             let offset = (j * width + i) * 3;
-
-                //             // 加锁写共享缓冲区
-                //             let mut pixels_lock = pixels.lock().unwrap();
-                //             pixels_lock[offset] = ir;
-                //             pixels_lock[offset + 1] = ig;
-                //             pixels_lock[offset + 2] = ib;
-                //         }
-                //     }
-                // });
-                s.spawn(move |_| {
-                    // 每个线程独立维护一个局部缓冲区
-                    let mut local_pixels = vec![0u8; (end_row - start_row) * width * 3];
-
-                    for (local_j, j) in (start_row..end_row).enumerate() {
-                        for i in 0..width {
-                            // ... 计算颜色 ...
-                            let mut pixel_color = Color::default();
-                            for _ in 0..samples_per_pixel {
-                                let r = cam.get_ray(i as i32, j as i32);
-                                pixel_color += Self::ray_color(&r, max_depth, world);
-                            }
-                            let scale = 1.0 / samples_per_pixel as f64;
-                            pixel_color *= scale;
-                            let ir = (pixel_color.x().sqrt() * 255.999) as u8;
-                            let ig = (pixel_color.y().sqrt() * 255.999) as u8;
-                            let ib = (pixel_color.z().sqrt() * 255.999) as u8;
-                            let local_offset = (local_j * width + i) * 3;
-                            local_pixels[local_offset] = ir;
-                            local_pixels[local_offset + 1] = ig;
-                            local_pixels[local_offset + 2] = ib;
+
+                s.spawn(move |_| loop {
+                    let tile_idx = next_tile.fetch_add(1, Ordering::Relaxed);
+                    let Some(tile) = tiles.get(tile_idx) else {
+                        break;
+                    };
+
+                    // 每个图块先写入局部缓冲区
+                    let tile_width = tile.x1 - tile.x0;
+                    let mut local_pixels = Vec::with_capacity(tile_width * (tile.y1 - tile.y0));
+                    for j in tile.y0..tile.y1 {
+                        for i in tile.x0..tile.x1 {
+                            local_pixels.push(pixel_fn(i, j));
                         }
                     }
 
-                    // 计算完成后，合并写入共享缓冲区（只锁一次）
+                    // 计算完成后，合并写入共享缓冲区（每个图块只锁一次）
                     let mut pixels_lock = pixels.lock().unwrap();
-                    let global_offset = start_row * width * 3;
-                    pixels_lock[global_offset..global_offset + local_pixels.len()]
-                        .copy_from_slice(&local_pixels);
+                    for (row, j) in (tile.y0..tile.y1).enumerate() {
+                        let global_offset = j * width + tile.x0;
+                        pixels_lock[global_offset..global_offset + tile_width]
+                            .clone_from_slice(&local_pixels[row * tile_width..(row + 1) * tile_width]);
+                    }
                 });
             }
         }).unwrap();
 
-        // 所有线程结束，输出结果
-        let pixels = Arc::try_unwrap(pixels).expect("Arc has other owners");
-        let pixels = pixels.into_inner().unwrap();
+        // 所有线程结束，取出结果
+        let pixels = Arc::try_unwrap(pixels).unwrap_or_else(|_| panic!("Arc has other owners"));
+        pixels.into_inner().unwrap()
+    }
 
-        let stdout = std::io::stdout();
-        let mut out = stdout.lock();
+    /// 对像素(i,j)进行多次采样
+    ///
+    /// 启用超采样时，像素被拆分为 supersample×supersample 个子像素，
+    /// 每个子像素采样 samples 次，结果等价于以更高分辨率渲染后
+    /// 在线性空间中做盒式降采样
+    ///
+    /// 每个采样开始前以(i, j, 采样序号, `seed`)的哈希重置当前线程的随机数状态，
+    /// 光线抖动和材质散射都从该状态取数，所以同一像素同一序号的采样
+    /// 无论由串行、条带还是图块渲染计算都完全相同
+    ///
+    /// # Arguments
+    /// * `i` - 像素列索引
+    /// * `j` - 像素行索引
+    /// * `first` - 第一个采样的序号，分多轮累积时用于区分各轮的随机序列
+    /// * `samples` - 每个子像素的采样次数
+    /// * `world` - 包含物体的Hittable对象
+    ///
+    /// # Returns
+    /// 返回像素的平均颜色、噪声估计和前景覆盖率
+    fn sample_pixel(&self, i: usize, j: usize, first: usize, samples: usize, world: &dyn Hittable) -> PixelSample {
+        // Welford在线算法：逐个样本更新均值和离差平方和
+        let mut mean = Color::default();
+        let mut m2 = Color::default();
+        let mut n = 0usize;
+        let mut foreground = Color::default();
+        let mut hits = 0usize;
+        for sy in 0..self.supersample {
+            for sx in 0..self.supersample {
+                for s in first..first + samples {
+                    let sub = (sy * self.supersample + sx) as u64;
+                    rtweekend::seed_random(rtweekend::hash_seed(&[i as u64, j as u64, sub, s as u64, self.seed]));
+                    let (sample, hit) = self.sample(i, j, sx, sy, world);
+                    if hit {
+                        foreground += sample;
+                        hits += 1;
+                    }
+                    n += 1;
+                    let delta = sample - mean;
+                    mean += delta / n as Real;
+                    m2 += delta * (sample - mean);
+                }
+            }
+        }
 
-        for j in 0..height {
-            for i in 0..width {
-                let offset = (j * width + i) * 3;
-                writeln!(
-                    out,
-                    "{} {} {}",
-                    pixels[offset], pixels[offset + 1], pixels[offset + 2]
-                ).unwrap();
+        let std_error = if n > 1 {
+            let n = n as Real;
+            let variance = m2 / (n - 1.0);
+            (0..3).map(|c| (variance[c] / n).sqrt()).sum::<Real>() / 3.0
+        } else {
+            0.0
+        };
+
+        let scale = 1.0 / n.max(1) as Real;
+        PixelSample {
+            color: mean,
+            std_error,
+            foreground: foreground * scale,
+            coverage: hits as Real * scale,
+        }
+    }
+
+    /// 对子像素(sx, sy)进行一次采样
+    ///
+    /// 结果中的NaN或无穷大分量被置为0并计入统计；
+    /// 设置了`firefly_clamp`时，每个通道的结果被限制在该值以内，
+    /// 以少量偏差换取更少的"萤火虫"亮点
+    ///
+    /// # Returns
+    /// 返回(采样颜色, 主光线是否命中物体)
+    fn sample(&self, i: usize, j: usize, sx: u32, sy: u32, world: &dyn Hittable) -> (Color, bool) {
+        let r = self.get_ray(i as i32, j as i32, sx, sy);
+        stats::count_primary_ray();
+        let (mut sample, hit) = self.trace(&r, self.max_depth, world);
+
+        // 退化的散射方向等会产生NaN/无穷大，置零以免污染整个像素的均值
+        if !(0..3).all(|c| sample[c].is_finite()) {
+            stats::count_discarded_sample();
+            for c in 0..3 {
+                if !sample[c].is_finite() {
+                    sample[c] = 0.0;
+                }
             }
         }
 
-        eprintln!("\nDone.");
-}
+        let sample = match self.firefly_clamp {
+            Some(max) => Color::new(sample.x().min(max), sample.y().min(max), sample.z().min(max)),
+            None => sample,
+        };
+        (sample, hit)
+    }
 
     /// 初始化相机参数
     /// 
@@ -220,8 +808,14 @@ impl Camera {
     /// - 视口大小和位置
     /// - 像素增量向量
     /// - 初始像素位置
+    ///
+    /// 采样次数、最大反弹次数和超采样倍数至少为1，为0时会被修正为1
     fn initialize(&mut self) {
-        self.image_height = (self.image_width as f64 / self.aspect_ratio) as i32;
+        self.samples_per_pixel = self.samples_per_pixel.max(1);
+        self.max_depth = self.max_depth.max(1);
+        self.supersample = self.supersample.max(1);
+
+        self.image_height = (self.image_width as Real / self.aspect_ratio) as i32;
         self.image_height = if self.image_height < 1 { 1 } else { self.image_height };
 
         // self.center = Point3::default();
@@ -235,7 +829,7 @@ impl Camera {
         let h = (theta / 2.0).tan();
         let viewport_height = 2.0 * h * self.focus_dist;
         // let viewport_height = 2.0;
-        let viewport_width = viewport_height * (self.image_width as f64 / self.image_height as f64);
+        let viewport_width = viewport_height * (self.image_width as Real / self.image_height as Real);
 
         // 计算相机坐标系的 u,v,w 单位基向量。
         self.w = vec3::unit_vector(self.lookfrom - self.lookat);
@@ -251,10 +845,12 @@ impl Camera {
         // let viewport_v = Vec3::new(0.0, -viewport_height, 0.0);
 
         // 计算从像素到像素的水平和垂直增量向量。
-        self.pixel_delta_u = viewport_u / self.image_width as f64;
-        self.pixel_delta_v = viewport_v / self.image_height as f64;
+        self.pixel_delta_u = viewport_u / self.image_width as Real;
+        self.pixel_delta_v = viewport_v / self.image_height as Real;
 
         // 计算左上角像素的位置。
+        self.viewport_center = self.center - (self.focus_dist * self.w);
+        self.viewport_half_height = 0.5 * viewport_height;
         let viewport_upper_left = self.center
             - (self.focus_dist * self.w)
             - (0.5 * viewport_u)
@@ -277,35 +873,59 @@ impl Camera {
     /// 
     /// # Returns
     /// 返回计算得到的颜色值，考虑光线反弹和材质散射
-    fn ray_color(r: &Ray, depth: i32, world: &dyn Hittable) -> Color {
+    fn ray_color(&self, r: &Ray, depth: u32, world: &dyn Hittable) -> Color {
+        self.trace(r, depth, world).0
+    }
+
+    /// 计算给定光线的颜色，同时报告光线是否命中了物体
+    ///
+    /// # Returns
+    /// 返回(颜色, 是否命中物体)；未命中时颜色为背景色
+    fn trace(&self, r: &Ray, depth: u32, world: &dyn Hittable) -> (Color, bool) {
         let mut rec = HitRecord::default();  // 创建命中记录
 
         // 如果达到光线反弹次数限制，停止收集光线
-        if depth <= 0 {
-            return Color::default();  // 返回黑色(无光)
+        if depth == 0 {
+            return (Color::default(), false);  // 返回黑色(无光)
         }
-        
+        stats::count_ray();
+
         // 检查光线是否命中场景中的物体
-        if world.hit(r, &Interval::new(0.001, rtweekend::INFINITY), &mut rec) {
-            let mut scattered = Ray::default();  // 散射光线
-            let mut attenuation = Color::default();  // 衰减颜色
-            
-            // 如果物体有材质
-            if let Some(mat) = rec.mat.clone() {
+        if world.hit(r, &Interval::new(self.hit_epsilon, rtweekend::INFINITY), &mut rec) {
+            // 如果物体有材质(设置了覆盖材质时优先使用覆盖材质)
+            if let Some(mat) = self.material_override.clone().or_else(|| rec.mat.clone()) {
+                // 材质自身发出的光
+                let color_from_emission = mat.emitted(r, &rec);
+
                 // 计算材质散射
-                if mat.scatter(r, &rec, &mut attenuation, &mut scattered) {
+                if let Some(srec) = mat.scatter2(r, &rec) {
+                    // 散射光线从沿法线偏移后的起点出发，避免自相交；
+                    // 材质把起点移到别处(如次表面散射的射出点)时直接使用材质给出的起点
+                    let direction = srec.scattered.direction();
+                    let origin = if srec.scattered.origin().to_array() == rec.p.to_array() {
+                        rec.spawn_origin(direction)
+                    } else {
+                        srec.scattered.origin()
+                    };
+                    let scattered = Ray::with_time(origin, direction, srec.scattered.time());
                     // 递归计算散射光线的颜色
-                    return attenuation * Self::ray_color(&scattered, depth - 1, world);
+                    let incoming = self.ray_color(&scattered, depth - 1, world);
+                    return (color_from_emission + srec.attenuation * incoming, true);
                 }
+                return (color_from_emission, true);  // 无散射则只有自发光
             }
-            return Color::default();  // 无散射则返回黑色
+            return (Color::default(), true);  // 无散射则返回黑色
         }
 
-        // 计算天空背景颜色(渐变色)
-        let unit_direction = vec3::unit_vector(r.direction());  // 归一化光线方向
-        let a = 0.5 * (unit_direction.y() + 1.0);  // 计算垂直方向的混合系数
-        // 混合白色和天蓝色，模拟天空效果
-        (1.0 - a) * Color::new(1.0, 1.0, 1.0) + a * Color::new(0.5, 0.7, 1.0)
+        // 未命中任何物体，返回背景颜色
+        stats::count_background_hit();
+        (self.background.value(r), false)
+    }
+
+    /// 生成从相机中心穿过像素(i,j)中心的光线(无抖动、无散景)
+    fn center_ray(&self, i: usize, j: usize) -> Ray {
+        let pixel_center = self.pixel00_loc + i as Real * self.pixel_delta_u + j as Real * self.pixel_delta_v;
+        Ray::with_time(self.center, self.distort(pixel_center) - self.center, self.shutter_open)
     }
 
     /// 生成通过像素(i,j)的光线
@@ -313,12 +933,14 @@ impl Camera {
     /// # Arguments
     /// * `i` - 像素列索引
     /// * `j` - 像素行索引
+    /// * `sx` - 超采样子像素列索引
+    /// * `sy` - 超采样子像素行索引
     /// 
     /// # Returns
     /// 返回从相机中心指向像素(i,j)的光线
-    fn get_ray(&self, i: i32, j: i32) -> Ray {
-        let pixel_center = self.pixel00_loc + i as f64 * self.pixel_delta_u + j as f64 * self.pixel_delta_v;
-        let pixel_sample = pixel_center + self.pixel_sample_square();
+    fn get_ray(&self, i: i32, j: i32, sx: u32, sy: u32) -> Ray {
+        let pixel_center = self.pixel00_loc + i as Real * self.pixel_delta_u + j as Real * self.pixel_delta_v;
+        let pixel_sample = self.distort(pixel_center + self.pixel_sample_square(sx, sy));
 
         let ray_origin = if self.defocus_angle <= 0.0 {
             self.center
@@ -327,21 +949,748 @@ impl Camera {
         };
         let ray_direction = pixel_sample - ray_origin;
 
-        Ray::new(ray_origin, ray_direction)
+        // 快门时间为零时不消耗随机数，保持静态场景的采样序列不变
+        let ray_time = if self.shutter_close > self.shutter_open {
+            rtweekend::random_double_range(self.shutter_open, self.shutter_close)
+        } else {
+            self.shutter_open
+        };
+
+        Ray::with_time(ray_origin, ray_direction, ray_time)
+    }
+
+    /// 对视口上的点施加径向镜头畸变
+    ///
+    /// 以视口中心为原点、视口半高为单位得到归一化半径r，
+    /// 把点沿径向移动到 r' = r(1 + k1 r² + k2 r⁴) 处；两个系数都为0时原样返回
+    ///
+    /// # Arguments
+    /// * `p` - 视口平面上的点
+    ///
+    /// # Returns
+    /// 返回畸变后的视口平面上的点
+    fn distort(&self, p: Point3) -> Point3 {
+        if self.distortion_k1 == 0.0 && self.distortion_k2 == 0.0 {
+            return p;
+        }
+        let offset = p - self.viewport_center;
+        let r2 = offset.squared_length() / (self.viewport_half_height * self.viewport_half_height);
+        let scale = 1.0 + self.distortion_k1 * r2 + self.distortion_k2 * r2 * r2;
+        self.viewport_center + scale * offset
     }
 
     /// 在像素区域内生成随机采样点
     /// 
+    /// 偏移按`filter`的分布抽取：盒式滤波落在子像素内，帐篷和高斯滤波
+    /// 以子像素中心为中心，可以延伸到相邻子像素
+    /// 
+    /// # Arguments
+    /// * `sx` - 超采样子像素列索引
+    /// * `sy` - 超采样子像素行索引
+    /// 
     /// # Returns
-    /// 返回像素区域内的随机偏移向量
-    fn pixel_sample_square(&self) -> Vec3 {
-        let px = -0.5 + rtweekend::random_double();
-        let py = -0.5 + rtweekend::random_double();
+    /// 返回子像素(sx, sy)对应的相对于像素中心的随机偏移向量
+    fn pixel_sample_square(&self, sx: u32, sy: u32) -> Vec3 {
+        let n = self.supersample as Real;
+        let (px, py) = match self.filter {
+            PixelFilter::Box => (
+                (sx as Real + rtweekend::random_double()) / n - 0.5,
+                (sy as Real + rtweekend::random_double()) / n - 0.5,
+            ),
+            filter => {
+                let (fx, fy) = Self::sample_filter(filter);
+                ((sx as Real + 0.5 + fx) / n - 0.5, (sy as Real + 0.5 + fy) / n - 0.5)
+            }
+        };
         px * self.pixel_delta_u + py * self.pixel_delta_v
     }
 
+    /// 按滤波器分布抽取相对于子像素中心的偏移(以子像素边长为单位)
+    fn sample_filter(filter: PixelFilter) -> (Real, Real) {
+        match filter {
+            PixelFilter::Box => (rtweekend::random_double() - 0.5, rtweekend::random_double() - 0.5),
+            PixelFilter::Tent => {
+                // 三角分布的逆CDF
+                let tent = |u: Real| {
+                    let u = 2.0 * u;
+                    if u < 1.0 { u.sqrt() - 1.0 } else { 1.0 - (2.0 - u).sqrt() }
+                };
+                (tent(rtweekend::random_double()), tent(rtweekend::random_double()))
+            }
+            PixelFilter::Gaussian { sigma } => {
+                // Box-Muller变换
+                let radius = sigma * (-2.0 * (1.0 - rtweekend::random_double()).ln()).sqrt();
+                let theta = 2.0 * rtweekend::PI * rtweekend::random_double();
+                (radius * theta.cos(), radius * theta.sin())
+            }
+        }
+    }
+
+    /// 在散景光圈内随机采样光线起点
     fn defocus_disk_sample(&self) -> Point3 {
-        let p = vec3::random_in_unit_disk();
+        let p = match self.bokeh {
+            BokehShape::Polygon { blades, rotation } if blades >= 3 => {
+                vec3::random_in_regular_polygon(blades, rtweekend::degrees_to_radians(rotation))
+            }
+            _ => vec3::random_in_unit_disk(),
+        };
         self.center + p.x() * self.defocus_disk_u + p.y() * self.defocus_disk_v
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable_list::HittableList;
+    use crate::material::Lambertian;
+    use crate::quad::Quad;
+    use crate::sphere::Sphere;
+
+    /// 从(0,0,-1)看向原点、视角90°的小尺寸测试相机
+    fn tiny_camera(width: i32) -> Camera {
+        Camera {
+            image_width: width,
+            samples_per_pixel: 4,
+            max_depth: 4,
+            ..Camera::default()
+        }
+    }
+
+    /// 原点处一个半径0.5的灰色漫反射球，位于测试相机画面中央
+    fn sphere_world() -> HittableList {
+        HittableList::new(Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            0.5,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )))
+    }
+
+    /// RGB8缓冲区中像素(i, j)的颜色
+    fn rgb_at(pixels: &[u8], width: usize, i: usize, j: usize) -> [u8; 3] {
+        let k = 3 * (j * width + i);
+        [pixels[k], pixels[k + 1], pixels[k + 2]]
+    }
+
+    #[test]
+    fn zero_samples_per_pixel_renders_like_one() {
+        let world = sphere_world();
+        let mut zero = Camera { samples_per_pixel: 0, ..tiny_camera(8) };
+        let mut one = Camera { samples_per_pixel: 1, ..tiny_camera(8) };
+
+        let pixels = zero.render_to_buffer(&world);
+        assert_eq!(zero.samples_per_pixel, 1);
+        assert_eq!(pixels.len(), 8 * 8 * 3);
+        assert_eq!(pixels, one.render_to_buffer(&world));
+    }
+
+    #[test]
+    fn zero_max_depth_still_shows_background() {
+        let world = sphere_world();
+        let mut cam = Camera { max_depth: 0, ..tiny_camera(8) };
+
+        let pixels = cam.render_to_buffer(&world);
+        assert_eq!(cam.max_depth, 1);
+        assert_eq!(pixels.len(), 8 * 8 * 3);
+        // 只允许一次求交：看到背景的角落为天空色，命中球体的中心没有后续反弹，为黑色
+        assert_ne!(rgb_at(&pixels, 8, 0, 0), [0, 0, 0]);
+        assert_eq!(rgb_at(&pixels, 8, 4, 4), [0, 0, 0]);
+    }
+
+    #[test]
+    fn noise_estimate_is_zero_on_flat_regions_and_higher_on_edges() {
+        // 白色背景前的黑色球，只允许一次求交：每个采样非黑即白
+        let world = HittableList::new(Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            0.5,
+            Arc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))),
+        )));
+        let mut cam = Camera {
+            samples_per_pixel: 64,
+            max_depth: 1,
+            background: Background::Solid(Color::new(1.0, 1.0, 1.0)),
+            ..tiny_camera(16)
+        };
+        cam.initialize();
+
+        let corner = cam.sample_pixel(0, 0, 0, cam.samples_per_pixel, &world);
+        let center = cam.sample_pixel(8, 8, 0, cam.samples_per_pixel, &world);
+        let silhouette = cam.sample_pixel(12, 8, 0, cam.samples_per_pixel, &world);
+        assert_eq!(corner.std_error, 0.0);
+        assert_eq!(center.std_error, 0.0);
+        assert!(silhouette.std_error > 0.02, "silhouette noise = {}", silhouette.std_error);
+
+        // 整幅图像的噪声：没有物体时为0，有轮廓时大于0
+        let (_, flat) = cam.render_with_stats(&HittableList::default());
+        let (_, edged) = cam.render_with_stats(&world);
+        assert_eq!(flat.noise, 0.0);
+        assert!(edged.noise > flat.noise);
+    }
+
+    #[test]
+    fn precision_sensitive_scene_renders_without_nans() {
+        // 半径1000的地面球加玻璃球和金属球，单精度(--features f32)下最容易出现自相交和NaN
+        use crate::material::{Dielectric, Metal};
+        let mut world = HittableList::default();
+        world.add(Arc::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))));
+        world.add(Arc::new(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, Arc::new(Dielectric::new(1.5)))));
+        world.add(Arc::new(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)))));
+        let mut cam = Camera {
+            image_width: 24,
+            aspect_ratio: 1.5,
+            vfov: 20.0,
+            lookfrom: Point3::new(13.0, 2.0, 3.0),
+            lookat: Point3::new(0.0, 0.0, 0.0),
+            samples_per_pixel: 8,
+            max_depth: 20,
+            ..Camera::default()
+        };
+
+        let linear = cam.render_linear(&world);
+        assert!(linear.iter().all(|c| (0..3).all(|k| c[k].is_finite())));
+        let (_, stats) = cam.render_with_stats(&world);
+        assert_eq!(stats.discarded_samples, 0);
+    }
+
+    /// 白色背景前覆盖世界x≥0半平面的黑色四边形，边缘穿过测试相机画面中央的竖直线
+    fn half_plane_world() -> HittableList {
+        HittableList::new(Arc::new(Quad::new(
+            Point3::new(0.0, -10.0, 0.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            Vec3::new(0.0, 20.0, 0.0),
+            Arc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))),
+        )))
+    }
+
+    #[test]
+    fn supersampled_edge_pixel_is_grey() {
+        let world = half_plane_world();
+        let black_and_white = Camera {
+            samples_per_pixel: 1,
+            max_depth: 1,
+            background: Background::Solid(Color::new(1.0, 1.0, 1.0)),
+            ..tiny_camera(5)
+        };
+
+        // 每像素一个采样：中间一列的像素非黑即白
+        let mut cam = black_and_white.clone();
+        let pixels = cam.render_to_buffer(&world);
+        assert!(matches!(rgb_at(&pixels, 5, 2, 2), [0, 0, 0] | [255, 255, 255]));
+
+        // 2×超采样：边缘穿过中间一列像素的中心，左右各两个子像素，线性平均为0.5
+        let mut cam = Camera { supersample: 2, ..black_and_white };
+        let pixels = cam.render_to_buffer(&world);
+        let edge = rgb_at(&pixels, 5, 2, 2);
+        assert_eq!(edge, color::encode(&[Color::new(0.5, 0.5, 0.5)], &EncodeOptions::default()).as_slice());
+        let sides = [rgb_at(&pixels, 5, 1, 2), rgb_at(&pixels, 5, 3, 2)];
+        assert!(sides.contains(&[0, 0, 0]) && sides.contains(&[255, 255, 255]));
+    }
+
+    #[test]
+    fn resumed_render_matches_single_run() {
+        let world = sphere_world();
+        let path = std::env::temp_dir().join(format!("rtck-{}-camera-resume.bin", std::process::id()));
+
+        // 一次渲染100个采样
+        let mut cam = Camera { samples_per_pixel: 100, ..tiny_camera(6) };
+        let one_shot = cam.render_checkpointed(&world).unwrap();
+
+        // 先渲染50个采样并保存检查点，再从检查点继续到100个
+        let mut cam = Camera {
+            samples_per_pixel: 50,
+            checkpoint_path: Some(path.clone()),
+            ..tiny_camera(6)
+        };
+        let first_half = cam.render_checkpointed(&world).unwrap();
+        cam.samples_per_pixel = 100;
+        let resumed = cam.resume_from(&world, &path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_ne!(first_half, one_shot);
+        assert_eq!(resumed.unwrap(), one_shot);
+    }
+
+    #[test]
+    fn resume_rejects_checkpoint_of_another_size() {
+        let world = sphere_world();
+        let path = std::env::temp_dir().join(format!("rtck-{}-camera-size.bin", std::process::id()));
+        Checkpoint::new(3, 3).save(&path).unwrap();
+
+        let mut cam = tiny_camera(6);
+        let result = cam.resume_from(&world, &path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn firefly_clamp_caps_every_sample() {
+        use crate::material::DiffuseLight;
+        // 亮度为10的光源球旁边放一个漫反射球，采样值从0到10不等
+        let mut world = sphere_world();
+        world.add(Arc::new(Sphere::new(Point3::new(0.6, 0.6, 0.0), 0.3, Arc::new(DiffuseLight::new(Color::new(10.0, 10.0, 10.0))))));
+
+        let max_sample = |cam: &mut Camera| {
+            cam.initialize();
+            let mut max: Real = 0.0;
+            for j in 0..8 {
+                for i in 0..8 {
+                    for s in 0..16u64 {
+                        rtweekend::seed_random(rtweekend::hash_seed(&[i, j, s]));
+                        let (sample, _) = cam.sample(i as usize, j as usize, 0, 0, &world);
+                        max = max.max(sample.x()).max(sample.y()).max(sample.z());
+                    }
+                }
+            }
+            max
+        };
+
+        assert!(max_sample(&mut tiny_camera(8)) > 5.0);
+        assert!(max_sample(&mut Camera { firefly_clamp: Some(1.5), ..tiny_camera(8) }) <= 1.5);
+    }
+
+    #[test]
+    fn polygon_bokeh_origins_stay_inside_the_aperture() {
+        let mut cam = Camera {
+            defocus_angle: 10.0,
+            focus_dist: 2.0,
+            bokeh: BokehShape::Polygon { blades: 6, rotation: 15.0 },
+            ..tiny_camera(4)
+        };
+        cam.initialize();
+        let radius = cam.defocus_disk_u.length();
+        rtweekend::seed_random(819);
+
+        let mut max_offset: Real = 0.0;
+        for _ in 0..5000 {
+            let offset = (cam.defocus_disk_sample() - cam.center).length();
+            max_offset = max_offset.max(offset);
+        }
+        assert!(max_offset <= radius * (1.0 + 1e-6));
+        assert!(max_offset > 0.9 * radius);
+    }
+
+    #[test]
+    fn every_pixel_is_rendered_exactly_once() {
+        for schedule in [RenderSchedule::Bands, RenderSchedule::Tiles { size: 1 }, RenderSchedule::Tiles { size: 7 }, RenderSchedule::Tiles { size: 64 }] {
+            // 宽高都不是图块大小的整数倍，边缘图块较小
+            let mut cam = Camera { image_width: 37, aspect_ratio: 37.0 / 23.0, schedule, ..tiny_camera(37) };
+            cam.initialize();
+            let (width, height) = (cam.image_width as usize, cam.image_height as usize);
+            assert_eq!(height, 23);
+
+            let visits = Mutex::new(vec![0usize; width * height]);
+            let pixels = cam.render_parallel(|i, j| {
+                visits.lock().unwrap()[j * width + i] += 1;
+                (i, j)
+            });
+
+            assert!(visits.into_inner().unwrap().iter().all(|&n| n == 1), "{:?}", schedule);
+            for (k, &(i, j)) in pixels.iter().enumerate() {
+                assert_eq!((i, j), (k % width, k / width));
+            }
+        }
+    }
+
+    /// 远离原点的地面高度，取到使命中点的舍入误差超过默认`hit_epsilon`的量级
+    #[cfg(not(feature = "f32"))]
+    const FAR: Real = 1e14;
+    #[cfg(feature = "f32")]
+    const FAR: Real = 1e6;
+
+    /// 经过(at, at, at)、略微倾斜、法线朝上的大块灰色漫反射地面
+    ///
+    /// 倾斜后命中点三个坐标的舍入误差都会落到平面距离上
+    ///
+    /// # Note
+    /// 直接返回四边形而不放进列表：坐标很大时包围盒的最小厚度会被舍入掉
+    fn far_floor(at: Real) -> Quad {
+        Quad::new(
+            Point3::new(at - 1000.0, at - 500.0, at - 1000.0),
+            Vec3::new(0.0, 1000.0, 2000.0),
+            Vec3::new(2000.0, 0.0, 0.0),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    /// 从地面上方向下发出光线，命中后向上半球散射，统计散射光线在`hit_epsilon`
+    /// 之后又命中地面本身(阴影痤疮)的次数
+    ///
+    /// # Arguments
+    /// * `at` - `far_floor`的位置
+    /// * `offset_origin` - 散射光线是否从`spawn_origin`出发，否则直接从命中点出发
+    fn self_intersections(world: &dyn Hittable, at: Real, hit_epsilon: Real, offset_origin: bool) -> usize {
+        rtweekend::seed_random(822);
+        let ray_t = Interval::new(hit_epsilon, rtweekend::INFINITY);
+        let eye = Point3::new(at, at + 100.0, at);
+        (0..1000)
+            .filter(|_| {
+                let (dx, dz) = (rtweekend::random_double_range(-50.0, 50.0), rtweekend::random_double_range(-50.0, 50.0));
+                let target = Point3::new(at + dx, at + 0.5 * dz, at + dz);
+                let mut rec = HitRecord::default();
+                assert!(world.hit(&Ray::new(eye, vec3::unit_vector(target - eye)), &ray_t, &mut rec));
+                // 与法线夹角不超过60°的散射方向
+                let direction = vec3::unit_vector(rec.normal + 0.5 * vec3::random_in_unit_sphere());
+                let origin = if offset_origin { rec.spawn_origin(direction) } else { rec.p };
+                world.hit(&Ray::new(origin, direction), &ray_t, &mut HitRecord::default())
+            })
+            .count()
+    }
+
+    /// 在`far_floor(at)`上方100处竖直俯视地面、白色背景下只反弹一次的相机
+    fn looking_down_at(at: Real) -> Camera {
+        Camera {
+            lookfrom: Point3::new(at, at + 100.0, at),
+            lookat: Point3::new(at, at, at),
+            vup: Vec3::new(0.0, 0.0, 1.0),
+            max_depth: 2,
+            background: Background::Solid(Color::new(1.0, 1.0, 1.0)),
+            ..tiny_camera(8)
+        }
+    }
+
+    #[test]
+    fn scaled_hit_epsilon_removes_acne_in_large_scenes() {
+        let world = far_floor(FAR);
+        // 默认的epsilon小于命中点坐标的舍入误差，部分散射光线又打回地面
+        assert!(self_intersections(&world, FAR, rtweekend::HIT_EPSILON, false) > 0);
+        // 按场景尺度放大后不再自相交
+        assert_eq!(self_intersections(&world, FAR, 1.0, false), 0);
+
+        // 每个像素都是反照率0.5乘以白色背景
+        let mut cam = Camera { hit_epsilon: 1.0, ..looking_down_at(FAR) };
+        for c in cam.render_linear(&world) {
+            assert!((c - Color::new(0.5, 0.5, 0.5)).length() < 1e-4, "{}", c);
+        }
+    }
+
+    #[test]
+    fn long_exposure_shows_a_moving_sphere_faintly_in_both_places() {
+        // 白色背景前的黑色球，两帧中分别位于左右两侧
+        let black = Arc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0)));
+        let frames = [-0.5, 0.5].map(|x| HittableList::new(Arc::new(Sphere::new(Point3::new(x, 0.0, 0.0), 0.25, black.clone()))));
+        let mut cam = Camera {
+            max_depth: 1,
+            background: Background::Solid(Color::new(1.0, 1.0, 1.0)),
+            ..tiny_camera(16)
+        };
+
+        let single = cam.render_to_buffer(&frames[0]);
+        let pixels = cam.render_long_exposure(&[&frames[0], &frames[1]]);
+        // 只出现在一帧中的位置平均后为线性0.5，gamma编码后约为181
+        let half = (256.0 * (0.5 as Real).sqrt()) as u8;
+        let spots = [rgb_at(&pixels, 16, 4, 8), rgb_at(&pixels, 16, 12, 8)];
+        assert_eq!(spots, [[half; 3]; 2]);
+        assert!([rgb_at(&single, 16, 4, 8), rgb_at(&single, 16, 12, 8)].contains(&[0, 0, 0]));
+        assert_eq!(rgb_at(&pixels, 16, 8, 8), [255, 255, 255]);
+        assert_eq!(rgb_at(&pixels, 16, 0, 0), [255, 255, 255]);
+    }
+
+    #[test]
+    fn edge_boost_gives_silhouette_pixels_more_samples() {
+        let world = sphere_world();
+        let mut cam = tiny_camera(16);
+        assert!(cam.sample_budget(&world).iter().all(|&n| n == cam.samples_per_pixel));
+
+        cam.edge_boost = Some(4.0);
+        let budget = cam.sample_budget(&world);
+        let at = |i: usize, j: usize| budget[j * 16 + i];
+        // 球体内部和背景的采样数不变，中间一行上的轮廓像素(半径约4像素)被提高
+        assert_eq!(at(8, 8), cam.samples_per_pixel);
+        assert_eq!(at(0, 0), cam.samples_per_pixel);
+        let silhouette = (10..15).map(|i| at(i, 8)).max().unwrap();
+        assert!(silhouette > at(8, 8), "silhouette budget = {}", silhouette);
+        assert!(silhouette <= 4 * cam.samples_per_pixel);
+    }
+
+    #[test]
+    fn serial_render_honors_the_edge_boost_budget() {
+        let world = sphere_world();
+        let mut cam = tiny_camera(16);
+        let budget = cam.sample_budget(&world);
+        let flat = color::encode(&cam.render_serial(&world, &budget), &EncodeOptions::default());
+
+        cam.edge_boost = Some(4.0);
+        let budget = cam.sample_budget(&world);
+        let serial = color::encode(&cam.render_serial(&world, &budget), &EncodeOptions::default());
+        // 与多线程渲染使用同一份采样预算，结果逐字节一致；轮廓像素多采样后与未提高时不同
+        assert_eq!(serial, cam.render_to_buffer(&world));
+        assert_ne!(serial, flat);
+    }
+
+    #[test]
+    fn serial_band_and_tile_renders_are_identical() {
+        let world = sphere_world();
+        let mut cam = tiny_camera(16);
+        cam.initialize();
+        let (width, height) = (cam.image_width as usize, cam.image_height as usize);
+        let encode = |linear: &[Color]| color::encode(linear, &EncodeOptions::default());
+
+        let budget = cam.sample_budget(&world);
+        let serial = encode(&cam.render_serial(&world, &budget));
+        // 单线程渲染整幅图像作为一个条带
+        let whole = [Tile { x0: 0, y0: 0, x1: width, y1: height }];
+        let one_band = encode(&Camera::render_tiles(width, height, &whole, 1, &|i, j| {
+            cam.sample_pixel(i, j, 0, cam.samples_per_pixel, &world).color
+        }));
+        let mut banded = Camera { schedule: RenderSchedule::Bands, ..cam.clone() };
+        let bands = banded.render_to_buffer(&world);
+        let mut tiled = Camera { schedule: RenderSchedule::Tiles { size: 4 }, ..cam.clone() };
+        assert_eq!(Camera::split_tiles(width, height, 4).len(), 16);
+        let tiles = tiled.render_to_buffer(&world);
+
+        assert_eq!(serial, one_band);
+        assert_eq!(serial, bands);
+        assert_eq!(serial, tiles);
+    }
+
+    #[test]
+    fn single_sphere_at_depth_two_casts_a_bounded_number_of_rays() {
+        let world = sphere_world();
+        let mut cam = Camera { max_depth: 2, ..tiny_camera(8) };
+        let (_, stats) = cam.render_with_stats(&world);
+
+        let primary = (8 * 8 * cam.samples_per_pixel) as u64;
+        assert_eq!(stats.primary_rays, primary);
+        // 每条主光线最多再反弹一次；命中球体的主光线至少有一条
+        assert!(stats.rays_cast > primary && stats.rays_cast <= 2 * primary, "rays = {}", stats.rays_cast);
+        // 凸的单个球体不会被自身的散射光线再次命中，每条路径都以背景结束
+        assert_eq!(stats.background_hits, primary);
+        assert!(stats.intersection_tests <= stats.rays_cast);
+        let average = stats.average_path_length();
+        assert!(average > 1.0 && average <= 2.0, "average path length = {}", average);
+    }
+
+    #[test]
+    fn positive_k1_pushes_corner_rays_outward() {
+        // 主光线方向与视线的夹角余弦，越小越靠外
+        let cosine_to_axis = |k1: Real, i: usize, j: usize| {
+            let mut cam = Camera { distortion_k1: k1, ..tiny_camera(16) };
+            cam.initialize();
+            let forward = vec3::unit_vector(cam.lookat - cam.lookfrom);
+            vec3::dot(forward, vec3::unit_vector(cam.center_ray(i, j).direction()))
+        };
+
+        for (i, j) in [(0, 0), (15, 0), (0, 15), (15, 15)] {
+            let pinhole = cosine_to_axis(0.0, i, j);
+            assert!(cosine_to_axis(0.3, i, j) < pinhole - 0.01);
+            assert!(cosine_to_axis(-0.3, i, j) > pinhole + 0.01);
+        }
+
+        // 系数为0时视口上的点原样返回，与针孔相机完全一致
+        let mut plain = tiny_camera(16);
+        plain.initialize();
+        assert_eq!(plain.distort(Point3::new(0.3, -0.2, 0.0)).e, [0.3, -0.2, 0.0]);
+    }
+
+    #[test]
+    fn hdr_render_keeps_values_above_one() {
+        use crate::material::DiffuseLight;
+        let world = HittableList::new(Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            0.5,
+            Arc::new(DiffuseLight::new(Color::new(5.0, 5.0, 5.0))),
+        )));
+        let mut cam = Camera { background: Background::Solid(Color::default()), ..tiny_camera(8) };
+
+        let hdr = cam.render_hdr(&world);
+        assert_eq!(hdr.len(), 8 * 8 * 3);
+        let k = 3 * (4 * 8 + 4);
+        for c in &hdr[k..k + 3] {
+            assert!((c - 5.0).abs() < 1e-4, "center = {}", c);
+        }
+        assert_eq!(&hdr[..3], &[0.0; 3]);
+        // 8位输出在同一位置截断为255
+        assert_eq!(rgb_at(&cam.render_to_buffer(&world), 8, 4, 4), [255; 3]);
+    }
+
+    #[test]
+    fn material_override_replaces_the_scene_materials() {
+        use crate::material::Metal;
+        let world = HittableList::new(Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            0.5,
+            Arc::new(Metal::new(Color::new(0.9, 0.1, 0.1), 0.0)),
+        )));
+        let mut cam = Camera {
+            max_depth: 2,
+            background: Background::Solid(Color::new(1.0, 1.0, 1.0)),
+            ..tiny_camera(8)
+        };
+        cam.initialize();
+        let center = |cam: &Camera| cam.sample_pixel(4, 4, 0, cam.samples_per_pixel, &world).color;
+
+        // 红色镜面：反射白色背景
+        assert!((center(&cam) - Color::new(0.9, 0.1, 0.1)).length() < 1e-4);
+
+        // 覆盖为灰色漫反射后，散射方向随机但都射向背景，颜色为灰色
+        cam.material_override = Some(Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))));
+        assert!((center(&cam) - Color::new(0.5, 0.5, 0.5)).length() < 1e-4);
+        let pixels = cam.render_to_buffer(&world);
+        let grey = (256.0 * (0.5 as Real).sqrt()) as u8;
+        assert_eq!(rgb_at(&pixels, 8, 4, 4), [grey; 3]);
+    }
+
+    #[test]
+    fn gaussian_offsets_cluster_nearer_the_center_than_box() {
+        rtweekend::seed_random(839);
+        let n = 20_000;
+        // 落在像素中心1/4范围内的比例，以及偏移的均值
+        let stats = |filter: PixelFilter| {
+            let offsets: Vec<(Real, Real)> = (0..n).map(|_| Camera::sample_filter(filter)).collect();
+            let near = offsets.iter().filter(|(x, y)| x.abs() < 0.25 && y.abs() < 0.25).count() as Real / n as Real;
+            let mean = offsets.iter().fold((0.0, 0.0), |(mx, my), (x, y)| (mx + x, my + y));
+            (near, mean.0 / n as Real, mean.1 / n as Real)
+        };
+
+        let (box_near, bx, by) = stats(PixelFilter::Box);
+        let (gauss_near, gx, gy) = stats(PixelFilter::Gaussian { sigma: 0.2 });
+        // 盒式为均匀分布，约1/4；σ=0.2的高斯约0.62
+        assert!((box_near - 0.25).abs() < 0.02, "box = {}", box_near);
+        assert!(gauss_near > 0.55, "gaussian = {}", gauss_near);
+        for m in [bx, by, gx, gy] {
+            assert!(m.abs() < 0.01, "mean offset = {}", m);
+        }
+    }
+
+    #[test]
+    fn expired_deadline_returns_a_black_image_of_the_right_size() {
+        let world = sphere_world();
+        let mut cam = tiny_camera(8);
+
+        let pixels = cam.render_with_deadline(&world, Duration::ZERO);
+        assert_eq!(pixels.len(), 8 * 8 * 3);
+        assert!(pixels.iter().all(|&c| c == 0));
+
+        // 时间充足时所有采样都完成，与普通渲染只差舍入
+        let full = cam.render_with_deadline(&world, Duration::from_secs(60));
+        let reference = cam.render_to_buffer(&world);
+        for (a, b) in full.iter().zip(&reference) {
+            assert!(a.abs_diff(*b) <= 1);
+        }
+    }
+
+    #[test]
+    fn rgba_alpha_follows_sphere_coverage() {
+        let world = sphere_world();
+        let mut cam = Camera { samples_per_pixel: 16, ..tiny_camera(16) };
+        let pixels = cam.render_rgba(&world);
+        assert_eq!(pixels.len(), 16 * 16 * 4);
+        let alpha = |i: usize, j: usize| pixels[4 * (j * 16 + i) + 3];
+
+        assert_eq!(alpha(8, 8), 255);
+        for (i, j) in [(0, 0), (15, 0), (0, 15), (15, 15)] {
+            assert_eq!(alpha(i, j), 0);
+            // 预乘alpha：透明像素的颜色为黑色
+            assert_eq!(&pixels[4 * (j * 16 + i)..4 * (j * 16 + i) + 3], &[0, 0, 0]);
+        }
+        // 轮廓(半径约4像素)上的像素部分覆盖
+        assert!((10..15).any(|i| (1..255).contains(&alpha(i, 8))));
+    }
+
+    #[test]
+    fn framed_box_projects_inside_the_image() {
+        let bbox = Aabb::from_points(Point3::new(10.0, -2.0, 5.0), Point3::new(14.0, 3.0, 6.0));
+        let mut cam = Camera {
+            aspect_ratio: 16.0 / 9.0,
+            vfov: 40.0,
+            lookfrom: Point3::new(0.0, 0.0, 0.0),
+            lookat: Point3::new(1.0, 0.2, -1.0),
+            ..tiny_camera(32)
+        };
+        cam.frame_scene(&bbox);
+        cam.initialize();
+        assert_eq!(cam.lookat.e, bbox.center().e);
+        let (width, height) = (cam.image_width as Real, cam.image_height as Real);
+
+        // 把包围盒的8个角点沿视线投影到视口平面，换算为像素坐标
+        for k in 0..8 {
+            let corner = Point3::new(
+                if k & 1 == 0 { 10.0 } else { 14.0 },
+                if k & 2 == 0 { -2.0 } else { 3.0 },
+                if k & 4 == 0 { 5.0 } else { 6.0 },
+            );
+            let d = corner - cam.center;
+            let depth = -vec3::dot(d, cam.w);
+            assert!(depth > 0.0, "corner {} is behind the camera", corner);
+            let on_viewport = cam.center + (cam.focus_dist / depth) * d - cam.pixel00_loc;
+            let i = vec3::dot(on_viewport, cam.pixel_delta_u) / cam.pixel_delta_u.squared_length();
+            let j = vec3::dot(on_viewport, cam.pixel_delta_v) / cam.pixel_delta_v.squared_length();
+            assert!((-0.5..width - 0.5).contains(&i), "corner {} at column {}", corner, i);
+            assert!((-0.5..height - 0.5).contains(&j), "corner {} at row {}", corner, j);
+        }
+    }
+
+    /// 一半散射方向为NaN的病态材质，另一半沿法线散射
+    struct Flaky;
+
+    impl Material for Flaky {
+        fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+            let direction = if rtweekend::random_double() < 0.5 {
+                Vec3::new(Real::NAN, Real::NAN, Real::NAN)
+            } else {
+                rec.normal
+            };
+            *attenuation = Color::new(0.5, 0.5, 0.5);
+            *scattered = Ray::with_time(rec.p, direction, r_in.time());
+            true
+        }
+    }
+
+    #[test]
+    fn nan_scatter_directions_do_not_poison_the_pixel() {
+        let world = HittableList::new(Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.5, Arc::new(Flaky))));
+        let mut cam = Camera {
+            samples_per_pixel: 64,
+            max_depth: 2,
+            // 上下同色的渐变：颜色处处为白，但按方向插值，NaN方向得到NaN颜色
+            background: Background::Gradient { bottom: Color::new(1.0, 1.0, 1.0), top: Color::new(1.0, 1.0, 1.0) },
+            ..tiny_camera(8)
+        };
+        cam.initialize();
+
+        // 正常的采样为0.5，NaN采样被置为0，平均值约为0.25
+        let pixel = cam.sample_pixel(4, 4, 0, cam.samples_per_pixel, &world).color;
+        for c in 0..3 {
+            assert!(pixel[c].is_finite());
+            assert!(pixel[c] > 0.1 && pixel[c] < 0.4, "pixel = {}", pixel);
+        }
+
+        let (pixels, stats) = cam.render_with_stats(&world);
+        assert!(stats.discarded_samples > 0);
+        assert!(stats.discarded_samples < stats.primary_rays);
+        assert_ne!(rgb_at(&pixels, 8, 4, 4), [0, 0, 0]);
+    }
+
+    #[test]
+    fn accumulate_blend_zero_keeps_prior_and_one_takes_the_frame() {
+        let world = sphere_world();
+        let reference = tiny_camera(8).render_linear(&world);
+        let black = vec![Color::default(); 8 * 8];
+
+        let mut prior = black.clone();
+        tiny_camera(8).accumulate_into(&world, &mut prior, 0.0);
+        assert!(prior.iter().all(|c| c.e == [0.0; 3]));
+
+        let mut prior = black.clone();
+        tiny_camera(8).accumulate_into(&world, &mut prior, 1.0);
+        assert!(prior.iter().zip(&reference).all(|(a, b)| a.e == b.e));
+
+        // 尺寸不符的缓冲区被重置为本帧
+        let mut prior = vec![Color::default(); 3];
+        tiny_camera(8).accumulate_into(&world, &mut prior, 0.0);
+        assert!(prior.iter().zip(&reference).all(|(a, b)| a.e == b.e) && prior.len() == reference.len());
+    }
+
+    #[test]
+    fn normal_offset_removes_acne_on_a_plane_at_1e5() {
+        let world = far_floor(1e5);
+        // 几乎为0的t下限：直接从命中点出发的光线会再次打中平面，沿法线偏移后不会
+        let tiny = 1e-12;
+        assert!(self_intersections(&world, 1e5, tiny, false) > 0);
+        assert_eq!(self_intersections(&world, 1e5, tiny, true), 0);
+
+        // 渲染时平面仍被命中，且没有自相交造成的暗点
+        let mut cam = Camera { hit_epsilon: tiny, ..looking_down_at(1e5) };
+        for c in cam.render_linear(&world) {
+            assert!((c - Color::new(0.5, 0.5, 0.5)).length() < 1e-4, "{}", c);
+        }
+    }
+}
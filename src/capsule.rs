@@ -0,0 +1,232 @@
+//! 胶囊体模块
+//!
+//! 提供由线段和半径定义的胶囊体(两端为半球的圆柱)
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::onb::Onb;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 胶囊体几何形状
+///
+/// 到线段ab的距离不超过半径的点集
+///
+/// # Fields
+/// - a, b: 线段的两个端点
+/// - radius: 半径
+/// - mat: 材质
+/// - frame: 局部坐标系，w轴为a指向b的方向
+/// - bbox: 包围盒
+pub struct Capsule {
+    a: Point3,
+    b: Point3,
+    radius: Real,
+    mat: Arc<dyn Material + Send + Sync>,
+    frame: Onb,
+    bbox: Aabb,
+}
+
+impl Capsule {
+    /// 创建新的胶囊体
+    ///
+    /// # Arguments
+    /// * `a` - 线段起点(一端半球的球心)
+    /// * `b` - 线段终点(另一端半球的球心)
+    /// * `radius` - 半径
+    /// * `material` - 材质
+    pub fn new(a: Point3, b: Point3, radius: Real, material: Arc<dyn Material + Send + Sync>) -> Self {
+        let rvec = Vec3::new(radius, radius, radius);
+        let bbox = Aabb::surrounding(
+            &Aabb::from_points(a - rvec, a + rvec),
+            &Aabb::from_points(b - rvec, b + rvec),
+        );
+        // 两端点重合时退化为球，任取一个轴向
+        let axis = if (b - a).near_zero() { Vec3::new(0.0, 1.0, 0.0) } else { b - a };
+
+        Self {
+            a,
+            b,
+            radius,
+            mat: material,
+            frame: Onb::new(axis),
+            bbox,
+        }
+    }
+
+    /// 光线与以`center`为球心的端部球面的两个交点参数
+    fn sphere_roots(&self, center: Point3, r: &Ray) -> Option<[Real; 2]> {
+        let d = r.direction();
+        let oc = r.origin() - center;
+        let a = d.squared_length();
+        let half_b = vec3::dot(d, oc);
+        let c = oc.squared_length() - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrtd = discriminant.sqrt();
+        Some([(-half_b - sqrtd) / a, (-half_b + sqrtd) / a])
+    }
+}
+
+impl Hittable for Capsule {
+    /// 实现胶囊体的光线命中检测
+    ///
+    /// 侧面的交点由无限圆柱的二次方程求得，只保留投影落在线段内的解；
+    /// 两端半球的交点由球面方程求得，只保留投影落在线段外侧的解。
+    /// 所有候选交点中取`ray_t`内最近的一个
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let ba = self.b - self.a;
+        let oa = r.origin() - self.a;
+        let d = r.direction();
+
+        let baba = vec3::dot(ba, ba);
+        let bard = vec3::dot(ba, d);
+        let baoa = vec3::dot(ba, oa);
+        // 交点在轴线上的投影(以baba为单位): y(t) = baoa + t * bard
+        let axial = |t: Real| baoa + t * bard;
+
+        let mut closest = ray_t.max;
+        let mut found = false;
+        let mut consider = |t: Real, keep: bool| {
+            if keep && ray_t.surrounds(t) && t < closest {
+                closest = t;
+                found = true;
+            }
+        };
+
+        // 侧面: |oa + t d|² baba - (y(t))² = r² baba
+        let qa = baba * d.squared_length() - bard * bard;
+        let qb = baba * vec3::dot(oa, d) - baoa * bard;
+        let qc = baba * oa.squared_length() - baoa * baoa - self.radius * self.radius * baba;
+        if qa.abs() > 1e-12 {
+            let discriminant = qb * qb - qa * qc;
+            if discriminant >= 0.0 {
+                let sqrtd = discriminant.sqrt();
+                for t in [(-qb - sqrtd) / qa, (-qb + sqrtd) / qa] {
+                    consider(t, (0.0..=baba).contains(&axial(t)));
+                }
+            }
+        }
+
+        // 两端半球(两端点重合时baba为0，a端的球面就是整个物体)
+        if let Some(roots) = self.sphere_roots(self.a, r) {
+            for t in roots {
+                consider(t, axial(t) <= 0.0);
+            }
+        }
+        if let Some(roots) = self.sphere_roots(self.b, r) {
+            for t in roots {
+                consider(t, axial(t) > baba);
+            }
+        }
+
+        if !found {
+            return false;
+        }
+
+        hit_record.t = closest;
+        hit_record.p = r.at(closest);
+
+        // 法线从线段上最近点指向命中点
+        let pa = hit_record.p - self.a;
+        let h = if baba > 0.0 { (vec3::dot(pa, ba) / baba).clamp(0.0, 1.0) } else { 0.0 };
+        let outward_normal = (pa - h * ba) / self.radius;
+
+        // u为绕轴的方位角，v为沿整个胶囊长度(含两端半球)的位置
+        let length = baba.sqrt();
+        let phi = vec3::dot(pa, self.frame.v()).atan2(vec3::dot(pa, self.frame.u()));
+        hit_record.u = (phi + rtweekend::PI) / (2.0 * rtweekend::PI);
+        hit_record.v = ((vec3::dot(pa, self.frame.w()) + self.radius) / (length + 2.0 * self.radius)).clamp(0.0, 1.0);
+        hit_record.tangent = -phi.sin() * self.frame.u() + phi.cos() * self.frame.v();
+
+        hit_record.set_face_normal(r, outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    /// 线段从原点到(0,2,0)、半径0.5的胶囊体
+    fn capsule(b: Point3) -> Capsule {
+        Capsule::new(Point3::default(), b, 0.5, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))
+    }
+
+    fn upright() -> Capsule {
+        capsule(Point3::new(0.0, 2.0, 0.0))
+    }
+
+    /// 命中时检查法线为单位长度且朝向光线来的一侧
+    fn cast(capsule: &Capsule, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        let r = Ray::new(origin, direction);
+        if !capsule.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return None;
+        }
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!(vec3::dot(rec.normal, direction) < 0.0);
+        Some(rec)
+    }
+
+    #[test]
+    fn side_and_hemispherical_ends_are_hit() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        let rec = cast(&upright(), Point3::new(-5.0, 1.0, 0.0), x).unwrap();
+        assert!((rec.t - 4.5).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal + x).length() < 1e-4);
+
+        let rec = cast(&upright(), Point3::new(0.0, 10.0, 0.0), down).unwrap();
+        assert!((rec.t - 7.5).abs() < 1e-4);
+        assert!((rec.normal + down).length() < 1e-4);
+
+        // 偏离轴线0.3处命中上端半球：y = 2 + √(0.25 - 0.09)
+        let rec = cast(&upright(), Point3::new(0.3, 10.0, 0.0), down).unwrap();
+        assert!((rec.t - 7.6).abs() < 1e-4);
+        assert!((rec.normal - Vec3::new(0.6, 0.8, 0.0)).length() < 1e-4);
+
+        // 两端点重合时就是球
+        let ball = capsule(Point3::default());
+        let rec = cast(&ball, Point3::new(-5.0, 0.0, 0.0), x).unwrap();
+        assert!((rec.t - 4.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rays_beside_or_beyond_the_ends_miss() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        assert!(cast(&upright(), Point3::new(-5.0, 1.0, 0.51), x).is_none());
+        assert!(cast(&upright(), Point3::new(-5.0, 2.51, 0.0), x).is_none());
+        assert!(cast(&upright(), Point3::new(-5.0, -0.51, 0.0), x).is_none());
+        // 在圆柱侧面的延长线上，但不在半球内
+        assert!(cast(&upright(), Point3::new(0.45, 10.0, 0.45), Vec3::new(0.0, -1.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn ray_from_inside_hits_the_back_face() {
+        let center = Point3::new(0.0, 1.0, 0.0);
+        let rec = cast(&upright(), center, Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 0.5).abs() < 1e-4);
+        assert!(!rec.front_face);
+        let rec = cast(&upright(), center, Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!((rec.t - 1.5).abs() < 1e-4);
+        assert!(!rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, -1.0, 0.0)).length() < 1e-4);
+    }
+}
@@ -0,0 +1,214 @@
+//! 渲染检查点模块
+//!
+//! 保存和加载线性颜色累积缓冲区，使长时间渲染可以中断后继续
+
+use super::color::{self, Color, EncodeOptions};
+use super::rtweekend::Real;
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// 检查点文件头标识
+const MAGIC: &[u8; 4] = b"RTCK";
+
+/// 文件头长度：标识加宽、高、采样数
+const HEADER_LEN: u64 = 4 + 3 * 8;
+
+/// 每个像素占用的字节数(三个f64)
+const PIXEL_LEN: u64 = 3 * 8;
+
+/// 渲染累积状态
+///
+/// # Fields
+/// - width: 图像宽度
+/// - height: 图像高度
+/// - samples: 已累积的采样轮数
+/// - accumulation: 每个像素的线性颜色累加和(未除以采样数)
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub width: usize,
+    pub height: usize,
+    pub samples: usize,
+    pub accumulation: Vec<Color>,
+}
+
+impl Checkpoint {
+    /// 创建空的累积状态
+    ///
+    /// # Arguments
+    /// * `width` - 图像宽度
+    /// * `height` - 图像高度
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            samples: 0,
+            accumulation: vec![Color::default(); width * height],
+        }
+    }
+
+    /// 累加一轮采样结果(每个像素一个样本)
+    ///
+    /// # Panics
+    /// 如果`pass`的长度与图像像素数不一致会panic
+    pub fn add_pass(&mut self, pass: &[Color]) {
+        assert_eq!(pass.len(), self.accumulation.len());
+        for (acc, c) in self.accumulation.iter_mut().zip(pass) {
+            *acc += *c;
+        }
+        self.samples += 1;
+    }
+
+    /// 将累积结果除以采样数，得到线性颜色缓冲区
+    pub fn to_linear(&self) -> Vec<Color> {
+        let scale = 1.0 / self.samples.max(1) as Real;
+        self.accumulation.iter().map(|c| *c * scale).collect()
+    }
+
+    /// 将累积结果按默认编码参数转换为RGB8像素数据
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        color::encode(&self.to_linear(), &EncodeOptions::default())
+    }
+
+    /// 将累积状态写入文件
+    ///
+    /// 格式(小端)：文件头"RTCK"，宽、高、采样数(各u64)，随后每个像素三个f64
+    #[allow(clippy::unnecessary_cast)] // 单精度模式下Real为f32
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        for n in [self.width, self.height, self.samples] {
+            out.write_all(&(n as u64).to_le_bytes())?;
+        }
+        for c in &self.accumulation {
+            for k in 0..3 {
+                out.write_all(&(c[k] as f64).to_le_bytes())?;
+            }
+        }
+        out.flush()
+    }
+
+    /// 从文件加载累积状态
+    ///
+    /// # Returns
+    /// 文件头不匹配、尺寸与文件长度不符或数据不完整时返回`InvalidData`错误
+    #[allow(clippy::unnecessary_cast)] // 单精度模式下Real为f32
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut input = BufReader::new(file);
+
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a checkpoint file"));
+        }
+
+        let read_u64 = |input: &mut BufReader<File>| -> io::Result<u64> {
+            let mut buf = [0u8; 8];
+            input.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        };
+        let width = read_u64(&mut input)?;
+        let height = read_u64(&mut input)?;
+        let samples = read_u64(&mut input)? as usize;
+
+        // 分配缓冲区前先核对尺寸，损坏的文件头不能导致溢出或巨量内存分配
+        let data_len = width.checked_mul(height).and_then(|n| n.checked_mul(PIXEL_LEN));
+        if data_len.and_then(|n| n.checked_add(HEADER_LEN)) != Some(file_len) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("checkpoint size {}x{} does not match file length {}", width, height, file_len),
+            ));
+        }
+        let (Ok(width), Ok(height)) = (usize::try_from(width), usize::try_from(height)) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "checkpoint too large"));
+        };
+
+        let mut checkpoint = Self::new(width, height);
+        checkpoint.samples = samples;
+        for c in checkpoint.accumulation.iter_mut() {
+            for k in 0..3 {
+                c[k] = f64::from_bits(read_u64(&mut input)?) as Real;
+            }
+        }
+        Ok(checkpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// 测试用的临时文件路径，按进程号和名称区分
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rtck-{}-{}.bin", std::process::id(), name))
+    }
+
+    /// 一个2×1、已累积3轮的检查点
+    fn sample_checkpoint() -> Checkpoint {
+        let mut checkpoint = Checkpoint::new(2, 1);
+        for k in 0..3 {
+            let k = k as Real;
+            checkpoint.add_pass(&[Color::new(0.25 * k, 0.5, 1.0), Color::new(2.0, 0.125 * k, 0.0)]);
+        }
+        checkpoint
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("round-trip");
+        let checkpoint = sample_checkpoint();
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!((loaded.width, loaded.height, loaded.samples), (2, 1, 3));
+        for (a, b) in loaded.accumulation.iter().zip(&checkpoint.accumulation) {
+            assert_eq!(a.to_array(), b.to_array());
+        }
+    }
+
+    #[test]
+    fn load_rejects_truncated_file() {
+        let path = temp_path("truncated");
+        sample_checkpoint().save(&path).unwrap();
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..data.len() - 5]).unwrap();
+        let result = Checkpoint::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn load_rejects_huge_dimensions_without_allocating() {
+        for (width, height) in [(u64::MAX, 2), (1 << 32, 1 << 32), (1 << 20, 1 << 20)] {
+            let path = temp_path(&format!("huge-{}-{}", width, height));
+            let mut data = MAGIC.to_vec();
+            for n in [width, height, 1] {
+                data.extend_from_slice(&n.to_le_bytes());
+            }
+            data.extend_from_slice(&[0; 48]);
+            fs::write(&path, &data).unwrap();
+            let result = Checkpoint::load(&path);
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn load_rejects_wrong_magic() {
+        let path = temp_path("magic");
+        fs::write(&path, b"NOPE").unwrap();
+        let result = Checkpoint::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -2,8 +2,11 @@
 //!
 //! 提供颜色类型定义和颜色空间转换功能
 
-use std::io::Write;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 use super::interval::Interval;
+use super::rtweekend::Real;
 use super::vec3::Vec3;
 
 /// 颜色类型别名，使用Vec3表示RGB颜色
@@ -29,7 +32,7 @@ const INTENSITY: Interval = Interval{ min: 0.0, max: 0.999 };
 /// 
 /// # Note
 /// 对于非正数输入返回0.0
-pub fn linear_to_gamma(linear_component: f64) -> f64 {
+pub fn linear_to_gamma(linear_component: Real) -> Real {
     if linear_component > 0.0 {
         linear_component.sqrt()
     } else {
@@ -37,12 +40,23 @@ pub fn linear_to_gamma(linear_component: f64) -> f64 {
     }
 }
 
+/// gamma颜色空间到线性颜色空间的转换，`linear_to_gamma`的逆变换
+///
+/// # Arguments
+/// * `gamma_component` - gamma空间颜色分量值
+///
+/// # Returns
+/// 返回线性空间颜色分量值
+pub fn gamma_to_linear(gamma_component: Real) -> Real {
+    gamma_component * gamma_component
+}
+
 impl Color {
     /// 将颜色值写入输出流(PPM格式)
     /// 
     /// # Arguments
     /// * `out` - 可写的输出流
-    /// * `samples_per_pixel` - 每个像素的采样次数，用于颜色值归一化
+    /// * `samples_per_pixel` - 每个像素的采样次数，用于颜色值归一化(为0时按1处理)
     /// 
     /// # 处理流程
     /// 1. 根据采样次数归一化颜色值
@@ -58,7 +72,7 @@ impl Color {
         let b = self.z();
 
         // Divide the color by the number of samples.
-        let scale = 1.0 / samples_per_pixel as f64;
+        let scale = 1.0 / samples_per_pixel.max(1) as Real;
         let r = scale * r;
         let g = scale * g;
         let b = scale * b;
@@ -74,4 +88,356 @@ impl Color {
             (256.0 * INTENSITY.clamp(g)) as i32,
             (256.0 * INTENSITY.clamp(b)) as i32)
     }
-}
\ No newline at end of file
+
+    /// 将颜色转换为8位RGB值
+    ///
+    /// # Returns
+    /// 返回gamma校正并量化后的[r, g, b]，颜色值应已按采样次数归一化
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        [
+            (256.0 * INTENSITY.clamp(linear_to_gamma(self.x()))) as u8,
+            (256.0 * INTENSITY.clamp(linear_to_gamma(self.y()))) as u8,
+            (256.0 * INTENSITY.clamp(linear_to_gamma(self.z()))) as u8,
+        ]
+    }
+}
+
+/// 色调映射算子
+///
+/// # Variants
+/// - Clamp: 不做映射，超过1的值在量化时被截断
+/// - Reinhard: c / (1 + c)，把[0, ∞)平滑压缩到[0, 1)
+/// - Aces: ACES电影曲线的Narkowicz拟合，高光过渡更自然
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tonemap {
+    #[default]
+    Clamp,
+    Reinhard,
+    Aces,
+}
+
+impl Tonemap {
+    /// 对单个线性分量应用色调映射
+    pub fn apply(&self, c: Real) -> Real {
+        match self {
+            Tonemap::Clamp => c,
+            Tonemap::Reinhard => c / (1.0 + c),
+            Tonemap::Aces => {
+                let (a, b, c2, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                (c * (a * c + b)) / (c * (c2 * c + d) + e)
+            }
+        }
+    }
+}
+
+/// 线性缓冲区到8位像素的编码参数
+///
+/// # Fields
+/// - exposure: 曝光补偿(档)，颜色先乘以 2^exposure
+/// - tonemap: 色调映射算子
+/// - gamma: 显示gamma，编码时取 c^(1/gamma)
+#[derive(Clone, Copy, Debug)]
+pub struct EncodeOptions {
+    pub exposure: Real,
+    pub tonemap: Tonemap,
+    pub gamma: Real,
+}
+
+impl Default for EncodeOptions {
+    /// 默认参数与`Color::to_rgb8`一致：无曝光补偿、不做色调映射、gamma为2
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            tonemap: Tonemap::Clamp,
+            gamma: 2.0,
+        }
+    }
+}
+
+/// 将线性颜色缓冲区编码为RGB8像素数据
+///
+/// 依次应用曝光 → 色调映射 → gamma → 8位量化，不修改输入缓冲区，
+/// 因此同一次渲染可以用不同参数反复编码
+///
+/// # Arguments
+/// * `buffer` - 按行优先排列的线性颜色(已按采样数归一化)
+/// * `opts` - 编码参数
+///
+/// # Returns
+/// 返回长度为 buffer.len() * 3 的RGB8数据
+pub fn encode(buffer: &[Color], opts: &EncodeOptions) -> Vec<u8> {
+    let scale = (2.0 as Real).powf(opts.exposure);
+    let gamma = |c: Real| {
+        if opts.gamma == 2.0 {
+            linear_to_gamma(c)
+        } else if c > 0.0 {
+            c.powf(1.0 / opts.gamma)
+        } else {
+            0.0
+        }
+    };
+
+    buffer
+        .iter()
+        .flat_map(|c| {
+            [c.x(), c.y(), c.z()].map(|v| (256.0 * INTENSITY.clamp(gamma(opts.tonemap.apply(v * scale)))) as u8)
+        })
+        .collect()
+}
+
+/// 将RGB8像素缓冲区以PPM格式写入输出流
+///
+/// # Arguments
+/// * `out` - 可写的输出流
+/// * `width` - 图像宽度
+/// * `height` - 图像高度
+/// * `pixels` - 按行优先排列的RGB8像素数据
+pub fn write_ppm(out: &mut dyn Write, width: usize, height: usize, pixels: &[u8]) -> std::io::Result<()> {
+    writeln!(out, "P3\n{} {}\n255", width, height)?;
+    for rgb in pixels.chunks_exact(3) {
+        writeln!(out, "{} {} {}", rgb[0], rgb[1], rgb[2])?;
+    }
+    Ok(())
+}
+
+/// 将线性浮点RGB缓冲区保存为Radiance HDR(RGBE)文件
+///
+/// 像素值不做色调映射和gamma校正，保留大于1的亮度；宽度在[8, 32767]内时扫描线以
+/// 新式游程编码写出，其它宽度格式不支持游程编码，以未压缩形式写出
+///
+/// # Arguments
+/// * `path` - 输出文件路径
+/// * `buffer` - 按行优先排列的线性RGB数据，长度为 宽 * 高 * 3
+/// * `width` - 图像宽度
+/// * `height` - 图像高度
+///
+/// # Returns
+/// 返回写入是否成功；缓冲区长度与尺寸不符时返回InvalidInput错误
+pub fn save_hdr(path: impl AsRef<Path>, buffer: &[f32], width: usize, height: usize) -> io::Result<()> {
+    if buffer.len() != width * height * 3 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("buffer has {} values, expected {}x{}x3", buffer.len(), width, height),
+        ));
+    }
+
+    let mut out = BufWriter::new(File::create(path)?);
+    write!(out, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", height, width)?;
+    if width == 0 {
+        return out.flush();
+    }
+    let rle = (8..=0x7fff).contains(&width);
+    let mut line = Vec::with_capacity(width * 4 + 4);
+    for row in buffer.chunks_exact(width * 3) {
+        let pixels: Vec<[u8; 4]> = row.chunks_exact(3).map(|rgb| to_rgbe(rgb[0], rgb[1], rgb[2])).collect();
+        line.clear();
+        if rle {
+            // 扫描线标记：2, 2, 宽度高字节, 宽度低字节，之后四个分量依次编码
+            line.extend([2, 2, (width >> 8) as u8, (width & 0xff) as u8]);
+            for component in 0..4 {
+                let data: Vec<u8> = pixels.iter().map(|p| p[component]).collect();
+                encode_rle(&mut line, &data);
+            }
+        } else {
+            line.extend(pixels.iter().flatten());
+        }
+        out.write_all(&line)?;
+    }
+    out.flush()
+}
+
+/// 以新式游程编码写出一条扫描线的一个分量
+///
+/// 3个及以上相同字节的游程写为(128 + 长度, 值)，每段最长127；
+/// 其余字节原样写出，前缀为个数，每段最多128个
+fn encode_rle(out: &mut Vec<u8>, data: &[u8]) {
+    let starts_run = |i: usize| i + 2 < data.len() && data[i] == data[i + 1] && data[i] == data[i + 2];
+    let mut i = 0;
+    while i < data.len() {
+        if starts_run(i) {
+            let run = data[i..].iter().take(127).take_while(|&&b| b == data[i]).count();
+            out.extend([128 + run as u8, data[i]]);
+            i += run;
+            continue;
+        }
+        // 原样写出，直到下一个游程开始
+        let start = i;
+        while i < data.len() && i - start < 128 && !starts_run(i) {
+            i += 1;
+        }
+        out.push((i - start) as u8);
+        out.extend_from_slice(&data[start..i]);
+    }
+}
+
+/// 将线性RGB转换为RGBE共享指数编码
+///
+/// 三个通道共用最大分量的二进制指数，尾数各占8位；非正或极小的值编码为全0
+fn to_rgbe(r: f32, g: f32, b: f32) -> [u8; 4] {
+    let v = r.max(g).max(b);
+    if v.is_nan() || v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // v = m * 2^e，其中m∈[0.5, 1)
+    let e = v.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f32.powi(e);
+    let mantissa = |c: f32| (c.max(0.0) * scale).min(255.0) as u8;
+    [mantissa(r), mantissa(g), mantissa(b), (e + 128).clamp(0, 255) as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_color_treats_zero_samples_as_one() {
+        let c = Color::new(0.25, 0.25, 0.25);
+        let (mut zero, mut one) = (Vec::new(), Vec::new());
+        c.write_color(&mut zero, 0).unwrap();
+        c.write_color(&mut one, 1).unwrap();
+        assert_eq!(zero, one);
+        assert_eq!(String::from_utf8(zero).unwrap(), "128 128 128\n");
+    }
+
+    #[test]
+    fn reencoding_with_another_gamma_leaves_the_linear_buffer_untouched() {
+        let buffer = vec![Color::new(0.0, 0.1, 0.2), Color::new(0.25, 0.5, 0.75), Color::new(1.0, 2.0, -1.0)];
+        let original = buffer.clone();
+
+        let default = encode(&buffer, &EncodeOptions::default());
+        let brighter = encode(&buffer, &EncodeOptions { gamma: 2.4, ..EncodeOptions::default() });
+        assert_eq!(default.len(), buffer.len() * 3);
+        assert_ne!(default, brighter);
+        // 黑色和超出范围的值不受gamma影响，中间调在更高的gamma下更亮
+        assert_eq!(&default[..1], &brighter[..1]);
+        assert!(brighter[4] > default[4]);
+        assert_eq!(&default[6..], &brighter[6..]);
+
+        for (a, b) in buffer.iter().zip(&original) {
+            assert_eq!(a.e, b.e);
+        }
+        // 再次以默认参数编码得到相同结果，且与逐像素的to_rgb8一致
+        assert_eq!(encode(&buffer, &EncodeOptions::default()), default);
+        assert_eq!(default, buffer.iter().flat_map(|c| c.to_rgb8()).collect::<Vec<u8>>());
+    }
+
+    /// 测试用的临时文件路径，按进程号和名称区分
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rthdr-{}-{}.hdr", std::process::id(), name))
+    }
+
+    /// 读回`save_hdr`写出的文件，返回(宽, 高, 各像素的RGBE)
+    ///
+    /// 按Radiance格式解析：扫描线以2, 2开头时为新式游程编码，否则为未压缩的RGBE
+    fn read_hdr(path: &Path) -> (usize, usize, Vec<[u8; 4]>) {
+        let bytes = std::fs::read(path).unwrap();
+        let header_end = bytes.windows(2).position(|w| w == b"\n\n").unwrap() + 2;
+        let header = std::str::from_utf8(&bytes[..header_end]).unwrap();
+        assert!(header.starts_with("#?RADIANCE\n"));
+        assert!(header.contains("FORMAT=32-bit_rle_rgbe\n"));
+
+        let line_end = header_end + bytes[header_end..].iter().position(|&b| b == b'\n').unwrap();
+        let resolution = std::str::from_utf8(&bytes[header_end..line_end]).unwrap();
+        let fields: Vec<&str> = resolution.split(' ').collect();
+        assert_eq!((fields[0], fields[2]), ("-Y", "+X"));
+        let (height, width): (usize, usize) = (fields[1].parse().unwrap(), fields[3].parse().unwrap());
+
+        let mut data = bytes[line_end + 1..].iter().copied();
+        let mut pixels = Vec::with_capacity(width * height);
+        for _ in 0..height {
+            let head: Vec<u8> = data.by_ref().take(4).collect();
+            if head[..2] != [2, 2] {
+                pixels.push([head[0], head[1], head[2], head[3]]);
+                pixels.extend((1..width).map(|_| [(); 4].map(|_| data.next().unwrap())));
+                continue;
+            }
+            assert_eq!(((head[2] as usize) << 8) | head[3] as usize, width);
+            let components: Vec<Vec<u8>> = (0..4)
+                .map(|_| {
+                    let mut values = Vec::with_capacity(width);
+                    while values.len() < width {
+                        let count = data.next().unwrap() as usize;
+                        if count > 128 {
+                            let value = data.next().unwrap();
+                            values.extend(std::iter::repeat_n(value, count - 128));
+                        } else {
+                            assert!(count > 0, "zero-length literal run");
+                            values.extend(data.by_ref().take(count));
+                        }
+                    }
+                    assert_eq!(values.len(), width, "runs overflow the scanline");
+                    values
+                })
+                .collect();
+            pixels.extend((0..width).map(|x| [0, 1, 2, 3].map(|c| components[c][x])));
+        }
+        assert!(data.next().is_none(), "trailing bytes after the last scanline");
+        (width, height, pixels)
+    }
+
+    #[test]
+    fn to_rgbe_shares_the_largest_exponent() {
+        assert_eq!(to_rgbe(1.0, 1.0, 1.0), [128, 128, 128, 129]);
+        assert_eq!(to_rgbe(0.5, 0.25, 0.0), [128, 64, 0, 128]);
+        assert_eq!(to_rgbe(1000.0, 0.0, 0.0), [250, 0, 0, 138]);
+        // 负值分量截断为0，全部非正、极小或NaN时整体为0
+        assert_eq!(to_rgbe(2.0, -1.0, 0.5), [128, 0, 32, 130]);
+        assert_eq!(to_rgbe(0.0, 0.0, 0.0), [0; 4]);
+        assert_eq!(to_rgbe(-1.0, -2.0, -3.0), [0; 4]);
+        assert_eq!(to_rgbe(1e-40, 0.0, 0.0), [0; 4]);
+        assert_eq!(to_rgbe(f32::NAN, f32::NAN, f32::NAN), [0; 4]);
+
+        // 解码 (m + 0.5) / 256 · 2^(e - 128) 与原值的相对误差不超过最大分量的1/256
+        for v in [0.001f32, 0.3, 1.7, 42.0, 65504.0] {
+            let [m, _, _, e] = to_rgbe(v, 0.0, 0.0);
+            let decoded = (m as f32 + 0.5) / 256.0 * 2f32.powi(e as i32 - 128);
+            assert!((decoded - v).abs() <= v / 256.0, "{v} decoded as {decoded}");
+        }
+    }
+
+    #[test]
+    fn save_hdr_run_length_encodes_scanlines() {
+        let (width, height) = (300, 3);
+        // 第一行是长游程(超过一段最长127)，第二行没有重复，第三行短游程与单个像素交替
+        let buffer: Vec<f32> = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = ((i % width) as f32, i / width);
+                match y {
+                    0 => [2.5, 0.5, 0.0],
+                    1 => [x / 10.0, 1.0 + x, 0.25],
+                    _ => [if (i / 3) % 2 == 0 { 0.75 } else { 0.1 * x }, 0.0, 0.0],
+                }
+            })
+            .collect();
+        let path = temp_path("rle");
+        save_hdr(&path, &buffer, width, height).unwrap();
+        let size = std::fs::metadata(&path).unwrap().len();
+        let (w, h, pixels) = read_hdr(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((w, h), (width, height));
+        let expected: Vec<[u8; 4]> = buffer.chunks_exact(3).map(|rgb| to_rgbe(rgb[0], rgb[1], rgb[2])).collect();
+        assert_eq!(pixels, expected);
+        // 第一行的四个分量各压缩为3段游程，整个文件明显小于未压缩的大小
+        assert!(size < (width * height * 4) as u64 * 3 / 4, "{size} bytes");
+    }
+
+    #[test]
+    fn save_hdr_writes_narrow_images_flat() {
+        let buffer = [0.5, 1.0, 2.0, 0.0, 0.0, 0.0, 8.0, 8.0, 8.0];
+        let path = temp_path("flat");
+        save_hdr(&path, &buffer, 1, 3).unwrap();
+        let (w, h, pixels) = read_hdr(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((w, h), (1, 3));
+        assert_eq!(pixels, vec![to_rgbe(0.5, 1.0, 2.0), [0; 4], to_rgbe(8.0, 8.0, 8.0)]);
+    }
+
+    #[test]
+    fn save_hdr_rejects_mismatched_buffers() {
+        let path = temp_path("mismatch");
+        let err = save_hdr(&path, &[1.0; 5], 1, 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,295 @@
+//! 圆锥模块
+//!
+//! 提供有限高度圆锥几何形状的实现，可选底面封盖，也可截去顶端得到圆台(灯罩、漏斗)
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::onb::Onb;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 圆锥几何形状
+///
+/// 圆锥由顶点沿轴线方向张开，侧面上任一点到顶点的向量与轴线夹角为`half_angle`
+///
+/// # Fields
+/// - apex: 顶点位置
+/// - axis: 轴线单位方向(从顶点指向底面)
+/// - half_angle: 半顶角(弧度)
+/// - height: 沿轴线的高度
+/// - min_height: 截去的顶端高度，0表示完整圆锥
+/// - capped: 是否封闭底面
+/// - mat: 圆锥材质
+pub struct Cone {
+    apex: Point3,
+    axis: Vec3,
+    half_angle: Real,
+    height: Real,
+    min_height: Real,
+    capped: bool,
+    mat: Arc<dyn Material + Send + Sync>,
+    frame: Onb,
+    bbox: Aabb,
+}
+
+impl Cone {
+    /// 创建新的圆锥
+    ///
+    /// # Arguments
+    /// * `apex` - 顶点位置
+    /// * `axis` - 轴线方向(从顶点指向底面，无需归一化)
+    /// * `half_angle` - 半顶角(角度制，应在(0, 90)之间)
+    /// * `height` - 沿轴线的高度
+    /// * `capped` - 是否封闭底面
+    /// * `material` - 圆锥材质
+    pub fn new(
+        apex: Point3,
+        axis: Vec3,
+        half_angle: Real,
+        height: Real,
+        capped: bool,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        let axis = vec3::unit_vector(axis);
+        let half_angle = rtweekend::degrees_to_radians(half_angle);
+
+        let mut cone = Self {
+            apex,
+            axis,
+            half_angle,
+            height,
+            min_height: 0.0,
+            capped,
+            mat: material,
+            frame: Onb::new(axis),
+            bbox: Aabb::default(),
+        };
+        cone.bbox = cone.compute_bbox();
+        cone
+    }
+
+    /// 截去顶端，得到从`min_height`到`height`的圆台
+    ///
+    /// 顶端截面保持敞开，配合`capped = false`可得到两端都敞开的灯罩或漏斗
+    ///
+    /// # Arguments
+    /// * `min_height` - 截面到顶点的轴向距离(限制在[0, height)内，等于height时圆台没有高度，v坐标无定义)
+    pub fn truncated(mut self, min_height: Real) -> Self {
+        self.min_height = min_height.clamp(0.0, self.height * (1.0 - 1e-6));
+        self.bbox = self.compute_bbox();
+        self
+    }
+
+    /// 包围盒：顶端截面圆盘(或顶点)与底面圆盘的包围盒的并集
+    fn compute_bbox(&self) -> Aabb {
+        let disk_box = |h: Real| {
+            let center = self.apex + h * self.axis;
+            let radius = h * self.half_angle.tan();
+            let extent = Vec3::new(
+                radius * (1.0 - self.axis.x() * self.axis.x()).max(0.0).sqrt(),
+                radius * (1.0 - self.axis.y() * self.axis.y()).max(0.0).sqrt(),
+                radius * (1.0 - self.axis.z() * self.axis.z()).max(0.0).sqrt(),
+            );
+            Aabb::from_points(center - extent, center + extent)
+        };
+        Aabb::surrounding(&disk_box(self.min_height), &disk_box(self.height))
+    }
+
+    /// 计算点绕轴线的方位角
+    ///
+    /// # Returns
+    /// 返回(映射到[0,1]的方位角参数u, 沿u增大方向的单位切向量)
+    fn azimuth(&self, p: Point3) -> (Real, Vec3) {
+        let d = p - self.apex;
+        let phi = vec3::dot(d, self.frame.v()).atan2(vec3::dot(d, self.frame.u()));
+        let tangent = -phi.sin() * self.frame.u() + phi.cos() * self.frame.v();
+        ((phi + rtweekend::PI) / (2.0 * rtweekend::PI), tangent)
+    }
+}
+
+impl Hittable for Cone {
+    /// 实现圆锥的光线命中检测
+    ///
+    /// 侧面满足隐式方程 (v·A)² = cos²θ |v|²，其中 v = P - apex，A为轴线方向；
+    /// 代入光线方程得到关于t的二次方程，再保留 min_height ≤ v·A ≤ height 的解
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let cos2 = self.half_angle.cos().powi(2);
+        let d = r.direction();
+        let co = r.origin() - self.apex;
+
+        let d_a = vec3::dot(d, self.axis);
+        let co_a = vec3::dot(co, self.axis);
+
+        // 二次方程系数: a t² + 2 b t + c = 0
+        let a = d_a * d_a - cos2 * d.squared_length();
+        let b = d_a * co_a - cos2 * vec3::dot(d, co);
+        let c = co_a * co_a - cos2 * co.squared_length();
+
+        let mut closest = ray_t.max;
+        let mut hit_side = false;
+
+        // 侧面
+        let mut side_roots = [Real::NAN; 2];
+        if a.abs() > 1e-12 {
+            let discriminant = b * b - a * c;
+            if discriminant >= 0.0 {
+                let sqrtd = discriminant.sqrt();
+                side_roots = [(-b - sqrtd) / a, (-b + sqrtd) / a];
+            }
+        } else if b.abs() > 1e-12 {
+            // 光线平行于某条母线，方程退化为一次
+            side_roots[0] = -c / (2.0 * b);
+        }
+        for t in side_roots {
+            if !ray_t.surrounds(t) || t >= closest {
+                continue;
+            }
+            let h = vec3::dot(r.at(t) - self.apex, self.axis);
+            if (self.min_height..=self.height).contains(&h) {
+                closest = t;
+                hit_side = true;
+            }
+        }
+
+        // 底面封盖
+        let mut hit_cap = false;
+        if self.capped && d_a.abs() > 1e-12 {
+            let t = (self.height - co_a) / d_a;
+            if ray_t.surrounds(t) && t < closest {
+                let base_center = self.apex + self.height * self.axis;
+                let base_radius = self.height * self.half_angle.tan();
+                if (r.at(t) - base_center).squared_length() <= base_radius * base_radius {
+                    closest = t;
+                    hit_cap = true;
+                }
+            }
+        }
+
+        if !hit_side && !hit_cap {
+            return false;
+        }
+
+        hit_record.t = closest;
+        hit_record.p = r.at(closest);
+        let v = hit_record.p - self.apex;
+        let h = vec3::dot(v, self.axis);
+
+        let outward_normal = if hit_cap {
+            hit_record.v = 1.0;
+            self.axis
+        } else {
+            hit_record.v = (h - self.min_height) / (self.height - self.min_height);
+            // 外法线 ∝ cos²θ v - (v·A) A，与母线垂直并背离轴线
+            vec3::unit_vector(cos2 * v - h * self.axis)
+        };
+        (hit_record.u, hit_record.tangent) = self.azimuth(hit_record.p);
+        hit_record.set_face_normal(r, outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::color::Color;
+
+    /// 顶点在(0,1,0)、开口向下、半顶角45°、高为1的圆锥，底面为y=0上半径1的圆盘
+    fn cone(capped: bool) -> Cone {
+        Cone::new(
+            Point3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            45.0,
+            1.0,
+            capped,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    fn cast(cone: &Cone, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        cone.hit(&Ray::new(origin, direction), &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec)
+            .then_some(rec)
+    }
+
+    #[test]
+    fn ray_across_the_axis_hits_the_lateral_surface() {
+        let rec = cast(&cone(true), Point3::new(-2.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 1.5).abs() < 1e-4);
+        assert!(rec.front_face);
+        // 法线与母线垂直：背离轴线并朝上
+        let expected = vec3::unit_vector(Vec3::new(-1.0, 1.0, 0.0));
+        assert!((rec.normal - expected).length() < 1e-4);
+    }
+
+    #[test]
+    fn ray_down_the_axis_hits_the_apex() {
+        let rec = cast(&cone(true), Point3::new(0.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((rec.p - Point3::new(0.0, 1.0, 0.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn rays_beside_or_above_the_apex_miss() {
+        let cone = cone(true);
+        // 经过顶点上方：隐式方程在顶点另一侧的镜像锥面上有解，但不在高度范围内
+        assert!(cast(&cone, Point3::new(-2.0, 1.5, 0.0), Vec3::new(1.0, 0.0, 0.0)).is_none());
+        // 从侧面擦过：该高度处的截面半径只有0.5
+        assert!(cast(&cone, Point3::new(-2.0, 0.5, 0.6), Vec3::new(1.0, 0.0, 0.0)).is_none());
+        // 从底面下方经过
+        assert!(cast(&cone, Point3::new(-2.0, -0.1, 0.0), Vec3::new(1.0, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn base_cap_is_hit_only_when_capped() {
+        let origin = Point3::new(0.2, -1.0, 0.0);
+        let up = Vec3::new(0.0, 1.0, 0.0);
+
+        let rec = cast(&cone(true), origin, up).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, -1.0, 0.0)).near_zero());
+
+        // 不封底时穿过底面，从内部命中截面半径为0.2处的侧面
+        let rec = cast(&cone(false), origin, up).unwrap();
+        assert!((rec.t - 1.8).abs() < 1e-4);
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn frustum_has_no_surface_above_min_height_and_a_smaller_box() {
+        let frustum = cone(true).truncated(0.5);
+        // 截面在y=0.5处，半径0.5；y=0.75处原本有侧面
+        assert!(cast(&frustum, Point3::new(-2.0, 0.75, 0.0), Vec3::new(1.0, 0.0, 0.0)).is_none());
+        let rec = cast(&frustum, Point3::new(-2.0, 0.25, 0.0), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 1.25).abs() < 1e-4);
+        assert!((0.0..=1.0).contains(&rec.v));
+        // 顶端敞开：沿轴线向下穿过截面，从内部命中底面
+        let rec = cast(&frustum, Point3::new(0.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+
+        let full = cone(true).bounding_box();
+        let bbox = frustum.bounding_box();
+        assert!((full.y.max - 1.0).abs() < 1e-3 && (bbox.y.max - 0.5).abs() < 1e-3);
+        assert!((bbox.y.min - full.y.min).abs() < 1e-6);
+        assert!((bbox.x.max - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn truncating_at_full_height_keeps_uvs_finite() {
+        let sliver = cone(false).truncated(1.0);
+        let rec = cast(&sliver, Point3::new(-2.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!(rec.u.is_finite() && rec.v.is_finite(), "uv = ({}, {})", rec.u, rec.v);
+        assert!((rec.t - 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,106 @@
+//! 均匀介质模块
+//!
+//! 提供密度恒定的参与介质(烟、雾)，以一个封闭物体作为边界
+
+use super::vec3::Vec3;
+use super::ray::Ray;
+use super::color::Color;
+use super::material::{Isotropic, Material};
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::texture::Texture;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 密度恒定的参与介质
+///
+/// 光线在介质中传播距离d后发生散射的概率为 1 - exp(-density * d)，
+/// 散射方向由相函数材质(通常为各向同性材质)决定
+///
+/// # Fields
+/// - boundary: 介质的边界，必须是封闭的凸物体
+/// - neg_inv_density: -1/density
+/// - phase_function: 相函数材质
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable>,
+    neg_inv_density: Real,
+    phase_function: Arc<dyn Material + Send + Sync>,
+}
+
+impl ConstantMedium {
+    /// 创建纯色的均匀介质
+    ///
+    /// # Arguments
+    /// * `boundary` - 介质边界
+    /// * `density` - 介质密度
+    /// * `albedo` - 散射反照率
+    pub fn new(boundary: Arc<dyn Hittable>, density: Real, albedo: Color) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function: Arc::new(Isotropic::new(albedo)),
+        }
+    }
+
+    /// 使用纹理创建均匀介质
+    ///
+    /// # Arguments
+    /// * `boundary` - 介质边界
+    /// * `density` - 介质密度
+    /// * `texture` - 散射反照率纹理
+    pub fn from_texture(boundary: Arc<dyn Hittable>, density: Real, texture: Arc<dyn Texture>) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function: Arc::new(Isotropic::from_texture(texture)),
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    /// 实现介质的光线命中检测
+    ///
+    /// 先求光线进出边界的两个交点，再按指数分布随机抽取散射距离，
+    /// 散射点落在边界内时视为命中。命中点的法线和正反面没有意义，取任意值
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let mut rec1 = HitRecord::default();
+        let mut rec2 = HitRecord::default();
+
+        if !self.boundary.hit(r, &Interval::new(-rtweekend::INFINITY, rtweekend::INFINITY), &mut rec1) {
+            return false;
+        }
+        if !self.boundary.hit(r, &Interval::new(rec1.t + 0.0001, rtweekend::INFINITY), &mut rec2) {
+            return false;
+        }
+
+        rec1.t = rec1.t.max(ray_t.min);
+        rec2.t = rec2.t.min(ray_t.max);
+        if rec1.t >= rec2.t {
+            return false;
+        }
+        rec1.t = rec1.t.max(0.0);
+
+        let ray_length = r.direction().length();
+        let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
+        let hit_distance = self.neg_inv_density * rtweekend::random_double().ln();
+        if hit_distance > distance_inside_boundary {
+            return false;
+        }
+
+        hit_record.t = rec1.t + hit_distance / ray_length;
+        hit_record.p = r.at(hit_record.t);
+        hit_record.normal = Vec3::new(1.0, 0.0, 0.0); // 任意值
+        hit_record.front_face = true; // 任意值
+        hit_record.u = 0.0;
+        hit_record.v = 0.0;
+        hit_record.tangent = Vec3::default();
+        hit_record.mat = Some(Arc::clone(&self.phase_function));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
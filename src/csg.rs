@@ -0,0 +1,363 @@
+//! 构造实体几何(CSG)模块
+//!
+//! 提供两个封闭物体的并集、交集和差集。沿光线依次求出两个物体的所有边界交点，
+//! 根据光线在各物体内外的状态判断组合实体的边界位置。
+//! 另外提供把封闭物体挖空为薄壳的包装
+
+use super::ray::Ray;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::instance::Instance;
+use super::transform::Transform;
+use super::vec3::Vec3;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 沿一条光线最多处理的边界交点数量，防止退化情况下死循环
+const MAX_CROSSINGS: usize = 64;
+
+/// 布尔运算类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CsgOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOp {
+    /// 由光线在两个物体内外的状态判断是否位于组合实体内部
+    fn inside(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            CsgOp::Union => in_a || in_b,
+            CsgOp::Intersection => in_a && in_b,
+            CsgOp::Difference => in_a && !in_b,
+        }
+    }
+}
+
+/// 两个物体及其布尔运算
+///
+/// # Fields
+/// - a, b: 参与运算的两个物体，都必须是封闭的
+/// - op: 布尔运算类型
+/// - bbox: 组合实体的包围盒
+struct CsgPair {
+    a: Arc<dyn Hittable>,
+    b: Arc<dyn Hittable>,
+    op: CsgOp,
+    bbox: Aabb,
+}
+
+impl CsgPair {
+    fn new(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>, op: CsgOp) -> Self {
+        let (box_a, box_b) = (a.bounding_box(), b.bounding_box());
+        let bbox = match op {
+            CsgOp::Union => Aabb::surrounding(&box_a, &box_b),
+            CsgOp::Intersection => Aabb {
+                x: Interval::new(box_a.x.min.max(box_b.x.min), box_a.x.max.min(box_b.x.max)),
+                y: Interval::new(box_a.y.min.max(box_b.y.min), box_a.y.max.min(box_b.y.max)),
+                z: Interval::new(box_a.z.min.max(box_b.z.min), box_a.z.max.min(box_b.z.max)),
+            },
+            CsgOp::Difference => box_a,
+        };
+        Self { a, b, op, bbox }
+    }
+
+    /// 组合实体的光线命中检测
+    ///
+    /// 从光线的负无穷远处出发(此时在两个物体外部)，每次取两个物体中较近的下一个交点，
+    /// 用命中记录的`front_face`更新该物体的内外状态。组合实体的内外状态发生变化、
+    /// 且交点落在`ray_t`内时即为所求的命中
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+
+        // 求物体在`after`之后的下一个交点，略微跳过`after`本身避免重复命中同一表面
+        let next_hit = |object: &Arc<dyn Hittable>, after: Real| -> Option<HitRecord> {
+            let min = if after == -rtweekend::INFINITY {
+                after
+            } else {
+                after + rtweekend::ORIGIN_OFFSET_EPSILON * after.abs().max(1.0)
+            };
+            let mut rec = HitRecord::default();
+            object.hit(r, &Interval::new(min, ray_t.max), &mut rec).then_some(rec)
+        };
+
+        let (mut in_a, mut in_b) = (false, false);
+        let mut next_a = next_hit(&self.a, -rtweekend::INFINITY);
+        let mut next_b = next_hit(&self.b, -rtweekend::INFINITY);
+
+        for _ in 0..MAX_CROSSINGS {
+            let from_a = match (&next_a, &next_b) {
+                (Some(ha), Some(hb)) => ha.t <= hb.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return false,
+            };
+
+            let was_inside = self.op.inside(in_a, in_b);
+            let rec = if from_a {
+                let rec = next_a.take().unwrap();
+                in_a = rec.front_face;
+                next_a = next_hit(&self.a, rec.t);
+                rec
+            } else {
+                let rec = next_b.take().unwrap();
+                in_b = rec.front_face;
+                next_b = next_hit(&self.b, rec.t);
+                rec
+            };
+            let now_inside = self.op.inside(in_a, in_b);
+
+            if was_inside != now_inside && ray_t.surrounds(rec.t) {
+                // 法线已朝向光线来的一侧，只需按组合实体的内外重新确定正反面
+                *hit_record = rec;
+                hit_record.front_face = now_inside;
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// 并集：位于任一物体内部的点
+pub struct Union {
+    pair: CsgPair,
+}
+
+impl Union {
+    /// 创建两个封闭物体的并集
+    ///
+    /// # Arguments
+    /// * `a` - 第一个物体
+    /// * `b` - 第二个物体
+    pub fn new(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self { pair: CsgPair::new(a, b, CsgOp::Union) }
+    }
+}
+
+impl Hittable for Union {
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        self.pair.hit(r, ray_t, hit_record)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.pair.bbox
+    }
+}
+
+/// 交集：同时位于两个物体内部的点
+pub struct Intersection {
+    pair: CsgPair,
+}
+
+impl Intersection {
+    /// 创建两个封闭物体的交集(例如两个球相交得到透镜)
+    ///
+    /// # Arguments
+    /// * `a` - 第一个物体
+    /// * `b` - 第二个物体
+    pub fn new(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self { pair: CsgPair::new(a, b, CsgOp::Intersection) }
+    }
+}
+
+impl Hittable for Intersection {
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        self.pair.hit(r, ray_t, hit_record)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.pair.bbox
+    }
+}
+
+/// 差集：位于`a`内部但不在`b`内部的点
+pub struct Difference {
+    pair: CsgPair,
+}
+
+impl Difference {
+    /// 从物体`a`中挖去物体`b`
+    ///
+    /// # Arguments
+    /// * `a` - 被挖的物体
+    /// * `b` - 挖去的物体，命中其表面时使用其材质
+    pub fn new(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self { pair: CsgPair::new(a, b, CsgOp::Difference) }
+    }
+}
+
+impl Hittable for Difference {
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        self.pair.hit(r, ray_t, hit_record)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.pair.bbox
+    }
+}
+
+/// 薄壳：封闭物体挖去内部后剩下的一层外壳
+///
+/// 内表面由物体绕包围盒中心沿各轴缩放得到，每个轴上两侧各向内退`thickness`。
+/// 球、轴对齐的长方体和轴对齐的圆柱壁厚处处恰为`thickness`，其它形状的壁厚只是近似。
+/// 常用于空心玻璃球，代替手动放置负半径内球的做法。距离场物体可用`Sdf::shell`得到精确的壳
+///
+/// # Fields
+/// - object: 原物体，壳太厚(内部被完全填满)时直接使用它
+/// - pair: 原物体与其缩小副本的差集，壳太厚时为None
+pub struct Shell {
+    object: Arc<dyn Hittable>,
+    pair: Option<CsgPair>,
+}
+
+impl Shell {
+    /// 创建封闭物体的薄壳
+    ///
+    /// # Arguments
+    /// * `object` - 封闭物体，内表面沿用其材质
+    /// * `thickness` - 壁厚，达到包围盒最小半边长时物体保持实心
+    pub fn new(object: Arc<dyn Hittable>, thickness: Real) -> Self {
+        let bbox = object.bounding_box();
+        let half = |axis: &Interval| 0.5 * axis.size();
+        let factors = [half(&bbox.x), half(&bbox.y), half(&bbox.z)].map(|h| (h - thickness) / h);
+        if factors.iter().any(|&f| f.is_nan() || f <= 0.0) {
+            return Self { object, pair: None };
+        }
+
+        let center = Vec3::new(
+            0.5 * (bbox.x.min + bbox.x.max),
+            0.5 * (bbox.y.min + bbox.y.max),
+            0.5 * (bbox.z.min + bbox.z.max),
+        );
+        let transform = Transform::translate(-center)
+            .then(&Transform::scale(Vec3::from_array(factors)))
+            .then(&Transform::translate(center));
+        let inner: Arc<dyn Hittable> = Arc::new(Instance::new(Arc::clone(&object), transform));
+        let pair = CsgPair::new(Arc::clone(&object), inner, CsgOp::Difference);
+        Self { object, pair: Some(pair) }
+    }
+}
+
+impl Hittable for Shell {
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        match &self.pair {
+            Some(pair) => pair.hit(r, ray_t, hit_record),
+            None => self.object.hit(r, ray_t, hit_record),
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+    use crate::vec3::Point3;
+
+    fn sphere(x: Real, radius: Real) -> Arc<dyn Hittable> {
+        Arc::new(Sphere::new(Point3::new(x, 0.0, 0.0), radius, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))))
+    }
+
+    fn cast(object: &dyn Hittable, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        object
+            .hit(&Ray::new(origin, direction), &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec)
+            .then_some(rec)
+    }
+
+    /// 命中的t和正反面，法线始终朝向光线来的一侧
+    fn assert_hit(rec: Option<HitRecord>, t: Real, front_face: bool, normal: Vec3) {
+        let rec = rec.expect("应当命中");
+        assert!((rec.t - t).abs() < 1e-4, "t = {}", rec.t);
+        assert_eq!(rec.front_face, front_face);
+        assert!((rec.normal - normal).length() < 1e-4, "normal = {:?}", rec.normal);
+    }
+
+    #[test]
+    fn lens_is_the_overlap_of_two_spheres() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        // 球心在x=±0.5、半径为1的两个球，透镜在x轴上的范围为[-0.5, 0.5]
+        let lens = Intersection::new(sphere(-0.5, 1.0), sphere(0.5, 1.0));
+        assert_hit(cast(&lens, Point3::new(-5.0, 0.0, 0.0), x), 4.5, true, -x);
+        // x=0.2处先进入右边的球，透镜的上表面属于左边的球：y = √(1 - 0.7²)
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        let y = (0.51 as Real).sqrt();
+        assert_hit(cast(&lens, Point3::new(0.2, 5.0, 0.0), down), 5.0 - y, true, Vec3::new(0.7, y, 0.0));
+        // 起点在两个球内部：先离开右边的球
+        assert_hit(cast(&lens, Point3::default(), x), 0.5, false, -x);
+        // 只经过其中一个球
+        assert!(cast(&lens, Point3::new(-1.2, 5.0, 0.0), down).is_none());
+        assert!(lens.bounding_box().x.max <= 0.5 + 1e-4);
+    }
+
+    #[test]
+    fn union_enters_the_first_and_leaves_the_last_operand() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let union = Union::new(sphere(-0.5, 1.0), sphere(0.5, 1.0));
+        assert_hit(cast(&union, Point3::new(-5.0, 0.0, 0.0), x), 3.5, true, -x);
+        // 重叠区域内部的两个表面不算边界
+        assert_hit(cast(&union, Point3::default(), x), 1.5, false, -x);
+        assert_hit(cast(&union, Point3::new(-1.2, 0.0, 0.0), x), 2.7, false, -x);
+        assert!(cast(&union, Point3::new(-5.0, 1.5, 0.0), x).is_none());
+    }
+
+    #[test]
+    fn difference_is_hollow_where_the_second_operand_was() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        // 半径2的球挖去同心的半径1的球
+        let hollow = Difference::new(sphere(0.0, 2.0), sphere(0.0, 1.0));
+        let ray = |start: Real| cast(&hollow, Point3::new(start, 0.0, 0.0), x);
+        assert_hit(ray(-5.0), 3.0, true, -x);
+        // 起点在实体部分：离开实体进入空腔
+        assert_hit(ray(-1.5), 0.5, false, -x);
+        // 起点在空腔中：穿过空腔后从空腔一侧进入实体
+        assert_hit(ray(0.0), 1.0, true, -x);
+        assert_hit(ray(1.5), 0.5, false, -x);
+
+        // 挖去偏心的球后，从被挖开的一侧射入的光线直接进入空腔，在另一侧命中空腔内壁
+        let bitten = Difference::new(sphere(0.0, 1.0), sphere(-1.0, 0.5));
+        assert_hit(cast(&bitten, Point3::new(-5.0, 0.0, 0.0), x), 4.5, true, -x);
+        // 空腔之外的部分：从球的左侧表面进入
+        assert_hit(cast(&bitten, Point3::new(-5.0, 0.0, 0.8), x), 5.0 - 0.6, true, Vec3::new(-0.6, 0.0, 0.8));
+    }
+
+    #[test]
+    fn shell_walls_are_crossed_outer_inner_inner_outer() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        // 半径2、壁厚0.5的空心球，内壁半径1.5
+        let shell = Shell::new(sphere(0.0, 2.0), 0.5);
+        let origin = Point3::new(-5.0, 0.0, 0.0);
+        let mut walls = Vec::new();
+        let mut start = rtweekend::HIT_EPSILON;
+        let mut rec = HitRecord::default();
+        while shell.hit(&Ray::new(origin, x), &Interval::new(start, rtweekend::INFINITY), &mut rec) {
+            assert!((rec.normal + x).length() < 1e-4, "normal = {:?}", rec.normal);
+            walls.push((rec.t, rec.front_face));
+            start = rec.t + 1e-3;
+        }
+        let expected = [(3.0, true), (3.5, false), (6.5, true), (7.0, false)];
+        assert_eq!(walls.len(), expected.len(), "walls = {:?}", walls);
+        for ((t, front_face), (expected_t, expected_front)) in walls.into_iter().zip(expected) {
+            assert!((t - expected_t).abs() < 1e-4, "t = {}", t);
+            assert_eq!(front_face, expected_front);
+        }
+
+        // 起点在空腔中时先命中内壁，内壁从空腔一侧看是正面
+        assert_hit(cast(&shell, Point3::default(), x), 1.5, true, -x);
+
+        // 壁厚达到半径时保持实心
+        let solid = Shell::new(sphere(0.0, 2.0), 2.0);
+        assert_hit(cast(&solid, Point3::default(), x), 2.0, false, -x);
+        assert_hit(cast(&solid, origin, x), 3.0, true, -x);
+    }
+}
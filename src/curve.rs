@@ -0,0 +1,387 @@
+//! 曲线模块
+//!
+//! 提供沿三次Bézier曲线扫掠而成的细长图元(PBRT风格)，用于渲染毛发、草叶等细丝，
+//! 不必把每根细丝展开成大量三角形
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::bezier;
+use super::onb::Onb;
+use super::rtweekend::Real;
+use std::sync::Arc;
+
+/// 递归细分的最大深度
+const MAX_DEPTH: i32 = 10;
+
+/// 曲线的截面类型
+#[derive(Clone, Copy, Debug)]
+pub enum CurveType {
+    /// 始终正对光线的扁平带，法线指向光线来向
+    Flat,
+    /// 始终正对光线的扁平带，但法线按圆形截面弯曲，着色效果接近圆柱
+    Cylinder,
+    /// 朝向固定的带状面，法线由两端的n0、n1沿曲线插值
+    Ribbon { n0: Vec3, n1: Vec3 },
+}
+
+/// 三次Bézier曲线图元
+///
+/// 曲线中心线为4个控制点定义的三次Bézier曲线，宽度从起点的width0线性变化到终点的width1。
+/// 求交时把控制点变换到以光线为z轴的坐标系，递归细分曲线直到每段足够平直，
+/// 再把每段当作线段判断光线到中心线的距离是否小于半宽
+///
+/// # Fields
+/// - control: 4个控制点
+/// - width: 起点和终点的宽度
+/// - curve_type: 截面类型
+/// - mat: 材质
+/// - bbox: 包围盒
+pub struct Curve {
+    control: [Point3; 4],
+    width: [Real; 2],
+    curve_type: CurveType,
+    mat: Arc<dyn Material + Send + Sync>,
+    bbox: Aabb,
+}
+
+/// 递归求交过程中的状态
+///
+/// # Fields
+/// - dir: 光线的单位方向
+/// - z_min, z_max: 光线坐标系中有效的深度范围，找到交点后z_max随之缩小
+/// - found: 目前最近的交点(深度, u, v)
+struct CurveSearch {
+    dir: Vec3,
+    z_min: Real,
+    z_max: Real,
+    found: Option<(Real, Real, Real)>,
+}
+
+impl Curve {
+    /// 创建新的曲线
+    ///
+    /// # Arguments
+    /// * `control` - 中心线的4个控制点
+    /// * `width0` - 起点宽度
+    /// * `width1` - 终点宽度
+    /// * `curve_type` - 截面类型
+    /// * `material` - 材质
+    pub fn new(
+        control: [Point3; 4],
+        width0: Real,
+        width1: Real,
+        curve_type: CurveType,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        // 控制点的凸包包含整条中心线，再向外扩展半宽
+        let half = 0.5 * width0.max(width1);
+        let rvec = Vec3::new(half, half, half);
+        let bbox = control
+            .iter()
+            .fold(Aabb::from_points(control[0] - rvec, control[0] + rvec), |acc, &p| {
+                Aabb::surrounding(&acc, &Aabb::from_points(p - rvec, p + rvec))
+            });
+        let curve_type = match curve_type {
+            CurveType::Ribbon { n0, n1 } => CurveType::Ribbon {
+                n0: vec3::unit_vector(n0),
+                n1: vec3::unit_vector(n1),
+            },
+            other => other,
+        };
+
+        Self {
+            control,
+            width: [width0, width1],
+            curve_type,
+            mat: material,
+            bbox,
+        }
+    }
+
+    /// 参数u处的宽度
+    fn width_at(&self, u: Real) -> Real {
+        (1.0 - u) * self.width[0] + u * self.width[1]
+    }
+
+    /// 带状曲线在参数u处的法线
+    fn ribbon_normal(n0: Vec3, n1: Vec3, u: Real) -> Vec3 {
+        let n = vec3::lerp(n0, n1, u);
+        if n.near_zero() { n0 } else { vec3::unit_vector(n) }
+    }
+
+    /// 判断光线坐标系中的一段曲线(向外扩展半宽后)是否可能与光线相交
+    ///
+    /// 光线位于z轴上，因此只需检查控制点包围盒在xy平面上是否包含原点，
+    /// 以及z范围是否与有效深度范围重叠
+    fn overlaps(cp: &[Point3; 4], max_width: Real, search: &CurveSearch) -> bool {
+        let half = 0.5 * max_width;
+        let mut min = cp[0];
+        let mut max = cp[0];
+        for p in &cp[1..] {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        min.x() - half <= 0.0
+            && max.x() + half >= 0.0
+            && min.y() - half <= 0.0
+            && max.y() + half >= 0.0
+            && max.z() + half >= search.z_min
+            && min.z() - half <= search.z_max
+    }
+
+    /// 递归求交
+    ///
+    /// # Arguments
+    /// * `cp` - 光线坐标系中当前段的控制点
+    /// * `u0`, `u1` - 当前段在整条曲线上的参数范围
+    /// * `depth` - 剩余细分深度
+    /// * `search` - 求交状态
+    fn intersect_segment(&self, cp: [Point3; 4], u0: Real, u1: Real, depth: i32, search: &mut CurveSearch) {
+        if depth > 0 {
+            let (left, right) = bezier::split_curve(cp, 0.5);
+            let um = 0.5 * (u0 + u1);
+            for (half, a, b) in [(left, u0, um), (right, um, u1)] {
+                let max_width = self.width_at(a).max(self.width_at(b));
+                if Self::overlaps(&half, max_width, search) {
+                    self.intersect_segment(half, a, b, depth - 1, search);
+                }
+            }
+            return;
+        }
+
+        // 原点必须位于两端切线的垂线之间，否则交点落在相邻段上
+        let edge = (cp[1].y() - cp[0].y()) * -cp[0].y() + cp[0].x() * (cp[0].x() - cp[1].x());
+        if edge < 0.0 {
+            return;
+        }
+        let edge = (cp[2].y() - cp[3].y()) * -cp[3].y() + cp[3].x() * (cp[3].x() - cp[2].x());
+        if edge < 0.0 {
+            return;
+        }
+
+        // 把这段近似为线段，求原点在线段上的投影参数w
+        let seg_x = cp[3].x() - cp[0].x();
+        let seg_y = cp[3].y() - cp[0].y();
+        let denom = seg_x * seg_x + seg_y * seg_y;
+        if denom == 0.0 {
+            return;
+        }
+        let w = (-cp[0].x() * seg_x - cp[0].y() * seg_y) / denom;
+
+        let u = (u0 + w * (u1 - u0)).clamp(u0, u1);
+        let mut hit_width = self.width_at(u);
+        if let CurveType::Ribbon { n0, n1 } = self.curve_type {
+            // 带状面倾斜时在屏幕上的投影宽度变窄
+            hit_width *= vec3::dot(Self::ribbon_normal(n0, n1, u), search.dir).abs();
+        }
+
+        let (b, db) = bezier::bernstein(w.clamp(0.0, 1.0));
+        let mut pc = Vec3::default();
+        let mut dpcdw = Vec3::default();
+        for i in 0..4 {
+            pc += b[i] * cp[i];
+            dpcdw += db[i] * cp[i];
+        }
+
+        // 宽度为0(尖端或侧对光线的带状面)时没有可见的面
+        let dist2 = pc.x() * pc.x() + pc.y() * pc.y();
+        if hit_width <= 0.0 || dist2 > 0.25 * hit_width * hit_width {
+            return;
+        }
+        if pc.z() <= search.z_min || pc.z() >= search.z_max {
+            return;
+        }
+
+        // v在中心线处为0.5，两侧边缘分别为0和1
+        let dist = dist2.sqrt();
+        let side = dpcdw.x() * -pc.y() + pc.x() * dpcdw.y();
+        let v = if side > 0.0 { 0.5 + dist / hit_width } else { 0.5 - dist / hit_width };
+
+        search.z_max = pc.z();
+        search.found = Some((pc.z(), u, v));
+    }
+}
+
+impl Hittable for Curve {
+    /// 实现曲线的光线命中检测
+    ///
+    /// 细分深度由曲线在光线坐标系中的弯曲程度和宽度决定，
+    /// 使得每段与其弦的偏差远小于曲线宽度
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let ray_length = r.direction().length();
+        if ray_length == 0.0 {
+            return false;
+        }
+
+        let frame = Onb::new(r.direction());
+        let cp = self.control.map(|p| {
+            let d = p - r.origin();
+            Vec3::new(vec3::dot(d, frame.u()), vec3::dot(d, frame.v()), vec3::dot(d, frame.w()))
+        });
+
+        let mut search = CurveSearch {
+            dir: frame.w(),
+            z_min: ray_t.min * ray_length,
+            z_max: ray_t.max * ray_length,
+            found: None,
+        };
+        let max_width = self.width[0].max(self.width[1]);
+        if !Self::overlaps(&cp, max_width, &search) {
+            return false;
+        }
+
+        let mut l0: Real = 0.0;
+        for i in 0..2 {
+            let second_diff = cp[i] - 2.0 * cp[i + 1] + cp[i + 2];
+            l0 = l0.max(second_diff.x().abs()).max(second_diff.y().abs()).max(second_diff.z().abs());
+        }
+        let eps = 0.05 * max_width;
+        let depth = if eps > 0.0 {
+            let r0 = ((2.0 as Real).sqrt() * 6.0 * l0 / (8.0 * eps)).log2() / 2.0;
+            if r0.is_nan() { 0 } else { (r0.round() as i32).clamp(0, MAX_DEPTH) }
+        } else {
+            MAX_DEPTH
+        };
+
+        self.intersect_segment(cp, 0.0, 1.0, depth, &mut search);
+        let Some((z, u, v)) = search.found else {
+            return false;
+        };
+
+        let t = z / ray_length;
+        let p = r.at(t);
+
+        let (b, db) = bezier::bernstein(u);
+        let mut center = Vec3::default();
+        let mut dpdu = Vec3::default();
+        for i in 0..4 {
+            center += b[i] * self.control[i];
+            dpdu += db[i] * self.control[i];
+        }
+        let tangent = if dpdu.near_zero() { Vec3::default() } else { vec3::unit_vector(dpdu) };
+
+        // 正对光线、垂直于切线的法线
+        let facing = {
+            let n = -frame.w() - vec3::dot(-frame.w(), tangent) * tangent;
+            if n.near_zero() { -frame.w() } else { vec3::unit_vector(n) }
+        };
+        let outward_normal = match self.curve_type {
+            CurveType::Flat => facing,
+            CurveType::Cylinder => {
+                // 按到中心线的相对距离把法线向侧面弯曲，模拟圆形截面
+                let side = vec3::cross(tangent, facing);
+                let s = (2.0 * (v - 0.5).abs()).min(1.0);
+                let s = if vec3::dot(p - center, side) < 0.0 { -s } else { s };
+                vec3::unit_vector((1.0 - s * s).sqrt() * facing + s * side)
+            }
+            CurveType::Ribbon { n0, n1 } => Self::ribbon_normal(n0, n1, u),
+        };
+
+        hit_record.t = t;
+        hit_record.p = p;
+        hit_record.u = u;
+        hit_record.v = v;
+        hit_record.tangent = tangent;
+        hit_record.set_face_normal(r, outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+    use crate::rtweekend;
+
+    /// 沿x轴从原点到(3,0,0)的直线曲线
+    fn straight(width0: Real, width1: Real, curve_type: CurveType) -> Curve {
+        let control = [0.0, 1.0, 2.0, 3.0].map(|x| Point3::new(x, 0.0, 0.0));
+        Curve::new(control, width0, width1, curve_type, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))
+    }
+
+    /// 从z = 5处垂直向下射向(x, y, 0)
+    fn cast_down(curve: &Curve, x: Real, y: Real) -> Option<HitRecord> {
+        cast(curve, Point3::new(x, y, 5.0), Vec3::new(0.0, 0.0, -1.0))
+    }
+
+    /// 命中时检查法线为单位长度且朝向光线来的一侧
+    fn cast(curve: &Curve, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        let r = Ray::new(origin, direction);
+        if !curve.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return None;
+        }
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!(vec3::dot(rec.normal, direction) < 0.0);
+        Some(rec)
+    }
+
+    #[test]
+    fn flat_curve_is_hit_within_its_half_width() {
+        let curve = straight(0.2, 0.2, CurveType::Flat);
+        let rec = cast_down(&curve, 1.5, 0.0).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-4);
+        assert!((rec.u - 0.5).abs() < 1e-3 && (rec.v - 0.5).abs() < 1e-3);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-4);
+        // 偏离中心线半个半宽，v相应偏离0.25
+        let rec = cast_down(&curve, 1.5, 0.05).unwrap();
+        assert!(((rec.v - 0.5).abs() - 0.25).abs() < 1e-3);
+
+        // 弯曲的曲线：u = 0.5处中心线在(1.5, 1.5, 0)
+        let arch = [Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 2.0, 0.0), Point3::new(2.0, 2.0, 0.0), Point3::new(3.0, 0.0, 0.0)];
+        let arch = Curve::new(arch, 0.1, 0.1, CurveType::Flat, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))));
+        let rec = cast_down(&arch, 1.5, 1.5).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-4);
+        assert!((rec.u - 0.5).abs() < 1e-2);
+    }
+
+    #[test]
+    fn rays_beside_or_past_the_ends_miss() {
+        let curve = straight(0.2, 0.2, CurveType::Flat);
+        assert!(cast_down(&curve, 1.5, 0.11).is_none());
+        assert!(cast_down(&curve, 3.2, 0.0).is_none());
+        assert!(cast_down(&curve, -0.2, 0.0).is_none());
+        // 宽度从0.2线性减小到0，u = 0.75处半宽为0.025
+        let tapered = straight(0.2, 0.0, CurveType::Flat);
+        assert!(cast_down(&tapered, 2.25, 0.02).is_some());
+        assert!(cast_down(&tapered, 2.25, 0.03).is_none());
+    }
+
+    #[test]
+    fn cylinder_and_ribbon_normals() {
+        // 圆柱截面：靠近边缘处法线向侧面弯曲
+        let rec = cast_down(&straight(0.2, 0.2, CurveType::Cylinder), 1.5, 0.09).unwrap();
+        assert!(rec.normal.y() > 0.8 && rec.normal.z() > 0.0, "normal = {:?}", rec.normal);
+        assert!(rec.normal.x().abs() < 1e-4);
+
+        // 带状面朝上时法线固定为+y，从侧面看去宽度为0
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let ribbon = straight(0.2, 0.2, CurveType::Ribbon { n0: up, n1: up });
+        assert!(cast_down(&ribbon, 1.5, 0.0).is_none());
+        let rec = cast(&ribbon, Point3::new(1.5, 5.0, 0.05), -up).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-4);
+        assert!((rec.normal - up).length() < 1e-4);
+    }
+
+    #[test]
+    fn ray_leaving_the_curve_does_not_hit_it_again() {
+        let curve = straight(0.2, 0.2, CurveType::Flat);
+        let rec = cast_down(&curve, 1.5, 0.0).unwrap();
+        assert!(cast(&curve, rec.p, Vec3::new(0.0, 0.0, -1.0)).is_none());
+        assert!(cast(&curve, rec.p, Vec3::new(0.3, 0.2, 1.0)).is_none());
+    }
+}
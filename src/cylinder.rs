@@ -0,0 +1,253 @@
+//! 圆柱模块
+//!
+//! 提供有限高度圆柱几何形状的实现，可选两端封盖
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::onb::Onb;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 圆柱几何形状
+///
+/// # Fields
+/// - base: 底面圆心
+/// - axis: 轴线单位方向(从底面指向顶面)
+/// - radius: 半径
+/// - height: 沿轴线的高度
+/// - capped: 是否封闭两端
+/// - mat: 圆柱材质
+pub struct Cylinder {
+    base: Point3,
+    axis: Vec3,
+    radius: Real,
+    height: Real,
+    capped: bool,
+    mat: Arc<dyn Material + Send + Sync>,
+    frame: Onb,
+    bbox: Aabb,
+}
+
+impl Cylinder {
+    /// 创建新的圆柱
+    ///
+    /// # Arguments
+    /// * `base` - 底面圆心
+    /// * `axis` - 轴线方向(从底面指向顶面，无需归一化)
+    /// * `radius` - 半径
+    /// * `height` - 沿轴线的高度
+    /// * `capped` - 是否封闭两端
+    /// * `material` - 圆柱材质
+    pub fn new(
+        base: Point3,
+        axis: Vec3,
+        radius: Real,
+        height: Real,
+        capped: bool,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        let axis = vec3::unit_vector(axis);
+
+        // 包围盒：两端圆盘的包围盒的并集
+        let top = base + height * axis;
+        let extent = Vec3::new(
+            radius * (1.0 - axis.x() * axis.x()).max(0.0).sqrt(),
+            radius * (1.0 - axis.y() * axis.y()).max(0.0).sqrt(),
+            radius * (1.0 - axis.z() * axis.z()).max(0.0).sqrt(),
+        );
+        let bbox = Aabb::surrounding(
+            &Aabb::from_points(base - extent, base + extent),
+            &Aabb::from_points(top - extent, top + extent),
+        );
+
+        Self {
+            base,
+            axis,
+            radius,
+            height,
+            capped,
+            mat: material,
+            frame: Onb::new(axis),
+            bbox,
+        }
+    }
+
+    /// 计算点绕轴线的方位角
+    ///
+    /// # Returns
+    /// 返回(映射到[0,1]的方位角参数u, 沿u增大方向的单位切向量)
+    fn azimuth(&self, p: Point3) -> (Real, Vec3) {
+        let d = p - self.base;
+        let phi = vec3::dot(d, self.frame.v()).atan2(vec3::dot(d, self.frame.u()));
+        let tangent = -phi.sin() * self.frame.u() + phi.cos() * self.frame.v();
+        ((phi + rtweekend::PI) / (2.0 * rtweekend::PI), tangent)
+    }
+}
+
+impl Hittable for Cylinder {
+    /// 实现圆柱的光线命中检测
+    ///
+    /// 侧面满足 |v - (v·A)A|² = r²，其中 v = P - base，A为轴线方向；
+    /// 代入光线方程得到关于t的二次方程，再保留 0 ≤ v·A ≤ height 的解。
+    /// 两端封盖为垂直于轴线的圆盘
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let d = r.direction();
+        let co = r.origin() - self.base;
+
+        let d_a = vec3::dot(d, self.axis);
+        let co_a = vec3::dot(co, self.axis);
+
+        // 去掉沿轴线的分量后在垂直平面内求解
+        let d_perp = d - d_a * self.axis;
+        let co_perp = co - co_a * self.axis;
+
+        // 二次方程系数: a t² + 2 b t + c = 0
+        let a = d_perp.squared_length();
+        let b = vec3::dot(d_perp, co_perp);
+        let c = co_perp.squared_length() - self.radius * self.radius;
+
+        let mut closest = ray_t.max;
+        let mut hit_side = false;
+
+        // 侧面(光线平行于轴线时a为0，只可能命中封盖)
+        if a > 1e-12 {
+            let discriminant = b * b - a * c;
+            if discriminant >= 0.0 {
+                let sqrtd = discriminant.sqrt();
+                for t in [(-b - sqrtd) / a, (-b + sqrtd) / a] {
+                    if !ray_t.surrounds(t) || t >= closest {
+                        continue;
+                    }
+                    let h = co_a + t * d_a;
+                    if (0.0..=self.height).contains(&h) {
+                        closest = t;
+                        hit_side = true;
+                    }
+                }
+            }
+        }
+
+        // 两端封盖
+        let mut hit_cap = None;
+        if self.capped && d_a.abs() > 1e-12 {
+            for (h, normal) in [(0.0, -self.axis), (self.height, self.axis)] {
+                let t = (h - co_a) / d_a;
+                if !ray_t.surrounds(t) || t >= closest {
+                    continue;
+                }
+                let cap_center = self.base + h * self.axis;
+                if (r.at(t) - cap_center).squared_length() <= self.radius * self.radius {
+                    closest = t;
+                    hit_cap = Some(normal);
+                }
+            }
+        }
+
+        if !hit_side && hit_cap.is_none() {
+            return false;
+        }
+
+        hit_record.t = closest;
+        hit_record.p = r.at(closest);
+        let v = hit_record.p - self.base;
+        let h = vec3::dot(v, self.axis);
+
+        let outward_normal = match hit_cap {
+            Some(normal) => {
+                hit_record.v = if vec3::dot(normal, self.axis) > 0.0 { 1.0 } else { 0.0 };
+                normal
+            }
+            None => {
+                hit_record.v = h / self.height;
+                (v - h * self.axis) / self.radius
+            }
+        };
+        (hit_record.u, hit_record.tangent) = self.azimuth(hit_record.p);
+        hit_record.set_face_normal(r, outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    /// 底面圆心在原点、沿y轴、半径1、高2的圆柱
+    fn cylinder(capped: bool) -> Cylinder {
+        Cylinder::new(
+            Point3::default(),
+            Vec3::new(0.0, 1.0, 0.0),
+            1.0,
+            2.0,
+            capped,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    /// 命中时检查法线为单位长度且朝向光线来的一侧
+    fn cast(cylinder: &Cylinder, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        let r = Ray::new(origin, direction);
+        if !cylinder.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return None;
+        }
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!(vec3::dot(rec.normal, direction) < 0.0);
+        Some(rec)
+    }
+
+    #[test]
+    fn side_and_caps_are_hit_at_the_expected_distance() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let rec = cast(&cylinder(true), Point3::new(-5.0, 1.0, 0.0), x).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal + x).length() < 1e-4);
+        assert!((rec.v - 0.5).abs() < 1e-4);
+
+        let rec = cast(&cylinder(true), Point3::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-4);
+
+        // 不封顶时穿过顶面，从内部命中底面以上的侧面
+        let rec = cast(&cylinder(false), Point3::new(0.0, 3.0, 0.0), Vec3::new(0.6, -1.0, 0.0)).unwrap();
+        assert!(!rec.front_face);
+        assert!((rec.p.x() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rays_beside_above_or_through_open_ends_miss() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        assert!(cast(&cylinder(true), Point3::new(-5.0, 2.5, 0.0), x).is_none());
+        assert!(cast(&cylinder(true), Point3::new(-5.0, 1.0, 1.01), x).is_none());
+        // 沿轴线穿过两端敞开的圆柱
+        assert!(cast(&cylinder(false), Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn ray_from_inside_hits_the_back_face() {
+        let center = Point3::new(0.0, 1.0, 0.0);
+        let rec = cast(&cylinder(true), center, Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-4);
+
+        let rec = cast(&cylinder(true), center, Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+        assert!(cast(&cylinder(false), center, Vec3::new(0.0, 1.0, 0.0)).is_none());
+    }
+}
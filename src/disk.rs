@@ -0,0 +1,222 @@
+//! 圆盘模块
+//!
+//! 提供圆盘和圆环面(带内半径的圆盘)几何形状的实现
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::onb::Onb;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 圆盘几何形状
+///
+/// 内半径大于0时为圆环面，中间挖空
+///
+/// # Fields
+/// - center: 圆心
+/// - normal: 单位法线(正面方向)
+/// - inner_radius: 内半径
+/// - outer_radius: 外半径
+/// - mat: 圆盘材质
+/// - frame: 局部坐标系，w轴为法线
+/// - bbox: 包围盒
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    inner_radius: Real,
+    outer_radius: Real,
+    mat: Arc<dyn Material + Send + Sync>,
+    frame: Onb,
+    bbox: Aabb,
+}
+
+impl Disk {
+    /// 创建新的圆盘
+    ///
+    /// # Arguments
+    /// * `center` - 圆心
+    /// * `normal` - 法线方向(无需归一化)
+    /// * `inner_radius` - 内半径，为0时是实心圆盘
+    /// * `outer_radius` - 外半径
+    /// * `material` - 圆盘材质
+    pub fn new(
+        center: Point3,
+        normal: Vec3,
+        inner_radius: Real,
+        outer_radius: Real,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        let normal = vec3::unit_vector(normal);
+
+        let extent = Vec3::new(
+            outer_radius * (1.0 - normal.x() * normal.x()).max(0.0).sqrt(),
+            outer_radius * (1.0 - normal.y() * normal.y()).max(0.0).sqrt(),
+            outer_radius * (1.0 - normal.z() * normal.z()).max(0.0).sqrt(),
+        );
+
+        Self {
+            center,
+            normal,
+            inner_radius,
+            outer_radius,
+            mat: material,
+            frame: Onb::new(normal),
+            bbox: Aabb::from_points(center - extent, center + extent),
+        }
+    }
+
+    /// 圆盘面积
+    fn area(&self) -> Real {
+        rtweekend::PI * (self.outer_radius * self.outer_radius - self.inner_radius * self.inner_radius)
+    }
+}
+
+impl Hittable for Disk {
+    /// 实现圆盘的光线命中检测
+    ///
+    /// 先求光线与所在平面的交点，再判断交点到圆心的距离是否在[内半径, 外半径]内。
+    /// 纹理坐标u为绕法线的方位角，v为从内半径到外半径的径向位置
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let denom = vec3::dot(self.normal, r.direction());
+
+        // 光线与平面平行时不相交
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+
+        let t = vec3::dot(self.normal, self.center - r.origin()) / denom;
+        if !ray_t.contains(t) {
+            return false;
+        }
+
+        let intersection = r.at(t);
+        let d = intersection - self.center;
+        let dist_squared = d.squared_length();
+        if dist_squared > self.outer_radius * self.outer_radius
+            || dist_squared < self.inner_radius * self.inner_radius
+        {
+            return false;
+        }
+
+        let phi = vec3::dot(d, self.frame.v()).atan2(vec3::dot(d, self.frame.u()));
+
+        hit_record.t = t;
+        hit_record.p = intersection;
+        hit_record.u = (phi + rtweekend::PI) / (2.0 * rtweekend::PI);
+        hit_record.v = (dist_squared.sqrt() - self.inner_radius) / (self.outer_radius - self.inner_radius);
+        hit_record.tangent = -phi.sin() * self.frame.u() + phi.cos() * self.frame.v();
+        hit_record.mat = Some(Arc::clone(&self.mat));
+        hit_record.set_face_normal(r, self.normal);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    /// 圆盘面积上均匀采样换算到立体角测度的概率密度
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> Real {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(origin, direction), &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return 0.0;
+        }
+
+        let distance_squared = rec.t * rec.t * direction.squared_length();
+        let cosine = (vec3::dot(direction, rec.normal) / direction.length()).abs();
+
+        distance_squared / (cosine * self.area())
+    }
+
+    /// 从`origin`指向圆盘上均匀随机一点的方向
+    fn random(&self, origin: Point3) -> Vec3 {
+        // 按面积均匀采样半径: r² 在[内半径², 外半径²]上均匀分布
+        let r_in2 = self.inner_radius * self.inner_radius;
+        let r_out2 = self.outer_radius * self.outer_radius;
+        let radius = rtweekend::random_double_range(r_in2, r_out2).sqrt();
+        let phi = 2.0 * rtweekend::PI * rtweekend::random_double();
+
+        let p = self.center + self.frame.transform(Vec3::new(radius * phi.cos(), radius * phi.sin(), 0.0));
+        p - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    /// 圆心在原点、法线朝+y、内半径0.5、外半径2的圆环面
+    fn annulus() -> Disk {
+        Disk::new(
+            Point3::default(),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.5,
+            2.0,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    /// 命中时检查法线为单位长度且朝向光线来的一侧
+    fn cast(disk: &Disk, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        let r = Ray::new(origin, direction);
+        if !disk.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return None;
+        }
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!(vec3::dot(rec.normal, direction) < 0.0);
+        Some(rec)
+    }
+
+    #[test]
+    fn both_faces_are_hit_at_the_expected_distance() {
+        let rec = cast(&annulus(), Point3::new(1.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-4);
+        assert!((rec.v - 1.0 / 3.0).abs() < 1e-4);
+
+        let rec = cast(&annulus(), Point3::new(0.0, -3.0, -1.5), Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn rays_through_the_hole_beyond_the_rim_or_parallel_miss() {
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        assert!(cast(&annulus(), Point3::new(0.3, 5.0, 0.0), down).is_none());
+        assert!(cast(&annulus(), Point3::new(1.5, 5.0, 1.5), down).is_none());
+        assert!(cast(&annulus(), Point3::new(-5.0, 0.0, 1.0), Vec3::new(1.0, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn ray_leaving_the_disk_does_not_hit_its_own_origin() {
+        // 起点就在圆环面上(例如散射光线)，不会在t≈0处再次命中
+        let on_disk = Point3::new(1.0, 0.0, 0.0);
+        assert!(cast(&annulus(), on_disk, Vec3::new(0.0, 1.0, 0.0)).is_none());
+        assert!(cast(&annulus(), on_disk, Vec3::new(0.3, -1.0, 0.2)).is_none());
+        // 起点在中间的孔里，斜向射到圆环面
+        let rec = cast(&annulus(), Point3::new(0.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0)).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn sampled_directions_hit_the_disk_with_positive_pdf() {
+        rtweekend::seed_random(1008);
+        let disk = annulus();
+        let origin = Point3::new(1.0, 3.0, 2.0);
+        for _ in 0..200 {
+            let direction = disk.random(origin);
+            let rec = cast(&disk, origin, direction).expect("采样方向应当命中圆盘");
+            let d = (rec.p - Point3::default()).length();
+            assert!((0.5 - 1e-4..=2.0 + 1e-4).contains(&d));
+            assert!(disk.pdf_value(origin, direction) > 0.0);
+        }
+    }
+}
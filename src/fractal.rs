@@ -0,0 +1,240 @@
+//! 分形模块
+//!
+//! 提供曼德尔球(Mandelbulb)、门格海绵和谢尔宾斯基四面体的距离估计函数，
+//! 可直接转换为有向距离场物体渲染，并通过轨道陷阱(orbit trap)给出用于着色的标量
+
+use super::vec3::{Point3, Vec3};
+use super::material::Material;
+use super::aabb::Aabb;
+use super::sdf::Sdf;
+use super::rtweekend::Real;
+use std::sync::Arc;
+
+/// 分形距离估计抽象接口
+///
+/// 距离估计与有向距离函数的要求相同：绝对值不超过到表面的真实距离
+pub trait Fractal: Send + Sync {
+    /// 估计点p到分形表面的距离，外部为正
+    fn distance(&self, p: Point3) -> Real;
+
+    /// 点p处的轨道陷阱值，范围[0,1]
+    ///
+    /// 由迭代过程中点的轨迹计算，在表面上形成随结构变化的图案，用于着色
+    fn orbit_trap(&self, p: Point3) -> Real;
+
+    /// 包含整个分形的包围盒
+    fn bounding_box(&self) -> Aabb;
+
+    /// 转换为距离场物体
+    ///
+    /// 命中点的纹理坐标u为轨道陷阱值、v为0.5，材质使用以u为参数的纹理即可按轨道陷阱着色
+    ///
+    /// # Arguments
+    /// * `material` - 材质
+    fn into_sdf(self, material: Arc<dyn Material + Send + Sync>) -> Sdf
+    where
+        Self: Sized + 'static,
+    {
+        let bbox = self.bounding_box();
+        let fractal = Arc::new(self);
+        let trap = Arc::clone(&fractal);
+        Sdf::new(move |p| fractal.distance(p), bbox, material).with_uv(move |p| (trap.orbit_trap(p), 0.5))
+    }
+}
+
+/// 曼德尔球
+///
+/// 以原点为中心，按球坐标把点的模长取`power`次幂、角度乘以`power`反复迭代 z ← z^power + p，
+/// 迭代不发散的点组成的集合。power为8时即经典的曼德尔球，整体位于半径约1.2的球内
+///
+/// # Fields
+/// - power: 幂次
+/// - iterations: 最大迭代次数，越大细节越多
+/// - bailout: 逃逸半径，模长超过它即认为发散
+#[derive(Clone, Copy, Debug)]
+pub struct Mandelbulb {
+    pub power: Real,
+    pub iterations: usize,
+    pub bailout: Real,
+}
+
+impl Mandelbulb {
+    /// 创建新的曼德尔球，逃逸半径为2
+    ///
+    /// # Arguments
+    /// * `power` - 幂次，经典形状为8
+    /// * `iterations` - 最大迭代次数
+    pub fn new(power: Real, iterations: usize) -> Self {
+        Self { power, iterations, bailout: 2.0 }
+    }
+
+    /// 迭代点p，返回最终模长、模长的导数和轨迹到原点的最小距离
+    fn iterate(&self, p: Point3) -> (Real, Real, Real) {
+        let mut z = p;
+        let mut dr = 1.0;
+        let mut r = z.length();
+        let mut trap = r;
+        for _ in 0..self.iterations {
+            if r > self.bailout || r == 0.0 {
+                break;
+            }
+            let theta = (z.z() / r).clamp(-1.0, 1.0).acos() * self.power;
+            let phi = z.y().atan2(z.x()) * self.power;
+            dr = self.power * r.powf(self.power - 1.0) * dr + 1.0;
+            let zr = r.powf(self.power);
+            z = zr * Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()) + p;
+            r = z.length();
+            trap = trap.min(r);
+        }
+        (r, dr, trap)
+    }
+}
+
+impl Fractal for Mandelbulb {
+    /// 使用解析导数的距离估计 0.5 * r * ln(r) / dr
+    fn distance(&self, p: Point3) -> Real {
+        let (r, dr, _) = self.iterate(p);
+        if r == 0.0 { 0.0 } else { 0.5 * r * r.ln() / dr }
+    }
+
+    /// 迭代轨迹到原点的最小距离
+    fn orbit_trap(&self, p: Point3) -> Real {
+        self.iterate(p).2.clamp(0.0, 1.0)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(Point3::new(-1.5, -1.5, -1.5), Point3::new(1.5, 1.5, 1.5))
+    }
+}
+
+/// 门格海绵
+///
+/// 从中心在原点、边长为2的立方体开始，每一级把立方体分为27个小立方体并挖去
+/// 面中心和体中心的7个，共进行`iterations`级
+///
+/// # Fields
+/// - iterations: 挖空的级数
+#[derive(Clone, Copy, Debug)]
+pub struct MengerSponge {
+    pub iterations: usize,
+}
+
+impl MengerSponge {
+    /// 创建新的门格海绵
+    ///
+    /// # Arguments
+    /// * `iterations` - 挖空的级数
+    pub fn new(iterations: usize) -> Self {
+        Self { iterations }
+    }
+
+    /// 返回到表面的有向距离和轨道陷阱值
+    ///
+    /// 每一级把点折叠到单个小立方体内，挖去的十字形空腔的距离按当前缩放换算回原空间，
+    /// 与已有形状做差集
+    fn evaluate(&self, p: Point3) -> (Real, Real) {
+        let q = p.to_array().map(Real::abs);
+        let outside = Vec3::new((q[0] - 1.0).max(0.0), (q[1] - 1.0).max(0.0), (q[2] - 1.0).max(0.0));
+        let mut d = outside.length() + (q[0] - 1.0).max(q[1] - 1.0).max(q[2] - 1.0).min(0.0);
+
+        let mut trap: Real = 1.0;
+        let mut s = 1.0;
+        for _ in 0..self.iterations {
+            // 每个分量折叠到[-1, 1)，即当前级小立方体的局部坐标
+            let a = p.to_array().map(|x| (x * s).rem_euclid(2.0) - 1.0);
+            s *= 3.0;
+            let r = a.map(|x| (1.0 - 3.0 * x.abs()).abs());
+            let da = r[0].max(r[1]);
+            let db = r[1].max(r[2]);
+            let dc = r[2].max(r[0]);
+            let c = (da.min(db).min(dc) - 1.0) / s;
+            if c > d {
+                d = c;
+                trap = trap.min(0.2 * da * db * dc);
+            }
+        }
+        (d, trap)
+    }
+}
+
+impl Fractal for MengerSponge {
+    fn distance(&self, p: Point3) -> Real {
+        self.evaluate(p).0
+    }
+
+    /// 各级空腔附近三个方向十字距离的乘积，越靠近空腔边角越小
+    fn orbit_trap(&self, p: Point3) -> Real {
+        self.evaluate(p).1.clamp(0.0, 1.0)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+    }
+}
+
+/// 谢尔宾斯基四面体
+///
+/// 以 (1,1,1)、(-1,-1,1)、(1,-1,-1)、(-1,1,-1) 为顶点的正四面体，
+/// 每一级替换为位于四个顶点处、边长减半的四个小四面体，共进行`iterations`级
+///
+/// # Fields
+/// - iterations: 细分级数
+#[derive(Clone, Copy, Debug)]
+pub struct Sierpinski {
+    pub iterations: usize,
+}
+
+impl Sierpinski {
+    /// 创建新的谢尔宾斯基四面体
+    ///
+    /// # Arguments
+    /// * `iterations` - 细分级数
+    pub fn new(iterations: usize) -> Self {
+        Self { iterations }
+    }
+
+    /// 返回到表面的有向距离和轨道陷阱值
+    ///
+    /// 每一级用三个对称平面把点反射到顶点(1,1,1)所在的一侧，再以该顶点为中心放大2倍，
+    /// 最后求到原四面体的距离并按总放大倍数换算回原空间
+    fn evaluate(&self, p: Point3) -> (Real, Real) {
+        let mut z = p.to_array();
+        let mut scale = 1.0;
+        let mut trap = Real::INFINITY;
+        for _ in 0..self.iterations {
+            if z[0] + z[1] < 0.0 {
+                (z[0], z[1]) = (-z[1], -z[0]);
+            }
+            if z[0] + z[2] < 0.0 {
+                (z[0], z[2]) = (-z[2], -z[0]);
+            }
+            if z[1] + z[2] < 0.0 {
+                (z[1], z[2]) = (-z[2], -z[1]);
+            }
+            z = z.map(|x| 2.0 * x - 1.0);
+            scale *= 2.0;
+            trap = trap.min(Vec3::from_array(z).length());
+        }
+
+        // 正四面体由四个面的半空间相交而成，外法线为 ±(1,1,1) 方向的四个组合
+        let [x, y, z] = z;
+        let plane = (-x - y - z).max(x + y - z).max(-x + y + z).max(x - y + z);
+        let d = (plane - 1.0) / (3.0 as Real).sqrt() / scale;
+        (d, trap / (3.0 as Real).sqrt())
+    }
+}
+
+impl Fractal for Sierpinski {
+    fn distance(&self, p: Point3) -> Real {
+        self.evaluate(p).0
+    }
+
+    /// 迭代轨迹到原点的最小距离，以四面体外接球半径为单位
+    fn orbit_trap(&self, p: Point3) -> Real {
+        self.evaluate(p).1.clamp(0.0, 1.0)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+    }
+}
@@ -0,0 +1,857 @@
+//! glTF模型加载模块
+//!
+//! 提供glTF 2.0场景(.gltf文本格式和.glb二进制格式)的加载函数，
+//! 读取其中的三角网格、节点变换层级和透视相机，便于直接使用Blender等工具导出的资源
+
+use super::vec3::{self, Point3, Vec3};
+use super::camera::Camera;
+use super::material::Material;
+use super::hittable_list::HittableList;
+use super::mesh::{MeshFace, TriangleMesh};
+use super::transform::Transform;
+use super::rtweekend::Real;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 节点层级的最大深度，用于发现循环引用
+const MAX_NODE_DEPTH: usize = 256;
+
+/// JSON数组和对象的最大嵌套深度，防止恶意文件耗尽栈空间
+const MAX_JSON_DEPTH: usize = 256;
+
+/// 没有缓冲区视图的访问器最多包含的分量数，防止伪造的count导致巨量内存分配
+const MAX_ZERO_ACCESSOR_VALUES: usize = 1 << 26;
+
+/// 从glTF文件加载的场景
+///
+/// # Fields
+/// - world: 所有网格，顶点已变换到世界坐标
+/// - cameras: 场景中的透视相机，按遍历节点的顺序排列
+pub struct GltfScene {
+    pub world: HittableList,
+    pub cameras: Vec<Camera>,
+}
+
+/// 从glTF 2.0文件加载场景
+///
+/// 支持.gltf(外部.bin文件或base64内嵌数据)和.glb格式。只读取三角形图元的
+/// POSITION、NORMAL和TEXCOORD_0属性，节点的matrix或TRS变换会沿层级累积后直接应用到顶点上。
+/// 透视相机转换为`Camera`，其余相机参数保持默认值。材质、动画和蒙皮等被忽略
+///
+/// # Arguments
+/// * `path` - glTF文件路径
+/// * `material` - 所有网格使用的材质
+///
+/// # Returns
+/// 返回加载的场景，文件无法读取或格式错误时返回错误
+pub fn load_gltf(path: impl AsRef<Path>, material: Arc<dyn Material + Send + Sync>) -> io::Result<GltfScene> {
+    let path = path.as_ref();
+    parse_gltf(fs::read(path)?, path.parent().unwrap_or(Path::new("")), material)
+}
+
+/// 从内存中的.gltf或.glb数据解析场景
+///
+/// # Arguments
+/// * `data` - 文件内容
+/// * `base_dir` - 外部缓冲区文件相对的目录
+/// * `material` - 所有网格使用的材质
+fn parse_gltf(data: Vec<u8>, base_dir: &Path, material: Arc<dyn Material + Send + Sync>) -> io::Result<GltfScene> {
+    let (text, binary_chunk) = if data.starts_with(b"glTF") {
+        parse_glb(&data)?
+    } else {
+        (String::from_utf8(data).map_err(|_| invalid("file is not valid UTF-8"))?, None)
+    };
+    let doc = parse_json(&text)?;
+    let buffers = load_buffers(&doc, base_dir, binary_chunk)?;
+
+    // 默认场景的根节点；没有场景时把所有不是其它节点子节点的节点作为根节点
+    let scenes = array(&doc, "scenes");
+    let scene_index = doc.get("scene").and_then(Json::as_usize).or((!scenes.is_empty()).then_some(0));
+    let roots: Vec<usize> = match scene_index {
+        Some(i) => {
+            let scene = scenes.get(i).ok_or_else(|| invalid("scene index out of range"))?;
+            array(scene, "nodes").iter().map(index).collect::<io::Result<_>>()?
+        }
+        None => {
+            let nodes = array(&doc, "nodes");
+            let mut is_child = vec![false; nodes.len()];
+            for node in nodes {
+                for child in array(node, "children") {
+                    if let Some(flag) = is_child.get_mut(index(child)?) {
+                        *flag = true;
+                    }
+                }
+            }
+            (0..nodes.len()).filter(|&i| !is_child[i]).collect()
+        }
+    };
+
+    let mut scene = GltfScene {
+        world: HittableList::default(),
+        cameras: Vec::new(),
+    };
+    let loader = Loader { doc: &doc, buffers: &buffers, material };
+    for root in roots {
+        loader.visit(root, &Transform::identity(), 0, &mut scene)?;
+    }
+    Ok(scene)
+}
+
+/// 遍历节点层级时共用的数据
+struct Loader<'a> {
+    doc: &'a Json,
+    buffers: &'a [Vec<u8>],
+    material: Arc<dyn Material + Send + Sync>,
+}
+
+impl Loader<'_> {
+    /// 处理一个节点及其子节点
+    ///
+    /// # Arguments
+    /// * `node_index` - 节点索引
+    /// * `parent` - 父节点的世界变换
+    /// * `depth` - 当前层级深度
+    /// * `scene` - 输出场景
+    fn visit(&self, node_index: usize, parent: &Transform, depth: usize, scene: &mut GltfScene) -> io::Result<()> {
+        if depth > MAX_NODE_DEPTH {
+            return Err(invalid("node hierarchy too deep"));
+        }
+        let node = array(self.doc, "nodes")
+            .get(node_index)
+            .ok_or_else(|| invalid("node index out of range"))?;
+        let transform = node_transform(node)?.then(parent);
+
+        if let Some(mesh) = node.get("mesh") {
+            let mesh = array(self.doc, "meshes")
+                .get(index(mesh)?)
+                .ok_or_else(|| invalid("mesh index out of range"))?;
+            for primitive in array(mesh, "primitives") {
+                if let Some(mesh) = self.load_primitive(primitive, &transform)? {
+                    scene.world.add(Arc::new(mesh));
+                }
+            }
+        }
+
+        if let Some(camera) = node.get("camera") {
+            let camera = array(self.doc, "cameras")
+                .get(index(camera)?)
+                .ok_or_else(|| invalid("camera index out of range"))?;
+            if let Some(perspective) = camera.get("perspective") {
+                // glTF相机位于局部原点，朝向-z，上方向为+y
+                let mut cam = Camera::default();
+                cam.vfov = (number(perspective, "yfov")? as Real).to_degrees();
+                if let Some(aspect) = perspective.get("aspectRatio").and_then(Json::as_f64) {
+                    cam.aspect_ratio = aspect as Real;
+                }
+                cam.lookfrom = transform.point(Point3::default());
+                cam.lookat = transform.point(Point3::new(0.0, 0.0, -1.0));
+                cam.vup = transform.vector(Vec3::new(0.0, 1.0, 0.0));
+                scene.cameras.push(cam);
+            }
+        }
+
+        for child in array(node, "children") {
+            self.visit(index(child)?, &transform, depth + 1, scene)?;
+        }
+        Ok(())
+    }
+
+    /// 把一个网格图元转换为世界坐标下的三角网格，非三角形图元返回None
+    fn load_primitive(&self, primitive: &Json, transform: &Transform) -> io::Result<Option<TriangleMesh>> {
+        let mode = primitive.get("mode").map(index).transpose()?.unwrap_or(4);
+        if mode != 4 {
+            return Ok(None);
+        }
+        let attributes = primitive.get("attributes").ok_or_else(|| invalid("primitive without attributes"))?;
+        let attribute = |name: &str| -> io::Result<Option<(Vec<f64>, usize)>> {
+            attributes.get(name).map(|a| self.read_accessor(index(a)?)).transpose()
+        };
+
+        let (raw_positions, components) = attribute("POSITION")?.ok_or_else(|| invalid("primitive without POSITION"))?;
+        if components != 3 {
+            return Err(invalid("POSITION must be VEC3"));
+        }
+        let positions: Vec<Point3> = raw_positions
+            .chunks_exact(3)
+            .map(|c| transform.point(Point3::new(c[0] as Real, c[1] as Real, c[2] as Real)))
+            .collect();
+        let vertex_count = positions.len();
+
+        let normals: Vec<Vec3> = match attribute("NORMAL")? {
+            Some((raw, 3)) if raw.len() == 3 * vertex_count => raw
+                .chunks_exact(3)
+                .map(|c| {
+                    let n = transform.normal(Vec3::new(c[0] as Real, c[1] as Real, c[2] as Real));
+                    if n.near_zero() { n } else { vec3::unit_vector(n) }
+                })
+                .collect(),
+            Some(_) => return Err(invalid("NORMAL does not match POSITION")),
+            None => Vec::new(),
+        };
+        // glTF纹理坐标的原点在图像左上角，这里翻转v使原点位于左下角
+        let uvs: Vec<[Real; 2]> = match attribute("TEXCOORD_0")? {
+            Some((raw, 2)) if raw.len() == 2 * vertex_count => {
+                raw.chunks_exact(2).map(|c| [c[0] as Real, 1.0 - c[1] as Real]).collect()
+            }
+            Some(_) => return Err(invalid("TEXCOORD_0 does not match POSITION")),
+            None => Vec::new(),
+        };
+
+        let indices: Vec<usize> = match primitive.get("indices") {
+            Some(accessor) => {
+                let (raw, _) = self.read_accessor(index(accessor)?)?;
+                raw.iter()
+                    .map(|&i| {
+                        (i >= 0.0 && (i as usize) < vertex_count)
+                            .then_some(i as usize)
+                            .ok_or_else(|| invalid("vertex index out of range"))
+                    })
+                    .collect::<io::Result<_>>()?
+            }
+            None => (0..vertex_count).collect(),
+        };
+
+        // 镜像变换会反转三角形的绕序，交换两个顶点使正面保持朝外
+        let x = transform.vector(Vec3::new(1.0, 0.0, 0.0));
+        let y = transform.vector(Vec3::new(0.0, 1.0, 0.0));
+        let z = transform.vector(Vec3::new(0.0, 0.0, 1.0));
+        let mirrored = vec3::dot(vec3::cross(x, y), z) < 0.0;
+
+        let faces = indices
+            .chunks_exact(3)
+            .map(|c| {
+                let tri = if mirrored { [c[0], c[2], c[1]] } else { [c[0], c[1], c[2]] };
+                MeshFace {
+                    positions: tri,
+                    normals: (!normals.is_empty()).then_some(tri),
+                    uvs: (!uvs.is_empty()).then_some(tri),
+                }
+            })
+            .collect();
+
+        Ok(Some(TriangleMesh::new(positions, normals, uvs, faces, Arc::clone(&self.material))))
+    }
+
+    /// 读取访问器的全部数据
+    ///
+    /// # Returns
+    /// 返回(按元素依次排列的分量, 每个元素的分量数)，归一化的整数分量转换为[0, 1]或[-1, 1]内的值
+    fn read_accessor(&self, accessor_index: usize) -> io::Result<(Vec<f64>, usize)> {
+        let accessor = array(self.doc, "accessors")
+            .get(accessor_index)
+            .ok_or_else(|| invalid("accessor index out of range"))?;
+        if accessor.get("sparse").is_some() {
+            return Err(invalid("sparse accessors are not supported"));
+        }
+
+        let count = usize_field(accessor, "count")?;
+        let components = match accessor.get("type").and_then(Json::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(invalid("invalid accessor type")),
+        };
+        let component_type = usize_field(accessor, "componentType")?;
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(invalid("invalid component type")),
+        };
+        let normalized = matches!(accessor.get("normalized"), Some(Json::Bool(true)));
+
+        // 没有缓冲区视图的访问器全部为0
+        let Some(view) = accessor.get("bufferView") else {
+            return match count.checked_mul(components) {
+                Some(n) if n <= MAX_ZERO_ACCESSOR_VALUES => Ok((vec![0.0; n], components)),
+                _ => Err(invalid("accessor count too large")),
+            };
+        };
+        let view = array(self.doc, "bufferViews")
+            .get(index(view)?)
+            .ok_or_else(|| invalid("buffer view index out of range"))?;
+        let buffer = self
+            .buffers
+            .get(usize_field(view, "buffer")?)
+            .ok_or_else(|| invalid("buffer index out of range"))?;
+        let view_offset = optional_usize(view, "byteOffset")?;
+        let view_length = usize_field(view, "byteLength")?;
+        let stride = match optional_usize(view, "byteStride")? {
+            0 => components * size,
+            stride => stride,
+        };
+        let data = view_offset
+            .checked_add(view_length)
+            .and_then(|end| buffer.get(view_offset..end))
+            .ok_or_else(|| invalid("buffer view out of range"))?;
+        let offset = optional_usize(accessor, "byteOffset")?;
+
+        // 分配之前先确认最后一个元素也落在视图内，这样下面的下标计算都不会溢出
+        if count > 0 {
+            let end = (count - 1)
+                .checked_mul(stride)
+                .and_then(|n| n.checked_add(offset))
+                .and_then(|n| n.checked_add(components * size));
+            if end.is_none_or(|end| end > view_length) {
+                return Err(invalid("accessor out of range"));
+            }
+        }
+
+        let mut values = Vec::with_capacity(count * components);
+        for i in 0..count {
+            for c in 0..components {
+                let at = offset + i * stride + c * size;
+                let b = data.get(at..at + size).ok_or_else(|| invalid("accessor out of range"))?;
+                let value = match component_type {
+                    5120 => {
+                        let v = b[0] as i8 as f64;
+                        if normalized { (v / 127.0).max(-1.0) } else { v }
+                    }
+                    5121 => {
+                        let v = b[0] as f64;
+                        if normalized { v / 255.0 } else { v }
+                    }
+                    5122 => {
+                        let v = i16::from_le_bytes([b[0], b[1]]) as f64;
+                        if normalized { (v / 32767.0).max(-1.0) } else { v }
+                    }
+                    5123 => {
+                        let v = u16::from_le_bytes([b[0], b[1]]) as f64;
+                        if normalized { v / 65535.0 } else { v }
+                    }
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                };
+                values.push(value);
+            }
+        }
+        Ok((values, components))
+    }
+}
+
+/// 节点的局部变换：matrix(按列存储的4×4矩阵)或 平移 × 旋转 × 缩放
+fn node_transform(node: &Json) -> io::Result<Transform> {
+    let floats = |key: &str, len: usize| -> io::Result<Option<Vec<Real>>> {
+        let Some(value) = node.get(key) else {
+            return Ok(None);
+        };
+        let values = value
+            .as_array()
+            .ok_or_else(|| invalid("invalid node transform"))?
+            .iter()
+            .map(|v| v.as_f64().map(|x| x as Real))
+            .collect::<Option<Vec<_>>>()
+            .filter(|v| v.len() == len)
+            .ok_or_else(|| invalid("invalid node transform"))?;
+        Ok(Some(values))
+    };
+
+    let (m, t) = if let Some(c) = floats("matrix", 16)? {
+        (
+            [[c[0], c[4], c[8]], [c[1], c[5], c[9]], [c[2], c[6], c[10]]],
+            Vec3::new(c[12], c[13], c[14]),
+        )
+    } else {
+        let t = floats("translation", 3)?.unwrap_or(vec![0.0, 0.0, 0.0]);
+        let q = floats("rotation", 4)?.unwrap_or(vec![0.0, 0.0, 0.0, 1.0]);
+        let s = floats("scale", 3)?.unwrap_or(vec![1.0, 1.0, 1.0]);
+
+        // 单位四元数(x, y, z, w)对应的旋转矩阵，各列再乘以对应的缩放
+        let (x, y, z, w) = (q[0], q[1], q[2], q[3]);
+        let r = [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w)],
+            [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w)],
+            [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y)],
+        ];
+        (r.map(|row| [row[0] * s[0], row[1] * s[1], row[2] * s[2]]), Vec3::new(t[0], t[1], t[2]))
+    };
+
+    Transform::new(m, t).ok_or_else(|| invalid("singular node transform"))
+}
+
+/// 加载所有缓冲区的数据
+///
+/// # Arguments
+/// * `doc` - glTF文档
+/// * `base_dir` - glTF文件所在目录，外部缓冲区文件相对于此目录
+/// * `binary_chunk` - .glb文件的二进制块，供没有uri的缓冲区使用
+fn load_buffers(doc: &Json, base_dir: &Path, mut binary_chunk: Option<Vec<u8>>) -> io::Result<Vec<Vec<u8>>> {
+    array(doc, "buffers")
+        .iter()
+        .map(|buffer| {
+            let data = match buffer.get("uri").and_then(Json::as_str) {
+                None => binary_chunk.take().ok_or_else(|| invalid("missing binary chunk"))?,
+                Some(uri) if uri.starts_with("data:") => {
+                    let (header, payload) = uri.split_once(',').ok_or_else(|| invalid("invalid data uri"))?;
+                    if !header.ends_with(";base64") {
+                        return Err(invalid("data uri is not base64"));
+                    }
+                    decode_base64(payload).ok_or_else(|| invalid("invalid base64 data"))?
+                }
+                Some(uri) => fs::read(base_dir.join(uri))?,
+            };
+            if data.len() < usize_field(buffer, "byteLength")? {
+                return Err(invalid("buffer shorter than byteLength"));
+            }
+            Ok(data)
+        })
+        .collect()
+}
+
+/// 拆分.glb文件，返回(JSON文本, 二进制块)
+fn parse_glb(data: &[u8]) -> io::Result<(String, Option<Vec<u8>>)> {
+    let u32_at = |at: usize| -> io::Result<usize> {
+        let b = data.get(at..at + 4).ok_or_else(|| invalid("truncated glb"))?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+    if u32_at(4)? != 2 {
+        return Err(invalid("unsupported glb version"));
+    }
+
+    let length = u32_at(8)?.min(data.len());
+    let mut json = None;
+    let mut binary = None;
+    let mut at = 12;
+    while at + 8 <= length {
+        let chunk_length = u32_at(at)?;
+        let chunk_type = u32_at(at + 4)?;
+        let chunk = data
+            .get(at + 8..at + 8 + chunk_length)
+            .ok_or_else(|| invalid("truncated glb chunk"))?;
+        match chunk_type {
+            // "JSON"
+            0x4E4F_534A => json = Some(String::from_utf8(chunk.to_vec()).map_err(|_| invalid("invalid glb JSON chunk"))?),
+            // "BIN\0"
+            0x004E_4942 => binary = Some(chunk.to_vec()),
+            _ => {}
+        }
+        at += 8 + chunk_length;
+    }
+
+    Ok((json.ok_or_else(|| invalid("glb without JSON chunk"))?, binary))
+}
+
+/// 解码标准base64字符串，末尾的'='填充可省略
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| -> Option<u32> {
+        Some(match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        } as u32)
+    };
+
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.trim_end_matches('=').bytes() {
+        acc = (acc << 6) | value(c)?;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// 构造格式错误
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("gltf: {}", msg))
+}
+
+/// 取对象中的数组字段，不存在时为空
+fn array<'a>(value: &'a Json, key: &str) -> &'a [Json] {
+    value.get(key).and_then(Json::as_array).unwrap_or(&[])
+}
+
+/// 把JSON值解释为索引
+fn index(value: &Json) -> io::Result<usize> {
+    value.as_usize().ok_or_else(|| invalid("invalid index"))
+}
+
+/// 取必需的非负整数字段
+fn usize_field(value: &Json, key: &str) -> io::Result<usize> {
+    value
+        .get(key)
+        .and_then(Json::as_usize)
+        .ok_or_else(|| invalid(&format!("missing or invalid {}", key)))
+}
+
+/// 取可选的非负整数字段，不存在时为0
+fn optional_usize(value: &Json, key: &str) -> io::Result<usize> {
+    value.get(key).map_or(Ok(0), |v| v.as_usize().ok_or_else(|| invalid(&format!("invalid {}", key))))
+}
+
+/// 取必需的数值字段
+fn number(value: &Json, key: &str) -> io::Result<f64> {
+    value
+        .get(key)
+        .and_then(Json::as_f64)
+        .ok_or_else(|| invalid(&format!("missing or invalid {}", key)))
+}
+
+/// 解析后的JSON值
+#[derive(Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// 取对象的字段
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// 非负整数
+    fn as_usize(&self) -> Option<usize> {
+        self.as_f64().filter(|x| *x >= 0.0 && x.fract() == 0.0).map(|x| x as usize)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// 解析JSON文本
+fn parse_json(text: &str) -> io::Result<Json> {
+    let mut parser = JsonParser { bytes: text.as_bytes(), pos: 0, depth: 0 };
+    let value = parser.value().ok_or_else(|| invalid("invalid JSON"))?;
+    parser.skip_whitespace();
+    if parser.pos != parser.bytes.len() {
+        return Err(invalid("trailing characters after JSON"));
+    }
+    Ok(value)
+}
+
+/// 递归下降的JSON解析器，出错时返回None
+///
+/// # Fields
+/// - bytes: JSON文本
+/// - pos: 当前位置
+/// - depth: 当前所在的数组和对象的嵌套层数
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl JsonParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    /// 跳过空白后若下一个字符为`c`则消耗它
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 消耗一个关键字
+    fn keyword(&mut self, word: &str, value: Json) -> Option<Json> {
+        self.bytes[self.pos..].starts_with(word.as_bytes()).then(|| {
+            self.pos += word.len();
+            value
+        })
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+        match *self.bytes.get(self.pos)? {
+            b'{' | b'[' => {
+                if self.depth == MAX_JSON_DEPTH {
+                    return None;
+                }
+                self.depth += 1;
+                let value = self.container();
+                self.depth -= 1;
+                value
+            }
+            b'"' => self.string().map(Json::String),
+            b't' => self.keyword("true", Json::Bool(true)),
+            b'f' => self.keyword("false", Json::Bool(false)),
+            b'n' => self.keyword("null", Json::Null),
+            _ => {
+                let start = self.pos;
+                while self.pos < self.bytes.len() && matches!(self.bytes[self.pos], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') {
+                    self.pos += 1;
+                }
+                std::str::from_utf8(&self.bytes[start..self.pos]).ok()?.parse().ok().map(Json::Number)
+            }
+        }
+    }
+
+    /// 解析对象或数组，当前位置必须是起始的括号
+    fn container(&mut self) -> Option<Json> {
+        match self.bytes[self.pos] {
+            b'{' => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if self.eat(b'}') {
+                    return Some(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    if !self.eat(b':') {
+                        return None;
+                    }
+                    fields.push((key, self.value()?));
+                    if self.eat(b'}') {
+                        return Some(Json::Object(fields));
+                    }
+                    if !self.eat(b',') {
+                        return None;
+                    }
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.eat(b']') {
+                    return Some(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    if self.eat(b']') {
+                        return Some(Json::Array(items));
+                    }
+                    if !self.eat(b',') {
+                        return None;
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// 解析字符串，当前位置必须是起始的引号
+    fn string(&mut self) -> Option<String> {
+        if self.bytes.get(self.pos) != Some(&b'"') {
+            return None;
+        }
+        self.pos += 1;
+
+        let mut out = Vec::new();
+        loop {
+            let c = *self.bytes.get(self.pos)?;
+            self.pos += 1;
+            match c {
+                b'"' => return String::from_utf8(out).ok(),
+                b'\\' => {
+                    let escaped = *self.bytes.get(self.pos)?;
+                    self.pos += 1;
+                    let ch = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let high = self.hex4()?;
+                            let code = if (0xD800..0xDC00).contains(&high) {
+                                // 代理对
+                                if !self.bytes[self.pos..].starts_with(b"\\u") {
+                                    return None;
+                                }
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                0x10000 + ((high - 0xD800) << 10) + (low.checked_sub(0xDC00)? & 0x3FF)
+                            } else {
+                                high
+                            };
+                            char::from_u32(code)?
+                        }
+                        _ => return None,
+                    };
+                    let mut buf = [0u8; 4];
+                    out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+                _ => out.push(c),
+            }
+        }
+    }
+
+    /// 解析4位十六进制数
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = std::str::from_utf8(self.bytes.get(self.pos..self.pos + 4)?).ok()?;
+        self.pos += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::interval::Interval;
+    use crate::material::Lambertian;
+    use crate::ray::Ray;
+    use crate::rtweekend;
+
+    /// 三个f32顶点(0,0,0)、(1,0,0)、(0,1,0)的base64编码
+    const TRIANGLE_BASE64: &str = "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA";
+
+    /// 绕某个轴旋转90°的四元数分量
+    const HALF_SQRT2: f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+    fn gray() -> Arc<dyn Material + Send + Sync> {
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+    }
+
+    /// 内嵌一个三角形网格和一个透视相机的glTF文档，节点列表由调用者给出
+    fn triangle_gltf(nodes: &str) -> String {
+        format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                "buffers": [{{"byteLength": 36, "uri": "data:application/octet-stream;base64,{}"}}],
+                "bufferViews": [{{"buffer": 0, "byteLength": 36}}],
+                "accessors": [{{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}}],
+                "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}}}]}}],
+                "cameras": [{{"type": "perspective", "perspective": {{"yfov": 0.8, "aspectRatio": 1.5, "znear": 0.1}}}}],
+                "nodes": {}
+            }}"#,
+            TRIANGLE_BASE64, nodes
+        )
+    }
+
+    fn parse(text: &str) -> io::Result<GltfScene> {
+        parse_gltf(text.as_bytes().to_vec(), Path::new(""), gray())
+    }
+
+    /// 从(x, y, 0)沿-z方向的光线与场景的交点
+    fn hit_down_z(scene: &GltfScene, x: Real, y: Real) -> Option<HitRecord> {
+        let r = Ray::new(Point3::new(x, y, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+        scene
+            .world
+            .hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec)
+            .then_some(rec)
+    }
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn embedded_triangle_loads_and_is_hit() {
+        let scene = parse(&triangle_gltf(r#"[{"mesh": 0, "translation": [0, 0, -2]}]"#)).unwrap();
+        assert_eq!(scene.world.objects().len(), 1);
+        assert!(scene.cameras.is_empty());
+
+        let rec = hit_down_z(&scene, 0.25, 0.25).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-4);
+        assert!(hit_down_z(&scene, 0.75, 0.75).is_none());
+    }
+
+    #[test]
+    fn node_transforms_compose_child_trs_then_parent() {
+        // 子节点：缩放2倍、绕z轴转90°、平移(1,0,0)；父节点再平移(0,0,-5)
+        // 三角形变为(1,0,-5)、(1,2,-5)、(-1,0,-5)
+        let nodes = format!(
+            r#"[{{"translation": [0, 0, -5], "children": [1]}},
+                {{"mesh": 0, "translation": [1, 0, 0], "rotation": [0, 0, {h}, {h}], "scale": [2, 2, 2]}}]"#,
+            h = HALF_SQRT2
+        );
+        let scene = parse(&triangle_gltf(&nodes)).unwrap();
+
+        let rec = hit_down_z(&scene, 0.0, 0.5).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-4);
+        assert_near(rec.p, Point3::new(0.0, 0.5, -5.0));
+        assert!(hit_down_z(&scene, 0.9, 1.7).is_some());
+        assert!(hit_down_z(&scene, -0.5, 1.5).is_none());
+        assert!(hit_down_z(&scene, 0.25, -0.25).is_none());
+    }
+
+    #[test]
+    fn perspective_camera_follows_its_node() {
+        // 绕y轴转90°后，朝向-z的相机改为朝向-x
+        let nodes = format!(r#"[{{"camera": 0, "translation": [0, 1, 3], "rotation": [0, {h}, 0, {h}]}}]"#, h = HALF_SQRT2);
+        let scene = parse(&triangle_gltf(&nodes)).unwrap();
+        assert_eq!(scene.cameras.len(), 1);
+
+        let cam = &scene.cameras[0];
+        assert!((cam.vfov - (0.8 as Real).to_degrees()).abs() < 1e-4);
+        assert!((cam.aspect_ratio - 1.5).abs() < 1e-6);
+        assert_near(cam.lookfrom, Point3::new(0.0, 1.0, 3.0));
+        assert_near(cam.lookat, Point3::new(-1.0, 1.0, 3.0));
+        assert_near(cam.vup, Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn huge_counts_and_offsets_are_rejected_without_allocating() {
+        let base = triangle_gltf(r#"[{"mesh": 0}]"#);
+        let cases = [
+            // 伪造的count
+            base.replace(r#""count": 3"#, r#""count": 1e17"#),
+            // 没有缓冲区视图的全零访问器
+            base.replace(r#""bufferView": 0, "#, "").replace(r#""count": 3"#, r#""count": 1e17"#),
+            // 视图偏移加长度溢出
+            base.replace(r#""buffer": 0, "byteLength": 36"#, r#""buffer": 0, "byteOffset": 1.8e19, "byteLength": 1.8e19"#),
+            // 访问器偏移溢出
+            base.replace(r#""componentType""#, r#""byteOffset": 1.8e19, "componentType""#),
+            // 步长乘以元素数溢出
+            base.replace(r#""byteLength": 36}]"#, r#""byteLength": 36, "byteStride": 1.8e19}]"#),
+            // 视图超出缓冲区
+            base.replace(r#""buffer": 0, "byteLength": 36"#, r#""buffer": 0, "byteOffset": 4, "byteLength": 36"#),
+        ];
+        for (k, text) in cases.iter().enumerate() {
+            assert_ne!(text, &base, "case {} did not apply", k);
+            let err = parse(text).err().unwrap_or_else(|| panic!("case {} loaded", k));
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn deeply_nested_json_is_rejected() {
+        assert!(parse_json(&format!("{}{}", "[".repeat(MAX_JSON_DEPTH), "]".repeat(MAX_JSON_DEPTH))).is_ok());
+        assert!(parse_json(&format!("{}{}", "[".repeat(MAX_JSON_DEPTH + 1), "]".repeat(MAX_JSON_DEPTH + 1))).is_err());
+        assert!(parse(&"[".repeat(200_000)).is_err());
+        assert!(parse(&r#"{"a":"#.repeat(200_000)).is_err());
+    }
+}
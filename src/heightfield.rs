@@ -0,0 +1,364 @@
+//! 高度场模块
+//!
+//! 提供由规则网格高度值定义的地形表面，可从灰度PGM图像加载。
+//! 求交时用二维DDA沿光线逐个遍历网格单元，只测试光线经过的单元中的两个三角形
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::mesh;
+use super::rtweekend::{self, Real};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 高度场地形
+///
+/// 网格位于xz平面内，共nx × nz个采样点，每个单元拆成两个三角形
+///
+/// # Fields
+/// - origin: 网格(0, 0)采样点在高度为0时的位置
+/// - cell_x, cell_z: 单元在x和z方向的尺寸
+/// - nx, nz: 两个方向上的采样点数量
+/// - heights: 按行(z方向)存储的高度值，第j行第i列为heights[j * nx + i]
+/// - mat: 材质
+/// - bbox: 包围盒
+pub struct Heightfield {
+    origin: Point3,
+    cell_x: Real,
+    cell_z: Real,
+    nx: usize,
+    nz: usize,
+    heights: Vec<Real>,
+    mat: Arc<dyn Material + Send + Sync>,
+    bbox: Aabb,
+}
+
+impl Heightfield {
+    /// 由高度网格创建高度场
+    ///
+    /// # Arguments
+    /// * `origin` - 网格角点位置，高度值相对于其y坐标
+    /// * `size_x` - 网格在x方向的总宽度
+    /// * `size_z` - 网格在z方向的总深度
+    /// * `nx` - x方向采样点数量(至少为2)
+    /// * `nz` - z方向采样点数量(至少为2)
+    /// * `heights` - 共nx × nz个高度值，按行存储
+    /// * `material` - 材质
+    ///
+    /// # Note
+    /// 采样点少于2×2或高度值数量不符时会panic
+    pub fn new(
+        origin: Point3,
+        size_x: Real,
+        size_z: Real,
+        nx: usize,
+        nz: usize,
+        heights: Vec<Real>,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        assert!(nx >= 2 && nz >= 2, "高度场至少需要2×2个采样点");
+        assert_eq!(heights.len(), nx * nz, "高度值数量与网格尺寸不符");
+
+        let (min_h, max_h) = heights
+            .iter()
+            .fold((rtweekend::INFINITY, -rtweekend::INFINITY), |(lo, hi), &h| (lo.min(h), hi.max(h)));
+        let bbox = Aabb::from_points(
+            origin + Vec3::new(0.0, min_h, 0.0),
+            origin + Vec3::new(size_x, max_h, size_z),
+        );
+
+        Self {
+            origin,
+            cell_x: size_x / (nx - 1) as Real,
+            cell_z: size_z / (nz - 1) as Real,
+            nx,
+            nz,
+            heights,
+            mat: material,
+            bbox,
+        }
+    }
+
+    /// 从灰度PGM图像(P2文本格式或P5二进制格式)加载高度场
+    ///
+    /// 像素值按最大灰度值线性映射到[0, max_height]，图像的列对应x方向，行对应z方向
+    ///
+    /// # Arguments
+    /// * `path` - PGM文件路径
+    /// * `origin` - 网格角点位置
+    /// * `size_x` - 网格在x方向的总宽度
+    /// * `size_z` - 网格在z方向的总深度
+    /// * `max_height` - 最大灰度值对应的高度
+    /// * `material` - 材质
+    ///
+    /// # Returns
+    /// 返回加载的高度场，文件无法读取或格式错误时返回错误
+    pub fn from_pgm(
+        path: impl AsRef<Path>,
+        origin: Point3,
+        size_x: Real,
+        size_z: Real,
+        max_height: Real,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> io::Result<Self> {
+        let (width, height, pixels) = read_pgm(&fs::read(path)?)?;
+        if width < 2 || height < 2 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "heightmap must be at least 2x2 pixels"));
+        }
+        let heights = pixels.into_iter().map(|v| v * max_height).collect();
+        Ok(Self::new(origin, size_x, size_z, width, height, heights, material))
+    }
+
+    /// 获取采样点(i, j)的世界坐标
+    fn vertex(&self, i: usize, j: usize) -> Point3 {
+        self.origin + Vec3::new(
+            i as Real * self.cell_x,
+            self.heights[j * self.nx + i],
+            j as Real * self.cell_z,
+        )
+    }
+
+    /// 测试光线与单元(i, j)中两个三角形的交点
+    ///
+    /// # Returns
+    /// 命中时返回(t, 三角形的三个顶点)
+    fn hit_cell(&self, i: usize, j: usize, r: &Ray, ray_t: &Interval) -> Option<(Real, [Point3; 3])> {
+        let p00 = self.vertex(i, j);
+        let p10 = self.vertex(i + 1, j);
+        let p01 = self.vertex(i, j + 1);
+        let p11 = self.vertex(i + 1, j + 1);
+
+        let mut nearest = None;
+        let mut closest = ray_t.max;
+        for tri in [[p00, p01, p11], [p00, p11, p10]] {
+            if let Some((t, _, _)) = mesh::intersect_triangle(tri[0], tri[1], tri[2], r, &Interval::new(ray_t.min, closest)) {
+                closest = t;
+                nearest = Some((t, tri));
+            }
+        }
+        nearest
+    }
+}
+
+impl Hittable for Heightfield {
+    /// 实现高度场的光线命中检测
+    ///
+    /// 在xz平面内用DDA(Amanatides-Woo算法)按光线经过的先后顺序遍历单元，
+    /// 每个单元的三角形都位于单元范围内，第一个有交点的单元即给出最近交点
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let Some(span) = self.bbox.hit_interval(r, ray_t) else {
+            return false;
+        };
+
+        let dir = r.direction();
+        let entry = r.at(span.min) - self.origin;
+        let cells = [self.nx - 1, self.nz - 1];
+        let cell_size = [self.cell_x, self.cell_z];
+        let entry_xz = [entry.x(), entry.z()];
+        let dir_xz = [dir.x(), dir.z()];
+
+        // 当前单元、步进方向、到下一条单元边界的t值以及跨过一个单元的t增量
+        let mut cell = [0_usize; 2];
+        let mut step = [0_isize; 2];
+        let mut t_next = [rtweekend::INFINITY; 2];
+        let mut t_delta = [rtweekend::INFINITY; 2];
+        for k in 0..2 {
+            let c = (entry_xz[k] / cell_size[k]).floor().clamp(0.0, (cells[k] - 1) as Real);
+            cell[k] = c as usize;
+            if dir_xz[k] > 0.0 {
+                step[k] = 1;
+                t_next[k] = span.min + ((c + 1.0) * cell_size[k] - entry_xz[k]) / dir_xz[k];
+                t_delta[k] = cell_size[k] / dir_xz[k];
+            } else if dir_xz[k] < 0.0 {
+                step[k] = -1;
+                t_next[k] = span.min + (c * cell_size[k] - entry_xz[k]) / dir_xz[k];
+                t_delta[k] = -cell_size[k] / dir_xz[k];
+            }
+        }
+
+        loop {
+            if let Some((t, [p0, p1, p2])) = self.hit_cell(cell[0], cell[1], r, ray_t) {
+                hit_record.t = t;
+                hit_record.p = r.at(t);
+
+                let local = hit_record.p - self.origin;
+                hit_record.u = local.x() / (self.cell_x * cells[0] as Real);
+                hit_record.v = local.z() / (self.cell_z * cells[1] as Real);
+                hit_record.tangent = Vec3::new(1.0, 0.0, 0.0);
+
+                // 三角形顶点顺序保证几何法线朝上(+y)
+                let outward_normal = vec3::unit_vector(vec3::cross(p1 - p0, p2 - p0));
+                hit_record.set_face_normal(r, outward_normal);
+                hit_record.mat = Some(Arc::clone(&self.mat));
+                return true;
+            }
+
+            // 走向较近的单元边界
+            let k = if t_next[0] < t_next[1] { 0 } else { 1 };
+            if t_next[k] > span.max {
+                return false;
+            }
+            let next = cell[k] as isize + step[k];
+            if next < 0 || next >= cells[k] as isize {
+                return false;
+            }
+            cell[k] = next as usize;
+            t_next[k] += t_delta[k];
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// 解析PGM灰度图像
+///
+/// # Returns
+/// 返回(宽, 高, 归一化到[0,1]的像素值)
+fn read_pgm(data: &[u8]) -> io::Result<(usize, usize, Vec<Real>)> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("pgm: {}", msg));
+
+    // 读取文件头中的下一个以空白分隔的字段，跳过注释
+    let mut pos = 0;
+    let next_token = |pos: &mut usize| -> Option<String> {
+        loop {
+            while *pos < data.len() && data[*pos].is_ascii_whitespace() {
+                *pos += 1;
+            }
+            if *pos < data.len() && data[*pos] == b'#' {
+                while *pos < data.len() && data[*pos] != b'\n' {
+                    *pos += 1;
+                }
+                continue;
+            }
+            break;
+        }
+        let start = *pos;
+        while *pos < data.len() && !data[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        (start < *pos).then(|| String::from_utf8_lossy(&data[start..*pos]).into_owned())
+    };
+    let next_number = |pos: &mut usize| -> io::Result<usize> {
+        next_token(pos)
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| invalid("invalid header"))
+    };
+
+    let magic = next_token(&mut pos).ok_or_else(|| invalid("empty file"))?;
+    let width = next_number(&mut pos)?;
+    let height = next_number(&mut pos)?;
+    let max_value = next_number(&mut pos)?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(invalid("invalid max value"));
+    }
+    let count = width * height;
+
+    let values: Vec<usize> = match magic.as_str() {
+        "P2" => (0..count).map(|_| next_number(&mut pos)).collect::<io::Result<_>>()?,
+        "P5" => {
+            // 头部之后恰好一个空白字符，然后是二进制像素
+            let body = data.get(pos + 1..).ok_or_else(|| invalid("missing pixel data"))?;
+            let bytes_per_value = if max_value < 256 { 1 } else { 2 };
+            if body.len() < count * bytes_per_value {
+                return Err(invalid("truncated pixel data"));
+            }
+            body.chunks_exact(bytes_per_value)
+                .take(count)
+                .map(|c| c.iter().fold(0, |acc, &b| (acc << 8) | b as usize))
+                .collect()
+        }
+        _ => return Err(invalid("unsupported format, expected P2 or P5")),
+    };
+
+    let pixels = values.into_iter().map(|v| v as Real / max_value as Real).collect();
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    /// 覆盖[0,4]²的5×5网格，高度由x坐标决定
+    fn terrain(height: impl Fn(Real) -> Real) -> Heightfield {
+        let heights = (0..25).map(|k| height((k % 5) as Real)).collect();
+        Heightfield::new(Point3::default(), 4.0, 4.0, 5, 5, heights, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))
+    }
+
+    /// x < 2处高度为0，x > 3处高度为2，中间为斜坡
+    fn step() -> Heightfield {
+        terrain(|x| if x < 3.0 { 0.0 } else { 2.0 })
+    }
+
+    /// 命中时检查法线为单位长度且朝向光线来的一侧
+    fn cast(field: &Heightfield, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        let r = Ray::new(origin, direction);
+        if !field.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return None;
+        }
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!(vec3::dot(rec.normal, direction) < 0.0);
+        Some(rec)
+    }
+
+    #[test]
+    fn slope_is_hit_at_its_interpolated_height() {
+        let slope = terrain(|x| 0.5 * x);
+        let rec = cast(&slope, Point3::new(2.3, 10.0, 1.7), Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((rec.t - (10.0 - 1.15)).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal - vec3::unit_vector(Vec3::new(-0.5, 1.0, 0.0))).length() < 1e-4);
+        assert!((rec.u - 2.3 / 4.0).abs() < 1e-4 && (rec.v - 1.7 / 4.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn traversal_crosses_empty_cells_to_reach_the_ramp() {
+        // 沿x方向水平穿过两个平坦单元后撞上斜坡的中点
+        let rec = cast(&step(), Point3::new(-1.0, 1.0, 1.5), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 3.5).abs() < 1e-4);
+        assert!(rec.front_face);
+        // 斜着穿过多个单元
+        let rec = cast(&step(), Point3::new(-1.0, 1.0, -1.0), Vec3::new(1.0, 0.0, 0.7)).unwrap();
+        assert!((rec.p.x() - 2.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rays_above_outside_or_leaving_the_terrain_miss() {
+        let field = step();
+        assert!(cast(&field, Point3::new(-1.0, 2.5, 1.5), Vec3::new(1.0, 0.0, 0.0)).is_none());
+        assert!(cast(&field, Point3::new(5.0, 10.0, 1.5), Vec3::new(0.0, -1.0, 0.0)).is_none());
+        assert!(cast(&field, Point3::new(1.0, 0.5, 1.5), Vec3::new(0.0, 1.0, 0.0)).is_none());
+        assert!(cast(&field, Point3::new(1.0, 0.5, 1.5), Vec3::new(-1.0, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn ray_starting_under_the_surface_hits_its_back_face() {
+        let rec = cast(&step(), Point3::new(3.5, 1.0, 1.5), Vec3::new(-1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+        let rec = cast(&step(), Point3::new(1.0, -1.0, 1.5), Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn pgm_text_and_binary_encodings_agree() {
+        let text = read_pgm(b"P2\n# comment\n2 2\n255\n0 51\n255 102\n").unwrap();
+        let binary = read_pgm(b"P5 2 2 255\n\x00\x33\xff\x66").unwrap();
+        assert_eq!(text.0, 2);
+        assert_eq!(text.1, 2);
+        assert_eq!(text.2, binary.2);
+        assert_eq!(text.2, vec![0.0, 0.2, 1.0, 0.4]);
+        assert!(read_pgm(b"P5 2 2 255\n\x00\x33").is_err());
+        assert!(read_pgm(b"P6 2 2 255\n").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! 非均匀介质模块
+//!
+//! 提供密度随位置变化的参与介质(云、烟柱)，使用delta tracking在边界内采样散射点
+
+use super::vec3::{Point3, Vec3};
+use super::ray::Ray;
+use super::color::Color;
+use super::material::{Isotropic, Material};
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::texture::Texture;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 单条光线在介质中最多的追踪步数，防止密度函数远小于上界时循环过久
+const MAX_STEPS: usize = 4096;
+
+/// 密度随位置变化的参与介质
+///
+/// 以`max_density`为上界按指数分布抽取试探距离，
+/// 在试探点以 density(p) / max_density 的概率接受为真实散射，否则视为虚碰撞继续前进。
+/// 这样得到的散射距离与按真实密度积分的结果无偏
+///
+/// # Fields
+/// - boundary: 介质的边界，必须是封闭的凸物体
+/// - density: 密度函数，取值应在[0, max_density]内
+/// - max_density: 密度上界(majorant)
+/// - phase_function: 相函数材质
+pub struct HeterogeneousMedium {
+    boundary: Arc<dyn Hittable>,
+    density: Box<dyn Fn(Point3) -> Real + Send + Sync>,
+    max_density: Real,
+    phase_function: Arc<dyn Material + Send + Sync>,
+}
+
+impl HeterogeneousMedium {
+    /// 由密度函数创建非均匀介质
+    ///
+    /// # Arguments
+    /// * `boundary` - 介质边界
+    /// * `density` - 世界坐标到密度的函数
+    /// * `max_density` - 密度上界，超过上界的密度会被截断
+    /// * `albedo` - 散射反照率
+    pub fn new(
+        boundary: Arc<dyn Hittable>,
+        density: impl Fn(Point3) -> Real + Send + Sync + 'static,
+        max_density: Real,
+        albedo: Color,
+    ) -> Self {
+        Self {
+            boundary,
+            density: Box::new(density),
+            max_density,
+            phase_function: Arc::new(Isotropic::new(albedo)),
+        }
+    }
+
+    /// 由三维纹理创建非均匀介质
+    ///
+    /// 密度取纹理在该点颜色三个分量的平均值(截断到[0, 1])乘以`max_density`
+    ///
+    /// # Arguments
+    /// * `boundary` - 介质边界
+    /// * `texture` - 密度纹理，只使用命中点位置参数
+    /// * `max_density` - 纹理值为1时的密度
+    /// * `albedo` - 散射反照率
+    pub fn from_texture(
+        boundary: Arc<dyn Hittable>,
+        texture: Arc<dyn Texture>,
+        max_density: Real,
+        albedo: Color,
+    ) -> Self {
+        let density = move |p: Point3| {
+            let c = texture.value(0.0, 0.0, p);
+            max_density * ((c.x() + c.y() + c.z()) / 3.0).clamp(0.0, 1.0)
+        };
+        Self::new(boundary, density, max_density, albedo)
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    /// 实现介质的光线命中检测
+    ///
+    /// 先求光线在边界内的区间，再在区间内做delta tracking。
+    /// 命中点的法线和正反面没有意义，取任意值
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        if self.max_density <= 0.0 {
+            return false;
+        }
+
+        let mut rec1 = HitRecord::default();
+        let mut rec2 = HitRecord::default();
+
+        if !self.boundary.hit(r, &Interval::new(-rtweekend::INFINITY, rtweekend::INFINITY), &mut rec1) {
+            return false;
+        }
+        if !self.boundary.hit(r, &Interval::new(rec1.t + 0.0001, rtweekend::INFINITY), &mut rec2) {
+            return false;
+        }
+
+        rec1.t = rec1.t.max(ray_t.min);
+        rec2.t = rec2.t.min(ray_t.max);
+        if rec1.t >= rec2.t {
+            return false;
+        }
+        rec1.t = rec1.t.max(0.0);
+
+        let ray_length = r.direction().length();
+        let mut t = rec1.t;
+        for _ in 0..MAX_STEPS {
+            // 1 - random_double() 落在(0, 1]内，避免对0取对数
+            t -= (1.0 - rtweekend::random_double()).ln() / (self.max_density * ray_length);
+            if t >= rec2.t {
+                return false;
+            }
+
+            let p = r.at(t);
+            let density = (self.density)(p).clamp(0.0, self.max_density);
+            if rtweekend::random_double() * self.max_density < density {
+                hit_record.t = t;
+                hit_record.p = p;
+                hit_record.normal = Vec3::new(1.0, 0.0, 0.0); // 任意值
+                hit_record.front_face = true; // 任意值
+                hit_record.u = 0.0;
+                hit_record.v = 0.0;
+                hit_record.tangent = Vec3::default();
+                hit_record.mat = Some(Arc::clone(&self.phase_function));
+                return true;
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
@@ -2,11 +2,15 @@
 //!
 //! 提供光线与物体相交的记录结构和抽象接口
 
+use std::any::Any;
 use std::sync::Arc;
 use super::vec3::{self, Vec3, Point3};
 use super::ray::Ray;
 use super::interval::Interval;
+use super::rtweekend::{self, Real};
 use super::material::Material;
+use super::aabb::Aabb;
+use super::onb::Onb;
 
 /// 光线与物体相交的记录
 /// 
@@ -15,19 +19,25 @@ use super::material::Material;
 /// - normal: 命中点法线向量
 /// - mat: 命中物体的材质
 /// - t: 光线参数值
+/// - u, v: 命中点的表面参数坐标(纹理坐标)
+/// - tangent: 沿u增大方向的单位切向量，无参数化的物体为零向量
 /// - front_face: 是否命中物体正面
 #[derive(Clone, Default)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
     pub mat: Option<Arc<dyn Material + Send + Sync>>,
-    pub t: f64,
+    pub t: Real,
+    pub u: Real,
+    pub v: Real,
+    pub tangent: Vec3,
     pub front_face: bool,
 }
 /// 可命中物体的抽象接口
 /// 
-/// 任何可以被光线命中的物体都应实现此trait
-pub trait Hittable: Send + Sync {
+/// 任何可以被光线命中的物体都应实现此trait。
+/// 以`Any`为父trait，便于在运行时识别具体类型(如展开嵌套的HittableList)
+pub trait Hittable: Any + Send + Sync {
     /// 检查光线是否命中物体
     /// 
     /// # Arguments
@@ -38,6 +48,35 @@ pub trait Hittable: Send + Sync {
     /// # Returns
     /// 如果光线命中物体返回true，否则返回false
     fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool;
+
+    /// 检查光线在`ray_t`范围内是否与物体有任意交点
+    ///
+    /// 用于阴影和可见性测试，只关心是否被遮挡而不需要最近的交点，
+    /// 物体列表和加速结构找到第一个交点即可返回。默认调用`hit`
+    ///
+    /// # Arguments
+    /// * `r` - 入射光线
+    /// * `ray_t` - 光线参数有效范围
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.hit(r, ray_t, &mut HitRecord::default())
+    }
+
+    /// 获取物体的轴对齐包围盒
+    fn bounding_box(&self) -> Aabb;
+
+    /// 从`origin`沿`direction`方向采样到该物体的概率密度(立体角测度)
+    ///
+    /// 用于向光源进行重要性采样，默认返回0.0
+    fn pdf_value(&self, _origin: Point3, _direction: Vec3) -> Real {
+        0.0
+    }
+
+    /// 从`origin`出发随机生成一个指向该物体的方向
+    ///
+    /// 默认返回任意的固定方向，需要作为光源采样的物体应重写
+    fn random(&self, _origin: Point3) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
 }
 
 impl HitRecord {
@@ -61,4 +100,42 @@ impl HitRecord {
            -outward_normal
        };
    }
+
+    /// 命中点的切线空间
+    ///
+    /// 切向量先对法线做Gram-Schmidt正交化，副切向量为 法线 × 切向量
+    ///
+    /// # Returns
+    /// 返回u轴为切向量、v轴为副切向量、w轴为法线的正交基；
+    /// 物体没有提供切向量或切向量与法线平行时返回None
+    pub fn tangent_frame(&self) -> Option<Onb> {
+        let n = self.normal;
+        let t = self.tangent - vec3::dot(self.tangent, n) * n;
+        if t.near_zero() {
+            return None;
+        }
+        let t = vec3::unit_vector(t);
+        Some(Onb::from_axes(t, vec3::cross(n, t), n))
+    }
+
+    /// 计算从命中点出发、沿`direction`散射的光线起点
+    ///
+    /// 起点沿法线向`direction`所在的一侧偏移，偏移量随命中点坐标的量级增大，
+    /// 抵消浮点误差使命中点略微落到表面另一侧的问题，坐标很大的场景中
+    /// 也不会出现自相交噪点
+    ///
+    /// # Arguments
+    /// * `direction` - 散射方向(反射时在法线一侧，折射时在另一侧)
+    ///
+    /// # Returns
+    /// 返回偏移后的光线起点
+    pub fn spawn_origin(&self, direction: Vec3) -> Point3 {
+        let magnitude = self.p.x().abs().max(self.p.y().abs()).max(self.p.z().abs()).max(1.0);
+        let offset = rtweekend::ORIGIN_OFFSET_EPSILON * magnitude * self.normal;
+        if vec3::dot(direction, self.normal) >= 0.0 {
+            self.p + offset
+        } else {
+            self.p - offset
+        }
+    }
 }
\ No newline at end of file
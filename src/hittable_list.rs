@@ -2,8 +2,9 @@
 //!
 //! 提供HittableList结构体，用于管理多个可命中物体的集合
 
-use std::rc::Rc;
-use std::sync::Arc;
+use std::any::Any;
+use std::borrow::Cow;
+use std::sync::{Arc, OnceLock};
 
 use super::hittable::{
     HitRecord,
@@ -11,14 +12,21 @@ use super::hittable::{
 };
 use super::ray::Ray;
 use super::interval::Interval;
+use super::aabb::{self, Aabb};
+use super::stats;
 
 /// 可命中物体列表，包含多个实现Hittable trait的对象
 /// 
 /// # Fields
 /// - objects: 可命中物体集合，使用引用计数智能指针管理
+/// - bboxes: 各物体包围盒的惰性缓存，首次求交时建立，`add`/`clear`时失效
+///
+/// 直接修改`objects`不会使缓存失效：增删物体后缓存因长度不符而被忽略，
+/// 但原地替换物体后缓存会过期，此时应改用`clear`和`add`重建列表
 #[derive(Default)]
 pub struct HittableList {
     pub objects: Vec<Arc<dyn Hittable>>,
+    bboxes: OnceLock<Vec<Aabb>>,
 }
 
 impl HittableList {
@@ -32,12 +40,14 @@ impl HittableList {
     pub fn new(object: Arc<dyn Hittable>) -> Self {
         Self {
             objects: vec![object],
+            bboxes: OnceLock::new(),
         }
     }
 
     /// 清空物体列表
     pub fn clear(&mut self) {
         self.objects.clear();
+        self.bboxes = OnceLock::new();
     }
 
     /// 向列表中添加新物体
@@ -46,6 +56,41 @@ impl HittableList {
     /// * `object` - 要添加的物体
     pub fn add(&mut self, object: Arc<dyn Hittable>) {
         self.objects.push(object);
+        self.bboxes = OnceLock::new();
+    }
+
+    /// 列表中的物体
+    pub fn objects(&self) -> &[Arc<dyn Hittable>] {
+        &self.objects
+    }
+
+    /// 取出列表中的物体
+    pub fn into_objects(self) -> Vec<Arc<dyn Hittable>> {
+        self.objects
+    }
+
+    /// 获取各物体包围盒的缓存，与`objects`一一对应
+    ///
+    /// 缓存建立后`objects`的长度被直接修改时，不使用缓存而临时计算
+    fn cached_boxes(&self) -> Cow<'_, [Aabb]> {
+        let boxes = || self.objects.iter().map(|object| object.bounding_box()).collect();
+        let cached = self.bboxes.get_or_init(boxes);
+        if cached.len() == self.objects.len() { Cow::Borrowed(cached) } else { Cow::Owned(boxes()) }
+    }
+
+    /// 递归展开嵌套的HittableList，返回只包含非列表物体的扁平列表
+    /// 
+    /// 减少逐层动态分发，提升遍历的局部性，适合在构建加速结构前调用
+    pub fn flatten(&self) -> HittableList {
+        let mut flat = HittableList::default();
+        for object in &self.objects {
+            let any: &dyn Any = object.as_ref();
+            match any.downcast_ref::<HittableList>() {
+                Some(list) => flat.objects.extend(list.flatten().into_objects()),
+                None => flat.add(Arc::clone(object)),
+            }
+        }
+        flat
     }
 
     // pub fn hit(&self, r: &Ray, ray_tmin: f64, ray_tmax: f64, rec: &mut HitRecord) -> bool {
@@ -77,14 +122,20 @@ impl Hittable for HittableList {
     /// 如果光线命中任何物体返回true，否则返回false
     /// 
     /// # Note
-    /// 只记录最近的命中结果
+    /// 只记录最近的命中结果；光线在当前最近距离内未穿过物体包围盒时直接跳过该物体
     fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
         let mut temp_rec = HitRecord::default();
         let mut hit_anything = false;
         let mut closest_so_far = ray_t.max;
+        let boxes = self.cached_boxes();
 
-        for object in self.objects.iter() {
-            if object.hit(r, &Interval::new(ray_t.min, closest_so_far), &mut temp_rec) {
+        for (k, object) in self.objects.iter().enumerate() {
+            let t_range = Interval::new(ray_t.min, closest_so_far);
+            if !boxes[k].hit(r, &t_range) {
+                continue;
+            }
+            stats::count_intersection_test();
+            if object.hit(r, &t_range, &mut temp_rec) {
                 hit_anything = true;
                 closest_so_far = temp_rec.t;
                 *rec = temp_rec.clone();
@@ -92,4 +143,132 @@ impl Hittable for HittableList {
         }
         hit_anything
     }
-}
\ No newline at end of file
+
+    /// 任一物体被命中即返回，不需要比较距离
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        let boxes = self.cached_boxes();
+        self.objects.iter().enumerate().any(|(k, object)| {
+            if !boxes[k].hit(r, ray_t) {
+                return false;
+            }
+            stats::count_intersection_test();
+            object.hit_any(r, ray_t)
+        })
+    }
+
+    /// 包含列表中所有物体的包围盒
+    fn bounding_box(&self) -> Aabb {
+        self.cached_boxes()
+            .iter()
+            .fold(aabb::EMPTY, |bbox, b| Aabb::surrounding(&bbox, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::{Lambertian, Material};
+    use crate::quad::Quad;
+    use crate::rtweekend::{self, Real};
+    use crate::sphere::Sphere;
+    use crate::vec3::{Point3, Vec3};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn gray() -> Arc<dyn Material + Send + Sync> {
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+    }
+
+    /// 列表中具体类型为T的物体个数
+    fn count_of<T: Any>(list: &HittableList) -> usize {
+        list.objects.iter().filter(|object| (object.as_ref() as &dyn Any).is::<T>()).count()
+    }
+
+    /// 由六个四边形组成的单位立方体，每个面单独加入列表
+    fn quad_box() -> HittableList {
+        let (dx, dy, dz) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let o = Point3::default();
+        let mut sides = HittableList::default();
+        sides.add(Arc::new(Quad::new(o + dz, dx, dy, gray())));
+        sides.add(Arc::new(Quad::new(o + dx + dz, -dz, dy, gray())));
+        sides.add(Arc::new(Quad::new(o + dx, -dx, dy, gray())));
+        sides.add(Arc::new(Quad::new(o, dz, dy, gray())));
+        sides.add(Arc::new(Quad::new(o + dy + dz, dx, -dz, gray())));
+        sides.add(Arc::new(Quad::new(o, dx, dz, gray())));
+        sides
+    }
+
+    #[test]
+    fn flatten_inlines_nested_lists() {
+        // 场景 = [球, [立方体的六个面], [[空列表]]]
+        let mut world = HittableList::new(Arc::new(Sphere::new(Point3::new(3.0, 0.0, 0.0), 1.0, gray())));
+        world.add(Arc::new(quad_box()));
+        world.add(Arc::new(HittableList::new(Arc::new(HittableList::default()))));
+
+        let flat = world.flatten();
+        assert_eq!(flat.objects.len(), 7);
+        assert_eq!(count_of::<HittableList>(&flat), 0);
+        assert_eq!(count_of::<Quad>(&flat), 6);
+        assert_eq!(count_of::<Sphere>(&flat), 1);
+
+        // 展开前后求交结果相同
+        let r = Ray::new(Point3::new(0.5, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+        let (mut a, mut b) = (HitRecord::default(), HitRecord::default());
+        assert!(world.hit(&r, &ray_t, &mut a) && flat.hit(&r, &ray_t, &mut b));
+        assert_eq!(a.t, b.t);
+    }
+
+    /// 记录`hit`被调用次数的包装物体
+    struct Probe {
+        inner: Sphere,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Hittable for Probe {
+        fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.inner.hit(r, ray_t, rec)
+        }
+
+        fn bounding_box(&self) -> Aabb {
+            self.inner.bounding_box()
+        }
+    }
+
+    #[test]
+    fn bounding_boxes_skip_objects_behind_the_ray() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut world = HittableList::default();
+        for k in 0..100 {
+            let center = Point3::new((k % 10) as Real - 4.5, (k / 10) as Real - 4.5, 5.0);
+            world.add(Arc::new(Probe { inner: Sphere::new(center, 0.4, gray()), calls: calls.clone() }));
+        }
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+
+        // 100个球都在光线起点后方，全部被包围盒剔除
+        let away = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!world.hit(&away, &ray_t, &mut HitRecord::default()));
+        assert!(!world.hit_any(&away, &ray_t));
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+        // 朝向球阵时只对包围盒被穿过的少数球求交
+        let toward = Ray::new(Point3::new(0.5, 0.5, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(world.hit(&toward, &ray_t, &mut HitRecord::default()));
+        let n = calls.load(Ordering::Relaxed);
+        assert!((1..10).contains(&n), "hit calls = {}", n);
+
+        // 加入新物体后缓存失效，新物体参与求交
+        world.add(Arc::new(Sphere::new(Point3::new(0.0, 0.0, -5.0), 1.0, gray())));
+        let mut rec = HitRecord::default();
+        assert!(world.hit(&away, &ray_t, &mut rec));
+        assert!((rec.t - 4.0).abs() < 1e-4);
+        assert_eq!(world.objects().len(), 101);
+
+        // 直接向objects添加物体后，过期的缓存不会被使用
+        world.objects.push(Arc::new(Sphere::new(Point3::new(0.0, 0.0, -2.0), 1.0, gray())));
+        assert!(world.hit(&away, &ray_t, &mut rec));
+        assert!((rec.t - 1.0).abs() < 1e-4);
+        assert!(world.hit_any(&away, &ray_t));
+    }
+}
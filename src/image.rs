@@ -0,0 +1,1004 @@
+//! 图像解码模块
+//!
+//! 不依赖外部库解码常见的图像格式，供图像纹理使用：
+//! PNG(所有颜色类型和位深，含隔行扫描)、基线JPEG(哈夫曼编码，灰度或YCbCr，任意色度采样)
+//! 以及PPM/PGM。解码结果统一为8位RGB，透明通道被丢弃
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 8位RGB图像
+///
+/// # Fields
+/// - width: 宽度
+/// - height: 高度
+/// - pixels: 按行优先排列的像素，第一行为图像顶部
+#[derive(Clone, Debug, Default)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    /// 获取像素(i, j)，i为列、j为行
+    pub fn pixel(&self, i: usize, j: usize) -> [u8; 3] {
+        self.pixels[j * self.width + i]
+    }
+}
+
+/// 从文件加载图像，格式由文件内容判断
+///
+/// # Returns
+/// 文件无法读取时返回IO错误，格式不支持或数据损坏时返回`InvalidData`错误
+pub fn load_image(path: impl AsRef<Path>) -> io::Result<Image> {
+    decode_image(&fs::read(path)?)
+}
+
+/// 解码内存中的图像数据
+///
+/// # Returns
+/// 格式不支持或数据损坏时返回`InvalidData`错误
+pub fn decode_image(data: &[u8]) -> io::Result<Image> {
+    if data.starts_with(PNG_SIGNATURE) {
+        decode_png(data)
+    } else if data.starts_with(&[0xff, 0xd8]) {
+        decode_jpeg(data)
+    } else if data.len() >= 2 && data[0] == b'P' && matches!(data[1], b'2' | b'3' | b'5' | b'6') {
+        decode_netpbm(data)
+    } else {
+        Err(invalid("image", "unsupported format, expected PNG, JPEG or PPM/PGM"))
+    }
+}
+
+/// 构造`InvalidData`错误
+fn invalid(format: &str, msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", format, msg))
+}
+
+/// 检查图像尺寸，防止损坏的文件头导致巨量内存分配
+fn check_size(format: &str, width: usize, height: usize) -> io::Result<()> {
+    if width == 0 || height == 0 || width.checked_mul(height).is_none_or(|n| n > 1 << 28) {
+        return Err(invalid(format, "invalid image size"));
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// DEFLATE
+// ---------------------------------------------------------------------------
+
+/// 低位在前的位读取器(DEFLATE使用)
+struct LsbBitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> LsbBitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0, buffer: 0, count: 0 }
+    }
+
+    /// 读取n位(n不超过16)
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            let byte = *self.data.get(self.pos).ok_or_else(|| invalid("deflate", "unexpected end of data"))?;
+            self.pos += 1;
+            self.buffer |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << n) - 1);
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// 丢弃当前字节中剩余的位
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+/// 范式哈夫曼码表
+///
+/// # Fields
+/// - counts: 各码长的码字数量
+/// - symbols: 按(码长, 符号)排序的符号
+struct Huffman {
+    counts: [u16; 17],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// 由各符号的码长构建码表，码长为0的符号不出现
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 17];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 17];
+        for len in 1..16 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    /// 逐位读取码字并解码出符号
+    ///
+    /// # Arguments
+    /// * `next_bit` - 读取下一位
+    fn decode(&self, mut next_bit: impl FnMut() -> io::Result<u32>) -> io::Result<u16> {
+        // 同一码长的码字连续，first为该码长的第一个码字，index为其在symbols中的位置
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=16 {
+            code |= next_bit()? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("huffman", "invalid code"))
+    }
+}
+
+/// 长度码257..285的基础长度和额外位数
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+
+/// 距离码0..29的基础距离和额外位数
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097,
+    6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// 码长码表中各码长出现的顺序
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// 解压zlib数据流(RFC 1950/1951)，不校验Adler-32
+fn inflate_zlib(data: &[u8]) -> io::Result<Vec<u8>> {
+    if data.len() < 2 || data[0] & 0x0f != 8 || !((data[0] as u16) << 8 | data[1] as u16).is_multiple_of(31) {
+        return Err(invalid("zlib", "invalid header"));
+    }
+    if data[1] & 0x20 != 0 {
+        return Err(invalid("zlib", "preset dictionary not supported"));
+    }
+
+    let mut reader = LsbBitReader::new(&data[2..]);
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = reader.data.get(reader.pos..reader.pos + 4).ok_or_else(|| invalid("deflate", "truncated block"))?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                if len != !u16::from_le_bytes([header[2], header[3]]) as usize {
+                    return Err(invalid("deflate", "stored block length mismatch"));
+                }
+                let start = reader.pos + 4;
+                let block = reader.data.get(start..start + len).ok_or_else(|| invalid("deflate", "truncated block"))?;
+                out.extend_from_slice(block);
+                reader.pos = start + len;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                inflate_block(&mut reader, &mut out, &Huffman::new(&lengths), &Huffman::new(&[5; 30]))?;
+            }
+            2 => {
+                let (literal, distance) = read_dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &literal, &distance)?;
+            }
+            _ => return Err(invalid("deflate", "invalid block type")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// 读取动态哈夫曼块的字面量/长度码表和距离码表
+fn read_dynamic_tables(reader: &mut LsbBitReader) -> io::Result<(Huffman, Huffman)> {
+    let n_literal = reader.bits(5)? as usize + 257;
+    let n_distance = reader.bits(5)? as usize + 1;
+    let n_code = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &k in &CODE_LENGTH_ORDER[..n_code] {
+        code_lengths[k] = reader.bits(3)? as u8;
+    }
+    let code_table = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(n_literal + n_distance);
+    while lengths.len() < n_literal + n_distance {
+        let symbol = code_table.decode(|| reader.bits(1))?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or_else(|| invalid("deflate", "repeat without previous length"))?;
+                (previous, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat));
+    }
+    if lengths.len() > n_literal + n_distance {
+        return Err(invalid("deflate", "too many code lengths"));
+    }
+    Ok((Huffman::new(&lengths[..n_literal]), Huffman::new(&lengths[n_literal..])))
+}
+
+/// 解码一个压缩块直到块结束符
+fn inflate_block(reader: &mut LsbBitReader, out: &mut Vec<u8>, literal: &Huffman, distance: &Huffman) -> io::Result<()> {
+    loop {
+        let symbol = literal.decode(|| reader.bits(1))? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let k = symbol - 257;
+                let len = LENGTH_BASE[k] as usize + reader.bits(LENGTH_EXTRA[k] as u32)? as usize;
+                let d = distance.decode(|| reader.bits(1))? as usize;
+                if d >= 30 {
+                    return Err(invalid("deflate", "invalid distance code"));
+                }
+                let dist = DIST_BASE[d] as usize + reader.bits(DIST_EXTRA[d] as u32)? as usize;
+                if dist > out.len() {
+                    return Err(invalid("deflate", "distance too far back"));
+                }
+                // 复制区域可能与输出重叠，需要逐字节复制
+                let start = out.len() - dist;
+                for i in 0..len {
+                    out.push(out[start + i]);
+                }
+            }
+            _ => return Err(invalid("deflate", "invalid literal/length code")),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// PNG
+// ---------------------------------------------------------------------------
+
+/// PNG文件签名
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Adam7隔行扫描各遍的(起始列, 起始行, 列间隔, 行间隔)
+const ADAM7: [(usize, usize, usize, usize); 7] =
+    [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+/// 解码PNG图像
+fn decode_png(data: &[u8]) -> io::Result<Image> {
+    let mut pos = PNG_SIGNATURE.len();
+    let mut header = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut compressed = Vec::new();
+    // 读到IEND为止，数据在此之前结束说明文件被截断
+    loop {
+        let chunk = data.get(pos..pos + 8).ok_or_else(|| invalid("png", "missing IEND"))?;
+        let len = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as usize;
+        let kind = &chunk[4..8];
+        // 不校验CRC，但要求它存在
+        let body = data.get(pos + 8..pos + 12 + len).ok_or_else(|| invalid("png", "truncated chunk"))?;
+        let body = &body[..len];
+        match kind {
+            b"IHDR" => {
+                if body.len() < 13 {
+                    return Err(invalid("png", "invalid IHDR"));
+                }
+                let width = u32::from_be_bytes([body[0], body[1], body[2], body[3]]) as usize;
+                let height = u32::from_be_bytes([body[4], body[5], body[6], body[7]]) as usize;
+                header = Some((width, height, body[8], body[9], body[12]));
+            }
+            b"PLTE" => palette = body.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        // 跳过长度、类型、数据和CRC
+        pos += 12 + len;
+    }
+
+    let (width, height, depth, color_type, interlace) = header.ok_or_else(|| invalid("png", "missing IHDR"))?;
+    check_size("png", width, height)?;
+    let channels = match (color_type, depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (2 | 6, 8 | 16) => if color_type == 2 { 3 } else { 4 },
+        (4, 8 | 16) => 2,
+        _ => return Err(invalid("png", "unsupported color type or bit depth")),
+    };
+    if color_type == 3 && palette.is_empty() {
+        return Err(invalid("png", "missing palette"));
+    }
+
+    let raw = inflate_zlib(&compressed)?;
+    let bits_per_pixel = channels * depth as usize;
+    let mut image = Image { width, height, pixels: vec![[0; 3]; width * height] };
+    let passes: &[(usize, usize, usize, usize)] = if interlace == 1 { &ADAM7 } else { &[(0, 0, 1, 1)] };
+
+    let mut offset = 0;
+    for &(x0, y0, dx, dy) in passes {
+        let pass_width = (width + dx - 1 - x0) / dx;
+        let pass_height = (height + dy - 1 - y0) / dy;
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+        let stride = (pass_width * bits_per_pixel).div_ceil(8);
+        let len = (stride + 1) * pass_height;
+        let scanlines = raw.get(offset..offset + len).ok_or_else(|| invalid("png", "truncated image data"))?;
+        offset += len;
+        let rows = unfilter(scanlines, stride, pass_height, bits_per_pixel.div_ceil(8))?;
+
+        for (j, row) in rows.chunks_exact(stride).enumerate() {
+            for i in 0..pass_width {
+                let pixel = png_pixel(row, i, channels, depth, color_type, &palette)?;
+                image.pixels[(y0 + j * dy) * width + x0 + i * dx] = pixel;
+            }
+        }
+    }
+    Ok(image)
+}
+
+/// 还原PNG扫描线的过滤，返回去掉过滤类型字节后的像素数据
+///
+/// # Arguments
+/// * `data` - 每行以过滤类型字节开头的扫描线
+/// * `stride` - 每行像素数据的字节数
+/// * `rows` - 行数
+/// * `bpp` - 每像素的字节数(不足1字节按1计)，即Sub和Paeth过滤参考的左侧距离
+fn unfilter(data: &[u8], stride: usize, rows: usize, bpp: usize) -> io::Result<Vec<u8>> {
+    let mut out = vec![0u8; stride * rows];
+    for j in 0..rows {
+        let filter = data[j * (stride + 1)];
+        let line = &data[j * (stride + 1) + 1..(j + 1) * (stride + 1)];
+        let (previous, current) = out.split_at_mut(j * stride);
+        let up = if j > 0 { &previous[(j - 1) * stride..] } else { &[][..] };
+        let current = &mut current[..stride];
+        for k in 0..stride {
+            let a = if k >= bpp { current[k - bpp] } else { 0 };
+            let b = up.get(k).copied().unwrap_or(0);
+            let c = if k >= bpp { up.get(k - bpp).copied().unwrap_or(0) } else { 0 };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(invalid("png", "invalid filter type")),
+            };
+            current[k] = line[k].wrapping_add(predictor);
+        }
+    }
+    Ok(out)
+}
+
+/// Paeth预测：取a、b、c中最接近 a + b - c 的一个
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// 取出一行中第i个像素并转换为8位RGB
+fn png_pixel(row: &[u8], i: usize, channels: usize, depth: u8, color_type: u8, palette: &[[u8; 3]]) -> io::Result<[u8; 3]> {
+    // 第c个通道的8位值；低位深的灰度按比例放大，调色板索引保持原值
+    let sample = |c: usize| -> u8 {
+        match depth {
+            16 => row[(i * channels + c) * 2],
+            8 => row[i * channels + c],
+            _ => {
+                let bit = i * depth as usize;
+                let value = (row[bit / 8] >> (8 - depth as usize - bit % 8)) & ((1 << depth) - 1);
+                if color_type == 3 { value } else { (value as u16 * 255 / ((1 << depth) - 1)) as u8 }
+            }
+        }
+    };
+    Ok(match color_type {
+        3 => *palette.get(sample(0) as usize).ok_or_else(|| invalid("png", "palette index out of range"))?,
+        0 | 4 => [sample(0); 3],
+        _ => [sample(0), sample(1), sample(2)],
+    })
+}
+
+// ---------------------------------------------------------------------------
+// JPEG
+// ---------------------------------------------------------------------------
+
+/// 之字形扫描顺序到8x8块中自然顺序的映射
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14,
+    21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59, 52, 45, 38, 31, 39, 46, 53, 60,
+    61, 54, 47, 55, 62, 63,
+];
+
+/// JPEG图像分量
+///
+/// # Fields
+/// - id: 分量标识
+/// - h, v: 水平和垂直采样因子
+/// - quant: 量化表编号
+/// - dc_table, ac_table: 哈夫曼表编号
+/// - dc_pred: 上一个块的直流系数
+/// - width_blocks: 每行的8x8块数(按MCU对齐)
+/// - samples: 解码后的样本，宽为 width_blocks * 8
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quant: usize,
+    dc_table: usize,
+    ac_table: usize,
+    dc_pred: i32,
+    width_blocks: usize,
+    samples: Vec<u8>,
+}
+
+/// 高位在前的位读取器(JPEG熵编码数据使用)，处理0xFF00字节填充
+///
+/// 完整的熵编码数据不会读到标记之后，遇到标记说明扫描数据被截断
+struct MsbBitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> MsbBitReader<'a> {
+    fn bit(&mut self) -> io::Result<u32> {
+        if self.count == 0 {
+            let byte = match self.data.get(self.pos) {
+                Some(&0xff) if self.data.get(self.pos + 1) == Some(&0x00) => {
+                    self.pos += 2;
+                    0xff
+                }
+                Some(&0xff) => return Err(invalid("jpeg", "unexpected marker in scan")),
+                Some(&byte) => {
+                    self.pos += 1;
+                    byte
+                }
+                None => return Err(invalid("jpeg", "unexpected end of scan")),
+            };
+            self.buffer = byte as u32;
+            self.count = 8;
+        }
+        self.count -= 1;
+        Ok((self.buffer >> self.count) & 1)
+    }
+
+    /// 读取n位并按JPEG规则扩展为有符号数
+    fn receive_extend(&mut self, n: u32) -> io::Result<i32> {
+        if n == 0 {
+            return Ok(0);
+        }
+        if n > 16 {
+            return Err(invalid("jpeg", "invalid coefficient size"));
+        }
+        let mut value = 0i32;
+        for _ in 0..n {
+            value = (value << 1) | self.bit()? as i32;
+        }
+        Ok(if value < 1 << (n - 1) { value - (1 << n) + 1 } else { value })
+    }
+
+    /// 跳到下一个重启标记之后，并清空位缓冲
+    fn restart(&mut self) -> io::Result<()> {
+        self.count = 0;
+        while self.pos + 1 < self.data.len() && !(self.data[self.pos] == 0xff && (0xd0..=0xd7).contains(&self.data[self.pos + 1])) {
+            self.pos += 1;
+        }
+        if self.pos + 1 >= self.data.len() {
+            return Err(invalid("jpeg", "missing restart marker"));
+        }
+        self.pos += 2;
+        Ok(())
+    }
+}
+
+/// 解码基线JPEG图像
+fn decode_jpeg(data: &[u8]) -> io::Result<Image> {
+    let mut quant = [[0u16; 64]; 4];
+    let mut dc_tables: [Option<Huffman>; 4] = Default::default();
+    let mut ac_tables: [Option<Huffman>; 4] = Default::default();
+    let mut components: Vec<Component> = Vec::new();
+    let (mut width, mut height) = (0, 0);
+    let mut restart_interval = 0;
+
+    let mut pos = 2;
+    loop {
+        // 标记之前可以有任意个0xFF填充
+        while data.get(pos) == Some(&0xff) && data.get(pos + 1) == Some(&0xff) {
+            pos += 1;
+        }
+        let marker = match data.get(pos..pos + 2) {
+            Some(&[0xff, marker]) => marker,
+            _ => return Err(invalid("jpeg", "expected marker")),
+        };
+        pos += 2;
+        if marker == 0xd9 {
+            return Err(invalid("jpeg", "no scan data"));
+        }
+        let len = data.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize).ok_or_else(|| invalid("jpeg", "truncated segment"))?;
+        let segment = data.get(pos + 2..pos + len).ok_or_else(|| invalid("jpeg", "truncated segment"))?;
+        pos += len;
+
+        match marker {
+            // DQT
+            0xdb => {
+                let mut s = segment;
+                while !s.is_empty() {
+                    let (precision, id) = ((s[0] >> 4) as usize, (s[0] & 3) as usize);
+                    let size = 64 * (precision + 1);
+                    let values = s.get(1..1 + size).ok_or_else(|| invalid("jpeg", "truncated quantization table"))?;
+                    for k in 0..64 {
+                        quant[id][ZIGZAG[k]] =
+                            if precision == 0 { values[k] as u16 } else { u16::from_be_bytes([values[2 * k], values[2 * k + 1]]) };
+                    }
+                    s = &s[1 + size..];
+                }
+            }
+            // DHT
+            0xc4 => {
+                let mut s = segment;
+                while s.len() >= 17 {
+                    let (class, id) = (s[0] >> 4, (s[0] & 3) as usize);
+                    let counts = &s[1..17];
+                    let total: usize = counts.iter().map(|&c| c as usize).sum();
+                    let symbols = s.get(17..17 + total).ok_or_else(|| invalid("jpeg", "truncated huffman table"))?;
+                    let mut table = Huffman { counts: [0; 17], symbols: symbols.iter().map(|&x| x as u16).collect() };
+                    for len in 1..=16 {
+                        table.counts[len] = counts[len - 1] as u16;
+                    }
+                    if class == 0 {
+                        dc_tables[id] = Some(table);
+                    } else {
+                        ac_tables[id] = Some(table);
+                    }
+                    s = &s[17 + total..];
+                }
+            }
+            // DRI
+            0xdd => {
+                restart_interval = segment.get(0..2).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize).unwrap_or(0);
+            }
+            // SOF0(基线)、SOF1(扩展顺序，8位精度时与基线相同)
+            0xc0 | 0xc1 => {
+                if segment.len() < 6 || segment[0] != 8 {
+                    return Err(invalid("jpeg", "only 8-bit precision is supported"));
+                }
+                height = u16::from_be_bytes([segment[1], segment[2]]) as usize;
+                width = u16::from_be_bytes([segment[3], segment[4]]) as usize;
+                check_size("jpeg", width, height)?;
+                let n = segment[5] as usize;
+                if n != 1 && n != 3 {
+                    return Err(invalid("jpeg", "only grayscale and YCbCr images are supported"));
+                }
+                for k in 0..n {
+                    let c = segment.get(6 + 3 * k..9 + 3 * k).ok_or_else(|| invalid("jpeg", "truncated frame header"))?;
+                    let (h, v) = ((c[1] >> 4) as usize, (c[1] & 15) as usize);
+                    if !(1..=4).contains(&h) || !(1..=4).contains(&v) {
+                        return Err(invalid("jpeg", "invalid sampling factor"));
+                    }
+                    components.push(Component {
+                        id: c[0],
+                        h,
+                        v,
+                        quant: (c[2] & 3) as usize,
+                        dc_table: 0,
+                        ac_table: 0,
+                        dc_pred: 0,
+                        width_blocks: 0,
+                        samples: Vec::new(),
+                    });
+                }
+            }
+            0xc2 | 0xc3 | 0xc5..=0xc7 | 0xc9..=0xcb | 0xcd..=0xcf => {
+                return Err(invalid("jpeg", "progressive, lossless and arithmetic-coded JPEG are not supported"));
+            }
+            // SOS
+            0xda => {
+                if components.is_empty() {
+                    return Err(invalid("jpeg", "scan before frame header"));
+                }
+                let n = *segment.first().ok_or_else(|| invalid("jpeg", "truncated scan header"))? as usize;
+                if n != components.len() {
+                    return Err(invalid("jpeg", "non-interleaved scans are not supported"));
+                }
+                for k in 0..n {
+                    let s = segment.get(1 + 2 * k..3 + 2 * k).ok_or_else(|| invalid("jpeg", "truncated scan header"))?;
+                    let component = components.iter_mut().find(|c| c.id == s[0]).ok_or_else(|| invalid("jpeg", "unknown component"))?;
+                    component.dc_table = (s[1] >> 4) as usize & 3;
+                    component.ac_table = (s[1] & 15) as usize & 3;
+                }
+                let scan = JpegScan { quant: &quant, dc_tables: &dc_tables, ac_tables: &ac_tables, restart_interval };
+                scan.decode(&data[pos..], &mut components, width, height)?;
+                return Ok(jpeg_to_rgb(&components, width, height));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 一次交织扫描所需的表
+struct JpegScan<'a> {
+    quant: &'a [[u16; 64]; 4],
+    dc_tables: &'a [Option<Huffman>; 4],
+    ac_tables: &'a [Option<Huffman>; 4],
+    restart_interval: usize,
+}
+
+impl JpegScan<'_> {
+    /// 解码交织扫描的熵编码数据，写入各分量的样本
+    fn decode(&self, data: &[u8], components: &mut [Component], width: usize, height: usize) -> io::Result<()> {
+        let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+        let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+        let mcu_x = width.div_ceil(8 * h_max);
+        let mcu_y = height.div_ceil(8 * v_max);
+        for c in components.iter_mut() {
+            c.width_blocks = mcu_x * c.h;
+            c.samples = vec![0; c.width_blocks * 8 * mcu_y * c.v * 8];
+        }
+
+        let mut reader = MsbBitReader { data, pos: 0, buffer: 0, count: 0 };
+        let mut block = [0i32; 64];
+        for mcu in 0..mcu_x * mcu_y {
+            if self.restart_interval > 0 && mcu > 0 && mcu % self.restart_interval == 0 {
+                reader.restart()?;
+                for c in components.iter_mut() {
+                    c.dc_pred = 0;
+                }
+            }
+            let (mx, my) = (mcu % mcu_x, mcu / mcu_x);
+            for c in components.iter_mut() {
+                let dc = self.dc_tables[c.dc_table].as_ref().ok_or_else(|| invalid("jpeg", "missing huffman table"))?;
+                let ac = self.ac_tables[c.ac_table].as_ref().ok_or_else(|| invalid("jpeg", "missing huffman table"))?;
+                for by in 0..c.v {
+                    for bx in 0..c.h {
+                        self.decode_block(&mut reader, c, dc, ac, &mut block)?;
+                        let x0 = (mx * c.h + bx) * 8;
+                        let y0 = (my * c.v + by) * 8;
+                        let stride = c.width_blocks * 8;
+                        for (k, value) in idct(&block).into_iter().enumerate() {
+                            c.samples[(y0 + k / 8) * stride + x0 + k % 8] = value;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 解码一个8x8块的系数并反量化，结果按自然顺序存放
+    fn decode_block(
+        &self,
+        reader: &mut MsbBitReader,
+        c: &mut Component,
+        dc: &Huffman,
+        ac: &Huffman,
+        block: &mut [i32; 64],
+    ) -> io::Result<()> {
+        block.fill(0);
+        let quant = &self.quant[c.quant];
+        let size = dc.decode(|| reader.bit())? as u32;
+        c.dc_pred = c.dc_pred.saturating_add(reader.receive_extend(size)?);
+        block[0] = c.dc_pred.saturating_mul(quant[0] as i32);
+
+        let mut k = 1;
+        while k < 64 {
+            let symbol = ac.decode(|| reader.bit())?;
+            let (run, size) = ((symbol >> 4) as usize, (symbol & 15) as u32);
+            if size == 0 {
+                // EOB结束本块，ZRL跳过16个零
+                if run != 15 {
+                    break;
+                }
+                k += 16;
+                continue;
+            }
+            k += run;
+            if k >= 64 {
+                return Err(invalid("jpeg", "coefficient index out of range"));
+            }
+            block[ZIGZAG[k]] = reader.receive_extend(size)? * quant[ZIGZAG[k]] as i32;
+            k += 1;
+        }
+        Ok(())
+    }
+}
+
+/// 8x8二维逆离散余弦变换，加上电平偏移128并截断到[0,255]
+///
+/// 按行、列分离为两次一维变换，直接按定义计算
+fn idct(block: &[i32; 64]) -> [u8; 64] {
+    let cos_table: [[f32; 8]; 8] = std::array::from_fn(|x| {
+        std::array::from_fn(|u| {
+            let scale = if u == 0 { std::f32::consts::FRAC_1_SQRT_2 } else { 1.0 };
+            scale * ((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0).cos()
+        })
+    });
+
+    let mut rows = [0f32; 64];
+    for y in 0..8 {
+        for x in 0..8 {
+            rows[y * 8 + x] = (0..8).map(|u| cos_table[x][u] * block[y * 8 + u] as f32).sum::<f32>() / 2.0;
+        }
+    }
+    std::array::from_fn(|k| {
+        let (y, x) = (k / 8, k % 8);
+        let value = (0..8).map(|v| cos_table[y][v] * rows[v * 8 + x]).sum::<f32>() / 2.0;
+        (value + 128.0).round().clamp(0.0, 255.0) as u8
+    })
+}
+
+/// 把各分量的样本按采样因子放大到全分辨率并转换为RGB
+fn jpeg_to_rgb(components: &[Component], width: usize, height: usize) -> Image {
+    let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+    let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+    let sample = |c: &Component, x: usize, y: usize| -> f32 {
+        let (sx, sy) = (x * c.h / h_max, y * c.v / v_max);
+        c.samples[sy * c.width_blocks * 8 + sx] as f32
+    };
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            if components.len() == 1 {
+                let g = sample(&components[0], x, y) as u8;
+                pixels.push([g; 3]);
+                continue;
+            }
+            let luma = sample(&components[0], x, y);
+            let cb = sample(&components[1], x, y) - 128.0;
+            let cr = sample(&components[2], x, y) - 128.0;
+            let to_u8 = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+            pixels.push([
+                to_u8(luma + 1.402 * cr),
+                to_u8(luma - 0.344136 * cb - 0.714136 * cr),
+                to_u8(luma + 1.772 * cb),
+            ]);
+        }
+    }
+    Image { width, height, pixels }
+}
+
+// ---------------------------------------------------------------------------
+// PPM/PGM
+// ---------------------------------------------------------------------------
+
+/// 解码PPM(P3/P6)或PGM(P2/P5)图像，16位样本取高8位精度
+fn decode_netpbm(data: &[u8]) -> io::Result<Image> {
+    let mut pos = 0;
+    // 读取文件头中的下一个以空白分隔的数字，跳过注释
+    let next_number = |pos: &mut usize| -> io::Result<usize> {
+        loop {
+            while *pos < data.len() && data[*pos].is_ascii_whitespace() {
+                *pos += 1;
+            }
+            if *pos < data.len() && data[*pos] == b'#' {
+                while *pos < data.len() && data[*pos] != b'\n' {
+                    *pos += 1;
+                }
+                continue;
+            }
+            break;
+        }
+        let start = *pos;
+        while *pos < data.len() && data[*pos].is_ascii_digit() {
+            *pos += 1;
+        }
+        std::str::from_utf8(&data[start..*pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| invalid("netpbm", "invalid header"))
+    };
+
+    let kind = data[1];
+    pos += 2;
+    let width = next_number(&mut pos)?;
+    let height = next_number(&mut pos)?;
+    let max_value = next_number(&mut pos)?;
+    check_size("netpbm", width, height)?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(invalid("netpbm", "invalid max value"));
+    }
+
+    let channels = if matches!(kind, b'3' | b'6') { 3 } else { 1 };
+    let count = width * height * channels;
+    let values: Vec<usize> = if matches!(kind, b'2' | b'3') {
+        (0..count).map(|_| next_number(&mut pos)).collect::<io::Result<_>>()?
+    } else {
+        // 头部之后恰好一个空白字符，然后是二进制像素
+        let bytes_per_value = if max_value < 256 { 1 } else { 2 };
+        let body = data.get(pos + 1..).ok_or_else(|| invalid("netpbm", "missing pixel data"))?;
+        if body.len() < count * bytes_per_value {
+            return Err(invalid("netpbm", "truncated pixel data"));
+        }
+        body.chunks_exact(bytes_per_value)
+            .take(count)
+            .map(|c| c.iter().fold(0, |acc, &b| (acc << 8) | b as usize))
+            .collect()
+    };
+
+    let scale = |v: usize| (v.min(max_value) * 255 / max_value) as u8;
+    let pixels = values
+        .chunks_exact(channels)
+        .map(|c| if channels == 3 { [scale(c[0]), scale(c[1]), scale(c[2])] } else { [scale(c[0]); 3] })
+        .collect();
+    Ok(Image { width, height, pixels })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 3×2 RGB8 PNG，未压缩(stored)的DEFLATE块；第二行使用Sub过滤
+    const PNG_STORED: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+        0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00, 0x00, 0x12, 0x16, 0xf1,
+        0x4d, 0x00, 0x00, 0x00, 0x1f, 0x49, 0x44, 0x41, 0x54, 0x78, 0x01, 0x01, 0x14, 0x00, 0xeb, 0xff,
+        0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x01, 0x0a, 0x14, 0x1e, 0x1e, 0x1e,
+        0x1e, 0x1e, 0x1e, 0x1e, 0x31, 0x33, 0x03, 0xef, 0x61, 0x84, 0x06, 0xb5, 0x00, 0x00, 0x00, 0x00,
+        0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    /// 与`PNG_STORED`相同的像素，以压缩级别9编码
+    const PNG_DEFLATE: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+        0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00, 0x00, 0x12, 0x16, 0xf1,
+        0x4d, 0x00, 0x00, 0x00, 0x14, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0xf8, 0xcf, 0xc0, 0xc0,
+        0x00, 0xc6, 0x8c, 0x5c, 0x22, 0x72, 0x10, 0x00, 0x00, 0x31, 0x33, 0x03, 0xef, 0xf6, 0x10, 0x90,
+        0x1b, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    /// 4×1 调色板PNG，2位索引0..3对应黑、白、红、蓝
+    const PNG_PALETTE: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+        0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x00, 0x00, 0x00, 0x84, 0x52, 0xe7,
+        0x5e, 0x00, 0x00, 0x00, 0x0c, 0x50, 0x4c, 0x54, 0x45, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff,
+        0x00, 0x00, 0x00, 0x00, 0xff, 0x01, 0x1d, 0x33, 0x4a, 0x00, 0x00, 0x00, 0x0a, 0x49, 0x44, 0x41,
+        0x54, 0x78, 0xda, 0x63, 0x90, 0x06, 0x00, 0x00, 0x1d, 0x00, 0x1c, 0x23, 0x7c, 0x8f, 0xac, 0x00,
+        0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    /// 8×8 灰度基线JPEG：单个块只有直流分量(差分8，量化值16)，解码后为均匀的144
+    const JPEG_GRAY: &[u8] = &[
+        0xff, 0xd8, 0xff, 0xdb, 0x00, 0x43, 0x00, 0x10, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0xff, 0xc0, 0x00, 0x0b, 0x08, 0x00, 0x08, 0x00, 0x08,
+        0x01, 0x01, 0x11, 0x00, 0xff, 0xc4, 0x00, 0x14, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0xff, 0xc4, 0x00, 0x14, 0x10, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xff, 0xda, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3f, 0x00, 0x43, 0xff, 0xd9,
+    ];
+    /// ASCII彩色PPM，带注释
+    const PPM_ASCII: &[u8] = b"P3\n# red blue\n2 1\n255\n255 0 0  0 0 255\n";
+
+    /// 二进制彩色PPM
+    const PPM_BINARY: &[u8] = b"P6 2 1 255\n\xff\x00\x00\x00\x00\xff";
+
+    /// ASCII灰度PGM，最大值15
+    const PGM_ASCII: &[u8] = b"P2\n3 1\n15\n0 5 15\n";
+
+    fn fixtures() -> [&'static [u8]; 7] {
+        [PNG_STORED, PNG_DEFLATE, PNG_PALETTE, JPEG_GRAY, PPM_ASCII, PPM_BINARY, PGM_ASCII]
+    }
+
+    #[test]
+    fn png_fixtures_decode_to_exact_pixels() {
+        for data in [PNG_STORED, PNG_DEFLATE] {
+            let image = decode_image(data).unwrap();
+            assert_eq!((image.width, image.height), (3, 2));
+            assert_eq!(image.pixels, [[255, 0, 0], [0, 255, 0], [0, 0, 255], [10, 20, 30], [40, 50, 60], [70, 80, 90]]);
+        }
+
+        let image = decode_image(PNG_PALETTE).unwrap();
+        assert_eq!((image.width, image.height), (4, 1));
+        assert_eq!(image.pixels, [[0, 0, 0], [255, 255, 255], [255, 0, 0], [0, 0, 255]]);
+    }
+
+    #[test]
+    fn jpeg_fixture_decodes_to_uniform_gray() {
+        let image = decode_image(JPEG_GRAY).unwrap();
+        assert_eq!((image.width, image.height), (8, 8));
+        assert!(image.pixels.iter().all(|p| *p == [144, 144, 144]), "{:?}", image.pixels[0]);
+    }
+
+    #[test]
+    fn netpbm_fixtures_decode_to_exact_pixels() {
+        for data in [PPM_ASCII, PPM_BINARY] {
+            let image = decode_image(data).unwrap();
+            assert_eq!((image.width, image.height), (2, 1));
+            assert_eq!(image.pixels, [[255, 0, 0], [0, 0, 255]]);
+        }
+
+        let image = decode_image(PGM_ASCII).unwrap();
+        assert_eq!(image.pixels, [[0, 0, 0], [85, 85, 85], [255, 255, 255]]);
+    }
+
+    #[test]
+    fn truncated_input_is_rejected() {
+        // JPEG允许缺少EOI；ASCII格式截在最后一个数字中间时无法察觉；其余前缀都必须返回错误而不是panic
+        let complete_from = [
+            PNG_STORED.len(),
+            PNG_DEFLATE.len(),
+            PNG_PALETTE.len(),
+            JPEG_GRAY.len() - 2,
+            PPM_ASCII.len() - "255\n".len(),
+            PPM_BINARY.len(),
+            PGM_ASCII.len() - "15\n".len(),
+        ];
+        for (data, complete_from) in fixtures().into_iter().zip(complete_from) {
+            for len in 0..complete_from {
+                assert!(decode_image(&data[..len]).is_err(), "prefix {} of {} bytes decoded", len, data.len());
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_input_is_rejected() {
+        // 超大尺寸
+        let mut huge = PNG_STORED.to_vec();
+        huge[16..24].copy_from_slice(&[0x7f, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff]);
+        assert!(decode_image(&huge).is_err());
+        assert!(decode_image(b"P6 100000 100000 255\n\x00").is_err());
+
+        // 错误的zlib头
+        let mut bad_zlib = PNG_DEFLATE.to_vec();
+        bad_zlib[41] = 0x79;
+        assert!(decode_image(&bad_zlib).is_err());
+
+        // 最大值为0、未知格式
+        assert!(decode_image(b"P3\n1 1\n0\n0 0 0\n").is_err());
+        assert!(decode_image(b"P7\n1 1\n255\n").is_err());
+        assert!(decode_image(b"GIF89a").is_err());
+
+        // 扫描数据被截掉的JPEG
+        let mut no_scan = JPEG_GRAY[..JPEG_GRAY.len() - 3].to_vec();
+        no_scan.extend_from_slice(&[0xff, 0xd9]);
+        assert!(decode_image(&no_scan).is_err());
+    }
+
+    #[test]
+    fn flipped_bytes_never_panic() {
+        for data in fixtures() {
+            for k in 0..data.len() {
+                for mask in [0x01, 0x80, 0xff] {
+                    let mut corrupt = data.to_vec();
+                    corrupt[k] ^= mask;
+                    let _ = decode_image(&corrupt);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,446 @@
+//! 实例模块
+//!
+//! 提供共享几何体的实例，每个实例带有自己的物体到世界的变换，
+//! 多个实例可以引用同一个物体而不复制其数据。
+//! 另外提供只做单一变换的轻量包装(平移、旋转、缩放)，省去一般矩阵运算
+
+use super::ray::Ray;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::transform::Transform;
+use super::vec3::{self, Point3, Vec3};
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 带变换的几何体实例
+///
+/// # Fields
+/// - object: 共享的物体(位于物体空间)
+/// - transform: 物体空间到世界空间的变换
+/// - bbox: 世界空间中的包围盒
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    transform: Transform,
+    bbox: Aabb,
+}
+
+impl Instance {
+    /// 创建新的实例
+    ///
+    /// # Arguments
+    /// * `object` - 共享的物体
+    /// * `transform` - 物体空间到世界空间的变换
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Self {
+        let bbox = transform.bounding_box(&object.bounding_box());
+        Self { object, transform, bbox }
+    }
+
+    /// 获取实例的变换
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl Hittable for Instance {
+    /// 实现实例的光线命中检测
+    ///
+    /// 把光线变换到物体空间后求交，光线方向不做归一化，因此两个空间中的t相同。
+    /// 命中后把交点、法线和切向量变换回世界空间
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+
+        let object_ray = Ray::with_time(
+            self.transform.inverse_point(r.origin()),
+            self.transform.inverse_vector(r.direction()),
+            r.time(),
+        );
+        if !self.object.hit(&object_ray, ray_t, hit_record) {
+            return false;
+        }
+
+        // 法线经逆转置变换后与光线方向的点积符号不变，front_face无需重新计算
+        hit_record.p = self.transform.point(hit_record.p);
+        hit_record.normal = vec3::unit_vector(self.transform.normal(hit_record.normal));
+        if !hit_record.tangent.near_zero() {
+            hit_record.tangent = vec3::unit_vector(self.transform.vector(hit_record.tangent));
+        }
+
+        true
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+
+        let object_ray = Ray::with_time(
+            self.transform.inverse_point(r.origin()),
+            self.transform.inverse_vector(r.direction()),
+            r.time(),
+        );
+        self.object.hit_any(&object_ray, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// 平移包装：把内部物体整体移动一个偏移量
+///
+/// # Fields
+/// - object: 内部物体
+/// - offset: 偏移量
+/// - bbox: 平移后的包围盒
+pub struct Translate {
+    object: Arc<dyn Hittable>,
+    offset: Vec3,
+    bbox: Aabb,
+}
+
+impl Translate {
+    /// 创建平移后的物体
+    ///
+    /// # Arguments
+    /// * `object` - 内部物体
+    /// * `offset` - 偏移量
+    pub fn new(object: Arc<dyn Hittable>, offset: Vec3) -> Self {
+        let bbox = object.bounding_box();
+        let bbox = Aabb::new(
+            Interval::new(bbox.x.min + offset.x(), bbox.x.max + offset.x()),
+            Interval::new(bbox.y.min + offset.y(), bbox.y.max + offset.y()),
+            Interval::new(bbox.z.min + offset.z(), bbox.z.max + offset.z()),
+        );
+        Self { object, offset, bbox }
+    }
+}
+
+impl Hittable for Translate {
+    /// 把光线起点反向平移后与内部物体求交，再把交点平移回来
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let offset_ray = Ray::with_time(r.origin() - self.offset, r.direction(), r.time());
+        if !self.object.hit(&offset_ray, ray_t, hit_record) {
+            return false;
+        }
+
+        hit_record.p += self.offset;
+        true
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        let offset_ray = Ray::with_time(r.origin() - self.offset, r.direction(), r.time());
+        self.object.hit_any(&offset_ray, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+    /// 平移不改变立体角，直接在内部物体的坐标系中计算
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> Real {
+        self.object.pdf_value(origin - self.offset, direction)
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        self.object.random(origin - self.offset)
+    }
+}
+
+/// 绕y轴旋转包装
+///
+/// # Fields
+/// - object: 内部物体
+/// - sin_theta, cos_theta: 旋转角的正弦和余弦
+/// - bbox: 旋转后的包围盒
+pub struct RotateY {
+    object: Arc<dyn Hittable>,
+    sin_theta: Real,
+    cos_theta: Real,
+    bbox: Aabb,
+}
+
+impl RotateY {
+    /// 创建绕y轴旋转后的物体
+    ///
+    /// # Arguments
+    /// * `object` - 内部物体
+    /// * `degrees` - 旋转角度(角度制，从+y方向看去逆时针为正)
+    pub fn new(object: Arc<dyn Hittable>, degrees: Real) -> Self {
+        let radians = rtweekend::degrees_to_radians(degrees);
+        let bbox = Transform::rotate(Vec3::new(0.0, 1.0, 0.0), degrees).bounding_box(&object.bounding_box());
+        Self {
+            object,
+            sin_theta: radians.sin(),
+            cos_theta: radians.cos(),
+            bbox,
+        }
+    }
+
+    /// 从物体空间旋转到世界空间
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x() + self.sin_theta * v.z(),
+            v.y(),
+            -self.sin_theta * v.x() + self.cos_theta * v.z(),
+        )
+    }
+
+    /// 从世界空间旋转到物体空间
+    fn to_object(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x() - self.sin_theta * v.z(),
+            v.y(),
+            self.sin_theta * v.x() + self.cos_theta * v.z(),
+        )
+    }
+}
+
+impl Hittable for RotateY {
+    /// 把光线反向旋转到物体空间求交，再把交点、法线和切向量旋转回世界空间
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let rotated = Ray::with_time(self.to_object(r.origin()), self.to_object(r.direction()), r.time());
+        if !self.object.hit(&rotated, ray_t, hit_record) {
+            return false;
+        }
+
+        hit_record.p = self.to_world(hit_record.p);
+        hit_record.normal = self.to_world(hit_record.normal);
+        hit_record.tangent = self.to_world(hit_record.tangent);
+        true
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        let rotated = Ray::with_time(self.to_object(r.origin()), self.to_object(r.direction()), r.time());
+        self.object.hit_any(&rotated, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// 绕过原点的任意轴旋转包装
+///
+/// # Fields
+/// - object: 内部物体
+/// - rotation: 旋转变换
+/// - bbox: 旋转后的包围盒
+pub struct Rotate {
+    object: Arc<dyn Hittable>,
+    rotation: Transform,
+    bbox: Aabb,
+}
+
+impl Rotate {
+    /// 创建绕任意轴旋转后的物体
+    ///
+    /// # Arguments
+    /// * `object` - 内部物体
+    /// * `axis` - 过原点的旋转轴(无需归一化)
+    /// * `degrees` - 旋转角度(角度制，沿轴方向看去逆时针为正)
+    pub fn new(object: Arc<dyn Hittable>, axis: Vec3, degrees: Real) -> Self {
+        let rotation = Transform::rotate(axis, degrees);
+        let bbox = rotation.bounding_box(&object.bounding_box());
+        Self { object, rotation, bbox }
+    }
+}
+
+impl Hittable for Rotate {
+    /// 把光线反向旋转到物体空间求交，再把交点、法线和切向量旋转回世界空间。
+    /// 旋转矩阵是正交矩阵，法线与普通向量的变换方式相同
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let rotated = Ray::with_time(
+            self.rotation.inverse_vector(r.origin()),
+            self.rotation.inverse_vector(r.direction()),
+            r.time(),
+        );
+        if !self.object.hit(&rotated, ray_t, hit_record) {
+            return false;
+        }
+
+        hit_record.p = self.rotation.vector(hit_record.p);
+        hit_record.normal = self.rotation.vector(hit_record.normal);
+        hit_record.tangent = self.rotation.vector(hit_record.tangent);
+        true
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        let rotated = Ray::with_time(
+            self.rotation.inverse_vector(r.origin()),
+            self.rotation.inverse_vector(r.direction()),
+            r.time(),
+        );
+        self.object.hit_any(&rotated, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// 沿坐标轴的非均匀缩放包装
+///
+/// # Fields
+/// - object: 内部物体
+/// - factors: 三个轴向的缩放因子
+/// - inv_factors: 缩放因子的倒数
+/// - bbox: 缩放后的包围盒
+pub struct Scale {
+    object: Arc<dyn Hittable>,
+    factors: Vec3,
+    inv_factors: Vec3,
+    bbox: Aabb,
+}
+
+impl Scale {
+    /// 创建缩放后的物体(以原点为缩放中心)
+    ///
+    /// # Arguments
+    /// * `object` - 内部物体
+    /// * `factors` - 三个轴向的缩放因子，可以为负(镜像)
+    ///
+    /// # Note
+    /// 任一缩放因子为0时会panic
+    pub fn new(object: Arc<dyn Hittable>, factors: Vec3) -> Self {
+        let bbox = Transform::scale(factors).bounding_box(&object.bounding_box());
+        Self {
+            object,
+            factors,
+            inv_factors: Vec3::new(1.0 / factors.x(), 1.0 / factors.y(), 1.0 / factors.z()),
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Scale {
+    /// 把光线按缩放因子的倒数变换到物体空间求交，再把交点和切向量按缩放因子变换回来。
+    /// 法线需要乘以逆转置矩阵，对角缩放矩阵的逆转置就是各因子取倒数，变换后重新归一化
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let scaled = Ray::with_time(r.origin() * self.inv_factors, r.direction() * self.inv_factors, r.time());
+        if !self.object.hit(&scaled, ray_t, hit_record) {
+            return false;
+        }
+
+        hit_record.p = hit_record.p * self.factors;
+        hit_record.normal = vec3::unit_vector(hit_record.normal * self.inv_factors);
+        if !hit_record.tangent.near_zero() {
+            hit_record.tangent = vec3::unit_vector(hit_record.tangent * self.factors);
+        }
+        true
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        let scaled = Ray::with_time(r.origin() * self.inv_factors, r.direction() * self.inv_factors, r.time());
+        self.object.hit_any(&scaled, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+
+    fn sphere(center: Point3, radius: Real) -> Arc<dyn Hittable> {
+        Arc::new(Sphere::new(center, radius, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))))
+    }
+
+    fn cast(object: &dyn Hittable, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        object
+            .hit(&Ray::new(origin, direction), &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec)
+            .then_some(rec)
+    }
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn mirrored_instance_keeps_outward_normals_and_face_flags() {
+        let shared = sphere(Point3::new(1.0, 0.0, 0.0), 0.5);
+        let mirrored = Instance::new(Arc::clone(&shared), Transform::scale(Vec3::new(-1.0, 1.0, 1.0)));
+        let x = Vec3::new(1.0, 0.0, 0.0);
+
+        // 镜像后球心在x=-1处
+        let rec = cast(&mirrored, Point3::new(-5.0, 0.0, 0.0), x).unwrap();
+        assert!((rec.t - 3.5).abs() < 1e-4);
+        assert_close(rec.p, Point3::new(-1.5, 0.0, 0.0));
+        assert_close(rec.normal, -x);
+        assert!(rec.front_face);
+
+        // 从内部射出：命中背面，法线仍朝向光线来的一侧
+        let rec = cast(&mirrored, Point3::new(-1.0, 0.0, 0.0), x).unwrap();
+        assert!((rec.t - 0.5).abs() < 1e-4);
+        assert_close(rec.normal, -x);
+        assert!(!rec.front_face);
+
+        // 原物体不受影响
+        assert!(cast(&*shared, Point3::new(-5.0, 0.0, 0.0), x).is_some_and(|rec| (rec.t - 5.5).abs() < 1e-4));
+        assert!(!mirrored.hit_any(&Ray::new(Point3::new(5.0, 0.0, 0.0), x), &Interval::new(0.0, rtweekend::INFINITY)));
+    }
+
+    #[test]
+    fn instance_hits_follow_the_composed_transform() {
+        // 单位球先沿x拉伸为2倍，再绕z轴转90°：长轴沿y，再平移到(0,0,5)
+        let transform = Transform::scale(Vec3::new(2.0, 1.0, 1.0))
+            .then(&Transform::rotate(Vec3::new(0.0, 0.0, 1.0), 90.0))
+            .then(&Transform::translate(Vec3::new(0.0, 0.0, 5.0)));
+        let ellipsoid = Instance::new(sphere(Point3::default(), 1.0), transform);
+        let bbox = ellipsoid.bounding_box();
+        assert!((bbox.y.max - 2.0).abs() < 1e-4 && (bbox.x.max - 1.0).abs() < 1e-4 && (bbox.z.min - 4.0).abs() < 1e-4);
+
+        // 沿y射入，在长轴端点命中
+        let rec = cast(&ellipsoid, Point3::new(0.0, -10.0, 5.0), Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!((rec.t - 8.0).abs() < 1e-4);
+        assert_close(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+        // 世界空间中x=0.5处的表面：(0.5)² + (y/2)² = 1
+        let rec = cast(&ellipsoid, Point3::new(0.5, -10.0, 5.0), Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        let y = -2.0 * (0.75 as Real).sqrt();
+        assert!((rec.t - (10.0 + y)).abs() < 1e-4);
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert_close(rec.normal, vec3::unit_vector(Vec3::new(0.5, y / 4.0, 0.0)));
+    }
+
+    #[test]
+    fn scaled_sphere_is_an_ellipsoid_with_inverse_transpose_normals() {
+        let unit = sphere(Point3::default(), 1.0);
+        let ellipsoid = Scale::new(Arc::clone(&unit), Vec3::new(2.0, 1.0, 1.0));
+        let down = Vec3::new(0.0, -1.0, 0.0);
+
+        // x=1处的表面：(1/2)² + y² = 1，法线∝(x/4, y, 0)而不是(x, y, 0)
+        let y = (0.75 as Real).sqrt();
+        let rec = cast(&ellipsoid, Point3::new(1.0, 5.0, 0.0), down).unwrap();
+        assert!((rec.t - (5.0 - y)).abs() < 1e-4);
+        assert_close(rec.p, Point3::new(1.0, y, 0.0));
+        assert_close(rec.normal, vec3::unit_vector(Vec3::new(0.25, y, 0.0)));
+        assert!(rec.front_face);
+
+        // 与同样缩放的Instance结果相同
+        let instance = Instance::new(Arc::clone(&unit), Transform::scale(Vec3::new(2.0, 1.0, 1.0)));
+        let other = cast(&instance, Point3::new(1.0, 5.0, 0.0), down).unwrap();
+        assert!((other.t - rec.t).abs() < 1e-4);
+        assert_close(other.normal, rec.normal);
+
+        // 镜像缩放：法线的x分量随之翻转，从内部射出时命中背面
+        let mirrored = Scale::new(Arc::clone(&unit), Vec3::new(-2.0, 1.0, 1.0));
+        let rec = cast(&mirrored, Point3::new(-1.0, 5.0, 0.0), down).unwrap();
+        assert_close(rec.normal, vec3::unit_vector(Vec3::new(-0.25, y, 0.0)));
+        let rec = cast(&mirrored, Point3::default(), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+        assert_close(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+
+        let bbox = ellipsoid.bounding_box();
+        assert!((bbox.x.max - 2.0).abs() < 1e-4 && (bbox.y.max - 1.0).abs() < 1e-4);
+        assert!(ellipsoid.hit_any(&Ray::new(Point3::new(1.9, 5.0, 0.0), down), &Interval::new(0.0, rtweekend::INFINITY)));
+        assert!(!ellipsoid.hit_any(&Ray::new(Point3::new(2.1, 5.0, 0.0), down), &Interval::new(0.0, rtweekend::INFINITY)));
+    }
+}
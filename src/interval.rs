@@ -2,17 +2,17 @@
 //!
 //! 提供区间运算功能，用于表示和操作数值范围
 
-use super::rtweekend;
+use super::rtweekend::{self, Real};
 
 /// 数值区间结构体，表示[min, max]范围内的实数
 /// 
 /// # Fields
 /// - min: 区间下限(包含)
 /// - max: 区间上限(包含)
-#[derive(Default)]
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Interval {
-  pub min: f64,
-  pub max: f64,
+  pub min: Real,
+  pub max: Real,
 }
 
 impl Interval {
@@ -24,15 +24,27 @@ impl Interval {
     /// 
     /// # Panics
     /// 如果min > max会panic
-  pub fn new(min: f64, max: f64) -> Self {
+  pub fn new(min: Real, max: Real) -> Self {
     Self { min, max }
   }
 
+    /// 创建恰好包含两个区间的最小区间
+    /// 
+    /// # Arguments
+    /// * `a` - 第一个区间
+    /// * `b` - 第二个区间
+  pub fn enclosing(a: &Interval, b: &Interval) -> Self {
+    Self {
+      min: a.min.min(b.min),
+      max: a.max.max(b.max),
+    }
+  }
+
     /// 计算区间大小
     /// 
     /// # Returns
     /// 返回max - min的值
-  pub fn size(&self) -> f64 {
+  pub fn size(&self) -> Real {
     self.max - self.min
   }
 
@@ -43,7 +55,7 @@ impl Interval {
     /// 
     /// # Returns
     /// 如果min ≤ x ≤ max返回true，否则false
-  pub fn contains(&self, x: f64) -> bool {
+  pub fn contains(&self, x: Real) -> bool {
     self.min <= x && x <= self.max
   }
 
@@ -54,7 +66,7 @@ impl Interval {
     /// 
     /// # Returns
     /// 如果min < x < max返回true，否则false
-  pub fn surrounds(&self, x: f64) -> bool {
+  pub fn surrounds(&self, x: Real) -> bool {
     self.min < x && x < self.max
   }
 
@@ -65,7 +77,7 @@ impl Interval {
     /// 
     /// # Returns
     /// 如果x < min返回min，如果x > max返回max，否则返回x
-  pub fn clamp(&self, x: f64) -> f64 {
+  pub fn clamp(&self, x: Real) -> Real {
     if x < self.min {
       self.min
     } else if x > self.max {
@@ -74,6 +86,15 @@ impl Interval {
       x
     }
   }
+
+    /// 向两侧扩展区间
+    /// 
+    /// # Arguments
+    /// * `delta` - 扩展后的总增量，两侧各扩展一半
+  pub fn expand(&self, delta: Real) -> Self {
+    let padding = delta / 2.0;
+    Self::new(self.min - padding, self.max + padding)
+  }
 }
 
 /// 空区间常量，表示不包含任何值的区间
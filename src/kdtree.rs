@@ -0,0 +1,475 @@
+//! kd树模块
+//!
+//! 提供用表面积启发式(SAH)选择划分平面的kd树加速结构。
+//! 与BVH划分物体不同，kd树划分空间，跨越划分平面的物体会同时出现在两侧的子树中
+
+use super::ray::Ray;
+use super::accelerator::{Accelerator, Stats};
+use super::hittable::{HitRecord, Hittable};
+use super::hittable_list::HittableList;
+use super::interval::Interval;
+use super::aabb::{self, Aabb};
+use super::stats;
+use super::rtweekend::Real;
+use std::sync::Arc;
+
+/// 一次物体求交的开销
+const INTERSECT_COST: Real = 80.0;
+
+/// 访问一个内部节点的开销
+const TRAVERSAL_COST: Real = 1.0;
+
+/// 划分后一侧为空时代价的折扣，鼓励切掉空白区域
+const EMPTY_BONUS: Real = 0.5;
+
+/// 叶节点最多包含的物体数，超过时仍尝试划分
+const MAX_LEAF_SIZE: usize = 1;
+
+/// 允许连续出现的代价高于叶节点的划分次数
+const MAX_BAD_REFINES: usize = 3;
+
+/// 遍历栈的容量，同时是树深的上限
+const STACK_SIZE: usize = 64;
+
+/// kd树节点
+#[derive(Clone, Copy, Debug)]
+enum KdNode {
+    /// 内部节点：低于划分平面的子节点紧跟在其后，`above`为高于划分平面的子节点索引
+    Interior { axis: usize, split: Real, above: usize },
+    /// 叶节点：物体在`indices`中的范围
+    Leaf { offset: usize, count: usize },
+}
+
+/// 划分候选事件：物体包围盒在某轴上的起点或终点
+///
+/// # Fields
+/// - t: 坐标
+/// - start: 是否为起点
+#[derive(Clone, Copy)]
+struct BoundEdge {
+    t: Real,
+    start: bool,
+}
+
+/// kd树
+///
+/// # Fields
+/// - objects: 所有物体
+/// - nodes: 深度优先顺序的节点数组，根节点位于索引0
+/// - indices: 各叶节点引用的物体索引，依次连续存放
+/// - bbox: 整棵树的包围盒
+pub struct KdTree {
+    objects: Vec<Arc<dyn Hittable>>,
+    nodes: Vec<KdNode>,
+    indices: Vec<usize>,
+    bbox: Aabb,
+}
+
+impl KdTree {
+    /// 为物体列表构建kd树，列表会先被展开
+    ///
+    /// # Arguments
+    /// * `list` - 物体列表
+    pub fn new(list: &HittableList) -> Self {
+        Self::from_list(list)
+    }
+
+    /// 递归构建节点
+    ///
+    /// # Arguments
+    /// * `node_bounds` - 节点对应的空间范围
+    /// * `items` - 与该范围重叠的物体索引
+    /// * `bounds` - 所有物体的包围盒
+    /// * `depth` - 剩余允许的深度
+    /// * `bad_refines` - 祖先中代价高于叶节点的划分次数
+    fn build_recursive(
+        &mut self,
+        node_bounds: Aabb,
+        items: Vec<usize>,
+        bounds: &[Aabb],
+        depth: usize,
+        bad_refines: usize,
+    ) {
+        let node_index = self.nodes.len();
+        if items.len() <= MAX_LEAF_SIZE || depth == 0 {
+            self.push_leaf(&items);
+            return;
+        }
+
+        let Some((axis, split, cost)) = best_split(&node_bounds, &items, bounds) else {
+            self.push_leaf(&items);
+            return;
+        };
+        let leaf_cost = INTERSECT_COST * items.len() as Real;
+        let bad_refines = if cost > leaf_cost { bad_refines + 1 } else { bad_refines };
+        if (cost > 4.0 * leaf_cost && items.len() < 16) || bad_refines == MAX_BAD_REFINES {
+            self.push_leaf(&items);
+            return;
+        }
+
+        // 跨越划分平面的物体两侧都要包含，恰好落在平面上的物体归入上方
+        let below: Vec<usize> = items.iter().copied().filter(|&i| bounds[i].axis_interval(axis).min < split).collect();
+        let above: Vec<usize> = items
+            .iter()
+            .copied()
+            .filter(|&i| {
+                let interval = bounds[i].axis_interval(axis);
+                interval.max > split || interval.min >= split
+            })
+            .collect();
+
+        let mut below_bounds = node_bounds;
+        let mut above_bounds = node_bounds;
+        set_axis(&mut below_bounds, axis, Interval::new(node_bounds.axis_interval(axis).min, split));
+        set_axis(&mut above_bounds, axis, Interval::new(split, node_bounds.axis_interval(axis).max));
+
+        self.nodes.push(KdNode::Leaf { offset: 0, count: 0 });
+        self.build_recursive(below_bounds, below, bounds, depth - 1, bad_refines);
+        let above_index = self.nodes.len();
+        self.build_recursive(above_bounds, above, bounds, depth - 1, bad_refines);
+        self.nodes[node_index] = KdNode::Interior { axis, split, above: above_index };
+    }
+
+    /// 添加叶节点
+    fn push_leaf(&mut self, items: &[usize]) {
+        self.nodes.push(KdNode::Leaf { offset: self.indices.len(), count: items.len() });
+        self.indices.extend_from_slice(items);
+    }
+}
+
+/// 设置包围盒某一轴的区间
+fn set_axis(bbox: &mut Aabb, axis: usize, interval: Interval) {
+    match axis {
+        0 => bbox.x = interval,
+        1 => bbox.y = interval,
+        _ => bbox.z = interval,
+    }
+}
+
+/// 用SAH寻找最优划分平面
+///
+/// 从节点最长的轴开始，把各物体包围盒的起点和终点排序后依次扫描，
+/// 估计以每个落在节点内部的端点为划分平面的代价。
+/// 最长轴上找不到可用平面时依次尝试其它轴
+///
+/// # Returns
+/// 返回(划分轴, 划分位置, 代价)，找不到可用平面时返回None
+fn best_split(node_bounds: &Aabb, items: &[usize], bounds: &[Aabb]) -> Option<(usize, Real, Real)> {
+    let total_area = node_bounds.surface_area();
+    let inv_total_area = if total_area > 0.0 { 1.0 / total_area } else { 0.0 };
+    let size = [node_bounds.x.size(), node_bounds.y.size(), node_bounds.z.size()];
+    let first_axis = node_bounds.longest_axis();
+
+    for retry in 0..3 {
+        let axis = (first_axis + retry) % 3;
+        let (other0, other1) = ((axis + 1) % 3, (axis + 2) % 3);
+        let range = node_bounds.axis_interval(axis);
+
+        let mut edges: Vec<BoundEdge> = items
+            .iter()
+            .flat_map(|&index| {
+                let interval = bounds[index].axis_interval(axis);
+                [BoundEdge { t: interval.min, start: true }, BoundEdge { t: interval.max, start: false }]
+            })
+            .collect();
+        // 坐标相同时起点排在前面
+        edges.sort_by(|a, b| a.t.total_cmp(&b.t).then(b.start.cmp(&a.start)));
+
+        let mut best: Option<(Real, Real)> = None;
+        let (mut n_below, mut n_above) = (0, items.len());
+        for edge in &edges {
+            if !edge.start {
+                n_above -= 1;
+            }
+            if edge.t > range.min && edge.t < range.max {
+                let below_width = edge.t - range.min;
+                let above_width = range.max - edge.t;
+                let face = size[other0] * size[other1];
+                let perimeter = size[other0] + size[other1];
+                let p_below = 2.0 * (face + below_width * perimeter) * inv_total_area;
+                let p_above = 2.0 * (face + above_width * perimeter) * inv_total_area;
+                let bonus = if n_below == 0 || n_above == 0 { EMPTY_BONUS } else { 0.0 };
+                let cost = TRAVERSAL_COST
+                    + INTERSECT_COST * (1.0 - bonus) * (p_below * n_below as Real + p_above * n_above as Real);
+                if best.is_none_or(|(_, c)| cost < c) {
+                    best = Some((edge.t, cost));
+                }
+            }
+            if edge.start {
+                n_below += 1;
+            }
+        }
+
+        if let Some((split, cost)) = best {
+            return Some((axis, split, cost));
+        }
+    }
+    None
+}
+
+impl Accelerator for KdTree {
+    fn build(objects: Vec<Arc<dyn Hittable>>) -> Self {
+        let bounds: Vec<Aabb> = objects.iter().map(|object| object.bounding_box()).collect();
+        let bbox = bounds.iter().fold(aabb::EMPTY, |acc, b| Aabb::surrounding(&acc, b));
+        let mut tree = Self {
+            objects,
+            nodes: Vec::new(),
+            indices: Vec::new(),
+            bbox,
+        };
+        if !tree.objects.is_empty() {
+            // 经验深度上限 8 + 1.3 log2(N)，并受遍历栈容量限制
+            let max_depth = (8.0 + 1.3 * (tree.objects.len() as Real).log2()).round() as usize;
+            tree.build_recursive(bbox, (0..tree.objects.len()).collect(), &bounds, max_depth.min(STACK_SIZE - 1), 0);
+        }
+        tree
+    }
+
+    fn primitive_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            primitive_count: self.objects.len(),
+            node_count: self.nodes.len(),
+            ..Stats::default()
+        };
+        // 子节点总是位于父节点之后
+        let mut depth = vec![0usize; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            match *node {
+                KdNode::Interior { above, .. } => {
+                    depth[index + 1] = depth[index] + 1;
+                    depth[above] = depth[index] + 1;
+                }
+                KdNode::Leaf { count, .. } => {
+                    stats.leaf_count += 1;
+                    stats.leaf_references += count;
+                    stats.max_depth = stats.max_depth.max(depth[index]);
+                }
+            }
+        }
+        stats
+    }
+}
+
+impl KdTree {
+    /// 按光线经过的顺序遍历叶节点
+    ///
+    /// 从根节点开始按光线经过的顺序访问节点，每个节点对应光线参数的一段区间。
+    /// 内部节点按光线方向确定先经过划分平面的哪一侧，后经过的子节点连同其区间入栈；
+    /// 已找到的最近交点在下一个节点的区间之前时即可结束。
+    /// 物体可能跨越多个叶节点，求交始终使用完整的[ray_t.min, 最近交点]范围
+    ///
+    /// # Arguments
+    /// * `any` - 为true时找到第一个交点即停止
+    /// * `hit_object` - 对给定索引的物体在给定范围内求交，命中时返回交点的t
+    ///
+    /// # Returns
+    /// 命中时返回最近交点的t(`any`为true时为第一个找到的交点)
+    fn traverse(
+        &self,
+        r: &Ray,
+        ray_t: &Interval,
+        any: bool,
+        mut hit_object: impl FnMut(usize, &Interval) -> Option<Real>,
+    ) -> Option<Real> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let span = self.bbox.hit_interval(r, ray_t)?;
+
+        let origin = r.origin();
+        let direction = r.direction();
+        let mut nearest = None;
+        let mut closest = ray_t.max;
+
+        let mut stack = [(0usize, 0.0, 0.0); STACK_SIZE];
+        let mut top = 0;
+        let (mut node, mut t_min, mut t_max) = (0, span.min, span.max);
+        loop {
+            if closest < t_min {
+                break;
+            }
+            stats::count_node_visit();
+            match self.nodes[node] {
+                KdNode::Interior { axis, split, above } => {
+                    let below = node + 1;
+                    // 光线与划分平面平行时只经过起点所在的一侧
+                    if direction[axis] == 0.0 {
+                        node = if origin[axis] < split { below } else { above };
+                        continue;
+                    }
+
+                    let t_plane = (split - origin[axis]) / direction[axis];
+                    let (first, second) = if direction[axis] > 0.0 { (below, above) } else { (above, below) };
+                    if t_plane >= t_max {
+                        node = first;
+                    } else if t_plane <= t_min {
+                        node = second;
+                    } else {
+                        stack[top] = (second, t_plane, t_max);
+                        top += 1;
+                        node = first;
+                        t_max = t_plane;
+                    }
+                    continue;
+                }
+                KdNode::Leaf { offset, count } => {
+                    for &index in &self.indices[offset..offset + count] {
+                        stats::count_intersection_test();
+                        if let Some(t) = hit_object(index, &Interval::new(ray_t.min, closest)) {
+                            if any {
+                                return Some(t);
+                            }
+                            closest = t;
+                            nearest = Some(t);
+                        }
+                    }
+                }
+            }
+
+            if top == 0 {
+                break;
+            }
+            top -= 1;
+            (node, t_min, t_max) = stack[top];
+        }
+        nearest
+    }
+}
+
+impl Hittable for KdTree {
+    /// 实现kd树的光线命中检测
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let mut temp_rec = HitRecord::default();
+        self.traverse(r, ray_t, false, |index, interval| {
+            if !self.objects[index].hit(r, interval, &mut temp_rec) {
+                return None;
+            }
+            *hit_record = temp_rec.clone();
+            Some(temp_rec.t)
+        })
+        .is_some()
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.traverse(r, ray_t, true, |index, interval| {
+            self.objects[index].hit_any(r, interval).then_some(interval.max)
+        })
+        .is_some()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::{Lambertian, Material};
+    use crate::quad::Quad;
+    use crate::rtweekend;
+    use crate::sphere::Sphere;
+    use crate::vec3::{Point3, Vec3};
+
+    fn gray() -> Arc<dyn Material + Send + Sync> {
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+    }
+
+    /// 随机小球加上重合的球和共面、轴对齐的四边形，固定种子保证每次相同
+    fn mixed_scene(spheres: usize) -> HittableList {
+        rtweekend::seed_random(1039);
+        let mut list = HittableList::default();
+        for _ in 0..spheres {
+            list.add(Arc::new(Sphere::new(Vec3::random_range(-10.0, 10.0), 0.5, gray())));
+        }
+        // 同一个物体加入两次，以及两个完全相同的球
+        let shared: Arc<dyn Hittable> = Arc::new(Sphere::new(Point3::new(1.0, 2.0, 3.0), 1.5, gray()));
+        list.add(Arc::clone(&shared));
+        list.add(shared);
+        for _ in 0..2 {
+            list.add(Arc::new(Sphere::new(Point3::new(-4.0, 0.0, 0.0), 1.0, gray())));
+        }
+        // 没有厚度的四边形：z = 0平面上两个重叠的，以及x = 5平面上的一个
+        let (x, y, z) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        list.add(Arc::new(Quad::new(Point3::new(-8.0, -8.0, 0.0), 16.0 * x, 16.0 * y, gray())));
+        list.add(Arc::new(Quad::new(Point3::new(-2.0, -2.0, 0.0), 4.0 * x, 4.0 * y, gray())));
+        list.add(Arc::new(Quad::new(Point3::new(5.0, -6.0, -6.0), 12.0 * y, 12.0 * z, gray())));
+        list
+    }
+
+    /// 在固定种子的随机光线上比较两个物体的`hit`和`hit_any`，返回命中的光线数
+    fn assert_same_hits(expected: &dyn Hittable, actual: &dyn Hittable, rays: usize) -> usize {
+        rtweekend::seed_random(1039);
+        let mut hits = 0;
+        for k in 0..rays {
+            let origin = Vec3::random_range(-15.0, 15.0);
+            // 一部分光线沿坐标轴，与划分平面平行
+            let direction = match k % 4 {
+                0 => Vec3::new(0.0, 0.0, if origin.z() > 0.0 { -1.0 } else { 1.0 }),
+                _ => Vec3::random_range(-1.0, 1.0),
+            };
+            let r = Ray::new(origin, direction);
+            let ray_t = Interval::new(rtweekend::HIT_EPSILON, if k % 3 == 0 { 8.0 } else { rtweekend::INFINITY });
+
+            let (mut a, mut b) = (HitRecord::default(), HitRecord::default());
+            let hit = expected.hit(&r, &ray_t, &mut a);
+            assert_eq!(hit, actual.hit(&r, &ray_t, &mut b), "ray {}", k);
+            if hit {
+                assert_eq!(a.t, b.t, "ray {}", k);
+                hits += 1;
+            }
+            assert_eq!(hit, actual.hit_any(&r, &ray_t), "ray {}", k);
+        }
+        hits
+    }
+
+    #[test]
+    fn kd_tree_hits_match_the_plain_list() {
+        let list = mixed_scene(200);
+        let tree = KdTree::new(&list);
+        assert_eq!(tree.primitive_count(), list.objects.len());
+        let hits = assert_same_hits(&list, &tree, 2000);
+        assert!(hits > 200, "only {} rays hit", hits);
+    }
+
+    #[test]
+    fn empty_and_single_object_trees() {
+        let empty = KdTree::build(Vec::new());
+        assert_eq!(empty.stats(), Stats::default());
+        assert!(!empty.hit_any(&Ray::new(Point3::default(), Vec3::new(1.0, 0.0, 0.0)), &Interval::new(0.0, 1e9)));
+
+        let one = HittableList::new(Arc::new(Sphere::new(Point3::default(), 8.0, gray())));
+        assert!(assert_same_hits(&one, &KdTree::new(&one), 200) > 20);
+    }
+
+    #[test]
+    fn stats_describe_a_full_tree_of_bounded_depth() {
+        // 两个远离的球：一次划分、两个叶节点
+        let mut pair = HittableList::new(Arc::new(Sphere::new(Point3::new(-5.0, 0.0, 0.0), 1.0, gray())));
+        pair.add(Arc::new(Sphere::new(Point3::new(5.0, 0.0, 0.0), 1.0, gray())));
+        let stats = KdTree::new(&pair).stats();
+        assert_eq!(stats, Stats { primitive_count: 2, node_count: 3, leaf_count: 2, max_depth: 1, leaf_references: 2 });
+
+        let list = mixed_scene(1000);
+        let n = list.objects.len();
+        let stats = KdTree::new(&list).stats();
+        assert_eq!(stats.primitive_count, n);
+        // 每个内部节点恰有两个子节点
+        assert_eq!(stats.node_count, 2 * stats.leaf_count - 1);
+        let depth_limit = (8.0 + 1.3 * (n as Real).log2()).round() as usize;
+        assert!(stats.max_depth <= depth_limit, "depth {} > {}", stats.max_depth, depth_limit);
+        assert!(stats.node_count < 32 * n, "{} nodes for {} primitives", stats.node_count, n);
+        // 每个物体至少出现在一个叶节点中，重复引用有限
+        assert!(stats.leaf_references >= n && stats.leaf_references < 8 * n, "{:?}", stats);
+        assert!(stats.average_leaf_size() < 4.0, "{:?}", stats);
+    }
+}
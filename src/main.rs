@@ -3,105 +3,59 @@
 //! 创建一个简单场景并渲染PPM格式图像
 
 pub mod vec3;
+pub mod aabb;
+pub mod accelerator;
+pub mod background;
 pub mod color;
 pub mod ray;
 pub mod hittable;
 pub mod sphere;
+pub mod quad;
+pub mod quadric;
+pub mod bezier;
+pub mod box3;
+pub mod bvh;
+pub mod capsule;
+pub mod cone;
+pub mod constant_medium;
+pub mod cylinder;
+pub mod csg;
+pub mod curve;
+pub mod disk;
+pub mod fractal;
+pub mod gltf;
+pub mod torus;
+pub mod heightfield;
+pub mod heterogeneous_medium;
 pub mod hittable_list;
+pub mod image;
+pub mod mesh;
 pub mod rtweekend;
+pub mod instance;
 pub mod interval;
+pub mod kdtree;
 pub mod camera;
+pub mod checkpoint;
 pub mod material;
-
-// use std::rc::Rc;
-use std::sync::Arc;
-
-use vec3::{Vec3, Point3};
-use color::Color;
-use sphere::Sphere;
-use hittable_list::HittableList;
-use camera::Camera;
-use material::{Material, Lambertian, Metal, Dielectric};
+pub mod microfacet;
+pub mod metaballs;
+pub mod moving_sphere;
+pub mod onb;
+pub mod packet;
+pub mod ply;
+pub mod polygon;
+pub mod principled;
+pub mod scenes;
+pub mod sdf;
+pub mod stats;
+pub mod subsurface;
+pub mod stl;
+pub mod texture;
+pub mod transform;
+pub mod voxel;
 
 fn main() {
-    // World
-    let mut world = HittableList::default();
-
-    let ground_material: Arc<dyn Material + Send + Sync> = Arc::new(
-        Lambertian::new(color::Color::new(0.5, 0.5, 0.5))
-    );
-    world.add(Arc::new(
-        Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material)
-    ));
-
-    for a in -8..8 {
-        for b in -8..8 {
-            let choose_mat = rtweekend::random_double();
-            let center = Point3::new(
-                a as f64 + 0.9 * rtweekend::random_double(),
-                0.2,
-                b as f64 + 0.9 * rtweekend::random_double(),
-            );
-
-            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                let sphere_material: Arc<dyn Material + Send + Sync> = if choose_mat < 0.8 {
-                    // diffuse
-                    let albedo = Color::random() * Color::random();
-                    Arc::new(Lambertian::new(albedo))
-                } else if choose_mat < 0.95 {
-                    // metal
-                    let albedo = Color::random_range(0.5, 1.0);
-                    let fuzz = rtweekend::random_double_range(0.0, 0.5);
-                    Arc::new(Metal::new(albedo, fuzz))
-                } else {
-                    // glass
-                    Arc::new(Dielectric::new(1.5))
-                };
-
-                world.add(Arc::new(
-                    Sphere::new(center, 0.2, sphere_material)
-                ));
-            }
-        }
-    }
-
-    let material1: Arc<dyn Material + Send + Sync> = Arc::new(
-        Dielectric::new(1.5)
-    );
-    
-    // 三个大球
-    world.add(Arc::new(
-        Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, material1)
-    ));
-
-    let material2: Arc<dyn Material + Send + Sync>= Arc::new(
-        Lambertian::new(Color::new(0.4, 0.2, 0.1))
-    );
-    world.add(Arc::new(
-        Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, material2)
-    ));
-
-    let material3: Arc<dyn Material + Send + Sync> = Arc::new(
-        Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)
-    );
-    world.add(Arc::new(
-        Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, material3)
-    ));
-
-    // Camera
-    let mut cam = Camera::default();
-    cam.aspect_ratio = 16.0 / 9.0;
-    cam.image_width = 1200;
-    cam.samples_per_pixel = 10;
-    cam.max_depth = 50;
-
-    cam.vfov = 20.0;
-    cam.lookfrom = Point3::new(13.0, 2.0, 3.0);
-    cam.lookat = Point3::new(0.0, 0.0, 0.0);
-    cam.vup = Vec3::new(0.0, 1.0, 0.0);
-
-    cam.defocus_angle = 0.6;
-    cam.focus_dist = 10.0;
+    let (mut cam, world) = scenes::random_spheres();
 
     // Render (统计时间)
     use std::time::Instant;
@@ -5,8 +5,45 @@
 use super::ray::Ray;
 use super::color::Color;
 use super::hittable::HitRecord;
-use super::vec3::{self};
-use super::rtweekend;
+use super::vec3::{self, Vec3};
+use super::rtweekend::{self, Real};
+use super::texture::{SolidColor, Texture};
+use super::microfacet::{self, Ggx};
+use super::onb::Onb;
+
+use std::sync::Arc;
+
+/// 一次散射的结果
+///
+/// # Fields
+/// - attenuation: 光线衰减颜色
+/// - scattered: 散射光线，起点通常为命中点；材质也可以把起点移到别处(如次表面散射的射出点)，此时积分器直接使用该起点
+/// - pdf: 散射方向的概率密度；镜面散射或材质不提供概率密度时为None
+/// - is_specular: 是否为镜面散射(方向确定，积分器应跳过PDF加权)
+#[derive(Clone, Copy, Debug)]
+pub struct ScatterRecord {
+    pub attenuation: Color,
+    pub scattered: Ray,
+    pub pdf: Option<Real>,
+    pub is_specular: bool,
+}
+
+impl ScatterRecord {
+    /// 把散射结果写入`scatter`的出参，供旧接口适配使用
+    ///
+    /// # Returns
+    /// 返回是否发生散射
+    pub fn write_out(srec: Option<ScatterRecord>, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        match srec {
+            Some(srec) => {
+                *attenuation = srec.attenuation;
+                *scattered = srec.scattered;
+                true
+            }
+            None => false,
+        }
+    }
+}
 
 /// 材质抽象接口，定义光线如何与物体表面交互
 /// 
@@ -23,14 +60,105 @@ pub trait Material {
     /// # Returns
     /// 返回是否发生散射
     fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool;
+
+    /// 计算光线的散射行为，以结构体返回结果
+    ///
+    /// 默认实现调用`scatter`，并由`scattering_pdf`补全概率密度；概率密度为0的材质
+    /// 无法参与PDF加权，按镜面散射处理。内置材质直接实现本方法，`scatter`只是对它的适配
+    ///
+    /// # Arguments
+    /// * `r_in` - 入射光线
+    /// * `rec` - 命中记录
+    ///
+    /// # Returns
+    /// 发生散射时返回散射记录，否则返回None
+    fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let mut attenuation = Color::default();
+        let mut scattered = Ray::default();
+        if !self.scatter(r_in, rec, &mut attenuation, &mut scattered) {
+            return None;
+        }
+
+        let pdf = Some(self.scattering_pdf(r_in, rec, &scattered)).filter(|pdf| *pdf > 0.0);
+        Some(ScatterRecord { attenuation, scattered, pdf, is_specular: pdf.is_none() })
+    }
+
+    /// 散射方向的概率密度
+    ///
+    /// 供基于PDF的积分器使用，返回材质在`scattered`方向上的散射概率密度
+    ///
+    /// # Arguments
+    /// * `r_in` - 入射光线
+    /// * `rec` - 命中记录
+    /// * `scattered` - 散射光线
+    ///
+    /// # Returns
+    /// 默认返回0.0，表示材质不提供散射概率密度
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> Real {
+        0.0
+    }
+
+    /// 材质自身发出的光
+    ///
+    /// # Arguments
+    /// * `r_in` - 入射光线
+    /// * `rec` - 命中记录
+    ///
+    /// # Returns
+    /// 默认返回黑色，表示材质不发光
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
+        Color::default()
+    }
+}
+
+/// 计算法线贴图扰动后的着色法线
+///
+/// 法线贴图以RGB编码切线空间法线(各分量由[0,1]映射到[-1,1])，
+/// 切线空间由`HitRecord::tangent_frame`给出。没有法线贴图或命中点
+/// 没有切向量时返回原法线。
+///
+/// # Arguments
+/// * `rec` - 命中记录
+/// * `normal_map` - 可选的法线贴图纹理
+pub(crate) fn shading_normal(rec: &HitRecord, normal_map: Option<&dyn Texture>) -> Vec3 {
+    let (Some(map), Some(frame)) = (normal_map, rec.tangent_frame()) else {
+        return rec.normal;
+    };
+
+    let c = map.value(rec.u, rec.v, rec.p);
+    let local = 2.0 * c - Color::new(1.0, 1.0, 1.0);
+    vec3::unit_vector(frame.transform(local))
+}
+
+/// 表面的单双面属性
+///
+/// # Variants
+/// - TwoSided: 正反两面行为相同
+/// - FrontOnly: 只有正面(法线朝外的一面)起作用，背面为黑色
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sidedness {
+    #[default]
+    TwoSided,
+    FrontOnly,
+}
+
+impl Sidedness {
+    /// 判断命中的一面是否起作用
+    pub fn accepts(&self, rec: &HitRecord) -> bool {
+        *self == Sidedness::TwoSided || rec.front_face
+    }
 }
 
 /// 漫反射材质(兰伯特材质)
 /// 
 /// # Fields
-/// - albedo: 反射率，决定材质的颜色
+/// - albedo: 反射率纹理，决定材质的颜色
+/// - normal_map: 可选的切线空间法线贴图
+/// - sided: 单双面属性，单面时背面吸收所有光线
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
+    pub normal_map: Option<Arc<dyn Texture>>,
+    pub sided: Sidedness,
 }
 
 impl Lambertian {
@@ -39,55 +167,220 @@ impl Lambertian {
     /// # Arguments
     /// * `a` - 反射率颜色
     pub fn new(a: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(a)))
+    }
+
+    /// 使用纹理创建漫反射材质
+    ///
+    /// # Arguments
+    /// * `albedo` - 反射率纹理
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
         Self {
-            albedo: a,
+            albedo,
+            normal_map: None,
+            sided: Sidedness::default(),
         }
     }
+
+    /// 设置法线贴图
+    pub fn with_normal_map(mut self, normal_map: Arc<dyn Texture>) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
 }
 
 impl Material for Lambertian {
     /// 实现漫反射材质的散射行为
     /// 
     /// 光线在表面随机反射，遵循兰伯特余弦定律
-    fn scatter(&self, _r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
-        // false
-        let mut scatter_direction = rec.normal + vec3::random_unit_vector();
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        ScatterRecord::write_out(self.scatter2(r_in, rec), attenuation, scattered)
+    }
+
+    fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        if !self.sided.accepts(rec) {
+            return None;
+        }
+
+        let normal = shading_normal(rec, self.normal_map.as_deref());
+        let mut scatter_direction = normal + vec3::random_unit_vector();
 
         // 捕捉退化的散射方向
         if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
+            scatter_direction = normal;
         }
 
-        *scattered = Ray::new(rec.p, scatter_direction);
-        *attenuation = self.albedo;
-        true
+        let cos_theta = vec3::dot(normal, vec3::unit_vector(scatter_direction));
+        Some(ScatterRecord {
+            attenuation: self.albedo.value(rec.u, rec.v, rec.p),
+            scattered: Ray::with_time(rec.p, scatter_direction, r_in.time()),
+            pdf: Some(cos_theta.max(0.0) / rtweekend::PI),
+            is_specular: false,
+        })
+    }
+
+    /// 兰伯特材质的散射概率密度: max(0, cosθ/π)
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Real {
+        let normal = shading_normal(rec, self.normal_map.as_deref());
+        let cos_theta = vec3::dot(normal, vec3::unit_vector(scattered.direction()));
+        if cos_theta < 0.0 { 0.0 } else { cos_theta / rtweekend::PI }
+    }
+}
+
+/// Oren-Nayar粗糙漫反射材质
+///
+/// 把表面看作朝向随机分布的V形微小凹槽，每个凹槽面都是兰伯特面。
+/// 考虑了凹槽间的遮挡和相互反射，粗糙表面在逆光方向更亮、边缘不会变暗，
+/// 适合陶土、混凝土、月面等纯兰伯特着色下显得过暗过平的哑光表面
+///
+/// # Fields
+/// - albedo: 反射率纹理
+/// - roughness: 粗糙度，即凹槽面朝向角的标准差(弧度)，0时退化为兰伯特材质
+/// - normal_map: 可选的切线空间法线贴图
+pub struct OrenNayar {
+    pub albedo: Arc<dyn Texture>,
+    pub roughness: Real,
+    pub normal_map: Option<Arc<dyn Texture>>,
+}
+
+impl OrenNayar {
+    /// 创建Oren-Nayar材质
+    ///
+    /// # Arguments
+    /// * `albedo` - 反射率颜色
+    /// * `roughness` - 凹槽面朝向角的标准差(弧度)，常见哑光材料约为0.3~0.6
+    pub fn new(albedo: Color, roughness: Real) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), roughness)
+    }
+
+    /// 使用纹理创建Oren-Nayar材质
+    ///
+    /// # Arguments
+    /// * `albedo` - 反射率纹理
+    /// * `roughness` - 凹槽面朝向角的标准差(弧度)
+    pub fn from_texture(albedo: Arc<dyn Texture>, roughness: Real) -> Self {
+        Self { albedo, roughness: roughness.max(0.0), normal_map: None }
+    }
+
+    /// 设置法线贴图
+    pub fn with_normal_map(mut self, normal_map: Arc<dyn Texture>) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    /// BRDF相对于兰伯特项 albedo/π 的比例因子
+    ///
+    /// 使用Oren-Nayar的定性模型：A + B·max(0, cos(φi - φo))·sinα·tanβ，
+    /// 其中α、β分别为入射角和出射角中较大和较小的一个
+    ///
+    /// # Arguments
+    /// * `normal` - 着色法线
+    /// * `wo` - 指向观察者的单位向量
+    /// * `wi` - 指向散射方向的单位向量
+    fn factor(&self, normal: Vec3, wo: Vec3, wi: Vec3) -> Real {
+        let sigma2 = self.roughness * self.roughness;
+        let a = 1.0 - 0.5 * sigma2 / (sigma2 + 0.33);
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+        let cos_i = vec3::dot(normal, wi).max(0.0);
+        let cos_o = vec3::dot(normal, wo).max(0.0);
+        let sin_i = (1.0 - cos_i * cos_i).max(0.0).sqrt();
+        let sin_o = (1.0 - cos_o * cos_o).max(0.0).sqrt();
+
+        // 两个方向在切平面上投影的夹角余弦，任一方向接近法线时方位角无意义，取0
+        let cos_phi = if sin_i > 1e-4 && sin_o > 1e-4 {
+            vec3::dot(wi - cos_i * normal, wo - cos_o * normal) / (sin_i * sin_o)
+        } else {
+            0.0
+        };
+
+        let (sin_alpha, tan_beta) = if cos_i > cos_o {
+            (sin_o, sin_i / cos_i)
+        } else if cos_o > 0.0 {
+            (sin_i, sin_o / cos_o)
+        } else {
+            (sin_i, 0.0)
+        };
+        a + b * cos_phi.max(0.0) * sin_alpha * tan_beta
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        ScatterRecord::write_out(self.scatter2(r_in, rec), attenuation, scattered)
+    }
+
+    /// 与兰伯特材质一样按余弦分布采样，BRDF中的比例因子计入衰减
+    fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let normal = shading_normal(rec, self.normal_map.as_deref());
+        let mut scatter_direction = normal + vec3::random_unit_vector();
+        if scatter_direction.near_zero() {
+            scatter_direction = normal;
+        }
+
+        let wi = vec3::unit_vector(scatter_direction);
+        let wo = -vec3::unit_vector(r_in.direction());
+        let cos_theta = vec3::dot(normal, wi);
+        Some(ScatterRecord {
+            attenuation: self.factor(normal, wo, wi) * self.albedo.value(rec.u, rec.v, rec.p),
+            scattered: Ray::with_time(rec.p, scatter_direction, r_in.time()),
+            pdf: Some(cos_theta.max(0.0) / rtweekend::PI),
+            is_specular: false,
+        })
+    }
+
+    /// 余弦采样的概率密度: max(0, cosθ/π)
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Real {
+        let normal = shading_normal(rec, self.normal_map.as_deref());
+        let cos_theta = vec3::dot(normal, vec3::unit_vector(scattered.direction()));
+        if cos_theta < 0.0 { 0.0 } else { cos_theta / rtweekend::PI }
     }
 }
 
 /// 金属材质，模拟金属表面反射
 /// 
 /// # Fields
-/// - albedo: 金属颜色/反射率
+/// - albedo: 金属颜色/反射率纹理
+/// - fuzz: 表面粗糙度纹理，取红色通道作为标量，限制在[0,1]
+/// - normal_map: 可选的切线空间法线贴图
 /// 
 /// 在光线追踪中，albedo 通常表示：
 /// // 表面反射的光能量比例（0~1），可能还带有颜色分量。
 /// // 标量 albedo（0~1）：表示表面反射光的能量比例，剩下的能量被吸收。
 /// // 向量 albedo（RGB）：不仅表示反射比例，还表示反射颜色，比如 (0.8, 0.8, 0.0) 就是反射 80% 的红光和绿光，不反射蓝光。
 pub struct Metal {
-  pub albedo: Color,
-  pub fuzz: f64,
+  pub albedo: Arc<dyn Texture>,
+  pub fuzz: Arc<dyn Texture>,
+  pub normal_map: Option<Arc<dyn Texture>>,
 }
 
 impl Metal {
+  /// 模糊反射落到表面以下时的最大重新采样次数
+  const FUZZ_RETRIES: usize = 8;
+
   /// 创建新的金属材质
   /// 
   /// # Arguments
   /// * `a` - 金属颜色/反射率
-  pub fn new(a: Color, f: f64) -> Self {
-    Self {
-      albedo: a,
-      fuzz: if f < 1.0 { f } else { 1.0 },
-    }
+  /// * `f` - 表面粗糙度
+  pub fn new(a: Color, f: Real) -> Self {
+    let f = if f < 1.0 { f } else { 1.0 };
+    Self::from_textures(Arc::new(SolidColor::new(a)), Arc::new(SolidColor::from_rgb(f, f, f)))
+  }
+
+  /// 使用纹理创建金属材质，颜色和粗糙度可随表面位置变化
+  /// 
+  /// # Arguments
+  /// * `albedo` - 金属颜色/反射率纹理
+  /// * `fuzz` - 粗糙度纹理(取红色通道)
+  pub fn from_textures(albedo: Arc<dyn Texture>, fuzz: Arc<dyn Texture>) -> Self {
+    Self { albedo, fuzz, normal_map: None }
+  }
+
+  /// 设置法线贴图
+  pub fn with_normal_map(mut self, normal_map: Arc<dyn Texture>) -> Self {
+    self.normal_map = Some(normal_map);
+    self
   }
 }
 
@@ -96,26 +389,131 @@ impl Material for Metal {
   /// 
   /// 光线在表面完美反射(镜面反射)
   fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+    ScatterRecord::write_out(self.scatter2(r_in, rec), attenuation, scattered)
+  }
+
+  fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
     // 计算反射方向：入射光线方向关于法线的镜面反射
     // 1. 先归一化入射光线方向
     // 2. 使用vec3::reflect函数计算反射向量
-    let reflected = vec3::reflect(vec3::unit_vector(r_in.direction()), rec.normal);
+    let normal = shading_normal(rec, self.normal_map.as_deref());
+    let reflected = vec3::reflect(vec3::unit_vector(r_in.direction()), normal);
     
     // 创建新的散射光线：
-    *scattered = Ray::new(rec.p, reflected + self.fuzz * vec3::random_in_unit_sphere());
-    
-    // 设置衰减颜色为材质的反射率(albedo)
-    // 金属会吸收部分光线能量，用albedo表示反射的颜色和强度
-    *attenuation = self.albedo;
+    // 模糊偏移使光线落到表面以下时重新采样，最多尝试FUZZ_RETRIES次，
+    // 仍失败则退回无模糊的镜面反射，避免掠射角处能量被直接丢弃而偏暗
+    let fuzz = self.fuzz.value(rec.u, rec.v, rec.p).x().clamp(0.0, 1.0);
+    let direction = (0..Self::FUZZ_RETRIES)
+      .map(|_| reflected + fuzz * vec3::random_in_unit_sphere())
+      .find(|d| vec3::dot(*d, rec.normal) > 0.0)
+      .unwrap_or(reflected);
     
     // 确保反射光线在半球空间内（点积大于0：夹脚小于90度）
-    vec3::dot(scattered.direction(), rec.normal) > 0.0
+    if vec3::dot(direction, rec.normal) <= 0.0 {
+      return None;
+    }
+
+    // 衰减颜色为材质的反射率(albedo)
+    // 金属会吸收部分光线能量，用albedo表示反射的颜色和强度
+    Some(ScatterRecord {
+      attenuation: self.albedo.value(rec.u, rec.v, rec.p),
+      scattered: Ray::with_time(rec.p, direction, r_in.time()),
+      pdf: None,
+      is_specular: true,
+    })
   }
 }
 
+/// 基于GGX微表面模型的粗糙金属
+///
+/// 与`Metal`在反射方向附近加随机偏移的做法不同，本材质按GGX可见法线分布采样微表面，
+/// 用Smith遮蔽-阴影函数和Schlick菲涅尔项计算权重，高光形状和掠射角处的
+/// 亮度变化都符合物理
+///
+/// # Fields
+/// - albedo: 垂直入射时的反射率(金属颜色)纹理
+/// - roughness: 感知粗糙度纹理，取红色通道作为标量，限制在[0,1]
+/// - normal_map: 可选的切线空间法线贴图
+pub struct GgxMetal {
+    pub albedo: Arc<dyn Texture>,
+    pub roughness: Arc<dyn Texture>,
+    pub normal_map: Option<Arc<dyn Texture>>,
+}
+
+impl GgxMetal {
+    /// 创建GGX金属材质
+    ///
+    /// # Arguments
+    /// * `albedo` - 金属颜色/垂直入射反射率
+    /// * `roughness` - 感知粗糙度，0为镜面
+    pub fn new(albedo: Color, roughness: Real) -> Self {
+        Self::from_textures(Arc::new(SolidColor::new(albedo)), Arc::new(SolidColor::from_rgb(roughness, roughness, roughness)))
+    }
+
+    /// 使用纹理创建GGX金属材质，颜色和粗糙度可随表面位置变化
+    ///
+    /// # Arguments
+    /// * `albedo` - 金属颜色纹理
+    /// * `roughness` - 粗糙度纹理(取红色通道)
+    pub fn from_textures(albedo: Arc<dyn Texture>, roughness: Arc<dyn Texture>) -> Self {
+        Self { albedo, roughness, normal_map: None }
+    }
+
+    /// 设置法线贴图
+    pub fn with_normal_map(mut self, normal_map: Arc<dyn Texture>) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    /// 命中点的微表面分布和以着色法线为z轴的局部坐标系
+    fn local(&self, rec: &HitRecord) -> (Ggx, Onb) {
+        let ggx = Ggx::from_roughness(self.roughness.value(rec.u, rec.v, rec.p).x());
+        (ggx, Onb::new(shading_normal(rec, self.normal_map.as_deref())))
+    }
+}
+
+impl Material for GgxMetal {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        ScatterRecord::write_out(self.scatter2(r_in, rec), attenuation, scattered)
+    }
+
+    /// 按可见法线分布采样微表面法线并镜面反射
+    ///
+    /// 以该策略采样时 f·cosθ/pdf 化简为 F·G2/G1(wo)，直接作为衰减；
+    /// 反射到宏观表面以下的样本被遮挡，能量损失对应单次散射模型的暗化
+    fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let (ggx, frame) = self.local(rec);
+        let wo = frame.to_local(-vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let h = ggx.sample_visible_normal(wo);
+        let wi = vec3::reflect(-wo, h);
+        if wi.z() <= 0.0 || vec3::dot(frame.transform(wi), rec.normal) <= 0.0 {
+            return None;
+        }
+
+        let f = microfacet::schlick_fresnel(self.albedo.value(rec.u, rec.v, rec.p), vec3::dot(wo, h));
+        Some(ScatterRecord {
+            attenuation: f * (ggx.g2(wo, wi) / ggx.g1(wo)),
+            scattered: Ray::with_time(rec.p, frame.transform(wi), r_in.time()),
+            pdf: Some(ggx.reflection_pdf(wo, wi)).filter(|pdf| *pdf > 0.0),
+            is_specular: false,
+        })
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Real {
+        let (ggx, frame) = self.local(rec);
+        let wo = frame.to_local(-vec3::unit_vector(r_in.direction()));
+        let wi = frame.to_local(vec3::unit_vector(scattered.direction()));
+        ggx.reflection_pdf(wo, wi)
+    }
+}
+
 /// 电介质材质（透明物体如玻璃、水等）
 pub struct Dielectric {
-  pub ir: f64, // 折射指数(Index of Refraction)
+  pub ir: Real, // 折射指数(Index of Refraction)
 }
 
 impl Dielectric {
@@ -123,7 +521,7 @@ impl Dielectric {
     /// 
     /// # Arguments
     /// * `index_of_refraction` - 材质折射率(如玻璃为1.5)
-    pub fn new(index_of_refraction: f64) -> Self {
+    pub fn new(index_of_refraction: Real) -> Self {
         Self {
             ir: index_of_refraction,
         }
@@ -139,7 +537,7 @@ impl Dielectric {
     /// # Reference
     /// http://graphics.stanford.edu/courses/cs148-10-summer/docs/2006--degreve--reflection_refraction.pdf
     #[inline]
-    fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
+    fn reflectance(cosine: Real, ref_idx: Real) -> Real {
        // 计算垂直入射时的基础反射率R0
        let r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
        let r0 = r0 * r0;
@@ -152,14 +550,28 @@ impl Material for Dielectric {
   /// 实现电介质材质的散射行为
   /// 同时考虑折射和全反射现象
   fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
-    // 电介质不吸收光线（全透射或全反射）
-    *attenuation = Color::new(1.0, 1.0, 1.0);
-    
+    ScatterRecord::write_out(self.scatter2(r_in, rec), attenuation, scattered)
+  }
+
+  fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+    // 电介质不吸收光线（全透射或全反射），总是发生散射（反射或折射）
+    Some(ScatterRecord {
+      attenuation: Color::new(1.0, 1.0, 1.0),
+      scattered: Ray::with_time(rec.p, Self::scatter_direction(r_in, rec, self.ir), r_in.time()),
+      pdf: None,
+      is_specular: true,
+    })
+  }
+}
+
+impl Dielectric {
+  /// 按给定折射率随机选择反射或折射，返回散射方向
+  fn scatter_direction(r_in: &Ray, rec: &HitRecord, ir: Real) -> Vec3 {
     // 根据光线入射面计算折射率比值
     let refraction_ratio = if rec.front_face { 
-        1.0 / self.ir  // 从空气进入介质
+        1.0 / ir  // 从空气进入介质
     } else { 
-        self.ir         // 从介质进入空气
+        ir         // 从介质进入空气
     };
 
     let unit_direction = vec3::unit_vector(r_in.direction());
@@ -168,13 +580,720 @@ impl Material for Dielectric {
 
     // 检查是否发生全反射（斯涅尔定律不成立）
     let cannot_refract = refraction_ratio * sin_theta > 1.0;
-    let direction = if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > rtweekend::random_double() { 
+    if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > rtweekend::random_double() { 
         vec3::reflect(unit_direction, rec.normal)  // 反射
     } else {
         vec3::refract(unit_direction, rec.normal, refraction_ratio)  // 折射
-    };
+    }
+  }
+}
+
+/// 带色散的电介质材质，红绿蓝三个通道使用不同的折射率
+///
+/// 每次散射随机选择一个通道，按该通道的折射率追踪，衰减只保留该通道(乘3补偿选择概率)。
+/// 路径上各次散射选中的通道不同时贡献为0，因此多次采样平均后是无偏的，
+/// 在折射边缘形成彩色分光
+///
+/// # Fields
+/// - ir: 红、绿、蓝通道的折射率
+pub struct DielectricDispersive {
+  pub ir: [Real; 3],
+}
+
+/// 色散计算使用的红、绿、蓝通道代表波长(nm)
+const CHANNEL_WAVELENGTHS: [Real; 3] = [650.0, 550.0, 450.0];
+
+impl DielectricDispersive {
+    /// 使用三个通道各自的折射率创建色散电介质
+    ///
+    /// # Arguments
+    /// * `ir_r` - 红色通道折射率
+    /// * `ir_g` - 绿色通道折射率
+    /// * `ir_b` - 蓝色通道折射率
+    pub fn new(ir_r: Real, ir_g: Real, ir_b: Real) -> Self {
+        Self { ir: [ir_r, ir_g, ir_b] }
+    }
 
-    *scattered = Ray::new(rec.p, direction);
-    true  // 总是发生散射（反射或折射）
+    /// 由d线(587.6nm)折射率和阿贝数创建色散电介质
+    ///
+    /// 使用柯西公式 n(λ) = A + B/λ²，由 V = (n_d - 1)/(n_F - n_C) 解出A、B，
+    /// 再在各通道的代表波长处求折射率。阿贝数越小色散越强(冕牌玻璃约60，火石玻璃约30)
+    ///
+    /// # Arguments
+    /// * `ir` - d线折射率
+    /// * `abbe` - 阿贝数(应大于0)
+    pub fn from_abbe(ir: Real, abbe: Real) -> Self {
+        let inv_sq = |lambda: Real| 1.0 / (lambda * lambda);
+        let b = (ir - 1.0) / (abbe * (inv_sq(486.1) - inv_sq(656.3)));
+        let a = ir - b * inv_sq(587.6);
+        let [r, g, bl] = CHANNEL_WAVELENGTHS.map(|lambda| a + b * inv_sq(lambda));
+        Self::new(r, g, bl)
+    }
+}
+
+impl Material for DielectricDispersive {
+  fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+    ScatterRecord::write_out(self.scatter2(r_in, rec), attenuation, scattered)
   }
-}
\ No newline at end of file
+
+  fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+    let channel = ((rtweekend::random_double() * 3.0) as usize).min(2);
+    let mut weight = Color::default();
+    weight[channel] = 3.0;
+    Some(ScatterRecord {
+      attenuation: weight,
+      scattered: Ray::with_time(rec.p, Dielectric::scatter_direction(r_in, rec, self.ir[channel]), r_in.time()),
+      pdf: None,
+      is_specular: true,
+    })
+  }
+}
+
+/// 各向同性材质，用于体积雾、烟等参与介质
+///
+/// 光线在介质内部向所有方向均匀散射
+///
+/// # Fields
+/// - albedo: 散射反照率纹理
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    /// 创建纯色的各向同性材质
+    ///
+    /// # Arguments
+    /// * `albedo` - 散射反照率
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    /// 使用纹理创建各向同性材质
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    /// 向单位球面上均匀随机的方向散射
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        *scattered = Ray::with_time(rec.p, vec3::random_unit_vector(), r_in.time());
+        *attenuation = self.albedo.value(rec.u, rec.v, rec.p);
+        true
+    }
+
+    /// 均匀球面分布的概率密度: 1/(4π)
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> Real {
+        1.0 / (4.0 * rtweekend::PI)
+    }
+}
+
+/// 自发光的漫反射材质
+///
+/// 既像兰伯特材质一样散射光线，又发出`emit * strength`的光，
+/// 可用于微微发光的涂装面板等
+///
+/// # Fields
+/// - albedo: 反射率
+/// - emit: 发光颜色
+/// - strength: 发光强度
+pub struct EmissiveLambertian {
+    pub albedo: Color,
+    pub emit: Color,
+    pub strength: Real,
+}
+
+impl EmissiveLambertian {
+    /// 创建自发光漫反射材质
+    ///
+    /// # Arguments
+    /// * `albedo` - 反射率
+    /// * `emit` - 发光颜色
+    /// * `strength` - 发光强度
+    pub fn new(albedo: Color, emit: Color, strength: Real) -> Self {
+        Self { albedo, emit, strength }
+    }
+}
+
+impl Material for EmissiveLambertian {
+    /// 与兰伯特材质相同的余弦分布散射
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        let mut scatter_direction = rec.normal + vec3::random_unit_vector();
+        if scatter_direction.near_zero() {
+            scatter_direction = rec.normal;
+        }
+
+        *scattered = Ray::with_time(rec.p, scatter_direction, r_in.time());
+        *attenuation = self.albedo;
+        true
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Real {
+        let cos_theta = vec3::dot(rec.normal, vec3::unit_vector(scattered.direction()));
+        if cos_theta < 0.0 { 0.0 } else { cos_theta / rtweekend::PI }
+    }
+
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
+        self.emit * self.strength
+    }
+}
+
+/// 漫射光源材质
+///
+/// 不散射光线，只向外发光
+///
+/// # Fields
+/// - emit: 发光颜色纹理
+/// - sided: 单双面属性，单面光源只从正面发光
+pub struct DiffuseLight {
+    pub emit: Arc<dyn Texture>,
+    pub sided: Sidedness,
+}
+
+impl DiffuseLight {
+    /// 创建纯色的双面光源
+    ///
+    /// # Arguments
+    /// * `emit` - 发光颜色(可大于1表示更强的光)
+    pub fn new(emit: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(emit)))
+    }
+
+    /// 使用纹理创建双面光源
+    pub fn from_texture(emit: Arc<dyn Texture>) -> Self {
+        Self { emit, sided: Sidedness::default() }
+    }
+}
+
+impl Material for DiffuseLight {
+    /// 光源不散射光线
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord, _attenuation: &mut Color, _scattered: &mut Ray) -> bool {
+        false
+    }
+
+    fn emitted(&self, _r_in: &Ray, rec: &HitRecord) -> Color {
+        if !self.sided.accepts(rec) {
+            return Color::default();
+        }
+        self.emit.value(rec.u, rec.v, rec.p)
+    }
+}
+
+/// 法线贴图包装材质
+///
+/// 用法线贴图扰动命中记录中的法线后交给内部材质处理，
+/// 使玻璃、发光体等没有内置法线贴图选项的材质也能获得表面细节
+///
+/// # Fields
+/// - inner: 被包装的材质
+/// - normal_map: 切线空间法线贴图，通常用`ImageTexture::load_linear`加载
+pub struct NormalMap {
+    pub inner: Arc<dyn Material + Send + Sync>,
+    pub normal_map: Arc<dyn Texture>,
+}
+
+impl NormalMap {
+    /// 创建法线贴图包装材质
+    ///
+    /// # Arguments
+    /// * `inner` - 被包装的材质
+    /// * `normal_map` - 切线空间法线贴图
+    pub fn new(inner: Arc<dyn Material + Send + Sync>, normal_map: Arc<dyn Texture>) -> Self {
+        Self { inner, normal_map }
+    }
+
+    /// 返回法线被扰动后的命中记录
+    fn perturbed(&self, rec: &HitRecord) -> HitRecord {
+        let mut rec = rec.clone();
+        rec.normal = shading_normal(&rec, Some(self.normal_map.as_ref()));
+        rec
+    }
+}
+
+impl Material for NormalMap {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        self.inner.scatter(r_in, &self.perturbed(rec), attenuation, scattered)
+    }
+
+    fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        self.inner.scatter2(r_in, &self.perturbed(rec))
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Real {
+        self.inner.scattering_pdf(r_in, &self.perturbed(rec), scattered)
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        self.inner.emitted(r_in, &self.perturbed(rec))
+    }
+}
+
+/// 凹凸贴图包装材质
+///
+/// 用高度纹理的有限差分求出表面斜率并扰动法线，再交给内部材质处理。
+/// 比法线贴图简单，适合程序化纹理和灰度高度图
+///
+/// # Fields
+/// - inner: 被包装的材质
+/// - height: 高度纹理，取RGB三个分量的平均值作为高度
+/// - scale: 高度缩放，越大凹凸越明显，为负时凹凸反转
+/// - delta: 有限差分的步长
+pub struct BumpMap {
+    pub inner: Arc<dyn Material + Send + Sync>,
+    pub height: Arc<dyn Texture>,
+    pub scale: Real,
+    pub delta: Real,
+}
+
+impl BumpMap {
+    /// 创建凹凸贴图包装材质，有限差分步长默认为0.001
+    ///
+    /// # Arguments
+    /// * `inner` - 被包装的材质
+    /// * `height` - 高度纹理
+    /// * `scale` - 高度缩放
+    pub fn new(inner: Arc<dyn Material + Send + Sync>, height: Arc<dyn Texture>, scale: Real) -> Self {
+        Self { inner, height, scale, delta: 0.001 }
+    }
+
+    /// 设置有限差分的步长
+    ///
+    /// # Note
+    /// 步长应小于高度纹理的细节尺度，但过小会放大浮点误差。
+    /// 图像纹理按最近像素取值，步长不应小于一个像素(1 / 图像宽度)，
+    /// 否则高度差只在像素边界处非零，表面会出现网格状条纹
+    pub fn with_delta(mut self, delta: Real) -> Self {
+        self.delta = delta;
+        self
+    }
+
+    /// 返回法线被扰动后的命中记录
+    ///
+    /// 沿切向量和副切向量各偏移一个步长求高度差，偏移同时作用于纹理坐标和命中点位置，
+    /// 因此按(u, v)取值的图像纹理和按三维位置取值的程序化纹理都适用。
+    /// 命中点没有切向量时法线保持不变
+    fn perturbed(&self, rec: &HitRecord) -> HitRecord {
+        let mut rec = rec.clone();
+        let Some(frame) = rec.tangent_frame() else {
+            return rec;
+        };
+
+        let height = |u: Real, v: Real, p: Vec3| {
+            let c = self.height.value(u, v, p);
+            (c.x() + c.y() + c.z()) / 3.0
+        };
+        let d = self.delta;
+        let h = height(rec.u, rec.v, rec.p);
+        let dh_du = (height(rec.u + d, rec.v, rec.p + d * frame.u()) - h) / d;
+        let dh_dv = (height(rec.u, rec.v + d, rec.p + d * frame.v()) - h) / d;
+
+        rec.normal = vec3::unit_vector(frame.transform(Vec3::new(-self.scale * dh_du, -self.scale * dh_dv, 1.0)));
+        rec
+    }
+}
+
+impl Material for BumpMap {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        self.inner.scatter(r_in, &self.perturbed(rec), attenuation, scattered)
+    }
+
+    fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        self.inner.scatter2(r_in, &self.perturbed(rec))
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Real {
+        self.inner.scattering_pdf(r_in, &self.perturbed(rec), scattered)
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        self.inner.emitted(r_in, &self.perturbed(rec))
+    }
+}
+
+/// 清漆层包装材质
+///
+/// 在任意基底材质上覆盖一层薄的透明电介质涂层(清漆、车漆的透明层)。
+/// 涂层是独立粗糙度的GGX反射，按涂层的菲涅尔反射率随机选择在涂层反射，
+/// 或穿过涂层交给基底材质处理。涂层使用几何法线，基底的法线贴图不影响涂层的光滑程度
+///
+/// # Fields
+/// - base: 基底材质
+/// - roughness: 涂层的感知粗糙度，[0,1]
+/// - ior: 涂层折射率，决定垂直入射时的反射率
+pub struct Clearcoat {
+    pub base: Arc<dyn Material + Send + Sync>,
+    pub roughness: Real,
+    pub ior: Real,
+}
+
+impl Clearcoat {
+    /// 创建折射率为1.5的清漆层
+    ///
+    /// # Arguments
+    /// * `base` - 基底材质
+    /// * `roughness` - 涂层的感知粗糙度
+    pub fn new(base: Arc<dyn Material + Send + Sync>, roughness: Real) -> Self {
+        Self { base, roughness: roughness.clamp(0.0, 1.0), ior: 1.5 }
+    }
+
+    /// 设置涂层折射率
+    pub fn with_ior(mut self, ior: Real) -> Self {
+        self.ior = ior;
+        self
+    }
+
+    /// 涂层的菲涅尔反射率
+    ///
+    /// # Arguments
+    /// * `cosine` - 入射方向与(微)表面法线夹角的余弦
+    fn fresnel(&self, cosine: Real) -> Real {
+        let f0 = ((self.ior - 1.0) / (self.ior + 1.0)).powi(2);
+        microfacet::schlick_fresnel(Color::new(f0, f0, f0), cosine).x()
+    }
+}
+
+impl Material for Clearcoat {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        ScatterRecord::write_out(self.scatter2(r_in, rec), attenuation, scattered)
+    }
+
+    /// 按可见法线采样涂层的微表面法线，以该微表面的菲涅尔反射率为概率在涂层反射，
+    /// 此时衰减化简为 G2/G1；否则原样返回基底材质的散射结果
+    fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let frame = Onb::new(rec.normal);
+        let wo = frame.to_local(-vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return self.base.scatter2(r_in, rec);
+        }
+
+        let ggx = Ggx::from_roughness(self.roughness);
+        let h = ggx.sample_visible_normal(wo);
+        if rtweekend::random_double() >= self.fresnel(vec3::dot(wo, h)) {
+            return self.base.scatter2(r_in, rec);
+        }
+
+        let wi = vec3::reflect(-wo, h);
+        if wi.z() <= 0.0 {
+            return None;
+        }
+        let scattered = Ray::with_time(rec.p, frame.transform(wi), r_in.time());
+        Some(ScatterRecord {
+            attenuation: Color::new(1.0, 1.0, 1.0) * (ggx.g2(wo, wi) / ggx.g1(wo)),
+            pdf: Some(self.scattering_pdf(r_in, rec, &scattered)).filter(|pdf| *pdf > 0.0),
+            scattered,
+            is_specular: false,
+        })
+    }
+
+    /// 涂层与基底散射概率密度的混合，按宏观表面的菲涅尔反射率加权
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Real {
+        let frame = Onb::new(rec.normal);
+        let wo = frame.to_local(-vec3::unit_vector(r_in.direction()));
+        let wi = frame.to_local(vec3::unit_vector(scattered.direction()));
+        let f = self.fresnel(wo.z());
+        f * Ggx::from_roughness(self.roughness).reflection_pdf(wo, wi) + (1.0 - f) * self.base.scattering_pdf(r_in, rec, scattered)
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        self.base.emitted(r_in, rec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Point3;
+
+    /// 在原点、法线朝+z的正面命中记录
+    fn hit_facing_z() -> HitRecord {
+        HitRecord {
+            p: Point3::default(),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            t: 1.0,
+            front_face: true,
+            ..HitRecord::default()
+        }
+    }
+
+    #[test]
+    fn lambertian_pdf_integrates_to_one() {
+        rtweekend::seed_random(804);
+        let mat = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let rec = hit_facing_z();
+        let r_in = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+
+        // 在整个球面上均匀采样方向，∫pdf dω ≈ 4π · mean(pdf)
+        let n = 200_000;
+        let sum: Real = (0..n)
+            .map(|_| mat.scattering_pdf(&r_in, &rec, &Ray::new(rec.p, vec3::random_unit_vector())))
+            .sum();
+        let integral = 4.0 * rtweekend::PI * sum / n as Real;
+        assert!((integral - 1.0).abs() < 0.02, "integral = {}", integral);
+    }
+
+    /// 对同一命中点多次散射，返回散射方向与镜面反射方向夹角余弦的最小值
+    fn min_cosine_to_mirror(mat: &dyn Material, rec: &HitRecord, r_in: &Ray) -> Real {
+        let mirror = vec3::reflect(vec3::unit_vector(r_in.direction()), rec.normal);
+        (0..200)
+            .map(|_| {
+                let srec = mat.scatter2(r_in, rec).expect("metal above the surface always scatters");
+                vec3::dot(mirror, vec3::unit_vector(srec.scattered.direction()))
+            })
+            .fold(1.0, Real::min)
+    }
+
+    #[test]
+    fn checker_fuzz_metal_alternates_sharp_and_blurry() {
+        use crate::texture::CheckerTexture;
+        rtweekend::seed_random(815);
+        let mat = Metal::from_textures(
+            Arc::new(CheckerTexture::from_colors(1.0, Color::new(0.9, 0.1, 0.1), Color::new(0.1, 0.1, 0.9))),
+            Arc::new(CheckerTexture::from_colors(1.0, Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0))),
+        );
+        let r_in = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(1.0, 0.0, -1.0));
+
+        // 格子坐标之和为偶数：模糊度0，每次都是完美镜面反射
+        let sharp = HitRecord { p: Point3::new(0.5, 0.5, 0.5), ..hit_facing_z() };
+        assert!(min_cosine_to_mirror(&mat, &sharp, &r_in) > 1.0 - 1e-6);
+        assert_eq!(mat.scatter2(&r_in, &sharp).unwrap().attenuation.to_array(), [0.9, 0.1, 0.1]);
+
+        // 相邻的奇数格：模糊度1，反射方向明显分散
+        let blurry = HitRecord { p: Point3::new(1.5, 0.5, 0.5), ..hit_facing_z() };
+        assert!(min_cosine_to_mirror(&mat, &blurry, &r_in) < 0.9);
+        assert_eq!(mat.scatter2(&r_in, &blurry).unwrap().attenuation.to_array(), [0.1, 0.1, 0.9]);
+    }
+
+    #[test]
+    fn flat_normal_map_leaves_shading_normal_unchanged() {
+        use crate::texture::SolidColor;
+        let flat: Arc<dyn Texture> = Arc::new(SolidColor::from_rgb(0.5, 0.5, 1.0));
+        let tilted: Arc<dyn Texture> = Arc::new(SolidColor::from_rgb(0.9, 0.5, 0.8));
+
+        let normal = vec3::unit_vector(Vec3::new(0.3, -0.4, 0.8));
+        let rec = HitRecord {
+            normal,
+            tangent: vec3::unit_vector(vec3::cross(normal, Vec3::new(0.0, 1.0, 0.0))),
+            ..hit_facing_z()
+        };
+        assert!((shading_normal(&rec, Some(flat.as_ref())) - normal).length() < 1e-5);
+        assert!((shading_normal(&rec, Some(tilted.as_ref())) - normal).length() > 0.1);
+
+        // 包装材质：平坦法线贴图下的镜面反射与原材质完全一致
+        let mirror: Arc<dyn Material + Send + Sync> = Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.0));
+        let mapped = NormalMap::new(Arc::clone(&mirror), flat);
+        let r_in = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.2, 0.1, -1.0));
+        let a = mirror.scatter2(&r_in, &rec).unwrap().scattered.direction();
+        let b = mapped.scatter2(&r_in, &rec).unwrap().scattered.direction();
+        assert!((a - b).length() < 1e-5);
+    }
+
+    #[test]
+    fn isotropic_scatters_uniformly_with_textured_albedo() {
+        use crate::texture::CheckerTexture;
+        rtweekend::seed_random(818);
+        let mat = Isotropic::from_texture(Arc::new(CheckerTexture::from_colors(
+            1.0,
+            Color::new(0.2, 0.4, 0.6),
+            Color::new(0.9, 0.8, 0.7),
+        )));
+        let rec = HitRecord { p: Point3::new(0.5, 0.5, 0.5), ..HitRecord::default() };
+        let r_in = Ray::new(Point3::default(), Vec3::new(1.0, 0.0, 0.0));
+
+        // 均匀球面分布：方向均值接近0，且每个方向都是单位向量
+        let n = 20_000;
+        let mut mean = Vec3::default();
+        for _ in 0..n {
+            let mut attenuation = Color::default();
+            let mut scattered = Ray::default();
+            assert!(mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+            assert!((scattered.direction().length() - 1.0).abs() < 1e-4);
+            mean += scattered.direction() / n as Real;
+        }
+        assert!(mean.length() < 0.03, "mean direction = {:?}", mean);
+
+        let mut attenuation = Color::default();
+        let mut scattered = Ray::default();
+        mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered);
+        assert_eq!(attenuation.to_array(), [0.2, 0.4, 0.6]);
+        let odd = HitRecord { p: Point3::new(1.5, 0.5, 0.5), ..rec };
+        mat.scatter(&r_in, &odd, &mut attenuation, &mut scattered);
+        assert_eq!(attenuation.to_array(), [0.9, 0.8, 0.7]);
+    }
+
+    #[test]
+    fn emissive_lambertian_scatters_and_emits() {
+        rtweekend::seed_random(824);
+        let mat = EmissiveLambertian::new(Color::new(0.5, 0.5, 0.5), Color::new(1.0, 0.5, 0.25), 4.0);
+        let rec = hit_facing_z();
+        let r_in = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+
+        for _ in 0..100 {
+            let (mut attenuation, mut scattered) = (Color::default(), Ray::default());
+            assert!(mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+            assert_eq!(attenuation.e, [0.5, 0.5, 0.5]);
+            assert!(vec3::dot(scattered.direction(), rec.normal) >= 0.0);
+        }
+        assert_eq!(mat.emitted(&r_in, &rec).e, [4.0, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn front_only_light_is_black_from_behind() {
+        use crate::hittable::Hittable;
+        use crate::interval::Interval;
+        use crate::quad::Quad;
+
+        let emit = Color::new(4.0, 4.0, 4.0);
+        let one_sided = Arc::new(DiffuseLight { sided: Sidedness::FrontOnly, ..DiffuseLight::new(emit) });
+        let two_sided = Arc::new(DiffuseLight::new(emit));
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+
+        // z = 0平面上法线朝+z的四边形，分别从正面(+z)和背面(-z)看过去
+        let front = Ray::new(Point3::new(0.5, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let back = Ray::new(Point3::new(0.5, 0.5, -1.0), Vec3::new(0.0, 0.0, 1.0));
+        let emitted = |light: Arc<DiffuseLight>, r: &Ray| {
+            let quad = Quad::new(Point3::default(), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), light.clone());
+            let mut rec = HitRecord::default();
+            assert!(quad.hit(r, &ray_t, &mut rec));
+            light.emitted(r, &rec)
+        };
+
+        assert_eq!(emitted(one_sided.clone(), &front).e, emit.e);
+        assert_eq!(emitted(one_sided, &back).e, [0.0; 3]);
+        assert_eq!(emitted(two_sided.clone(), &front).e, emit.e);
+        assert_eq!(emitted(two_sided, &back).e, emit.e);
+
+        // 单面的漫反射材质从背面命中时吸收光线
+        let mut rec = hit_facing_z();
+        let paint = Lambertian { sided: Sidedness::FrontOnly, ..Lambertian::new(Color::new(0.5, 0.5, 0.5)) };
+        assert!(paint.scatter2(&front, &rec).is_some());
+        rec.front_face = false;
+        assert!(paint.scatter2(&back, &rec).is_none());
+    }
+
+    #[test]
+    fn dispersive_prism_splits_white_light_by_channel() {
+        rtweekend::seed_random(835);
+        let prism = DielectricDispersive::from_abbe(1.5, 20.0);
+        assert!(prism.ir[0] < prism.ir[1] && prism.ir[1] < prism.ir[2]);
+
+        // 以45°斜射入玻璃表面，记录各通道折射光线与法线夹角的正弦
+        let rec = hit_facing_z();
+        let r_in = Ray::new(Point3::new(-1.0, 0.0, 1.0), Vec3::new(1.0, 0.0, -1.0));
+        let mut sin_t: [Option<Real>; 3] = [None; 3];
+        for _ in 0..300 {
+            let (mut attenuation, mut scattered) = (Color::default(), Ray::default());
+            assert!(prism.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+            let channel = (0..3).find(|&c| attenuation[c] > 0.0).unwrap();
+            assert_eq!(attenuation[channel], 3.0);
+            assert_eq!(attenuation.e.iter().filter(|&&a| a > 0.0).count(), 1);
+
+            let d = vec3::unit_vector(scattered.direction());
+            if d.z() < 0.0 {
+                // 同一通道的折射方向总是相同
+                let s = d.x();
+                let expected = *sin_t[channel].get_or_insert(s);
+                assert!((s - expected).abs() < 1e-5);
+            }
+        }
+
+        // 蓝光折射率最大，偏折最强，最靠近法线；三个通道的方向互不相同
+        let [r, g, b] = sin_t.map(|s| s.expect("every channel refracts at least once"));
+        assert!(r > g + 1e-3 && g > b + 1e-3, "sin θt = {} {} {}", r, g, b);
+        let sin_i = (0.5 as Real).sqrt();
+        for (s, n) in [r, g, b].into_iter().zip(prism.ir) {
+            assert!((s - sin_i / n).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn grazing_fuzzy_metal_always_reflects_above_the_surface() {
+        rtweekend::seed_random(836);
+        let mat = Metal::new(Color::new(0.8, 0.8, 0.8), 1.0);
+        let rec = hit_facing_z();
+        // 几乎贴着表面入射，镜面反射方向只比表面高0.01
+        let r_in = Ray::new(Point3::new(-1.0, 0.0, 0.01), Vec3::new(1.0, 0.0, -0.01));
+        for _ in 0..1000 {
+            let (mut attenuation, mut scattered) = (Color::default(), Ray::default());
+            assert!(mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+            assert!(vec3::dot(scattered.direction(), rec.normal) > 0.0);
+            assert_eq!(attenuation.e, [0.8, 0.8, 0.8]);
+        }
+    }
+
+    #[test]
+    fn smooth_ggx_metal_matches_mirror_metal() {
+        rtweekend::seed_random(1057);
+        let albedo = Color::new(0.9, 0.6, 0.3);
+        let (ggx, metal) = (GgxMetal::new(albedo, 0.0), Metal::new(albedo, 0.0));
+        let rec = hit_facing_z();
+        // 30°入射，Schlick菲涅尔项与垂直入射反射率只差 (1 - cos30°)^5 ≈ 4e-5
+        let r_in = Ray::new(Point3::new(-0.5, 0.0, 0.866), Vec3::new(0.5, 0.0, -0.866));
+        let mirror = metal.scatter2(&r_in, &rec).unwrap();
+        let n = 1000;
+        let mut cosine = 0.0;
+        for _ in 0..n {
+            let srec = ggx.scatter2(&r_in, &rec).expect("smooth metal always reflects");
+            cosine += vec3::dot(vec3::unit_vector(srec.scattered.direction()), vec3::unit_vector(mirror.scattered.direction())) / n as Real;
+            assert!((srec.attenuation - mirror.attenuation).length() < 1e-3, "attenuation = {:?}", srec.attenuation);
+        }
+        // alpha不低于Ggx::MIN_ALPHA，GGX的长尾偶尔采到偏离稍远的方向，但平均几乎就是镜面方向
+        assert!(cosine > 0.9995, "mean cosine to mirror = {cosine}");
+    }
+
+    #[test]
+    fn white_ggx_metal_never_gains_energy() {
+        rtweekend::seed_random(1057);
+        let rec = hit_facing_z();
+        for roughness in [0.2, 0.5, 1.0] {
+            let mat = GgxMetal::new(Color::new(1.0, 1.0, 1.0), roughness);
+            for cos in [1.0, 0.5, 0.1] {
+                let sin = (1.0 - cos * cos as Real).sqrt();
+                let r_in = Ray::new(Point3::new(-sin, 0.0, cos), Vec3::new(sin, 0.0, -cos));
+                let n = 10_000;
+                let mut albedo = 0.0;
+                for _ in 0..n {
+                    let Some(srec) = mat.scatter2(&r_in, &rec) else { continue };
+                    // F = 1 时每个样本的衰减 G2/G1 都不超过1
+                    assert!(srec.attenuation.x() <= 1.0 + 1e-6, "attenuation = {:?}", srec.attenuation);
+                    albedo += srec.attenuation.x() / n as Real;
+                }
+                assert!(albedo <= 1.0, "roughness {roughness}, cos {cos}: albedo = {albedo}");
+            }
+        }
+    }
+
+    #[test]
+    fn scatter_and_scatter2_agree() {
+        let materials: [(&str, Box<dyn Material>, bool); 4] = [
+            ("lambertian", Box::new(Lambertian::new(Color::new(0.5, 0.4, 0.3))), false),
+            ("metal", Box::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.3)), true),
+            ("glass", Box::new(Dielectric::new(1.5)), true),
+            ("dispersive", Box::new(DielectricDispersive::from_abbe(1.5, 40.0)), true),
+        ];
+        let mut back = hit_facing_z();
+        back.front_face = false;
+        back.normal = -back.normal;
+
+        for (name, mat, specular) in &materials {
+            for rec in [hit_facing_z(), back.clone()] {
+                for seed in 0..50u64 {
+                    let r_in = Ray::new(Point3::new(-1.0, 0.0, 1.0), Vec3::new(1.0, 0.3, -1.0));
+
+                    rtweekend::seed_random(seed);
+                    let (mut attenuation, mut scattered) = (Color::default(), Ray::default());
+                    let scatters = mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered);
+
+                    rtweekend::seed_random(seed);
+                    let srec = mat.scatter2(&r_in, &rec);
+                    assert_eq!(scatters, srec.is_some(), "{}", name);
+                    if let Some(srec) = srec {
+                        assert_eq!(srec.attenuation.e, attenuation.e, "{}", name);
+                        assert_eq!(srec.scattered.direction().e, scattered.direction().e, "{}", name);
+                        assert_eq!(srec.is_specular, *specular, "{}", name);
+                        assert_eq!(srec.pdf.is_none(), *specular, "{}", name);
+                    }
+                }
+            }
+        }
+    }
+}
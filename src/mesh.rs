@@ -0,0 +1,834 @@
+//! 三角网格模块
+//!
+//! 提供共享顶点缓冲区的索引三角网格。所有三角形共用同一份顶点位置、法线和纹理坐标，
+//! 每个面只保存索引，不需要为每个三角形单独创建一个Hittable对象。
+//! 每个网格内部按三角面构建一棵底层BVH，配合`Instance`共享同一网格时无需重复构建。
+//! 同时提供Wavefront OBJ文件的加载函数
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::{self, Aabb};
+use super::bvh::{self, BvhBuilder, BvhTree, SplitMethod};
+use super::texture::Texture;
+use super::rtweekend::{self, Real};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 网格缓存文件头标识
+const CACHE_MAGIC: &[u8; 4] = b"RTMS";
+
+/// 网格缓存中表示缺省索引的值
+const NO_INDEX: u64 = u64::MAX;
+
+/// 网格中的一个三角面
+///
+/// 位置、法线和纹理坐标分别有各自的索引(与OBJ格式一致)，
+/// 法线和纹理坐标可以缺省
+///
+/// # Fields
+/// - positions: 三个顶点在位置缓冲区中的索引
+/// - normals: 三个顶点在法线缓冲区中的索引
+/// - uvs: 三个顶点在纹理坐标缓冲区中的索引
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MeshFace {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+}
+
+impl MeshFace {
+    /// 创建只有位置索引的三角面
+    pub fn new(positions: [usize; 3]) -> Self {
+        Self { positions, normals: None, uvs: None }
+    }
+}
+
+/// 索引三角网格
+///
+/// # Fields
+/// - positions: 顶点位置缓冲区
+/// - normals: 顶点法线缓冲区
+/// - uvs: 顶点纹理坐标缓冲区
+/// - faces: 三角面索引列表，按底层BVH的叶节点顺序排列
+/// - blas: 以三角面为图元的底层BVH
+/// - mat: 整个网格共用的材质
+/// - bbox: 包围盒
+pub struct TriangleMesh {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<[Real; 2]>,
+    faces: Vec<MeshFace>,
+    blas: BvhTree,
+    mat: Arc<dyn Material + Send + Sync>,
+    bbox: Aabb,
+}
+
+impl TriangleMesh {
+    /// 创建新的三角网格
+    ///
+    /// # Arguments
+    /// * `positions` - 顶点位置缓冲区
+    /// * `normals` - 顶点法线缓冲区(可以为空)
+    /// * `uvs` - 顶点纹理坐标缓冲区(可以为空)
+    /// * `faces` - 三角面列表，其中的索引必须落在对应缓冲区范围内
+    /// * `material` - 网格材质
+    ///
+    /// # Note
+    /// 索引越界时会panic。构建底层BVH时三角面会被重新排序
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<[Real; 2]>,
+        faces: Vec<MeshFace>,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        for face in &faces {
+            assert!(face.positions.iter().all(|&i| i < positions.len()), "顶点位置索引越界");
+            if let Some(n) = face.normals {
+                assert!(n.iter().all(|&i| i < normals.len()), "顶点法线索引越界");
+            }
+            if let Some(t) = face.uvs {
+                assert!(t.iter().all(|&i| i < uvs.len()), "纹理坐标索引越界");
+            }
+        }
+
+        let bounds = face_bounds(&positions, &faces);
+        let bbox = bounds.iter().fold(aabb::EMPTY, |acc, b| Aabb::surrounding(&acc, b));
+
+        let (blas, order) = BvhBuilder::new(Vec::new())
+            .with_split_method(SplitMethod::Sah { bins: 12 })
+            .build_tree(&bounds);
+        let faces = order.iter().map(|&i| faces[i]).collect();
+
+        Self {
+            positions,
+            normals,
+            uvs,
+            faces,
+            blas,
+            mat: material,
+            bbox,
+        }
+    }
+
+    /// 由顶点位置和三角形索引创建网格(没有法线和纹理坐标)
+    ///
+    /// # Arguments
+    /// * `positions` - 顶点位置缓冲区
+    /// * `indices` - 每个三角形的三个顶点索引
+    /// * `material` - 网格材质
+    pub fn from_indexed(
+        positions: Vec<Point3>,
+        indices: &[[usize; 3]],
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        let faces = indices.iter().map(|&i| MeshFace::new(i)).collect();
+        Self::new(positions, Vec::new(), Vec::new(), faces, material)
+    }
+
+    /// 三角面数量
+    pub fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    /// 把网格缓冲区和底层BVH的结构写入缓存文件，下次可用`load_cache`跳过模型解析和BVH构建
+    ///
+    /// 格式(小端)：文件头"RTMS"，位置、法线、纹理坐标和三角面的数量(各u64)，
+    /// 随后依次为各位置和法线的三个分量、各纹理坐标的两个分量(f64)，
+    /// 各三角面的位置、法线、纹理坐标索引(各3个u64，缺省为u64::MAX)，最后为BVH结构。
+    /// 材质不写入文件
+    #[allow(clippy::unnecessary_cast)] // 单精度模式下Real为f32
+    pub fn save_cache(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(CACHE_MAGIC)?;
+        for n in [self.positions.len(), self.normals.len(), self.uvs.len(), self.faces.len()] {
+            out.write_all(&(n as u64).to_le_bytes())?;
+        }
+        for v in self.positions.iter().chain(&self.normals) {
+            for k in 0..3 {
+                out.write_all(&(v[k] as f64).to_le_bytes())?;
+            }
+        }
+        for uv in &self.uvs {
+            for x in uv {
+                out.write_all(&(*x as f64).to_le_bytes())?;
+            }
+        }
+        for face in &self.faces {
+            let indices = [Some(face.positions), face.normals, face.uvs];
+            for i in indices.iter().flat_map(|i| i.map_or([NO_INDEX; 3], |i| i.map(|k| k as u64))) {
+                out.write_all(&i.to_le_bytes())?;
+            }
+        }
+        self.blas.write(&mut out)?;
+        out.flush()
+    }
+
+    /// 从缓存文件加载网格
+    ///
+    /// # Arguments
+    /// * `path` - `save_cache`写入的文件
+    /// * `material` - 网格材质
+    ///
+    /// # Returns
+    /// 文件头不匹配、索引越界或数据不完整时返回`InvalidData`错误
+    #[allow(clippy::unnecessary_cast)] // 单精度模式下Real为f32
+    pub fn load_cache(path: impl AsRef<Path>, material: Arc<dyn Material + Send + Sync>) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("mesh cache: {}", msg));
+        let mut input = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != CACHE_MAGIC {
+            return Err(invalid("not a mesh cache file"));
+        }
+
+        let mut counts = [0usize; 4];
+        for n in &mut counts {
+            *n = bvh::read_u64(&mut input)? as usize;
+        }
+        let [n_positions, n_normals, n_uvs, n_faces] = counts;
+        let mut read_real = || bvh::read_u64(&mut input).map(|bits| f64::from_bits(bits) as Real);
+        let mut read_vec = |n: usize| -> io::Result<Vec<Vec3>> {
+            (0..n).map(|_| Ok(Vec3::new(read_real()?, read_real()?, read_real()?))).collect()
+        };
+        let positions = read_vec(n_positions)?;
+        let normals = read_vec(n_normals)?;
+        let uvs = (0..n_uvs).map(|_| Ok([read_real()?, read_real()?])).collect::<io::Result<Vec<_>>>()?;
+
+        let mut read_indices = |len: usize| -> io::Result<Option<[usize; 3]>> {
+            let raw = [bvh::read_u64(&mut input)?, bvh::read_u64(&mut input)?, bvh::read_u64(&mut input)?];
+            if raw == [NO_INDEX; 3] {
+                return Ok(None);
+            }
+            if raw.iter().any(|&i| i >= len as u64) {
+                return Err(invalid("index out of range"));
+            }
+            Ok(Some(raw.map(|i| i as usize)))
+        };
+        let faces = (0..n_faces)
+            .map(|_| {
+                let positions = read_indices(n_positions)?.ok_or_else(|| invalid("face without positions"))?;
+                Ok(MeshFace { positions, normals: read_indices(n_normals)?, uvs: read_indices(n_uvs)? })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut blas = BvhTree::read(&mut input, faces.len())?;
+        let bounds = face_bounds(&positions, &faces);
+        blas.refit(|i| bounds[i]);
+        let bbox = bounds.iter().fold(aabb::EMPTY, |acc, b| Aabb::surrounding(&acc, b));
+
+        Ok(Self {
+            positions,
+            normals,
+            uvs,
+            faces,
+            blas,
+            mat: material,
+            bbox,
+        })
+    }
+
+    /// 为没有顶点法线的面生成平滑法线
+    ///
+    /// 每个角点的法线为共用该顶点位置、且面法线与本面夹角不超过`crease_angle`的
+    /// 所有面的面积加权平均，夹角更大的相邻面之间保留硬边。已有顶点法线的面保持不变
+    ///
+    /// # Arguments
+    /// * `crease_angle` - 折痕角(角度制)，0时等同于平面着色，180时全部平滑
+    pub fn with_smooth_normals(mut self, crease_angle: Real) -> Self {
+        let cos_crease = rtweekend::degrees_to_radians(crease_angle).cos();
+
+        // 叉积的长度为面积的两倍，直接作为加权的面法线
+        let face_normals: Vec<Vec3> = self
+            .faces
+            .iter()
+            .map(|face| {
+                let [p0, p1, p2] = face.positions.map(|i| self.positions[i]);
+                vec3::cross(p1 - p0, p2 - p0)
+            })
+            .collect();
+        let mut incident = vec![Vec::new(); self.positions.len()];
+        for (index, face) in self.faces.iter().enumerate() {
+            for &p in &face.positions {
+                incident[p].push(index);
+            }
+        }
+
+        for (index, face) in self.faces.iter_mut().enumerate() {
+            if face.normals.is_some() || face_normals[index].squared_length() == 0.0 {
+                continue;
+            }
+            let n = vec3::unit_vector(face_normals[index]);
+            face.normals = Some(face.positions.map(|p| {
+                let sum = incident[p]
+                    .iter()
+                    .map(|&other| face_normals[other])
+                    .filter(|other| other.squared_length() > 0.0 && vec3::dot(vec3::unit_vector(*other), n) >= cos_crease)
+                    .fold(Vec3::default(), |acc, other| acc + other);
+                self.normals.push(vec3::unit_vector(sum));
+                self.normals.len() - 1
+            }));
+        }
+        self
+    }
+
+    /// Loop细分
+    ///
+    /// 每一级把每个三角形分成四个，并按Loop规则平滑顶点位置，
+    /// 多级细分后粗糙的控制网格逼近光滑曲面。边界边(只属于一个面)按三次B样条曲线细分，
+    /// 边界上的角点(连接的边界边不是两条)保持不动。
+    /// 顶点法线和纹理坐标在各自的索引空间中取边中点线性插值
+    ///
+    /// # Arguments
+    /// * `levels` - 细分级数，每级面数变为4倍
+    ///
+    /// # Returns
+    /// 返回细分后的新网格，材质与原网格共用
+    pub fn subdivide(&self, levels: usize) -> TriangleMesh {
+        let mut mesh = TriangleMesh::new(
+            self.positions.clone(),
+            self.normals.clone(),
+            self.uvs.clone(),
+            self.faces.clone(),
+            Arc::clone(&self.mat),
+        );
+        for _ in 0..levels {
+            mesh = mesh.subdivide_once(true);
+        }
+        mesh
+    }
+
+    /// 置换贴图
+    ///
+    /// 先把每个三角形均匀细分(只插入边中点，不平滑)，再让每个顶点沿其法线移动
+    /// `scale * 高度`。高度取纹理颜色三个分量的平均值，纹理坐标取该顶点所在任一面的
+    /// 顶点纹理坐标(没有时为0)。每个位置只移动一次，因此纹理接缝处不会出现裂缝。
+    /// 顶点法线由面法线按面积加权平均得到；原有的顶点法线在置换后不再有效，会被丢弃，
+    /// 需要平滑着色时可再调用`with_smooth_normals`
+    ///
+    /// # Arguments
+    /// * `levels` - 细分级数，每级面数变为4倍
+    /// * `height` - 高度纹理
+    /// * `scale` - 置换幅度
+    ///
+    /// # Returns
+    /// 返回置换后的新网格，材质与原网格共用
+    pub fn displace(&self, levels: usize, height: &dyn Texture, scale: Real) -> TriangleMesh {
+        let mut mesh = TriangleMesh::new(
+            self.positions.clone(),
+            self.normals.clone(),
+            self.uvs.clone(),
+            self.faces.clone(),
+            Arc::clone(&self.mat),
+        );
+        for _ in 0..levels {
+            mesh = mesh.subdivide_once(false);
+        }
+
+        // 面法线的叉积长度为面积的两倍，直接累加即为面积加权
+        let mut vertex_normals = vec![Vec3::default(); mesh.positions.len()];
+        let mut vertex_uvs = vec![[0.0; 2]; mesh.positions.len()];
+        for face in &mesh.faces {
+            let [p0, p1, p2] = face.positions.map(|i| mesh.positions[i]);
+            let n = vec3::cross(p1 - p0, p2 - p0);
+            for (k, &i) in face.positions.iter().enumerate() {
+                vertex_normals[i] += n;
+                if let Some(uv) = face.uvs {
+                    vertex_uvs[i] = mesh.uvs[uv[k]];
+                }
+            }
+        }
+
+        let positions = mesh
+            .positions
+            .iter()
+            .zip(vertex_normals.iter().zip(&vertex_uvs))
+            .map(|(&p, (&n, uv))| {
+                if n.near_zero() {
+                    return p;
+                }
+                let c = height.value(uv[0], uv[1], p);
+                p + (scale * (c.x() + c.y() + c.z()) / 3.0) * vec3::unit_vector(n)
+            })
+            .collect();
+        let faces = mesh
+            .faces
+            .iter()
+            .map(|face| MeshFace { normals: None, ..*face })
+            .collect();
+
+        TriangleMesh::new(positions, Vec::new(), mesh.uvs, faces, Arc::clone(&self.mat))
+    }
+
+    /// 进行一级细分
+    ///
+    /// # Arguments
+    /// * `smooth` - 为true时按Loop规则平滑顶点位置，否则只在边中点插入新顶点
+    fn subdivide_once(&self, smooth: bool) -> TriangleMesh {
+        let edge_key = |a: usize, b: usize| (a.min(b), a.max(b));
+
+        // 每条边所对的顶点，共享该边的面数即列表长度
+        let mut edges: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+        for face in &self.faces {
+            let [a, b, c] = face.positions;
+            for (x, y, z) in [(a, b, c), (b, c, a), (c, a, b)] {
+                edges.entry(edge_key(x, y)).or_default().push(z);
+            }
+        }
+
+        let n = self.positions.len();
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut boundary: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for (&(a, b), opposite) in &edges {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+            // 边界边和非流形边都按折痕处理
+            if opposite.len() != 2 {
+                boundary[a].push(b);
+                boundary[b].push(a);
+            }
+        }
+
+        // 原有顶点的新位置
+        let old = &self.positions;
+        let mut positions: Vec<Point3> = (0..n)
+            .map(|i| {
+                let v = old[i];
+                if !smooth {
+                    v
+                } else if !boundary[i].is_empty() {
+                    match boundary[i][..] {
+                        [b0, b1] => 0.75 * v + 0.125 * (old[b0] + old[b1]),
+                        _ => v,
+                    }
+                } else if neighbors[i].is_empty() {
+                    v
+                } else {
+                    let k = neighbors[i].len() as Real;
+                    let c = 0.375 + 0.25 * (2.0 * rtweekend::PI / k).cos();
+                    let beta = (0.625 - c * c) / k;
+                    let sum = neighbors[i].iter().fold(Vec3::default(), |acc, &j| acc + old[j]);
+                    (1.0 - k * beta) * v + beta * sum
+                }
+            })
+            .collect();
+
+        // 每条边新增一个顶点
+        let mut edge_points: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for (&(a, b), opposite) in &edges {
+            let p = match opposite[..] {
+                [c, d] if smooth => 0.375 * (old[a] + old[b]) + 0.125 * (old[c] + old[d]),
+                _ => 0.5 * (old[a] + old[b]),
+            };
+            edge_points.insert((a, b), positions.len());
+            positions.push(p);
+        }
+
+        let mut normals = self.normals.clone();
+        let mut normal_midpoints = BTreeMap::new();
+        let mut uvs = self.uvs.clone();
+        let mut uv_midpoints = BTreeMap::new();
+
+        // 三个顶点索引和三条边(ab, bc, ca)的中点索引组成四个子三角形，绕序与原三角形一致
+        let split = |[a, b, c]: [usize; 3], [ab, bc, ca]: [usize; 3]| {
+            [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+        };
+        let edges_of = |[a, b, c]: [usize; 3]| [(a, b), (b, c), (c, a)];
+
+        let mut faces = Vec::with_capacity(4 * self.faces.len());
+        for face in &self.faces {
+            let mid = edges_of(face.positions).map(|(x, y)| edge_points[&edge_key(x, y)]);
+            let sub_positions = split(face.positions, mid);
+
+            let sub_normals = face.normals.map(|idx| {
+                let mid = edges_of(idx).map(|(x, y)| {
+                    midpoint_index(&mut normals, &mut normal_midpoints, x, y, |p, q| {
+                        let m = p + q;
+                        if m.near_zero() { p } else { vec3::unit_vector(m) }
+                    })
+                });
+                split(idx, mid)
+            });
+            let sub_uvs = face.uvs.map(|idx| {
+                let mid = edges_of(idx).map(|(x, y)| {
+                    midpoint_index(&mut uvs, &mut uv_midpoints, x, y, |p, q| {
+                        [0.5 * (p[0] + q[0]), 0.5 * (p[1] + q[1])]
+                    })
+                });
+                split(idx, mid)
+            });
+
+            for k in 0..4 {
+                faces.push(MeshFace {
+                    positions: sub_positions[k],
+                    normals: sub_normals.map(|s| s[k]),
+                    uvs: sub_uvs.map(|s| s[k]),
+                });
+            }
+        }
+
+        TriangleMesh::new(positions, normals, uvs, faces, Arc::clone(&self.mat))
+    }
+}
+
+/// 取属性缓冲区中两个元素的中点在缓冲区中的索引，同一对元素只生成一次中点
+///
+/// # Arguments
+/// * `buffer` - 属性缓冲区，新生成的中点追加在末尾
+/// * `cache` - 已生成的中点索引
+/// * `a`, `b` - 两个元素的索引
+/// * `midpoint` - 计算中点的函数
+fn midpoint_index<T: Copy>(
+    buffer: &mut Vec<T>,
+    cache: &mut BTreeMap<(usize, usize), usize>,
+    a: usize,
+    b: usize,
+    midpoint: impl Fn(T, T) -> T,
+) -> usize {
+    *cache.entry((a.min(b), a.max(b))).or_insert_with(|| {
+        buffer.push(midpoint(buffer[a], buffer[b]));
+        buffer.len() - 1
+    })
+}
+
+impl Hittable for TriangleMesh {
+    /// 实现网格的光线命中检测
+    ///
+    /// 遍历底层BVH求出最近的三角面，之后只为该面填写命中记录。
+    /// 正反面由三角形的几何法线判断(默认正面为顶点逆时针排列的一侧)；
+    /// 有顶点法线时着色法线为顶点法线按重心坐标的插值，使低多边形网格呈现光滑外观，
+    /// 否则使用几何法线。纹理坐标由顶点纹理坐标按重心坐标插值，缺省时直接使用重心坐标
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let mut nearest = None;
+        let closest = self.blas.closest_hit(r, ray_t, |index, interval| {
+            let face = &self.faces[index];
+            let [p0, p1, p2] = face.positions.map(|i| self.positions[i]);
+            let (t, b1, b2) = intersect_triangle(p0, p1, p2, r, interval)?;
+            nearest = Some((face, b1, b2));
+            Some(t)
+        });
+        let (Some(closest), Some((face, b1, b2))) = (closest, nearest) else {
+            return false;
+        };
+
+        let [p0, p1, p2] = face.positions.map(|i| self.positions[i]);
+        let e1 = p1 - p0;
+        let e2 = p2 - p0;
+        let b0 = 1.0 - b1 - b2;
+
+        hit_record.t = closest;
+        hit_record.p = r.at(closest);
+        (hit_record.u, hit_record.v) = match face.uvs {
+            Some(uv) => {
+                let [t0, t1, t2] = uv.map(|i| self.uvs[i]);
+                (
+                    b0 * t0[0] + b1 * t1[0] + b2 * t2[0],
+                    b0 * t0[1] + b1 * t1[1] + b2 * t2[1],
+                )
+            }
+            None => (b1, b2),
+        };
+        // 有顶点法线时让几何法线与其朝向一致，顶点绕序不统一的模型也能得到正确的内外侧
+        let mut outward_normal = vec3::unit_vector(vec3::cross(e1, e2));
+        let mut shading_normal = None;
+        if let Some(n) = face.normals {
+            let [n0, n1, n2] = n.map(|i| self.normals[i]);
+            let interpolated = b0 * n0 + b1 * n1 + b2 * n2;
+            if vec3::dot(outward_normal, interpolated) < 0.0 {
+                outward_normal = -outward_normal;
+            }
+            if !interpolated.near_zero() {
+                shading_normal = Some(vec3::unit_vector(interpolated));
+            }
+        }
+
+        hit_record.tangent = vec3::unit_vector(e1);
+        hit_record.set_face_normal(r, outward_normal);
+        // 正反面仍按几何法线判断，插值法线在轮廓附近可能与光线同向
+        if let Some(n) = shading_normal {
+            hit_record.normal = if hit_record.front_face { n } else { -n };
+        }
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.blas.any_hit(r, ray_t, |index, interval| {
+            let [p0, p1, p2] = self.faces[index].positions.map(|i| self.positions[i]);
+            intersect_triangle(p0, p1, p2, r, interval).is_some()
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// 求各三角面的包围盒
+fn face_bounds(positions: &[Point3], faces: &[MeshFace]) -> Vec<Aabb> {
+    faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = face.positions.map(|i| positions[i]);
+            Aabb::surrounding(&Aabb::from_points(a, b), &Aabb::from_points(a, c))
+        })
+        .collect()
+}
+
+/// 检测光线与三角形的交点(Möller–Trumbore算法)
+///
+/// # Arguments
+/// * `p0`, `p1`, `p2` - 三角形的三个顶点
+/// * `r` - 入射光线
+/// * `ray_t` - 光线参数有效范围
+///
+/// # Returns
+/// 命中时返回(t, 重心坐标b1, 重心坐标b2)，交点为 (1-b1-b2)p0 + b1 p1 + b2 p2
+pub fn intersect_triangle(p0: Point3, p1: Point3, p2: Point3, r: &Ray, ray_t: &Interval) -> Option<(Real, Real, Real)> {
+    let e1 = p1 - p0;
+    let e2 = p2 - p0;
+
+    let pvec = vec3::cross(r.direction(), e2);
+    let det = vec3::dot(e1, pvec);
+    // 光线与三角形所在平面平行
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = r.origin() - p0;
+    let b1 = vec3::dot(tvec, pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let qvec = vec3::cross(tvec, e1);
+    let b2 = vec3::dot(r.direction(), qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = vec3::dot(e2, qvec) * inv_det;
+    if !ray_t.surrounds(t) {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+/// 从Wavefront OBJ文件加载三角网格
+///
+/// 支持顶点位置(v)、纹理坐标(vt)、法线(vn)和面(f)，面的索引可以是
+/// `v`、`v/vt`、`v//vn`或`v/vt/vn`形式，也可以是相对末尾的负索引。
+/// 多于三个顶点的面按扇形拆分为三角形；材质库、分组等其它语句被忽略
+///
+/// # Arguments
+/// * `path` - OBJ文件路径
+/// * `material` - 整个网格使用的材质
+///
+/// # Returns
+/// 返回加载的网格，文件无法读取或格式错误时返回错误
+pub fn load_obj(path: impl AsRef<Path>, material: Arc<dyn Material + Send + Sync>) -> io::Result<TriangleMesh> {
+    parse_obj(BufReader::new(File::open(path)?), material)
+}
+
+/// 从任意输入流解析OBJ格式的网格
+fn parse_obj(input: impl BufRead, material: Arc<dyn Material + Send + Sync>) -> io::Result<TriangleMesh> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut faces = Vec::new();
+
+    for (line_no, line) in input.lines().enumerate() {
+        let line = line?;
+        let invalid = |msg: &str| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("obj line {}: {}", line_no + 1, msg))
+        };
+
+        // 去掉行尾注释
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        // 读取至少required个、至多max个数值
+        let mut numbers = |required: usize, max: usize| -> io::Result<Vec<Real>> {
+            let values = tokens
+                .by_ref()
+                .take(max)
+                .map(|t| t.parse::<Real>().map_err(|_| invalid("invalid number")))
+                .collect::<io::Result<Vec<_>>>()?;
+            if values.len() < required {
+                return Err(invalid("missing coordinate"));
+            }
+            Ok(values)
+        };
+
+        match keyword {
+            "v" => {
+                let v = numbers(3, 3)?;
+                positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vn" => {
+                let v = numbers(3, 3)?;
+                normals.push(Vec3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                // v和w可以省略，v默认为0，w被忽略
+                let v = numbers(1, 3)?;
+                uvs.push([v[0], v.get(1).copied().unwrap_or(0.0)]);
+            }
+            "f" => {
+                let corners = tokens
+                    .map(|t| {
+                        parse_face_corner(t, positions.len(), uvs.len(), normals.len())
+                            .ok_or_else(|| invalid("invalid face index"))
+                    })
+                    .collect::<io::Result<Vec<_>>>()?;
+                if corners.len() < 3 {
+                    return Err(invalid("face has fewer than 3 vertices"));
+                }
+
+                // 扇形拆分: (0, i, i+1)
+                for i in 1..corners.len() - 1 {
+                    let tri = [corners[0], corners[i], corners[i + 1]];
+                    // 三个顶点都带有纹理坐标/法线时才使用
+                    let face_uvs = match tri.map(|c| c.1) {
+                        [Some(a), Some(b), Some(c)] => Some([a, b, c]),
+                        _ => None,
+                    };
+                    let face_normals = match tri.map(|c| c.2) {
+                        [Some(a), Some(b), Some(c)] => Some([a, b, c]),
+                        _ => None,
+                    };
+                    faces.push(MeshFace {
+                        positions: tri.map(|c| c.0),
+                        normals: face_normals,
+                        uvs: face_uvs,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(TriangleMesh::new(positions, normals, uvs, faces, material))
+}
+
+/// 解析面语句中的一个顶点`v/vt/vn`，并把1起始或负数的索引转换为0起始的索引
+///
+/// # Returns
+/// 返回(位置索引, 纹理坐标索引, 法线索引)，索引无效时返回None
+fn parse_face_corner(
+    token: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+) -> Option<(usize, Option<usize>, Option<usize>)> {
+    let resolve = |s: &str, count: usize| -> Option<usize> {
+        let i: i64 = s.parse().ok()?;
+        let index = if i < 0 { count as i64 + i } else { i - 1 };
+        (0..count as i64).contains(&index).then_some(index as usize)
+    };
+
+    let mut parts = token.split('/');
+    let position = resolve(parts.next()?, position_count)?;
+    let uv = match parts.next() {
+        Some(s) if !s.is_empty() => Some(resolve(s, uv_count)?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(s) if !s.is_empty() => Some(resolve(s, normal_count)?),
+        _ => None,
+    };
+
+    Some((position, uv, normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    fn parse(text: &str) -> io::Result<TriangleMesh> {
+        parse_obj(text.as_bytes(), Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))
+    }
+
+    /// 网格中各面的(位置, 纹理坐标, 法线)索引，排序后返回(建立BLAS时面的顺序会被打乱)
+    type Corners = ([usize; 3], Option<[usize; 3]>, Option<[usize; 3]>);
+    fn sorted_faces(mesh: &TriangleMesh) -> Vec<Corners> {
+        let mut faces: Vec<Corners> = mesh.faces.iter().map(|f| (f.positions, f.uvs, f.normals)).collect();
+        faces.sort();
+        faces
+    }
+
+    /// 单位正方形的四个角和一个额外的顶点
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0.5 1.5 0\n";
+
+    #[test]
+    fn polygons_are_split_into_fans() {
+        let mesh = parse(&format!("{}f 1 2 3 4\nf 1 2 3 5 4\nf 1 2 3\n", SQUARE)).unwrap();
+        let faces: Vec<[usize; 3]> = sorted_faces(&mesh).into_iter().map(|f| f.0).collect();
+        assert_eq!(faces, [[0, 1, 2], [0, 1, 2], [0, 1, 2], [0, 2, 3], [0, 2, 4], [0, 4, 3]]);
+        assert!(mesh.faces.iter().all(|f| f.uvs.is_none() && f.normals.is_none()));
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_current_vertex() {
+        // 第二个面引用的是它之前刚定义的三个顶点
+        let mesh = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 2 0 0\nv 2 1 0\nf -4 -2 -1\n").unwrap();
+        let faces: Vec<[usize; 3]> = sorted_faces(&mesh).into_iter().map(|f| f.0).collect();
+        assert_eq!(faces, [[0, 1, 2], [1, 3, 4]]);
+    }
+
+    #[test]
+    fn all_corner_forms_are_accepted() {
+        let header = format!("{}vt 0 0\nvt 1 0 0\nvt 1\nvn 0 0 1\n", SQUARE);
+        let mesh = parse(&format!(
+            "{}f 1 2 3\nf 1/1 2/2 3/3\nf 1//1 2//1 3//1\nf 1/1/1 2/2/1 3/3/1\nf 1/1 2 3//1\n",
+            header
+        ))
+        .unwrap();
+        // 最后一个面三个顶点的写法不一致，只保留共有的位置
+        assert_eq!(
+            sorted_faces(&mesh),
+            [
+                ([0, 1, 2], None, None),
+                ([0, 1, 2], None, None),
+                ([0, 1, 2], None, Some([0, 0, 0])),
+                ([0, 1, 2], Some([0, 1, 2]), None),
+                ([0, 1, 2], Some([0, 1, 2]), Some([0, 0, 0])),
+            ]
+        );
+        // 只有u的纹理坐标，v默认为0；第三个分量被忽略
+        assert_eq!(mesh.uvs, [[0.0, 0.0], [1.0, 0.0], [1.0, 0.0]]);
+    }
+
+    #[test]
+    fn invalid_indices_and_coordinates_are_rejected() {
+        for (text, line) in [
+            ("f 1 2 6\n", 6),
+            ("f 0 1 2\n", 6),
+            ("f -6 1 2\n", 6),
+            ("f 1/1 2 3\n", 6),
+            ("f 1//2 2 3\n", 6),
+            ("f 1 2\n", 6),
+            ("vt\n", 6),
+            ("vn 0 1\n", 6),
+        ] {
+            let err = parse(&format!("{}{}", SQUARE, text)).err().unwrap_or_else(|| panic!("{:?} loaded", text));
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(err.to_string().starts_with(&format!("obj line {}:", line)), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! 元球模块
+//!
+//! 提供由若干高斯势场叠加而成的隐式曲面(元球/blob)，用于建模圆润、相互融合的有机形状
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::{self, Aabb};
+use super::sphere;
+use super::rtweekend::Real;
+use std::sync::Arc;
+
+/// 步进步长相对于最小元球半径的比例
+const STEP_FRACTION: Real = 0.1;
+
+/// 找到符号变化后二分细化的次数
+const BISECTION_STEPS: usize = 40;
+
+/// 单个元球
+///
+/// 在点p处贡献的势为 strength * exp(-|p - center|² / radius²)
+///
+/// # Fields
+/// - center: 中心
+/// - radius: 影响半径(高斯函数的宽度)
+/// - strength: 强度
+#[derive(Clone, Copy, Debug)]
+pub struct Metaball {
+    pub center: Point3,
+    pub radius: Real,
+    pub strength: Real,
+}
+
+impl Metaball {
+    /// 创建新的元球
+    ///
+    /// # Arguments
+    /// * `center` - 中心
+    /// * `radius` - 影响半径
+    /// * `strength` - 强度
+    pub fn new(center: Point3, radius: Real, strength: Real) -> Self {
+        Self { center, radius, strength }
+    }
+}
+
+/// 元球组成的隐式曲面
+///
+/// 曲面为总势场 F(p) = Σ 各元球的势 等于`threshold`的等值面，F大于阈值的一侧为物体内部。
+/// 在任一点若每个元球的贡献都小于 threshold / n，则总和必小于阈值，
+/// 因此曲面一定落在各元球贡献达到 threshold / n 的球形区域之内。
+/// 实际取贡献达到 threshold / 2n 的稍大区域，保证区域边界严格位于物体外部，
+/// 求交时只在光线穿过这些区域的部分步进
+///
+/// # Fields
+/// - balls: 各元球
+/// - threshold: 等值面阈值
+/// - bounds: 各元球的影响区域半径，贡献不可能达到 threshold / 2n 的元球为0
+/// - step: 步进距离
+/// - mat: 材质
+/// - bbox: 包围盒
+pub struct Metaballs {
+    balls: Vec<Metaball>,
+    threshold: Real,
+    bounds: Vec<Real>,
+    step: Real,
+    mat: Arc<dyn Material + Send + Sync>,
+    bbox: Aabb,
+}
+
+impl Metaballs {
+    /// 创建新的元球曲面
+    ///
+    /// # Arguments
+    /// * `balls` - 元球列表
+    /// * `threshold` - 等值面阈值，必须为正
+    /// * `material` - 材质
+    pub fn new(balls: Vec<Metaball>, threshold: Real, material: Arc<dyn Material + Send + Sync>) -> Self {
+        let n = balls.len() as Real;
+        let bounds: Vec<Real> = balls
+            .iter()
+            .map(|ball| {
+                // strength * exp(-d²/r²) = threshold / 2n  =>  d = r * sqrt(ln(2n * strength / threshold))
+                let ratio = 2.0 * n * ball.strength / threshold;
+                if ratio > 1.0 { ball.radius.abs() * ratio.ln().sqrt() } else { 0.0 }
+            })
+            .collect();
+
+        let bbox = balls.iter().zip(&bounds).filter(|(_, bound)| **bound > 0.0).fold(
+            aabb::EMPTY,
+            |acc, (ball, &bound)| {
+                let rvec = Vec3::new(bound, bound, bound);
+                Aabb::surrounding(&acc, &Aabb::from_points(ball.center - rvec, ball.center + rvec))
+            },
+        );
+        let step = STEP_FRACTION
+            * balls.iter().map(|ball| ball.radius.abs()).fold(Real::INFINITY, Real::min);
+
+        Self {
+            balls,
+            threshold,
+            bounds,
+            step,
+            mat: material,
+            bbox,
+        }
+    }
+
+    /// 总势场减去阈值，内部为正、外部为负
+    fn field(&self, p: Point3) -> Real {
+        self.balls
+            .iter()
+            .map(|ball| ball.strength * (-(p - ball.center).squared_length() / (ball.radius * ball.radius)).exp())
+            .sum::<Real>()
+            - self.threshold
+    }
+
+    /// 势场的解析梯度，指向势场增大(物体内部)的方向
+    fn gradient(&self, p: Point3) -> Vec3 {
+        self.balls.iter().fold(Vec3::default(), |acc, ball| {
+            let r2 = ball.radius * ball.radius;
+            let d = p - ball.center;
+            acc - (2.0 * ball.strength / r2 * (-d.squared_length() / r2).exp()) * d
+        })
+    }
+
+    /// 光线穿过各元球影响区域的参数区间，按起点排序并合并重叠部分
+    fn spans(&self, r: &Ray, ray_t: &Interval) -> Vec<Interval> {
+        let d = r.direction();
+        let a = d.squared_length();
+
+        let mut spans: Vec<Interval> = self
+            .balls
+            .iter()
+            .zip(&self.bounds)
+            .filter(|(_, bound)| **bound > 0.0)
+            .filter_map(|(ball, &bound)| {
+                let oc = r.origin() - ball.center;
+                let half_b = vec3::dot(d, oc);
+                let c = oc.squared_length() - bound * bound;
+                let discriminant = half_b * half_b - a * c;
+                if discriminant < 0.0 {
+                    return None;
+                }
+                let sqrtd = discriminant.sqrt();
+                let t0 = ((-half_b - sqrtd) / a).max(ray_t.min);
+                let t1 = ((-half_b + sqrtd) / a).min(ray_t.max);
+                (t0 < t1).then(|| Interval::new(t0, t1))
+            })
+            .collect();
+
+        spans.sort_by(|x, y| x.min.total_cmp(&y.min));
+        let mut merged: Vec<Interval> = Vec::with_capacity(spans.len());
+        for span in spans {
+            match merged.last_mut() {
+                Some(last) if span.min <= last.max => last.max = last.max.max(span.max),
+                _ => merged.push(span),
+            }
+        }
+        merged
+    }
+}
+
+impl Hittable for Metaballs {
+    /// 实现元球的光线命中检测
+    ///
+    /// 在光线穿过的影响区域内以固定步长采样势场，发现符号变化后二分求出等值面位置。
+    /// 起点在物体内部时找到的是离开物体的表面。法线由势场的解析梯度得到
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+        let dt = self.step / r.direction().length();
+
+        let mut found = None;
+        'spans: for span in self.spans(r, ray_t) {
+            let mut t0 = span.min;
+            let mut f0 = self.field(r.at(t0));
+            while t0 < span.max {
+                let t1 = (t0 + dt).min(span.max);
+                let f1 = self.field(r.at(t1));
+                if (f0 > 0.0) != (f1 > 0.0) {
+                    let (mut lo, mut hi) = (t0, t1);
+                    for _ in 0..BISECTION_STEPS {
+                        let mid = 0.5 * (lo + hi);
+                        if (self.field(r.at(mid)) > 0.0) == (f0 > 0.0) {
+                            lo = mid;
+                        } else {
+                            hi = mid;
+                        }
+                    }
+                    found = Some(hi);
+                    break 'spans;
+                }
+                t0 = t1;
+                f0 = f1;
+            }
+        }
+        let Some(t) = found else {
+            return false;
+        };
+        if !ray_t.surrounds(t) {
+            return false;
+        }
+
+        hit_record.t = t;
+        hit_record.p = r.at(t);
+
+        let gradient = self.gradient(hit_record.p);
+        let outward_normal = if gradient.near_zero() {
+            vec3::unit_vector(-r.direction())
+        } else {
+            vec3::unit_vector(-gradient)
+        };
+        (hit_record.u, hit_record.v) = sphere::get_sphere_uv(outward_normal);
+        hit_record.tangent = sphere::sphere_tangent(outward_normal);
+        hit_record.set_face_normal(r, outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+    use crate::rtweekend;
+
+    /// 半径1、强度1的元球，阈值0.5
+    fn blobs(centers: &[Point3], threshold: Real) -> Metaballs {
+        let balls = centers.iter().map(|&c| Metaball::new(c, 1.0, 1.0)).collect();
+        Metaballs::new(balls, threshold, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))
+    }
+
+    /// 命中时检查法线为单位长度且朝向光线来的一侧
+    fn cast(blobs: &Metaballs, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        let r = Ray::new(origin, direction);
+        if !blobs.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return None;
+        }
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!(vec3::dot(rec.normal, direction) < 0.0);
+        Some(rec)
+    }
+
+    #[test]
+    fn single_ball_is_a_sphere_at_the_threshold_radius() {
+        // exp(-d²) = 0.5
+        let radius = (2.0 as Real).ln().sqrt();
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let ball = blobs(&[Point3::default()], 0.5);
+        let rec = cast(&ball, Point3::new(-5.0, 0.0, 0.0), x).unwrap();
+        assert!((rec.t - (5.0 - radius)).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal + x).length() < 1e-4);
+
+        assert!(cast(&ball, Point3::new(-5.0, radius + 0.01, 0.0), x).is_none());
+        assert!(cast(&ball, Point3::new(-5.0, 0.0, 0.0), -x).is_none());
+        // 阈值高于任何一点的势时没有表面
+        assert!(cast(&blobs(&[Point3::default()], 2.0), Point3::new(-5.0, 0.0, 0.0), x).is_none());
+    }
+
+    #[test]
+    fn nearby_balls_merge_into_one_surface() {
+        // 两球中点的势为2exp(-1) > 0.5，表面在x = 0处的高度满足 2exp(-(1 + y²)) = 0.5
+        let pair = blobs(&[Point3::new(-1.0, 0.0, 0.0), Point3::new(1.0, 0.0, 0.0)], 0.5);
+        let y = ((4.0 as Real).ln() - 1.0).sqrt();
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        let rec = cast(&pair, Point3::new(0.0, 5.0, 0.0), down).unwrap();
+        assert!((rec.t - (5.0 - y)).abs() < 1e-4);
+        assert!((rec.normal + down).length() < 1e-4);
+        // 沿连线穿过时只有一个入口，没有中间的缝隙
+        let rec = cast(&pair, Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!(rec.p.x() < -1.0);
+        let rec = cast(&pair, Point3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!(rec.p.x() > 1.0);
+    }
+
+    #[test]
+    fn ray_from_inside_finds_the_exit() {
+        let radius = (2.0 as Real).ln().sqrt();
+        let ball = blobs(&[Point3::default()], 0.5);
+        let rec = cast(&ball, Point3::default(), Vec3::new(0.0, 0.0, 1.0)).unwrap();
+        assert!((rec.t - radius).abs() < 1e-4);
+        assert!(!rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-4);
+    }
+}
@@ -0,0 +1,217 @@
+//! 微表面模型模块
+//!
+//! 提供GGX(Trowbridge-Reitz)法线分布、Smith遮蔽-阴影函数和可见法线采样，
+//! 所有方向都在以宏观法线为z轴的局部坐标系中表示
+
+use super::color::Color;
+use super::rtweekend::{self, Real};
+use super::vec3::{self, Vec3};
+
+/// 各向同性GGX微表面分布
+///
+/// # Fields
+/// - alpha: 粗糙度参数，通常取感知粗糙度的平方
+#[derive(Clone, Copy, Debug)]
+pub struct Ggx {
+    pub alpha: Real,
+}
+
+impl Ggx {
+    /// alpha的下限，避免分布退化为无穷尖的峰导致数值溢出
+    pub const MIN_ALPHA: Real = 1e-3;
+
+    /// 由感知粗糙度创建分布
+    ///
+    /// # Arguments
+    /// * `roughness` - 感知粗糙度，限制在[0,1]，alpha取其平方
+    pub fn from_roughness(roughness: Real) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        Self { alpha: (roughness * roughness).max(Self::MIN_ALPHA) }
+    }
+
+    /// 法线分布函数D(h)
+    ///
+    /// # Arguments
+    /// * `h` - 微表面法线(局部坐标，单位向量)
+    pub fn d(&self, h: Vec3) -> Real {
+        if h.z() <= 0.0 {
+            return 0.0;
+        }
+        let a2 = self.alpha * self.alpha;
+        let k = h.z() * h.z() * (a2 - 1.0) + 1.0;
+        a2 / (rtweekend::PI * k * k)
+    }
+
+    /// Smith辅助函数Λ(w)
+    fn lambda(&self, w: Vec3) -> Real {
+        let cos2 = w.z() * w.z();
+        if cos2 <= 0.0 {
+            return Real::INFINITY;
+        }
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        0.5 * ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0)
+    }
+
+    /// 单方向遮蔽函数G1(w)
+    pub fn g1(&self, w: Vec3) -> Real {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// 高度相关的遮蔽-阴影函数G2(wo, wi)
+    pub fn g2(&self, wo: Vec3, wi: Vec3) -> Real {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// 按可见法线分布D_wo(h)采样微表面法线(Heitz 2018)
+    ///
+    /// 只会采到从`wo`方向可见的微表面，与直接按D(h)采样相比没有浪费的样本，方差更小
+    ///
+    /// # Arguments
+    /// * `wo` - 出射方向(局部坐标，z > 0)
+    ///
+    /// # Returns
+    /// 返回采样得到的微表面法线
+    pub fn sample_visible_normal(&self, wo: Vec3) -> Vec3 {
+        // 把视线方向拉伸到alpha = 1的半球配置
+        let vh = vec3::unit_vector(Vec3::new(self.alpha * wo.x(), self.alpha * wo.y(), wo.z()));
+
+        let len2 = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if len2 > 0.0 {
+            Vec3::new(-vh.y(), vh.x(), 0.0) / len2.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vec3::cross(vh, t1);
+
+        // 在投影后的圆盘上均匀采样，再按视线方向压缩下半部分
+        let r = rtweekend::random_double().sqrt();
+        let phi = 2.0 * rtweekend::PI * rtweekend::random_double();
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        // 变换回原始的椭球配置
+        vec3::unit_vector(Vec3::new(self.alpha * nh.x(), self.alpha * nh.y(), nh.z().max(0.0)))
+    }
+
+    /// 按可见法线采样时反射方向`wi`的概率密度(立体角测度)
+    ///
+    /// # Arguments
+    /// * `wo` - 出射方向(局部坐标)
+    /// * `wi` - 反射方向(局部坐标)
+    pub fn reflection_pdf(&self, wo: Vec3, wi: Vec3) -> Real {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let h = vec3::unit_vector(wo + wi);
+        // D_wo(h) = G1(wo) D(h) (wo·h) / wo.z，再乘以反射的雅可比 1 / (4 wo·h)
+        self.g1(wo) * self.d(h) / (4.0 * wo.z())
+    }
+}
+
+/// Schlick近似的菲涅尔反射率，`f0`为垂直入射时的反射率(金属即其颜色)
+///
+/// # Arguments
+/// * `f0` - 垂直入射反射率
+/// * `cosine` - 入射方向与微表面法线夹角的余弦
+pub fn schlick_fresnel(f0: Color, cosine: Real) -> Color {
+    let k = (1.0 - cosine).clamp(0.0, 1.0).powi(5);
+    f0 + k * (Color::new(1.0, 1.0, 1.0) - f0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在xz平面内与法线夹角余弦为`cos`的出射方向
+    fn outgoing(cos: Real) -> Vec3 {
+        Vec3::new((1.0 - cos * cos).sqrt(), 0.0, cos)
+    }
+
+    /// 在上半球均匀采样的方向
+    fn uniform_hemisphere() -> Vec3 {
+        let w = vec3::random_unit_vector();
+        Vec3::new(w.x(), w.y(), w.z().abs())
+    }
+
+    #[test]
+    fn ndf_projected_area_integrates_to_one() {
+        rtweekend::seed_random(1057);
+        for roughness in [0.6, 0.9] {
+            let ggx = Ggx::from_roughness(roughness);
+            // ∫D(h) cosθh dω = 1，在半球上均匀采样估计
+            let n = 200_000;
+            let sum: Real = (0..n).map(|_| {
+                let h = uniform_hemisphere();
+                ggx.d(h) * h.z()
+            }).sum();
+            let integral = 2.0 * rtweekend::PI * sum / n as Real;
+            assert!((integral - 1.0).abs() < 0.03, "roughness {roughness}: integral = {integral}");
+        }
+    }
+
+    /// 按可见法线采样反射，返回(G2/G1的平均值, 反射方向在表面以上的比例)
+    ///
+    /// 白炉测试：F = 1 时单次散射的反照率即 G2/G1 的期望，反射到表面以下的样本计为0
+    fn furnace(ggx: &Ggx, wo: Vec3) -> (Real, Real) {
+        let n = 20_000;
+        let (mut albedo, mut above) = (0.0, 0);
+        for _ in 0..n {
+            let h = ggx.sample_visible_normal(wo);
+            assert!(vec3::dot(wo, h) >= 0.0, "sampled a back-facing microfacet");
+            let wi = vec3::reflect(-wo, h);
+            if wi.z() > 0.0 {
+                albedo += ggx.g2(wo, wi) / ggx.g1(wo);
+                above += 1;
+            }
+        }
+        (albedo / n as Real, above as Real / n as Real)
+    }
+
+    #[test]
+    fn visible_normal_reflections_never_gain_energy() {
+        rtweekend::seed_random(1057);
+        for cos in [1.0, 0.5, 0.1] {
+            let wo = outgoing(cos);
+            let mut previous = 1.0;
+            for roughness in [0.0, 0.3, 0.7, 1.0] {
+                let (albedo, _) = furnace(&Ggx::from_roughness(roughness), wo);
+                assert!(albedo <= 1.0, "roughness {roughness}, cos {cos}: albedo = {albedo}");
+                // 光滑时几乎没有损失，越粗糙丢失的多次反射能量越多
+                if roughness == 0.0 {
+                    assert!(albedo > 0.999, "cos {cos}: albedo = {albedo}");
+                }
+                assert!(albedo <= previous + 0.01, "roughness {roughness}, cos {cos}: {albedo} > {previous}");
+                previous = albedo;
+            }
+        }
+    }
+
+    #[test]
+    fn reflection_pdf_matches_visible_normal_sampling() {
+        rtweekend::seed_random(1057);
+        let ggx = Ggx::from_roughness(0.7);
+        for cos in [1.0, 0.3] {
+            let wo = outgoing(cos);
+            let n = 200_000;
+            let sum: Real = (0..n).map(|_| ggx.reflection_pdf(wo, uniform_hemisphere())).sum();
+            let integral = 2.0 * rtweekend::PI * sum / n as Real;
+            // 反射到表面以下的样本没有概率密度，积分等于反射留在表面以上的比例
+            let (_, above) = furnace(&ggx, wo);
+            assert!(integral <= 1.0 + 0.02, "cos {cos}: integral = {integral}");
+            assert!((integral - above).abs() < 0.02, "cos {cos}: integral = {integral}, above = {above}");
+        }
+    }
+
+    #[test]
+    fn schlick_fresnel_spans_f0_to_one() {
+        let f0 = Color::new(0.9, 0.6, 0.04);
+        assert_eq!(schlick_fresnel(f0, 1.0).to_array(), f0.to_array());
+        assert_eq!(schlick_fresnel(f0, 0.0).to_array(), [1.0, 1.0, 1.0]);
+        let mid = schlick_fresnel(f0, 0.5);
+        for (f, f0) in mid.to_array().into_iter().zip(f0.to_array()) {
+            assert!(f > f0 && f < 1.0);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! 运动球体模块
+//!
+//! 提供球心随时间线性移动的球体，配合相机的快门时间产生运动模糊
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::sphere;
+use super::rtweekend::Real;
+use std::sync::Arc;
+
+/// 运动球体
+///
+/// 球心在time0时位于center0，time1时位于center1，其间线性插值，
+/// 区间之外按同一速度外推
+///
+/// # Fields
+/// - center0, center1: 两个时刻的球心
+/// - time0, time1: 两个时刻
+/// - radius: 半径(允许为负，含义同`Sphere`)
+/// - mat: 材质
+/// - bbox: 包含两个时刻球体的包围盒
+pub struct MovingSphere {
+    center0: Point3,
+    center1: Point3,
+    time0: Real,
+    time1: Real,
+    radius: Real,
+    mat: Arc<dyn Material + Send + Sync>,
+    bbox: Aabb,
+}
+
+impl MovingSphere {
+    /// 创建新的运动球体
+    ///
+    /// # Arguments
+    /// * `center0` - time0时刻的球心
+    /// * `center1` - time1时刻的球心
+    /// * `time0` - 起始时刻
+    /// * `time1` - 结束时刻
+    /// * `radius` - 半径
+    /// * `material` - 材质
+    pub fn new(
+        center0: Point3,
+        center1: Point3,
+        time0: Real,
+        time1: Real,
+        radius: Real,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        let rvec = Vec3::new(radius.abs(), radius.abs(), radius.abs());
+        let bbox = Aabb::surrounding(
+            &Aabb::from_points(center0 - rvec, center0 + rvec),
+            &Aabb::from_points(center1 - rvec, center1 + rvec),
+        );
+
+        Self {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            mat: material,
+            bbox,
+        }
+    }
+
+    /// 计算给定时刻的球心
+    pub fn center(&self, time: Real) -> Point3 {
+        if self.time1 == self.time0 {
+            return self.center0;
+        }
+        let s = (time - self.time0) / (self.time1 - self.time0);
+        self.center0 + s * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    /// 取光线时刻的球心后按静止球体求交
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let center = self.center(r.time());
+        let oc = center - r.origin();
+        let a = r.direction().squared_length();
+        let b = vec3::dot(r.direction(), oc);
+        let c = oc.squared_length() - self.radius * self.radius;
+
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return false;
+        }
+        let sqrtd = discriminant.sqrt();
+
+        let mut root = (b - sqrtd) / a;
+        if !ray_t.surrounds(root) {
+            root = (b + sqrtd) / a;
+            if !ray_t.surrounds(root) {
+                return false;
+            }
+        }
+
+        hit_record.t = root;
+        hit_record.p = r.at(root);
+        let outward_normal = (hit_record.p - center) / self.radius;
+        hit_record.set_face_normal(r, outward_normal);
+        (hit_record.u, hit_record.v) = sphere::get_sphere_uv(outward_normal);
+        hit_record.tangent = sphere::sphere_tangent(outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -0,0 +1,64 @@
+//! 正交基模块
+//!
+//! 提供以给定方向为w轴的正交归一化基(ONB)，用于在局部坐标系中采样方向
+
+use super::vec3::{self, Vec3};
+
+/// 正交归一化基
+///
+/// # Fields
+/// - axis: 三个互相垂直的单位基向量 u, v, w
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Onb {
+    axis: [Vec3; 3],
+}
+
+impl Onb {
+    /// 以给定方向为w轴构建正交基
+    ///
+    /// # Arguments
+    /// * `n` - w轴方向(无需归一化)
+    pub fn new(n: Vec3) -> Self {
+        let w = vec3::unit_vector(n);
+        // 选择一个与w不平行的辅助向量
+        let a = if w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = vec3::unit_vector(vec3::cross(w, a));
+        let u = vec3::cross(w, v);
+        Self { axis: [u, v, w] }
+    }
+
+    /// 由三个互相垂直的单位向量直接构建正交基
+    ///
+    /// # Note
+    /// 不做正交化，调用者需保证输入已正交归一
+    pub fn from_axes(u: Vec3, v: Vec3, w: Vec3) -> Self {
+        Self { axis: [u, v, w] }
+    }
+
+    /// 获取u轴
+    pub fn u(&self) -> Vec3 { self.axis[0] }
+    /// 获取v轴
+    pub fn v(&self) -> Vec3 { self.axis[1] }
+    /// 获取w轴
+    pub fn w(&self) -> Vec3 { self.axis[2] }
+
+    /// 将局部坐标系中的向量变换到世界坐标系
+    ///
+    /// # Arguments
+    /// * `v` - 局部坐标(分量依次对应u, v, w)
+    pub fn transform(&self, v: Vec3) -> Vec3 {
+        v.x() * self.axis[0] + v.y() * self.axis[1] + v.z() * self.axis[2]
+    }
+
+    /// 将世界坐标系中的向量变换到局部坐标系，`transform`的逆变换
+    ///
+    /// # Arguments
+    /// * `v` - 世界坐标中的向量
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(vec3::dot(v, self.axis[0]), vec3::dot(v, self.axis[1]), vec3::dot(v, self.axis[2]))
+    }
+}
@@ -0,0 +1,84 @@
+//! 光线包模块
+//!
+//! 提供把若干条光线打包在一起遍历加速结构的数据结构。同一像素块的主光线、
+//! 或从相邻着色点射向同一光源的阴影光线方向相近，经过的节点大体相同，
+//! 打包后每个节点只需取一次并对所有光线一起做包围盒测试
+
+use super::ray::Ray;
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::rtweekend::Real;
+
+/// N条光线组成的光线包，常用4或8条
+///
+/// 各光线的起点和方向倒数按分量分开存放(SoA)，包围盒测试对各光线的循环可以被向量化
+///
+/// # Fields
+/// - rays: 各光线
+/// - ray_t: 各光线的参数有效范围
+/// - origin: 各光线起点的x、y、z分量
+/// - inv_dir: 各光线方向倒数的x、y、z分量
+#[derive(Clone, Copy, Debug)]
+pub struct RayPacket<const N: usize> {
+    rays: [Ray; N],
+    ray_t: [Interval; N],
+    origin: [[Real; N]; 3],
+    inv_dir: [[Real; N]; 3],
+}
+
+impl<const N: usize> RayPacket<N> {
+    /// 创建新的光线包
+    ///
+    /// # Arguments
+    /// * `rays` - 各光线
+    /// * `ray_t` - 各光线的参数有效范围，不足N条光线时可用空区间(`interval::EMPTY`)填充
+    ///
+    /// # Note
+    /// N不能超过32
+    pub fn new(rays: [Ray; N], ray_t: [Interval; N]) -> Self {
+        const { assert!(N <= 32, "光线包最多包含32条光线") };
+        let origin = [0, 1, 2].map(|axis| rays.map(|r| r.origin()[axis]));
+        let inv_dir = [0, 1, 2].map(|axis| rays.map(|r| 1.0 / r.direction()[axis]));
+        Self { rays, ray_t, origin, inv_dir }
+    }
+
+    /// 第`lane`条光线
+    pub fn ray(&self, lane: usize) -> &Ray {
+        &self.rays[lane]
+    }
+
+    /// 第`lane`条光线的参数有效范围
+    pub fn ray_t(&self, lane: usize) -> &Interval {
+        &self.ray_t[lane]
+    }
+
+    /// 对所有光线做包围盒测试
+    ///
+    /// 判断方式与`Aabb::hit`相同
+    ///
+    /// # Arguments
+    /// * `bbox` - 包围盒
+    /// * `t_max` - 各光线当前的参数上限(如已找到的最近交点)
+    ///
+    /// # Returns
+    /// 第i位为1表示第i条光线在[ray_t.min, t_max]内穿过包围盒
+    pub fn hit_mask(&self, bbox: &Aabb, t_max: &[Real; N]) -> u32 {
+        let mut t0 = self.ray_t.map(|t| t.min);
+        let mut t1 = *t_max;
+        for axis in 0..3 {
+            let ax = bbox.axis_interval(axis);
+            for lane in 0..N {
+                let a = (ax.min - self.origin[axis][lane]) * self.inv_dir[axis][lane];
+                let b = (ax.max - self.origin[axis][lane]) * self.inv_dir[axis][lane];
+                let (near, far) = if a < b { (a, b) } else { (b, a) };
+                if near > t0[lane] {
+                    t0[lane] = near;
+                }
+                if far < t1[lane] {
+                    t1[lane] = far;
+                }
+            }
+        }
+        (0..N).filter(|&lane| t0[lane] < t1[lane]).fold(0, |mask, lane| mask | (1 << lane))
+    }
+}
@@ -0,0 +1,408 @@
+//! PLY模型加载模块
+//!
+//! 提供斯坦福PLY格式三角网格的加载函数，支持ASCII和二进制(大端/小端)两种编码，
+//! 扫描模型和常用测试模型(斯坦福兔子、龙等)多以此格式发布
+
+use super::vec3::{Point3, Vec3};
+use super::material::Material;
+use super::mesh::{MeshFace, TriangleMesh};
+use super::rtweekend::Real;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+/// PLY属性的标量类型
+#[derive(Clone, Copy, Debug)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    /// 解析类型名，同时接受旧名称(char, int等)和带位数的名称(int8, float32等)
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return None,
+        })
+    }
+
+    /// 二进制编码下的字节数
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+/// 元素的一个属性
+enum Property {
+    /// 单个标量
+    Scalar { name: String, ty: ScalarType },
+    /// 先给出元素个数、再给出各元素的列表
+    List { name: String, count_ty: ScalarType, item_ty: ScalarType },
+}
+
+/// 头部声明的一种元素(如vertex、face)
+///
+/// # Fields
+/// - name: 元素名
+/// - count: 元素个数
+/// - properties: 每个元素依次包含的属性
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// 数据部分的读取器
+enum Body<R> {
+    /// ASCII编码，按空白分隔的记号依次读取
+    Ascii(std::vec::IntoIter<String>),
+    /// 二进制编码
+    Binary { reader: R, big_endian: bool },
+}
+
+impl<R: Read> Body<R> {
+    /// 读取一个标量值
+    fn read(&mut self, ty: ScalarType) -> io::Result<f64> {
+        match self {
+            Body::Ascii(tokens) => {
+                let token = tokens.next().ok_or_else(|| invalid("unexpected end of data"))?;
+                token.parse::<f64>().map_err(|_| invalid("invalid number"))
+            }
+            Body::Binary { reader, big_endian } => {
+                let mut buf = [0u8; 8];
+                let bytes = &mut buf[..ty.size()];
+                reader.read_exact(bytes)?;
+                // 统一转换为小端再解码
+                if *big_endian {
+                    bytes.reverse();
+                }
+                let b = buf;
+                Ok(match ty {
+                    ScalarType::I8 => b[0] as i8 as f64,
+                    ScalarType::U8 => b[0] as f64,
+                    ScalarType::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    ScalarType::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    ScalarType::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::F64 => f64::from_le_bytes(b),
+                })
+            }
+        }
+    }
+
+    /// 读取一个列表属性
+    fn read_list(&mut self, count_ty: ScalarType, item_ty: ScalarType) -> io::Result<Vec<f64>> {
+        let count = self.read(count_ty)?;
+        if count < 0.0 || count.fract() != 0.0 {
+            return Err(invalid("invalid list length"));
+        }
+        (0..count as usize).map(|_| self.read(item_ty)).collect()
+    }
+}
+
+/// 构造格式错误
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("ply: {}", msg))
+}
+
+/// 从PLY文件加载三角网格
+///
+/// 读取vertex元素的位置(x, y, z)、可选的法线(nx, ny, nz)和纹理坐标
+/// (u/v、s/t或texture_u/texture_v)，以及face元素的顶点索引列表
+/// (vertex_indices或vertex_index)。多边形面按扇形拆分为三角形，其它元素和属性被忽略
+///
+/// # Arguments
+/// * `path` - PLY文件路径
+/// * `material` - 整个网格使用的材质
+///
+/// # Returns
+/// 返回加载的网格，文件无法读取或格式错误时返回错误
+pub fn load_ply(path: impl AsRef<Path>, material: Arc<dyn Material + Send + Sync>) -> io::Result<TriangleMesh> {
+    parse_ply(BufReader::new(File::open(path)?), material)
+}
+
+/// 从任意输入流解析PLY格式的网格
+fn parse_ply(mut input: impl BufRead, material: Arc<dyn Material + Send + Sync>) -> io::Result<TriangleMesh> {
+    let mut line = String::new();
+    let mut next_line = |input: &mut dyn BufRead| -> io::Result<String> {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Err(invalid("unexpected end of header"));
+        }
+        Ok(line.trim().to_string())
+    };
+
+    if next_line(&mut input)? != "ply" {
+        return Err(invalid("missing magic number"));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        let header_line = next_line(&mut input)?;
+        let tokens: Vec<&str> = header_line.split_whitespace().collect();
+        match tokens[..] {
+            ["end_header"] => break,
+            ["format", name, _version] => {
+                format = Some(match name {
+                    "ascii" => None,
+                    "binary_little_endian" => Some(false),
+                    "binary_big_endian" => Some(true),
+                    _ => return Err(invalid("unknown format")),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| invalid("invalid element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_ty, item_ty, name] => {
+                let element = elements.last_mut().ok_or_else(|| invalid("property before element"))?;
+                element.properties.push(Property::List {
+                    name: name.to_string(),
+                    count_ty: ScalarType::parse(count_ty).ok_or_else(|| invalid("unknown property type"))?,
+                    item_ty: ScalarType::parse(item_ty).ok_or_else(|| invalid("unknown property type"))?,
+                });
+            }
+            ["property", ty, name] => {
+                let element = elements.last_mut().ok_or_else(|| invalid("property before element"))?;
+                element.properties.push(Property::Scalar {
+                    name: name.to_string(),
+                    ty: ScalarType::parse(ty).ok_or_else(|| invalid("unknown property type"))?,
+                });
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(invalid("invalid header line")),
+        }
+    }
+
+    let mut body = match format.ok_or_else(|| invalid("missing format"))? {
+        None => {
+            let mut text = String::new();
+            input.read_to_string(&mut text)?;
+            let tokens: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+            Body::Ascii(tokens.into_iter())
+        }
+        Some(big_endian) => Body::Binary { reader: input, big_endian },
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut polygons: Vec<Vec<f64>> = Vec::new();
+
+    for element in &elements {
+        for _ in 0..element.count {
+            let mut p = [0.0; 3];
+            let mut n = [0.0; 3];
+            let mut uv = [0.0; 2];
+            for property in &element.properties {
+                match property {
+                    Property::Scalar { name, ty } => {
+                        let value = body.read(*ty)? as Real;
+                        if element.name == "vertex" {
+                            match name.as_str() {
+                                "x" => p[0] = value,
+                                "y" => p[1] = value,
+                                "z" => p[2] = value,
+                                "nx" => n[0] = value,
+                                "ny" => n[1] = value,
+                                "nz" => n[2] = value,
+                                "u" | "s" | "texture_u" | "texture_s" => uv[0] = value,
+                                "v" | "t" | "texture_v" | "texture_t" => uv[1] = value,
+                                _ => {}
+                            }
+                        }
+                    }
+                    Property::List { name, count_ty, item_ty } => {
+                        let items = body.read_list(*count_ty, *item_ty)?;
+                        if element.name == "face" && (name == "vertex_indices" || name == "vertex_index") {
+                            polygons.push(items);
+                        }
+                    }
+                }
+            }
+            if element.name == "vertex" {
+                positions.push(Point3::new(p[0], p[1], p[2]));
+                normals.push(Vec3::new(n[0], n[1], n[2]));
+                uvs.push(uv);
+            }
+        }
+    }
+
+    let Some(vertex) = elements.iter().find(|e| e.name == "vertex") else {
+        return Err(invalid("missing vertex element"));
+    };
+    let has_property = |names: &[&str]| {
+        vertex.properties.iter().any(|p| matches!(p, Property::Scalar { name, .. } if names.contains(&name.as_str())))
+    };
+    let has_normals = has_property(&["nx"]);
+    let has_uvs = has_property(&["u", "s", "texture_u", "texture_s"]);
+    if !has_normals {
+        normals.clear();
+    }
+    if !has_uvs {
+        uvs.clear();
+    }
+
+    let mut faces = Vec::new();
+    for polygon in polygons {
+        let indices = polygon
+            .iter()
+            .map(|&i| {
+                (i >= 0.0 && i.fract() == 0.0 && (i as usize) < positions.len())
+                    .then_some(i as usize)
+                    .ok_or_else(|| invalid("face index out of range"))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        if indices.len() < 3 {
+            return Err(invalid("face has fewer than 3 vertices"));
+        }
+
+        // 扇形拆分: (0, i, i+1)，法线和纹理坐标与位置共用索引
+        for i in 1..indices.len() - 1 {
+            let tri = [indices[0], indices[i], indices[i + 1]];
+            faces.push(MeshFace {
+                positions: tri,
+                normals: has_normals.then_some(tri),
+                uvs: has_uvs.then_some(tri),
+            });
+        }
+    }
+
+    Ok(TriangleMesh::new(positions, normals, uvs, faces, material))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::interval::Interval;
+    use crate::material::Lambertian;
+    use crate::ray::Ray;
+    use crate::rtweekend;
+
+    fn parse(bytes: &[u8]) -> io::Result<TriangleMesh> {
+        parse_ply(bytes, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))
+    }
+
+    /// z=0平面上[0,1]²的正方形，四个顶点的纹理坐标与x、y相同，作为一个四边形面给出
+    const ASCII_QUAD: &str = "ply
+format ascii 1.0
+comment 正方形
+element vertex 4
+property float x
+property float y
+property float z
+property float confidence
+property float nx
+property float ny
+property float nz
+property float s
+property float t
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 0.9 0 0 1 0 0
+1 0 0 0.8 0 0 1 1 0
+1 1 0 0.7 0 0 1 1 1
+0 1 0 0.6 0 0 1 0 1
+4 0 1 2 3
+";
+
+    /// 与`ASCII_QUAD`相同的正方形的二进制编码，顶点带一个多余的uchar属性，没有法线
+    fn binary_quad(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+        let mut bytes = format!(
+            "ply\nformat {} 1.0\nelement vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+             property uchar flags\nproperty double u\nproperty double v\n\
+             element face 1\nproperty list uchar int vertex_indices\nend_header\n",
+            format
+        )
+        .into_bytes();
+        for [x, y] in [[0.0f64, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]] {
+            for value in [x as f32, y as f32, 0.0] {
+                bytes.extend(if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+            }
+            bytes.push(0xff);
+            for value in [x, y] {
+                bytes.extend(if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+            }
+        }
+        bytes.push(4);
+        for index in [0i32, 1, 2, 3] {
+            bytes.extend(if big_endian { index.to_be_bytes() } else { index.to_le_bytes() });
+        }
+        bytes
+    }
+
+    /// 从正方形上方垂直向下射向(x, y)
+    fn cast(mesh: &TriangleMesh, x: Real, y: Real) -> Option<HitRecord> {
+        let r = Ray::new(Point3::new(x, y, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+        mesh.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec).then_some(rec)
+    }
+
+    /// 四边形被拆成两个三角形，两个三角形内的点都能命中，纹理坐标等于x、y
+    fn assert_unit_square(mesh: &TriangleMesh) {
+        assert_eq!(mesh.face_count(), 2);
+        for (x, y) in [(0.25, 0.75), (0.75, 0.25)] {
+            let rec = cast(mesh, x, y).expect("正方形内应当命中");
+            assert!((rec.t - 5.0).abs() < 1e-4);
+            assert!(rec.front_face);
+            assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-4);
+            assert!((rec.u - x).abs() < 1e-4 && (rec.v - y).abs() < 1e-4, "uv = ({}, {})", rec.u, rec.v);
+        }
+        assert!(cast(mesh, 1.5, 0.5).is_none());
+    }
+
+    #[test]
+    fn ascii_quad_is_triangulated_and_skips_unknown_properties() {
+        assert_unit_square(&parse(ASCII_QUAD.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn binary_quads_decode_in_both_byte_orders() {
+        assert_unit_square(&parse(&binary_quad(false)).unwrap());
+        assert_unit_square(&parse(&binary_quad(true)).unwrap());
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        let out_of_range = ASCII_QUAD.replace("4 0 1 2 3", "4 0 1 2 4");
+        assert!(parse(out_of_range.as_bytes()).is_err());
+        let negative = ASCII_QUAD.replace("4 0 1 2 3", "3 0 -1 2");
+        assert!(parse(negative.as_bytes()).is_err());
+        let too_small = ASCII_QUAD.replace("4 0 1 2 3", "2 0 1");
+        assert!(parse(too_small.as_bytes()).is_err());
+
+        // 二进制数据被截断
+        let bytes = binary_quad(false);
+        assert!(parse(&bytes[..bytes.len() - 2]).is_err());
+        assert!(parse(b"plx\nformat ascii 1.0\nend_header\n").is_err());
+        assert!(parse(b"ply\nformat ascii 1.0\nelement vertex 1\n").is_err());
+        assert!(parse(b"ply\nformat utf16 1.0\nend_header\n").is_err());
+    }
+}
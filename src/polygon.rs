@@ -0,0 +1,255 @@
+//! 多边形模块
+//!
+//! 提供由任意平面顶点环定义的多边形，内部用耳切法拆分为三角形，凹多边形同样适用，
+//! 可直接用于建筑平面图、CAD轮廓等
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::{self, Aabb};
+use super::onb::Onb;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 平面多边形几何形状
+///
+/// 顶点按逆时针排列的一侧为正面。顶点不严格共面时投影到拟合平面上
+///
+/// # Fields
+/// - origin: 平面坐标系的原点(第一个顶点在平面上的投影)
+/// - frame: 平面坐标系，w轴为单位法线
+/// - points: 各顶点的平面坐标
+/// - triangles: 拆分得到的三角形(顶点索引)
+/// - cdf: 三角形面积的累积分布，用于按面积均匀采样
+/// - uv_min, uv_size: 顶点平面坐标的范围，用于把纹理坐标归一化到[0,1]
+/// - mat: 多边形材质
+/// - bbox: 包围盒
+/// - area: 面积
+pub struct Polygon {
+    origin: Point3,
+    frame: Onb,
+    points: Vec<[Real; 2]>,
+    triangles: Vec<[usize; 3]>,
+    cdf: Vec<Real>,
+    uv_min: [Real; 2],
+    uv_size: [Real; 2],
+    mat: Arc<dyn Material + Send + Sync>,
+    bbox: Aabb,
+    area: Real,
+}
+
+impl Polygon {
+    /// 创建新的多边形
+    ///
+    /// # Arguments
+    /// * `vertices` - 按顺序排列的顶点环，首尾不需要重复，不能自相交
+    /// * `material` - 多边形材质
+    ///
+    /// # Note
+    /// 顶点少于3个或所有顶点共线时会panic
+    pub fn new(vertices: Vec<Point3>, material: Arc<dyn Material + Send + Sync>) -> Self {
+        assert!(vertices.len() >= 3, "多边形至少需要3个顶点");
+
+        // Newell法求法线，对凹多边形和轻微不共面的顶点都稳定
+        let n = vertices.iter().zip(vertices.iter().cycle().skip(1)).fold(Vec3::default(), |acc, (a, b)| {
+            acc + Vec3::new(
+                (a.y() - b.y()) * (a.z() + b.z()),
+                (a.z() - b.z()) * (a.x() + b.x()),
+                (a.x() - b.x()) * (a.y() + b.y()),
+            )
+        });
+        assert!(n.length() > 0.0, "多边形顶点不能全部共线");
+        let frame = Onb::new(n);
+        let normal = frame.w();
+
+        let centroid = vertices.iter().fold(Vec3::default(), |acc, &p| acc + p) / vertices.len() as Real;
+        let origin = vertices[0] - vec3::dot(vertices[0] - centroid, normal) * normal;
+        let points: Vec<[Real; 2]> = vertices
+            .iter()
+            .map(|&p| [vec3::dot(p - origin, frame.u()), vec3::dot(p - origin, frame.v())])
+            .collect();
+
+        let triangles = triangulate(&points);
+        let mut area = 0.0;
+        let cdf = triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                area += 0.5 * cross(points[a], points[b], points[c]);
+                area
+            })
+            .collect();
+
+        let uv_min = [0, 1].map(|k| points.iter().map(|p| p[k]).fold(Real::INFINITY, Real::min));
+        let uv_max = [0, 1].map(|k| points.iter().map(|p| p[k]).fold(-Real::INFINITY, Real::max));
+        let uv_size = [0, 1].map(|k| (uv_max[k] - uv_min[k]).max(Real::MIN_POSITIVE));
+
+        let bbox = vertices.iter().fold(aabb::EMPTY, |bbox, &p| {
+            let projected = p - vec3::dot(p - origin, normal) * normal;
+            Aabb::surrounding(&bbox, &Aabb::from_points(projected, projected))
+        });
+
+        Self {
+            origin,
+            frame,
+            points,
+            triangles,
+            cdf,
+            uv_min,
+            uv_size,
+            mat: material,
+            bbox,
+            area,
+        }
+    }
+
+    /// 拆分得到的三角形数量
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// 平面坐标转换为世界坐标
+    fn to_world(&self, p: [Real; 2]) -> Point3 {
+        self.origin + p[0] * self.frame.u() + p[1] * self.frame.v()
+    }
+}
+
+/// 二维叉积 (b - a) × (c - a)，a、b、c逆时针排列时为正，其一半为三角形的有向面积
+fn cross(a: [Real; 2], b: [Real; 2], c: [Real; 2]) -> Real {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// 判断点p是否在逆时针三角形abc内(含边界)
+fn in_triangle(p: [Real; 2], a: [Real; 2], b: [Real; 2], c: [Real; 2]) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+/// 耳切法三角化
+///
+/// 反复找出"耳朵"(凸顶点，且其与相邻两顶点组成的三角形内没有其它顶点)切下，
+/// 共线的顶点直接去掉。遇到自相交等找不到耳朵的情况时，剩余部分按扇形拆分
+fn triangulate(points: &[[Real; 2]]) -> Vec<[usize; 3]> {
+    let signed_area: Real = (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a[0] * b[1] - b[0] * a[1]
+        })
+        .sum();
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    if signed_area < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let corner = |i: usize| [remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]];
+
+        if let Some(i) = (0..m).find(|&i| {
+            let [a, b, c] = corner(i).map(|k| points[k]);
+            cross(a, b, c) == 0.0
+        }) {
+            remaining.remove(i);
+            continue;
+        }
+
+        let ear = (0..m).find(|&i| {
+            let [ia, ib, ic] = corner(i);
+            let [a, b, c] = [ia, ib, ic].map(|k| points[k]);
+            cross(a, b, c) > 0.0
+                && remaining.iter().all(|&k| {
+                    let p = points[k];
+                    // 与三个角点重合的顶点(如连接孔洞的桥边两端)不算在内部
+                    [ia, ib, ic].contains(&k) || p == a || p == b || p == c || !in_triangle(p, a, b, c)
+                })
+        });
+        let Some(i) = ear else {
+            break;
+        };
+        triangles.push(corner(i));
+        remaining.remove(i);
+    }
+
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    triangles
+}
+
+impl Hittable for Polygon {
+    /// 实现多边形的光线命中检测
+    ///
+    /// 先求光线与所在平面的交点，再判断交点的平面坐标是否落在某个三角形内。
+    /// 纹理坐标为平面坐标在顶点范围内归一化的结果，切向量为平面坐标系的u轴
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let normal = self.frame.w();
+        let denom = vec3::dot(normal, r.direction());
+
+        // 光线与平面平行时不相交
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+
+        let t = vec3::dot(normal, self.origin - r.origin()) / denom;
+        if !ray_t.contains(t) {
+            return false;
+        }
+
+        let intersection = r.at(t);
+        let d = intersection - self.origin;
+        let p = [vec3::dot(d, self.frame.u()), vec3::dot(d, self.frame.v())];
+        let inside = self.triangles.iter().any(|&[a, b, c]| {
+            in_triangle(p, self.points[a], self.points[b], self.points[c])
+        });
+        if !inside {
+            return false;
+        }
+
+        hit_record.t = t;
+        hit_record.p = intersection;
+        hit_record.u = (p[0] - self.uv_min[0]) / self.uv_size[0];
+        hit_record.v = (p[1] - self.uv_min[1]) / self.uv_size[1];
+        hit_record.tangent = self.frame.u();
+        hit_record.mat = Some(Arc::clone(&self.mat));
+        hit_record.set_face_normal(r, normal);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    /// 多边形面积上均匀采样换算到立体角测度的概率密度
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> Real {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(origin, direction), &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return 0.0;
+        }
+
+        let distance_squared = rec.t * rec.t * direction.squared_length();
+        let cosine = (vec3::dot(direction, rec.normal) / direction.length()).abs();
+
+        distance_squared / (cosine * self.area)
+    }
+
+    /// 从`origin`指向多边形上均匀随机一点的方向
+    ///
+    /// 按面积选取三角形，再在三角形内均匀采样
+    fn random(&self, origin: Point3) -> Vec3 {
+        let x = rtweekend::random_double() * self.area;
+        let index = self.cdf.partition_point(|&c| c < x).min(self.triangles.len() - 1);
+        let [a, b, c] = self.triangles[index].map(|k| self.points[k]);
+
+        let (mut s, mut t) = (rtweekend::random_double(), rtweekend::random_double());
+        if s + t > 1.0 {
+            (s, t) = (1.0 - s, 1.0 - t);
+        }
+        let p = [
+            a[0] + s * (b[0] - a[0]) + t * (c[0] - a[0]),
+            a[1] + s * (b[1] - a[1]) + t * (c[1] - a[1]),
+        ];
+        self.to_world(p) - origin
+    }
+}
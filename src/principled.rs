@@ -0,0 +1,389 @@
+//! 原则化材质模块
+//!
+//! 提供Disney原则化BSDF，用金属度、粗糙度、透射等少量直观参数组合出漫反射、
+//! 光泽反射、金属、玻璃、绒面和清漆效果，DCC工具中制作的材质参数可以直接对应
+
+use super::color::Color;
+use super::hittable::HitRecord;
+use super::material::{self, Material, ScatterRecord};
+use super::microfacet::{self, Ggx};
+use super::onb::Onb;
+use super::ray::Ray;
+use super::rtweekend::{self, Real};
+use super::texture::{SolidColor, Texture};
+use super::vec3::{self, Vec3};
+use std::sync::Arc;
+
+/// Disney原则化BSDF
+///
+/// 由清漆层和基底组成，每次散射与`Clearcoat`一样按菲涅尔反射率逐层随机选择一个波瓣采样，
+/// 选中波瓣的衰减只含该波瓣自身的 f·cosθ/pdf，因此白色材质的反照率不超过1：
+/// - 清漆：折射率1.5、独立粗糙度的无色GGX反射层，以 clearcoat·F 的概率在该层反射，否则进入基底
+/// - 金属：以metallic的概率选择，GGX微表面反射，垂直入射反射率为基础色
+/// - 透射：在非金属部分中以transmission的概率选择，GGX粗糙玻璃，折射率由specular换算，透射光被基础色染色
+/// - 电介质：其余情况，以垂直入射反射率 0.08·specular 的菲涅尔反射率在GGX微表面反射，
+///   否则进入Lambert漫反射，绒面在掠射角把漫反射颜色向白色混合
+///
+/// 粗糙度为0、金属度为1时退化为镜面`Metal`；金属度、specular、绒面和清漆都为0时
+/// 垂直入射的光全部进入漫反射，与`Lambertian`一致
+///
+/// # Fields
+/// - base_color: 基础色纹理
+/// - metallic: 金属度，[0,1]
+/// - roughness: 感知粗糙度，作用于光泽反射和透射，[0,1]
+/// - specular: 电介质的镜面反射强度，0.5对应折射率1.5，[0,1]
+/// - transmission: 透射度，[0,1]
+/// - sheen: 绒面强度，在掠射角把漫反射向白色混合，用于布料，[0,1]
+/// - clearcoat: 清漆层强度，[0,1]
+/// - clearcoat_roughness: 清漆层的感知粗糙度，[0,1]
+/// - normal_map: 可选的切线空间法线贴图
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    pub metallic: Real,
+    pub roughness: Real,
+    pub specular: Real,
+    pub transmission: Real,
+    pub sheen: Real,
+    pub clearcoat: Real,
+    pub clearcoat_roughness: Real,
+    pub normal_map: Option<Arc<dyn Texture>>,
+}
+
+/// 清漆层(折射率1.5)垂直入射时的反射率
+const CLEARCOAT_F0: Real = 0.04;
+
+impl Principled {
+    /// 以给定基础色创建材质，其余参数取默认值：
+    /// 非金属、粗糙度0.5、specular 0.5、无透射、无绒面、无清漆(清漆粗糙度0.03)
+    ///
+    /// # Arguments
+    /// * `base_color` - 基础色
+    pub fn new(base_color: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(base_color)))
+    }
+
+    /// 以基础色纹理创建材质，其余参数同`new`
+    pub fn from_texture(base_color: Arc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            transmission: 0.0,
+            sheen: 0.0,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.03,
+            normal_map: None,
+        }
+    }
+
+    /// 设置金属度
+    pub fn with_metallic(mut self, metallic: Real) -> Self {
+        self.metallic = metallic.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 设置粗糙度
+    pub fn with_roughness(mut self, roughness: Real) -> Self {
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 设置电介质的镜面反射强度
+    pub fn with_specular(mut self, specular: Real) -> Self {
+        self.specular = specular.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 设置透射度
+    pub fn with_transmission(mut self, transmission: Real) -> Self {
+        self.transmission = transmission.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 设置绒面强度
+    pub fn with_sheen(mut self, sheen: Real) -> Self {
+        self.sheen = sheen.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 设置清漆层强度和粗糙度
+    pub fn with_clearcoat(mut self, clearcoat: Real, roughness: Real) -> Self {
+        self.clearcoat = clearcoat.clamp(0.0, 1.0);
+        self.clearcoat_roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 设置法线贴图
+    pub fn with_normal_map(mut self, normal_map: Arc<dyn Texture>) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    /// 由specular换算的电介质折射率，0.08·specular 为垂直入射反射率
+    fn ior(&self) -> Real {
+        let r = (0.08 * self.specular).sqrt();
+        (1.0 + r) / (1.0 - r)
+    }
+
+    /// 无色界面的Schlick菲涅尔反射率
+    ///
+    /// # Arguments
+    /// * `f0` - 垂直入射反射率
+    /// * `cosine` - 入射方向与(微)表面法线夹角的余弦
+    fn fresnel(f0: Real, cosine: Real) -> Real {
+        microfacet::schlick_fresnel(Color::new(f0, f0, f0), cosine).x()
+    }
+
+    /// 漫反射波瓣在余弦采样下的 f·cosθ/pdf：Lambert漫反射在掠射角按绒面强度向白色混合
+    fn diffuse_weight(&self, base: Color, wo: Vec3, wi: Vec3) -> Color {
+        let cos_d = vec3::dot(wi, vec3::unit_vector(wo + wi)).max(0.0);
+        let sheen = self.sheen * (1.0 - cos_d).clamp(0.0, 1.0).powi(5);
+        (1.0 - sheen) * base + Color::new(sheen, sheen, sheen)
+    }
+
+    /// 非透射波瓣在`wi`方向的混合概率密度(局部坐标)
+    ///
+    /// 各层的选择概率与`Clearcoat`一样用宏观表面的菲涅尔反射率近似；
+    /// 透射波瓣由微表面折射决定方向，不计入
+    fn reflection_pdf(&self, wo: Vec3, wi: Vec3) -> Real {
+        if wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let specular = Ggx::from_roughness(self.roughness).reflection_pdf(wo, wi);
+        let f = Self::fresnel(0.08 * self.specular, wo.z());
+        let dielectric = (1.0 - self.transmission) * (f * specular + (1.0 - f) * wi.z() / rtweekend::PI);
+        let base = self.metallic * specular + (1.0 - self.metallic) * dielectric;
+
+        let coat = self.clearcoat * Self::fresnel(CLEARCOAT_F0, wo.z());
+        coat * Ggx::from_roughness(self.clearcoat_roughness).reflection_pdf(wo, wi) + (1.0 - coat) * base
+    }
+
+    /// 沿微表面法线`h`反射，返回(反射方向, G2/G1)，反射到宏观表面以下时返回None
+    fn reflect(ggx: &Ggx, wo: Vec3, h: Vec3) -> Option<(Vec3, Real)> {
+        let wi = vec3::reflect(-wo, h);
+        if wi.z() <= 0.0 {
+            return None;
+        }
+        Some((wi, ggx.g2(wo, wi) / ggx.g1(wo)))
+    }
+
+    /// 采样粗糙玻璃波瓣：按微表面的菲涅尔反射率随机选择反射或折射
+    ///
+    /// 这样选择时反射和折射的 f·cosθ/pdf 都化简为 G2/G1
+    ///
+    /// # Returns
+    /// 返回(散射方向, 衰减)，散射方向落在错误一侧时返回None
+    fn sample_transmission(&self, base: Color, wo: Vec3, front_face: bool) -> Option<(Vec3, Color)> {
+        let ggx = Ggx::from_roughness(self.roughness);
+        let h = ggx.sample_visible_normal(wo);
+        let ior = self.ior();
+        let eta = if front_face { 1.0 / ior } else { ior };
+
+        let cos_i = vec3::dot(wo, h);
+        let sin2_t = eta * eta * (1.0 - cos_i * cos_i).max(0.0);
+        let fresnel = if sin2_t >= 1.0 {
+            1.0
+        } else {
+            // 从介质内部射出时用透射侧(空气中)的角度计算
+            let cos = if front_face { cos_i } else { (1.0 - sin2_t).sqrt() };
+            Self::fresnel(((1.0 - ior) / (1.0 + ior)).powi(2), cos)
+        };
+
+        let reflect = rtweekend::random_double() < fresnel;
+        let (wi, tint) = if reflect {
+            (vec3::reflect(-wo, h), Color::new(1.0, 1.0, 1.0))
+        } else {
+            (vec3::refract(-wo, h, eta), base)
+        };
+        // 反射必须留在宏观表面上方，折射必须穿到下方
+        if reflect != (wi.z() > 0.0) {
+            return None;
+        }
+        Some((wi, ggx.g2(wo, wi) / ggx.g1(wo) * tint))
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        ScatterRecord::write_out(self.scatter2(r_in, rec), attenuation, scattered)
+    }
+
+    /// 自上而下逐层选择一个波瓣采样
+    ///
+    /// 清漆和电介质反射以所采样微表面的菲涅尔反射率为概率选中，此时衰减化简为 G2/G1；
+    /// 金属和透射按参数值为概率选中，衰减即该波瓣的 f·cosθ/pdf。
+    /// 透射波瓣的样本不提供概率密度，按镜面散射报告
+    fn scatter2(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let frame = Onb::new(material::shading_normal(rec, self.normal_map.as_deref()));
+        let wo = frame.to_local(-vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let base = self.base_color.value(rec.u, rec.v, rec.p);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let coat = Ggx::from_roughness(self.clearcoat_roughness);
+        let ggx = Ggx::from_roughness(self.roughness);
+
+        let coat_h = (self.clearcoat > 0.0).then(|| coat.sample_visible_normal(wo));
+        let (wi, weight, transmitted) = match coat_h {
+            Some(h) if rtweekend::random_double() < self.clearcoat * Self::fresnel(CLEARCOAT_F0, vec3::dot(wo, h)) => {
+                let (wi, g) = Self::reflect(&coat, wo, h)?;
+                (wi, g * white, false)
+            }
+            _ if rtweekend::random_double() < self.metallic => {
+                let h = ggx.sample_visible_normal(wo);
+                let (wi, g) = Self::reflect(&ggx, wo, h)?;
+                (wi, g * microfacet::schlick_fresnel(base, vec3::dot(wo, h)), false)
+            }
+            _ if rtweekend::random_double() < self.transmission => {
+                let (wi, weight) = self.sample_transmission(base, wo, rec.front_face)?;
+                (wi, weight, true)
+            }
+            _ => {
+                let h = ggx.sample_visible_normal(wo);
+                if rtweekend::random_double() < Self::fresnel(0.08 * self.specular, vec3::dot(wo, h)) {
+                    let (wi, g) = Self::reflect(&ggx, wo, h)?;
+                    (wi, g * white, false)
+                } else {
+                    let mut wi = Vec3::new(0.0, 0.0, 1.0) + vec3::random_unit_vector();
+                    if wi.near_zero() {
+                        wi = Vec3::new(0.0, 0.0, 1.0);
+                    }
+                    let wi = vec3::unit_vector(wi);
+                    (wi, self.diffuse_weight(base, wo, wi), false)
+                }
+            }
+        };
+
+        let direction = frame.transform(wi);
+        if !transmitted && vec3::dot(direction, rec.normal) <= 0.0 {
+            return None;
+        }
+
+        Some(ScatterRecord {
+            attenuation: weight,
+            scattered: Ray::with_time(rec.p, direction, r_in.time()),
+            pdf: if transmitted { None } else { Some(self.reflection_pdf(wo, wi)).filter(|pdf| *pdf > 0.0) },
+            is_specular: transmitted,
+        })
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Real {
+        let frame = Onb::new(material::shading_normal(rec, self.normal_map.as_deref()));
+        let wo = frame.to_local(-vec3::unit_vector(r_in.direction()));
+        let wi = frame.to_local(vec3::unit_vector(scattered.direction()));
+        self.reflection_pdf(wo, wi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{Lambertian, Metal};
+    use crate::vec3::Point3;
+
+    /// 在原点、法线朝+z的正面命中记录
+    fn hit_facing_z() -> HitRecord {
+        HitRecord {
+            p: Point3::default(),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            t: 1.0,
+            front_face: true,
+            ..HitRecord::default()
+        }
+    }
+
+    /// 在xz平面内入射、与法线夹角余弦为`cos`的光线
+    fn incoming(cos: Real) -> Ray {
+        let sin = (1.0 - cos * cos).sqrt();
+        Ray::new(Point3::new(-sin, 0.0, cos), Vec3::new(sin, 0.0, -cos))
+    }
+
+    /// 多次散射的平均衰减(未散射的样本计为0)和散射方向与法线夹角余弦的平均值
+    fn mean_scatter(mat: &dyn Material, r_in: &Ray, n: usize) -> (Color, Real) {
+        let rec = hit_facing_z();
+        let (mut albedo, mut cosine) = (Color::default(), 0.0);
+        for _ in 0..n {
+            if let Some(srec) = mat.scatter2(r_in, &rec) {
+                albedo += srec.attenuation;
+                cosine += vec3::unit_vector(srec.scattered.direction()).z();
+            }
+        }
+        (albedo / n as Real, cosine / n as Real)
+    }
+
+    #[test]
+    fn white_materials_never_gain_energy() {
+        rtweekend::seed_random(1059);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let materials = [
+            ("diffuse", Principled::new(white).with_roughness(1.0)),
+            ("plastic", Principled::new(white).with_roughness(0.2)),
+            ("metal", Principled::new(white).with_metallic(1.0).with_roughness(0.5)),
+            ("glass", Principled::new(white).with_transmission(1.0).with_roughness(0.3)),
+            ("cloth", Principled::new(white).with_sheen(1.0).with_roughness(1.0)),
+            ("lacquer", Principled::new(white).with_clearcoat(1.0, 0.1).with_specular(1.0)),
+            ("everything", Principled::new(white).with_metallic(0.5).with_transmission(0.5).with_sheen(1.0).with_clearcoat(1.0, 0.5)),
+        ];
+        for (name, mat) in &materials {
+            for cos in [1.0, 0.5, 0.1] {
+                let (albedo, _) = mean_scatter(mat, &incoming(cos), 20_000);
+                for c in albedo.to_array() {
+                    assert!(c <= 1.0, "{name}, cos {cos}: albedo = {albedo:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_full_metal_matches_mirror_metal() {
+        rtweekend::seed_random(1059);
+        let base = Color::new(0.9, 0.6, 0.3);
+        let principled = Principled::new(base).with_metallic(1.0).with_roughness(0.0);
+        let rec = hit_facing_z();
+        let r_in = incoming(0.866);
+        let mirror = Metal::new(base, 0.0).scatter2(&r_in, &rec).unwrap();
+        let n = 1000;
+        let mut cosine = 0.0;
+        for _ in 0..n {
+            let srec = principled.scatter2(&r_in, &rec).expect("smooth metal always reflects");
+            cosine += vec3::dot(vec3::unit_vector(srec.scattered.direction()), vec3::unit_vector(mirror.scattered.direction())) / n as Real;
+            assert!((srec.attenuation - mirror.attenuation).length() < 1e-3, "attenuation = {:?}", srec.attenuation);
+        }
+        // alpha不低于Ggx::MIN_ALPHA，GGX的长尾偶尔采到偏离稍远的方向，但平均几乎就是镜面方向
+        assert!(cosine > 0.9995, "mean cosine to mirror = {cosine}");
+    }
+
+    #[test]
+    fn dielectric_without_specular_matches_lambertian_at_normal_incidence() {
+        rtweekend::seed_random(1059);
+        let base = Color::new(0.7, 0.5, 0.3);
+        let principled = Principled::new(base).with_specular(0.0).with_roughness(0.0);
+        let r_in = incoming(1.0);
+        let n = 50_000;
+        let (albedo, cosine) = mean_scatter(&principled, &r_in, n);
+        let (expected_albedo, expected_cosine) = mean_scatter(&Lambertian::new(base), &r_in, n);
+        assert!((albedo - expected_albedo).length() < 1e-3, "albedo = {albedo:?}, lambertian = {expected_albedo:?}");
+        // 余弦分布下 cosθ 的期望为2/3
+        assert!((cosine - expected_cosine).abs() < 0.01, "mean cosine = {cosine}, lambertian = {expected_cosine}");
+        assert!((cosine - 2.0 / 3.0).abs() < 0.01, "mean cosine = {cosine}");
+    }
+
+    #[test]
+    fn reflected_samples_carry_the_mixture_pdf() {
+        rtweekend::seed_random(1059);
+        let mat = Principled::new(Color::new(0.8, 0.8, 0.8)).with_metallic(0.3).with_roughness(0.4).with_clearcoat(0.5, 0.1);
+        let rec = hit_facing_z();
+        let r_in = incoming(0.7);
+        for _ in 0..1000 {
+            let Some(srec) = mat.scatter2(&r_in, &rec) else { continue };
+            assert!(!srec.is_specular);
+            let pdf = srec.pdf.expect("reflections report a pdf");
+            let expected = mat.scattering_pdf(&r_in, &rec, &srec.scattered);
+            // 清漆的GGX峰很尖，单精度下散射方向的舍入会让概率密度有可见的相对误差
+            assert!((pdf - expected).abs() <= 1e-3 * pdf.max(1.0), "pdf = {pdf}, scattering_pdf = {expected}");
+        }
+    }
+}
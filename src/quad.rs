@@ -0,0 +1,135 @@
+//! 四边形模块
+//!
+//! 提供由一个角点和两条边向量定义的平行四边形
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 平行四边形几何形状
+///
+/// 四个顶点为 Q、Q+u、Q+v、Q+u+v，正面法线方向为 u × v
+///
+/// # Fields
+/// - q: 角点
+/// - u, v: 两条边向量
+/// - w: 平面内点求平面坐标(α, β)所用的辅助向量 n / (n·n)，n = u × v
+/// - mat: 四边形材质
+/// - bbox: 包围盒
+/// - normal: 单位法线
+/// - d: 平面方程 n·P = d 的常数项
+/// - area: 面积
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    mat: Arc<dyn Material + Send + Sync>,
+    bbox: Aabb,
+    normal: Vec3,
+    d: Real,
+    area: Real,
+}
+
+impl Quad {
+    /// 创建新的平行四边形
+    ///
+    /// # Arguments
+    /// * `q` - 角点
+    /// * `u` - 第一条边向量
+    /// * `v` - 第二条边向量
+    /// * `material` - 四边形材质
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material + Send + Sync>) -> Self {
+        let n = vec3::cross(u, v);
+        let normal = vec3::unit_vector(n);
+
+        // 两条对角线的包围盒的并集
+        let bbox = Aabb::surrounding(
+            &Aabb::from_points(q, q + u + v),
+            &Aabb::from_points(q + u, q + v),
+        );
+
+        Self {
+            q,
+            u,
+            v,
+            w: n / vec3::dot(n, n),
+            mat: material,
+            bbox,
+            normal,
+            d: vec3::dot(normal, q),
+            area: n.length(),
+        }
+    }
+
+    /// 判断平面坐标(α, β)是否落在四边形内
+    fn is_interior(a: Real, b: Real) -> bool {
+        (0.0..=1.0).contains(&a) && (0.0..=1.0).contains(&b)
+    }
+}
+
+impl Hittable for Quad {
+    /// 实现平行四边形的光线命中检测
+    ///
+    /// 先求光线与所在平面的交点，再用平面坐标判断交点是否在四边形内，
+    /// 平面坐标同时作为纹理坐标(u, v)
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let denom = vec3::dot(self.normal, r.direction());
+
+        // 光线与平面平行时不相交
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+
+        let t = (self.d - vec3::dot(self.normal, r.origin())) / denom;
+        if !ray_t.contains(t) {
+            return false;
+        }
+
+        let intersection = r.at(t);
+        let planar_hitpt = intersection - self.q;
+        let alpha = vec3::dot(self.w, vec3::cross(planar_hitpt, self.v));
+        let beta = vec3::dot(self.w, vec3::cross(self.u, planar_hitpt));
+        if !Self::is_interior(alpha, beta) {
+            return false;
+        }
+
+        hit_record.t = t;
+        hit_record.p = intersection;
+        hit_record.u = alpha;
+        hit_record.v = beta;
+        hit_record.tangent = vec3::unit_vector(self.u);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+        hit_record.set_face_normal(r, self.normal);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    /// 四边形面积上均匀采样换算到立体角测度的概率密度
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> Real {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(origin, direction), &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return 0.0;
+        }
+
+        let distance_squared = rec.t * rec.t * direction.squared_length();
+        let cosine = (vec3::dot(direction, rec.normal) / direction.length()).abs();
+
+        distance_squared / (cosine * self.area)
+    }
+
+    /// 从`origin`指向四边形上均匀随机一点的方向
+    fn random(&self, origin: Point3) -> Vec3 {
+        let p = self.q + (rtweekend::random_double() * self.u) + (rtweekend::random_double() * self.v);
+        p - origin
+    }
+}
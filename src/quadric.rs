@@ -0,0 +1,235 @@
+//! 二次曲面模块
+//!
+//! 提供由一般二次曲面方程定义的几何形状，可表示椭球、抛物面、双曲面、圆锥面等
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::sphere;
+use super::rtweekend::Real;
+use std::sync::Arc;
+
+/// 一般二次曲面
+///
+/// 曲面为齐次坐标 P = (x, y, z, 1) 满足 Pᵀ Q P = 0 的点集，其中Q为4×4对称矩阵。
+/// Pᵀ Q P < 0 的一侧视为物体内部。抛物面、双曲面等无界曲面只保留包围盒内的部分
+///
+/// # Fields
+/// - q: 二次曲面矩阵(按对称矩阵使用)
+/// - bbox: 裁剪用的包围盒
+/// - mat: 材质
+pub struct Quadric {
+    q: [[Real; 4]; 4],
+    bbox: Aabb,
+    mat: Arc<dyn Material + Send + Sync>,
+}
+
+impl Quadric {
+    /// 由二次曲面矩阵创建曲面
+    ///
+    /// # Arguments
+    /// * `q` - 4×4二次曲面矩阵，非对称时取其对称部分 (Q + Qᵀ)/2
+    /// * `bbox` - 裁剪包围盒，包围盒外的交点被忽略
+    /// * `material` - 材质
+    pub fn new(q: [[Real; 4]; 4], bbox: Aabb, material: Arc<dyn Material + Send + Sync>) -> Self {
+        let mut sym = [[0.0; 4]; 4];
+        for (i, row) in sym.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = 0.5 * (q[i][j] + q[j][i]);
+            }
+        }
+
+        Self { q: sym, bbox, mat: material }
+    }
+
+    /// 创建轴对齐椭球 ((x-cx)/a)² + ((y-cy)/b)² + ((z-cz)/c)² = 1
+    ///
+    /// # Arguments
+    /// * `center` - 椭球中心
+    /// * `radii` - 三个半轴长度(a, b, c)
+    /// * `material` - 材质
+    pub fn ellipsoid(center: Point3, radii: Vec3, material: Arc<dyn Material + Send + Sync>) -> Self {
+        let inv = [
+            1.0 / (radii.x() * radii.x()),
+            1.0 / (radii.y() * radii.y()),
+            1.0 / (radii.z() * radii.z()),
+        ];
+        let c = [center.x(), center.y(), center.z()];
+
+        // 展开 Σ inv[i] (x_i - c_i)² - 1
+        let mut q = [[0.0; 4]; 4];
+        let mut constant = -1.0;
+        for i in 0..3 {
+            q[i][i] = inv[i];
+            q[i][3] = -inv[i] * c[i];
+            q[3][i] = -inv[i] * c[i];
+            constant += inv[i] * c[i] * c[i];
+        }
+        q[3][3] = constant;
+
+        let abs_radii = Vec3::new(radii.x().abs(), radii.y().abs(), radii.z().abs());
+        Self::new(q, Aabb::from_points(center - abs_radii, center + abs_radii), material)
+    }
+
+    /// 计算 aᵀ Q b，a和b为齐次坐标
+    fn bilinear(&self, a: [Real; 4], b: [Real; 4]) -> Real {
+        let mut sum = 0.0;
+        for (i, row) in self.q.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                sum += a[i] * value * b[j];
+            }
+        }
+        sum
+    }
+
+    /// 曲面在点p处的梯度(外法线方向)，即 2QP 的前三个分量
+    fn gradient(&self, p: Point3) -> Vec3 {
+        let h = [p.x(), p.y(), p.z(), 1.0];
+        let row = |i: usize| (0..4).map(|j| self.q[i][j] * h[j]).sum::<Real>();
+        2.0 * Vec3::new(row(0), row(1), row(2))
+    }
+}
+
+impl Hittable for Quadric {
+    /// 实现二次曲面的光线命中检测
+    ///
+    /// 代入齐次光线方程 P(t) = O + tD (O的w分量为1，D的w分量为0) 得到
+    /// (DᵀQD)t² + 2(DᵀQO)t + OᵀQO = 0，依次检查两个根并丢弃包围盒外的交点。
+    /// 纹理坐标按包围盒中心到命中点的方向做球面映射
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let o = r.origin();
+        let d = r.direction();
+        let oh = [o.x(), o.y(), o.z(), 1.0];
+        let dh = [d.x(), d.y(), d.z(), 0.0];
+
+        // 二次方程系数: a t² + 2 b t + c = 0
+        let a = self.bilinear(dh, dh);
+        let b = self.bilinear(dh, oh);
+        let c = self.bilinear(oh, oh);
+
+        let roots = if a.abs() < 1e-12 {
+            // 光线方向上退化为一次方程
+            if b.abs() < 1e-12 {
+                return false;
+            }
+            [-c / (2.0 * b), Real::NAN]
+        } else {
+            let discriminant = b * b - a * c;
+            if discriminant < 0.0 {
+                return false;
+            }
+            let sqrtd = discriminant.sqrt();
+            let (t0, t1) = ((-b - sqrtd) / a, (-b + sqrtd) / a);
+            if t0 < t1 { [t0, t1] } else { [t1, t0] }
+        };
+
+        let inside_bbox = |p: Point3| {
+            self.bbox.x.contains(p.x()) && self.bbox.y.contains(p.y()) && self.bbox.z.contains(p.z())
+        };
+        let Some(t) = roots.into_iter().find(|&t| ray_t.surrounds(t) && inside_bbox(r.at(t))) else {
+            return false;
+        };
+
+        hit_record.t = t;
+        hit_record.p = r.at(t);
+
+        let outward_normal = vec3::unit_vector(self.gradient(hit_record.p));
+        let dir = vec3::unit_vector(hit_record.p - self.bbox.center());
+        (hit_record.u, hit_record.v) = sphere::get_sphere_uv(dir);
+        hit_record.tangent = sphere::sphere_tangent(dir);
+        hit_record.set_face_normal(r, outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+    use crate::rtweekend;
+
+    fn gray() -> Arc<dyn Material + Send + Sync> {
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+    }
+
+    /// 中心(1,2,3)、半轴(2,1,0.5)的椭球
+    fn ellipsoid() -> Quadric {
+        Quadric::ellipsoid(Point3::new(1.0, 2.0, 3.0), Vec3::new(2.0, 1.0, 0.5), gray())
+    }
+
+    /// 抛物面 z = x² + y²，裁剪到 [-2,2]×[-2,2]×[0,4]
+    fn paraboloid() -> Quadric {
+        let mut q = [[0.0; 4]; 4];
+        q[0][0] = 1.0;
+        q[1][1] = 1.0;
+        // 只填上三角，取对称部分后两个非对角元各为-0.5
+        q[2][3] = -1.0;
+        let bbox = Aabb::from_points(Point3::new(-2.0, -2.0, 0.0), Point3::new(2.0, 2.0, 4.0));
+        Quadric::new(q, bbox, gray())
+    }
+
+    /// 命中时检查法线为单位长度且朝向光线来的一侧
+    fn cast(quadric: &Quadric, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        let r = Ray::new(origin, direction);
+        if !quadric.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return None;
+        }
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!(vec3::dot(rec.normal, direction) < 0.0);
+        Some(rec)
+    }
+
+    #[test]
+    fn ellipsoid_is_hit_on_each_semi_axis() {
+        let rec = cast(&ellipsoid(), Point3::new(1.0, 2.0, -5.0), Vec3::new(0.0, 0.0, 1.0)).unwrap();
+        assert!((rec.t - 7.5).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-4);
+
+        // x偏离中心1处：(1/2)² + (y-2)² = 1，法线∝(x/a², y/b², 0)
+        let y = (0.75 as Real).sqrt();
+        let rec = cast(&ellipsoid(), Point3::new(2.0, 10.0, 3.0), Vec3::new(0.0, -1.0, 0.0)).unwrap();
+        assert!((rec.t - (8.0 - y)).abs() < 1e-4);
+        assert!((rec.normal - vec3::unit_vector(Vec3::new(0.25, y, 0.0))).length() < 1e-4);
+
+        assert!(cast(&ellipsoid(), Point3::new(3.1, 10.0, 3.0), Vec3::new(0.0, -1.0, 0.0)).is_none());
+        assert!(cast(&ellipsoid(), Point3::new(1.0, 2.0, -5.0), Vec3::new(0.0, 0.0, -1.0)).is_none());
+    }
+
+    #[test]
+    fn ray_from_inside_the_ellipsoid_hits_the_back_face() {
+        let rec = cast(&ellipsoid(), Point3::new(1.0, 2.0, 3.0), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn unbounded_surfaces_are_clipped_to_the_box() {
+        // 从碗内向下命中碗底：内部(x² + y² - z < 0)的一侧是反面
+        let rec = cast(&paraboloid(), Point3::new(1.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0)).unwrap();
+        assert!((rec.t - 9.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+        assert!((rec.normal - vec3::unit_vector(Vec3::new(-2.0, 0.0, 1.0))).length() < 1e-4);
+
+        // 从侧面水平射入碗壁
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let rec = cast(&paraboloid(), Point3::new(-5.0, 0.0, 3.0), x).unwrap();
+        assert!((rec.t - (5.0 - (3.0 as Real).sqrt())).abs() < 1e-4);
+        assert!(rec.front_face);
+        // 交点x = ±√4.5在包围盒外
+        assert!(cast(&paraboloid(), Point3::new(-5.0, 0.0, 4.5), x).is_none());
+        assert!(cast(&paraboloid(), Point3::new(-5.0, 0.0, -1.0), x).is_none());
+    }
+}
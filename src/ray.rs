@@ -2,6 +2,8 @@
 //!
 //! 提供光线数据结构及相关运算
 
+use crate::interval::Interval;
+use crate::rtweekend::Real;
 use crate::vec3::{Point3, Vec3};
 
 /// 光线结构体，表示从原点沿方向传播的光线
@@ -9,12 +11,17 @@ use crate::vec3::{Point3, Vec3};
 /// # Fields
 /// - orig: 光线起点
 /// - dir: 光线传播方向(已归一化)
+/// - tm: 光线发出的时刻，用于运动模糊
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Ray {
     orig: Point3,
     dir: Vec3,
+    tm: Real,
 }
 
+/// `Ray::march`单次调用的最大采样点数
+pub const MAX_MARCH_STEPS: usize = 1 << 20;
+
 impl Ray {
     /// 创建新的光线
     /// 
@@ -22,9 +29,20 @@ impl Ray {
     /// * `origin` - 光线起点
     /// * `direction` - 光线方向(应已归一化)
     pub fn new(origin: Point3, direction: Vec3) -> Self {
+        Self::with_time(origin, direction, 0.0)
+    }
+
+    /// 创建在指定时刻发出的光线
+    ///
+    /// # Arguments
+    /// * `origin` - 光线起点
+    /// * `direction` - 光线方向
+    /// * `time` - 光线发出的时刻
+    pub fn with_time(origin: Point3, direction: Vec3, time: Real) -> Self {
         Ray {
             orig: origin,
             dir: direction,
+            tm: time,
         }
     }
 
@@ -38,6 +56,11 @@ impl Ray {
         self.dir
     }
 
+    /// 获取光线发出的时刻
+    pub fn time(&self) -> Real {
+        self.tm
+    }
+
     /// 计算光线在参数t处的位置
     /// 
     /// # Arguments
@@ -45,7 +68,107 @@ impl Ray {
     /// 
     /// # Returns
     /// 返回光线上的点: origin + t * direction
-    pub fn at(&self, t: f64) -> Point3 {
+    pub fn at(&self, t: Real) -> Point3 {
         self.orig + t * self.dir
     }
+
+    /// 计算光线在参数t处的位置，t会先被限制在区间内
+    ///
+    /// # Arguments
+    /// * `t` - 光线参数
+    /// * `ray_t` - 光线参数有效范围
+    pub fn at_clamped(&self, t: Real, ray_t: &Interval) -> Point3 {
+        self.at(ray_t.clamp(t))
+    }
+
+    /// 沿光线在[t0, t1]之间等步长采样，对每个采样点调用`f(t, point)`
+    ///
+    /// 用于体积积分等需要沿光线步进的场合
+    ///
+    /// # Arguments
+    /// * `t0` - 起始参数
+    /// * `t1` - 结束参数(包含)
+    /// * `step` - 步长
+    /// * `f` - 采样回调
+    ///
+    /// # Note
+    /// - t1 < t0，或t0、t1、step不是有限值(例如无界区间[t0, ∞))时不进行任何采样
+    /// - step ≤ 0 时只在区间中点采样一次
+    /// - 采样点最多`MAX_MARCH_STEPS`个，超出部分被截断
+    pub fn march(&self, t0: Real, t1: Real, step: Real, mut f: impl FnMut(Real, Point3)) {
+        if !(t0.is_finite() && t1.is_finite() && step.is_finite()) || t1 < t0 {
+            return;
+        }
+        if step <= 0.0 {
+            let t = 0.5 * (t0 + t1);
+            f(t, self.at(t));
+            return;
+        }
+
+        // 按索引计算采样点，避免累加误差漏掉终点
+        let steps = (((t1 - t0) / step + 1e-9).floor() as usize).min(MAX_MARCH_STEPS - 1);
+        for k in 0..=steps {
+            let t = t0 + k as Real * step;
+            f(t, self.at(t));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn march_visits_evenly_spaced_points() {
+        let r = Ray::new(Point3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+        let mut samples = Vec::new();
+        r.march(0.0, 1.0, 0.25, |t, p| samples.push((t, p)));
+
+        assert_eq!(samples.len(), 5);
+        for (k, (t, p)) in samples.iter().enumerate() {
+            let expected = 0.25 * k as Real;
+            assert!((t - expected).abs() < 1e-6);
+            assert!((*p - r.at(expected)).near_zero());
+        }
+    }
+
+    #[test]
+    fn march_handles_reversed_range_and_bad_step() {
+        let r = Ray::new(Point3::default(), Vec3::new(1.0, 0.0, 0.0));
+
+        let mut count = 0;
+        r.march(1.0, 0.0, 0.25, |_, _| count += 1);
+        assert_eq!(count, 0);
+
+        let mut samples = Vec::new();
+        r.march(0.0, 1.0, 0.0, |t, _| samples.push(t));
+        assert_eq!(samples, vec![0.5]);
+    }
+
+    #[test]
+    fn march_skips_unbounded_ranges_and_caps_the_sample_count() {
+        let r = Ray::new(Point3::default(), Vec3::new(1.0, 0.0, 0.0));
+
+        let mut count = 0;
+        r.march(0.0, Real::INFINITY, 0.25, |_, _| count += 1);
+        r.march(-Real::INFINITY, 1.0, 0.25, |_, _| count += 1);
+        r.march(0.0, 1.0, Real::NAN, |_, _| count += 1);
+        assert_eq!(count, 0);
+
+        let mut last = 0.0;
+        r.march(0.0, 1e12, 1e-3, |t, _| {
+            count += 1;
+            last = t;
+        });
+        assert_eq!(count, MAX_MARCH_STEPS);
+        assert!(last < 1e12);
+    }
+
+    #[test]
+    fn at_clamped_stays_inside_interval() {
+        let r = Ray::new(Point3::default(), Vec3::new(1.0, 0.0, 0.0));
+        let ray_t = Interval::new(0.0, 2.0);
+        assert_eq!(r.at_clamped(5.0, &ray_t).x(), 2.0);
+        assert_eq!(r.at_clamped(-1.0, &ray_t).x(), 0.0);
+    }
 }
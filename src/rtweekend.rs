@@ -2,11 +2,52 @@
 //!
 //! 提供数学常量和常用函数
 
+use std::cell::Cell;
+
+use rand::RngCore;
+
+/// 渲染器使用的浮点数类型
+///
+/// 默认为`f64`；启用`f32` feature后为`f32`，可使大场景的几何数据内存减半，
+/// 适合预览渲染。单精度的代价是求交精度下降：远离原点或尺度很大的物体
+/// (如半径1000的地面球)会出现更明显的自相交噪点，因此相关容差
+/// (见`HIT_EPSILON`和`NEAR_ZERO_EPSILON`)在单精度下相应放大。
+#[cfg(not(feature = "f32"))]
+pub type Real = f64;
+/// 渲染器使用的浮点数类型(单精度模式)
+#[cfg(feature = "f32")]
+pub type Real = f32;
+
 /// 表示正无穷大的常量
-pub const INFINITY: f64 = std::f64::INFINITY;
+pub const INFINITY: Real = Real::INFINITY;
 
 /// 圆周率π的常量
-pub const PI: f64 = std::f64::consts::PI;
+#[cfg(not(feature = "f32"))]
+pub const PI: Real = std::f64::consts::PI;
+/// 圆周率π的常量
+#[cfg(feature = "f32")]
+pub const PI: Real = std::f32::consts::PI;
+
+/// 光线求交的最小参数t，用于避免自相交造成的"阴影痤疮"
+#[cfg(not(feature = "f32"))]
+pub const HIT_EPSILON: Real = 0.001;
+/// 光线求交的最小参数t(单精度模式)
+#[cfg(feature = "f32")]
+pub const HIT_EPSILON: Real = 0.01;
+
+/// 散射光线起点沿法线偏移量的相对系数，实际偏移为 系数 × max(1, 命中点坐标绝对值的最大分量)
+#[cfg(not(feature = "f32"))]
+pub const ORIGIN_OFFSET_EPSILON: Real = 1e-9;
+/// 散射光线起点沿法线偏移量的相对系数(单精度模式)
+#[cfg(feature = "f32")]
+pub const ORIGIN_OFFSET_EPSILON: Real = 1e-4;
+
+/// 判断向量接近零的分量阈值
+#[cfg(not(feature = "f32"))]
+pub const NEAR_ZERO_EPSILON: Real = 1e-8;
+/// 判断向量接近零的分量阈值(单精度模式)
+#[cfg(feature = "f32")]
+pub const NEAR_ZERO_EPSILON: Real = 1e-6;
 
 /// 角度转弧度
 /// 
@@ -15,7 +56,7 @@ pub const PI: f64 = std::f64::consts::PI;
 /// 
 /// # Returns
 /// 返回对应的弧度值
-pub fn degrees_to_radians(degrees: f64) -> f64 {
+pub fn degrees_to_radians(degrees: Real) -> Real {
   degrees * PI / 180.0
 }
 
@@ -23,9 +64,94 @@ pub fn degrees_to_radians(degrees: f64) -> f64 {
 /// 
 /// # Returns
 /// 返回0.0(包含)到1.0(不包含)之间的随机数
-pub fn random_double() -> f64 {
+pub fn random_double() -> Real {
    // Returns a random real in [0,1).
-   rand::random::<f64>()
+   random_double_with(&mut LocalRng)
+}
+
+/// 使用指定的随机数生成器生成[0,1)范围内的随机浮点数
+/// 
+/// # Arguments
+/// * `rng` - 随机数生成器
+/// 
+/// # Returns
+/// 返回0.0(包含)到1.0(不包含)之间的随机数
+pub fn random_double_with(rng: &mut impl RngCore) -> Real {
+   let bits = rng.next_u64() >> (64 - RANDOM_MANTISSA_BITS);
+   bits as Real / (1u64 << RANDOM_MANTISSA_BITS) as Real
+}
+
+/// 从当前线程的随机数状态取数的生成器
+/// 
+/// 实现了`RngCore`，可传给接受`&mut impl Rng`的函数；与`random_double`
+/// 共用同一状态，因此同样受`seed_random`控制
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalRng;
+
+impl RngCore for LocalRng {
+   fn next_u32(&mut self) -> u32 {
+      (next_random_u64() >> 32) as u32
+   }
+
+   fn next_u64(&mut self) -> u64 {
+      next_random_u64()
+   }
+
+   fn fill_bytes(&mut self, dst: &mut [u8]) {
+      rand::rand_core::impls::fill_bytes_via_next(self, dst)
+   }
+}
+
+/// 随机浮点数使用的有效位数，保证结果严格小于1
+#[cfg(not(feature = "f32"))]
+const RANDOM_MANTISSA_BITS: u32 = 53;
+/// 随机浮点数使用的有效位数(单精度模式)
+#[cfg(feature = "f32")]
+const RANDOM_MANTISSA_BITS: u32 = 24;
+
+thread_local! {
+   /// 每个线程独立的随机数生成器状态，初始值来自系统熵源
+   static RNG_STATE: Cell<u64> = Cell::new(rand::random());
+}
+
+/// 重新设置当前线程的随机数种子
+/// 
+/// 渲染器在每个采样开始前以`hash_seed`得到的种子重置状态，使结果与任务划分无关
+/// 
+/// # Arguments
+/// * `seed` - 随机数种子
+pub fn seed_random(seed: u64) {
+   RNG_STATE.with(|state| state.set(seed));
+}
+
+/// 把多个整数键混合为一个随机数种子
+///
+/// 每个键依次与当前值相加后经过SplitMix64的混合函数，
+/// 相同的键序列总是得到相同的种子，键的微小差别会产生完全不同的种子
+///
+/// # Arguments
+/// * `keys` - 参与混合的键，例如(像素列, 像素行, 采样序号, 全局种子)
+///
+/// # Returns
+/// 返回64位种子
+pub fn hash_seed(keys: &[u64]) -> u64 {
+   keys.iter().fold(0, |h, &k| mix64(h.wrapping_add(k).wrapping_add(0x9E37_79B9_7F4A_7C15)))
+}
+
+/// SplitMix64的输出混合函数
+fn mix64(mut z: u64) -> u64 {
+   z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+   z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+   z ^ (z >> 31)
+}
+
+/// 生成下一个64位随机数(SplitMix64)
+fn next_random_u64() -> u64 {
+   RNG_STATE.with(|state| {
+      let s = state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+      state.set(s);
+      mix64(s)
+   })
 }
 
 /// 生成指定范围内的随机浮点数
@@ -36,7 +162,7 @@ pub fn random_double() -> f64 {
 /// 
 /// # Returns
 /// 返回min(包含)到max(不包含)之间的随机数
-pub fn random_double_range(min: f64, max: f64) -> f64 {
+pub fn random_double_range(min: Real, max: Real) -> Real {
    // Returns a random real in [min,max).
    min + (max - min) * random_double()
 }
\ No newline at end of file
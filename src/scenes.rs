@@ -0,0 +1,205 @@
+//! 示例场景模块
+//!
+//! 提供书中经典场景的构建函数，每个函数返回配置好的相机和场景
+
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::background::Background;
+use super::camera::Camera;
+use super::color::Color;
+use super::hittable_list::HittableList;
+use super::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use super::quad::Quad;
+use super::rtweekend::{self, Real};
+use super::sphere::Sphere;
+use super::texture::{CheckerTexture, ImageTexture, WrapMode};
+use super::vec3::{Point3, Vec3};
+
+/// 《一周末光线追踪》封面场景：地面大球、随机小球和三个大球
+///
+/// 小球的位置和材质使用当前线程的随机数生成，需要可复现时先调用`rtweekend::seed_random`
+///
+/// # Returns
+/// 返回(相机, 场景)
+pub fn random_spheres() -> (Camera, HittableList) {
+    let mut world = HittableList::default();
+
+    let checker = CheckerTexture::from_colors(0.32, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
+    let ground_material: Arc<dyn Material + Send + Sync> = Arc::new(
+        Lambertian::from_texture(Arc::new(checker))
+    );
+    world.add(Arc::new(
+        Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material)
+    ));
+
+    for a in -8..8 {
+        for b in -8..8 {
+            let choose_mat = rtweekend::random_double();
+            let center = Point3::new(
+                a as Real + 0.9 * rtweekend::random_double(),
+                0.2,
+                b as Real + 0.9 * rtweekend::random_double(),
+            );
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                let sphere_material: Arc<dyn Material + Send + Sync> = if choose_mat < 0.8 {
+                    // diffuse
+                    let albedo = Color::random() * Color::random();
+                    Arc::new(Lambertian::new(albedo))
+                } else if choose_mat < 0.95 {
+                    // metal
+                    let albedo = Color::random_range(0.5, 1.0);
+                    let fuzz = rtweekend::random_double_range(0.0, 0.5);
+                    Arc::new(Metal::new(albedo, fuzz))
+                } else {
+                    // glass
+                    Arc::new(Dielectric::new(1.5))
+                };
+
+                world.add(Arc::new(
+                    Sphere::new(center, 0.2, sphere_material)
+                ));
+            }
+        }
+    }
+
+    // 三个大球
+    let material1: Arc<dyn Material + Send + Sync> = Arc::new(
+        Dielectric::new(1.5)
+    );
+    world.add(Arc::new(
+        Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, material1)
+    ));
+
+    let material2: Arc<dyn Material + Send + Sync> = Arc::new(
+        Lambertian::new(Color::new(0.4, 0.2, 0.1))
+    );
+    world.add(Arc::new(
+        Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, material2)
+    ));
+
+    let material3: Arc<dyn Material + Send + Sync> = Arc::new(
+        Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)
+    );
+    world.add(Arc::new(
+        Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, material3)
+    ));
+
+    let mut cam = Camera::default();
+    cam.aspect_ratio = 16.0 / 9.0;
+    cam.image_width = 1200;
+    cam.samples_per_pixel = 10;
+    cam.max_depth = 50;
+
+    cam.vfov = 20.0;
+    cam.lookfrom = Point3::new(13.0, 2.0, 3.0);
+    cam.lookat = Point3::new(0.0, 0.0, 0.0);
+    cam.vup = Vec3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.6;
+    cam.focus_dist = 10.0;
+
+    (cam, world)
+}
+
+/// Cornell盒场景：红绿侧墙、白色地面/天花板/后墙、顶部光源、一个漫反射球和一个玻璃球
+///
+/// 盒子内部为 x∈[-1,1], y∈[0,2], z∈[-1,1]，正面敞开。
+/// 光源是贴在天花板下方的方形面光源
+///
+/// # Returns
+/// 返回(相机, 场景)
+pub fn cornell_box() -> (Camera, HittableList) {
+    let mut world = HittableList::default();
+
+    let red: Arc<dyn Material + Send + Sync> = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white: Arc<dyn Material + Send + Sync> = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green: Arc<dyn Material + Send + Sync> = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light: Arc<dyn Material + Send + Sync> = Arc::new(DiffuseLight::new(Color::new(15.0, 15.0, 15.0)));
+    let glass: Arc<dyn Material + Send + Sync> = Arc::new(Dielectric::new(1.5));
+
+    // 墙面
+    world.add(Arc::new(Quad::new(Point3::new(-1.0, 0.0, 1.0), Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 0.0, -2.0), red)));
+    world.add(Arc::new(Quad::new(Point3::new(1.0, 0.0, 1.0), Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 0.0, -2.0), green)));
+    world.add(Arc::new(Quad::new(Point3::new(-1.0, 0.0, -1.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0), Arc::clone(&white))));
+    world.add(Arc::new(Quad::new(Point3::new(-1.0, 2.0, -1.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0), Arc::clone(&white))));
+    world.add(Arc::new(Quad::new(Point3::new(-1.0, 0.0, -1.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Arc::clone(&white))));
+
+    // 光源略低于天花板，避免与天花板重合
+    world.add(Arc::new(Quad::new(Point3::new(-0.25, 1.999, -0.25), Vec3::new(0.5, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.5), light)));
+
+    world.add(Arc::new(Sphere::new(Point3::new(-0.45, 0.4, -0.4), 0.4, white)));
+    world.add(Arc::new(Sphere::new(Point3::new(0.45, 0.4, 0.3), 0.4, glass)));
+
+    let mut cam = Camera::default();
+    cam.aspect_ratio = 1.0;
+    cam.image_width = 600;
+    cam.samples_per_pixel = 200;
+    cam.max_depth = 50;
+    cam.background = Background::Solid(Color::default());
+
+    cam.vfov = 40.0;
+    cam.lookfrom = Point3::new(0.0, 1.0, 3.4);
+    cam.lookat = Point3::new(0.0, 1.0, 0.0);
+    cam.vup = Vec3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.0;
+
+    (cam, world)
+}
+
+/// 地球场景：贴有等距柱状投影地图的单个球体
+///
+/// # Arguments
+/// * `texture_path` - 地图图像路径(PNG、JPEG或PPM)，宽高比通常为2:1
+///
+/// # Returns
+/// 返回(相机, 场景)，图像无法加载时返回错误
+pub fn earth(texture_path: impl AsRef<Path>) -> io::Result<(Camera, HittableList)> {
+    let mut world = HittableList::default();
+
+    // 经度方向首尾相接，平铺可避免在接缝处截断到边缘像素
+    let earth_texture = ImageTexture::load(texture_path)?.with_wrap(WrapMode::Repeat);
+    let earth_surface: Arc<dyn Material + Send + Sync> = Arc::new(Lambertian::from_texture(Arc::new(earth_texture)));
+    world.add(Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 2.0, earth_surface)));
+
+    let mut cam = Camera::default();
+    cam.aspect_ratio = 16.0 / 9.0;
+    cam.image_width = 400;
+    cam.samples_per_pixel = 100;
+    cam.max_depth = 50;
+
+    cam.vfov = 20.0;
+    cam.lookfrom = Point3::new(0.0, 0.0, 12.0);
+    cam.lookat = Point3::new(0.0, 0.0, 0.0);
+    cam.vup = Vec3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.0;
+
+    Ok((cam, world))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::interval::Interval;
+    use crate::ray::Ray;
+
+    #[test]
+    fn random_spheres_ground_is_hit_from_above() {
+        rtweekend::seed_random(833);
+        let (cam, world) = random_spheres();
+        assert_eq!(cam.lookfrom.e, [13.0, 2.0, 3.0]);
+
+        // 小球分布在[-8, 8]²内，从其外侧竖直向下只会碰到地面大球
+        let r = Ray::new(Point3::new(20.0, 5.0, 20.0), Vec3::new(0.0, -1.0, 0.0));
+        let mut rec = HitRecord::default();
+        assert!(world.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec));
+        assert!(rec.p.y().abs() < 1.0 && rec.p.y() < 0.0, "hit at {}", rec.p);
+        assert!(rec.front_face && rec.normal.y() > 0.99);
+        assert!(rec.mat.is_some());
+    }
+}
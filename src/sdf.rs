@@ -0,0 +1,223 @@
+//! 有向距离场模块
+//!
+//! 提供由有向距离函数(SDF)定义的隐式曲面，使用球体追踪(sphere tracing)求交
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::sphere;
+use super::rtweekend::Real;
+use std::sync::Arc;
+
+/// 单条光线最多的步进次数
+const MAX_STEPS: usize = 256;
+
+/// 由命中点位置计算纹理坐标(u, v)的函数
+type UvFn = Box<dyn Fn(Point3) -> (Real, Real) + Send + Sync>;
+
+/// 有向距离场几何形状
+///
+/// 距离函数在物体外部为正、内部为负，其绝对值不能超过到表面的真实距离，
+/// 否则步进可能越过表面
+///
+/// # Fields
+/// - distance: 有向距离函数
+/// - bbox: 包围盒，步进只在其内部进行
+/// - epsilon: 判定命中表面的距离阈值，同时作为数值求梯度的步长
+/// - uv: 可选的纹理坐标函数，未设置时按法线方向做球面映射
+/// - mat: 材质
+pub struct Sdf {
+    distance: Box<dyn Fn(Point3) -> Real + Send + Sync>,
+    bbox: Aabb,
+    epsilon: Real,
+    uv: Option<UvFn>,
+    mat: Arc<dyn Material + Send + Sync>,
+}
+
+impl Sdf {
+    /// 创建新的距离场物体
+    ///
+    /// # Arguments
+    /// * `distance` - 有向距离函数
+    /// * `bbox` - 包含整个表面的包围盒
+    /// * `material` - 材质
+    pub fn new(
+        distance: impl Fn(Point3) -> Real + Send + Sync + 'static,
+        bbox: Aabb,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self {
+            distance: Box::new(distance),
+            bbox,
+            epsilon: 1e-4,
+            uv: None,
+            mat: material,
+        }
+    }
+
+    /// 设置命中阈值(默认1e-4)，细节很小的场景需要减小
+    pub fn with_epsilon(mut self, epsilon: Real) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// 挖空为厚度为`thickness`的薄壳，外表面不变，内表面为向内偏移`thickness`的等距面
+    ///
+    /// 新距离为 max(d, -d - thickness)，对精确的距离函数结果仍是精确的
+    pub fn shell(mut self, thickness: Real) -> Self {
+        let distance = self.distance;
+        self.distance = Box::new(move |p| {
+            let d = distance(p);
+            d.max(-d - thickness)
+        });
+        self
+    }
+
+    /// 设置纹理坐标函数，由命中点位置计算(u, v)
+    ///
+    /// 可用于把分形的轨道陷阱等位置相关的量传给纹理着色
+    pub fn with_uv(mut self, uv: impl Fn(Point3) -> (Real, Real) + Send + Sync + 'static) -> Self {
+        self.uv = Some(Box::new(uv));
+        self
+    }
+
+    /// 用中心差分估计距离场梯度，即表面的外法线方向
+    fn gradient(&self, p: Point3) -> Vec3 {
+        let h = self.epsilon;
+        let d = |offset: Vec3| (self.distance)(p + offset) - (self.distance)(p - offset);
+        Vec3::new(
+            d(Vec3::new(h, 0.0, 0.0)),
+            d(Vec3::new(0.0, h, 0.0)),
+            d(Vec3::new(0.0, 0.0, h)),
+        )
+    }
+}
+
+impl Hittable for Sdf {
+    /// 实现距离场的光线命中检测
+    ///
+    /// 从光线进入包围盒处开始，每次沿光线前进当前点到表面的距离，
+    /// 直到距离小于阈值(命中)或离开包围盒。起点在物体内部时(如折射光线)
+    /// 按距离的绝对值步进，找到的是离开物体的表面。
+    /// 法线由距离场的数值梯度得到，纹理坐标由纹理坐标函数计算，未设置时按法线方向做球面映射
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let Some(span) = self.bbox.hit_interval(r, ray_t) else {
+            return false;
+        };
+        let dir_len = r.direction().length();
+
+        let mut t = span.min;
+        let mut side = 1.0;
+        let mut found = false;
+        for step in 0..MAX_STEPS {
+            let d = (self.distance)(r.at(t));
+            if step == 0 && d < 0.0 {
+                side = -1.0;
+            }
+
+            // 至少走一步，避免在刚离开的表面上立即判定命中
+            let dist = side * d;
+            if dist < self.epsilon && step > 0 {
+                found = true;
+                break;
+            }
+
+            t += dist.max(self.epsilon) / dir_len;
+            if t >= span.max {
+                return false;
+            }
+        }
+        if !found || !ray_t.surrounds(t) {
+            return false;
+        }
+
+        hit_record.t = t;
+        hit_record.p = r.at(t);
+
+        let outward_normal = vec3::unit_vector(self.gradient(hit_record.p));
+        (hit_record.u, hit_record.v) = match &self.uv {
+            Some(uv) => uv(hit_record.p),
+            None => sphere::get_sphere_uv(outward_normal),
+        };
+        hit_record.tangent = sphere::sphere_tangent(outward_normal);
+        hit_record.set_face_normal(r, outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+    use crate::rtweekend;
+
+    /// 以原点为中心的单位球的距离场，包围盒留有余量
+    fn unit_sphere() -> Sdf {
+        Sdf::new(
+            |p: Point3| p.length() - 1.0,
+            Aabb::from_points(Point3::new(-1.5, -1.5, -1.5), Point3::new(1.5, 1.5, 1.5)),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    /// 命中时检查法线为单位长度且朝向光线来的一侧
+    fn cast(sdf: &Sdf, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        let r = Ray::new(origin, direction);
+        if !sdf.hit(&r, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return None;
+        }
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!(vec3::dot(rec.normal, direction) < 0.0);
+        Some(rec)
+    }
+
+    #[test]
+    fn sphere_field_is_hit_where_the_analytic_sphere_is() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let rec = cast(&unit_sphere(), Point3::new(-5.0, 0.0, 0.0), x).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-3);
+        assert!(rec.front_face);
+        assert!((rec.normal + x).length() < 1e-3);
+
+        // 方向不是单位向量时t按光线参数计算
+        let rec = cast(&unit_sphere(), Point3::new(-5.0, 0.6, 0.0), 2.0 * x).unwrap();
+        assert!((rec.t - (5.0 - 0.8) / 2.0).abs() < 1e-3);
+        assert!((rec.normal - Vec3::new(-0.8, 0.6, 0.0)).length() < 1e-3);
+    }
+
+    #[test]
+    fn rays_inside_the_box_but_beside_the_sphere_miss() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        assert!(cast(&unit_sphere(), Point3::new(-5.0, 1.05, 0.0), x).is_none());
+        assert!(cast(&unit_sphere(), Point3::new(-5.0, 2.0, 0.0), x).is_none());
+        // 背离球面
+        assert!(cast(&unit_sphere(), Point3::new(-5.0, 0.0, 0.0), -x).is_none());
+    }
+
+    #[test]
+    fn ray_from_inside_finds_the_exit() {
+        let rec = cast(&unit_sphere(), Point3::new(0.2, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!((rec.t - (0.96 as Real).sqrt()).abs() < 1e-3);
+        assert!(!rec.front_face);
+
+        // 薄壳：空腔中的光线先命中内表面，内表面从空腔一侧看是正面
+        let shell = unit_sphere().shell(0.25);
+        let rec = cast(&shell, Point3::default(), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 0.75).abs() < 1e-3);
+        assert!(rec.front_face);
+        let rec = cast(&shell, Point3::new(0.9, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 0.1).abs() < 1e-3);
+        assert!(!rec.front_face);
+    }
+}
@@ -5,8 +5,8 @@
 use super::vec3::{
   self,
   Point3,
+  Vec3,
 };
-use std::rc::Rc;
 use super::ray::Ray;
 use super::material::Material;
 use super::hittable::{
@@ -14,18 +14,28 @@ use super::hittable::{
   Hittable,
 };
 use super::interval::Interval;
+use super::onb::Onb;
+use super::aabb::Aabb;
+use super::rtweekend::{self, Real};
 use std::sync::Arc;
 
 /// 球体几何形状
 /// 
 /// # Fields
 /// - center: 球心位置
-/// - radius: 球体半径
+/// - radius: 球体半径(允许为负)
 /// - mat: 球体材质
+/// - bbox: 包围盒(按半径绝对值计算)
+///
+/// # Note
+/// 负半径用于"空心玻璃球"技巧：求交只依赖半径的平方，结果与正半径相同，
+/// 但外法线`(p - center) / radius`会指向球心，使嵌套在玻璃球中的负半径球
+/// 表现为内部气泡表面。
 pub struct Sphere {
   center: Point3,
-  radius: f64,
-  mat: Arc<dyn Material + Send + Sync>
+  radius: Real,
+  mat: Arc<dyn Material + Send + Sync>,
+  bbox: Aabb,
 }
 
 impl Sphere {
@@ -33,13 +43,15 @@ impl Sphere {
   /// 
   /// # Arguments
   /// * `center` - 球心位置
-  /// * `radius` - 球体半径
+  /// * `radius` - 球体半径，负值表示法线朝内
   /// * `material` - 球体材质
-  pub fn new(center: Point3, radius: f64, material: Arc<dyn Material + Send + Sync>) -> Self {
+  pub fn new(center: Point3, radius: Real, material: Arc<dyn Material + Send + Sync>) -> Self {
+    let rvec = Vec3::new(radius.abs(), radius.abs(), radius.abs());
     Self {
       center,
       radius,
       mat: material,
+      bbox: Aabb::from_points(center - rvec, center + rvec),
     }
   }
 }
@@ -87,13 +99,203 @@ impl Hittable for Sphere {
         hit_record.p = r.at(root);  // 计算命中点坐标
         
         // 计算单位法向量（从球心指向命中点）
+        // 半径为负时法线指向球心，用于空心玻璃球的内表面
         let outward_normal = (hit_record.p - self.center) / self.radius;
         // 设置法线方向（根据光线入射方向确定正面/背面）
         hit_record.set_face_normal(r, outward_normal);
+        // 纹理坐标和切向量按几何位置计算，不随半径符号翻转
+        let unit_p = (hit_record.p - self.center) / self.radius.abs();
+        (hit_record.u, hit_record.v) = get_sphere_uv(unit_p);
+        hit_record.tangent = sphere_tangent(unit_p);
         
         // 复制材质引用（使用Rc共享所有权）
         hit_record.mat = Some(Arc::clone(&self.mat));
 
         true  // 命中成功
     }
-}
\ No newline at end of file
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    /// 球体所张立体角内均匀采样的概率密度
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> Real {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(origin, direction), &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec) {
+            return 0.0;
+        }
+
+        // 起点在球内时各方向都会命中球面，按均匀球面分布计算
+        let dist_squared = (self.center - origin).squared_length();
+        if dist_squared <= self.radius * self.radius {
+            return 1.0 / (4.0 * rtweekend::PI);
+        }
+        let cos_theta_max = (1.0 - self.radius * self.radius / dist_squared).sqrt();
+        let solid_angle = 2.0 * rtweekend::PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    /// 在球体所张的圆锥内随机采样方向
+    fn random(&self, origin: Point3) -> Vec3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.squared_length();
+        if distance_squared <= self.radius * self.radius {
+            return vec3::random_unit_vector();
+        }
+        let uvw = Onb::new(direction);
+        uvw.transform(random_to_sphere(self.radius.abs(), distance_squared))
+    }
+}
+
+/// 计算单位球面上点的纹理坐标
+///
+/// # Arguments
+/// * `p` - 单位球面上的点(以球心为原点)
+///
+/// # Returns
+/// 返回(u, v)：u为绕y轴的角度(从x=-1起)映射到[0,1]，v为从y=-1到y=+1的角度映射到[0,1]
+pub fn get_sphere_uv(p: Point3) -> (Real, Real) {
+    let theta = (-p.y()).acos();
+    let phi = (-p.z()).atan2(p.x()) + rtweekend::PI;
+
+    (phi / (2.0 * rtweekend::PI), theta / rtweekend::PI)
+}
+
+/// 计算单位球面上点沿u增大方向的单位切向量，两极处返回零向量
+pub fn sphere_tangent(p: Point3) -> Vec3 {
+    let t = Vec3::new(p.z(), 0.0, -p.x());
+    if t.near_zero() { Vec3::default() } else { vec3::unit_vector(t) }
+}
+
+/// 在局部坐标系(w轴指向球心)中生成指向球体的随机方向
+///
+/// # Arguments
+/// * `radius` - 球体半径
+/// * `distance_squared` - 采样点到球心距离的平方
+fn random_to_sphere(radius: Real, distance_squared: Real) -> Vec3 {
+    let r1 = rtweekend::random_double();
+    let r2 = rtweekend::random_double();
+    let z = 1.0 + r2 * ((1.0 - radius * radius / distance_squared).sqrt() - 1.0);
+
+    let phi = 2.0 * rtweekend::PI * r1;
+    let x = phi.cos() * (1.0 - z * z).sqrt();
+    let y = phi.sin() * (1.0 - z * z).sqrt();
+
+    Vec3::new(x, y, z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable_list::HittableList;
+    use crate::material::Dielectric;
+
+    /// 命中点处的几何外法线(未按光线方向翻转)
+    fn geometric_normal(rec: &HitRecord) -> Vec3 {
+        if rec.front_face { rec.normal } else { -rec.normal }
+    }
+
+    #[test]
+    fn negative_radius_sphere_has_inward_normals() {
+        let glass: Arc<dyn Material + Send + Sync> = Arc::new(Dielectric::new(1.5));
+        let center = Point3::new(0.0, 0.0, 0.0);
+        let outer = Sphere::new(center, 0.5, Arc::clone(&glass));
+        let inner = Sphere::new(center, -0.4, glass);
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+
+        for dir in [Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.3, -0.2, 1.0), Vec3::new(-1.0, 0.5, 0.2)] {
+            let r = Ray::new(center - 2.0 * vec3::unit_vector(dir), dir);
+
+            let mut rec = HitRecord::default();
+            assert!(outer.hit(&r, &ray_t, &mut rec));
+            assert!(vec3::dot(geometric_normal(&rec), rec.p - center) > 0.0);
+
+            let mut rec = HitRecord::default();
+            assert!(inner.hit(&r, &ray_t, &mut rec));
+            assert!(vec3::dot(geometric_normal(&rec), rec.p - center) < 0.0);
+            assert!(((rec.p - center).length() - 0.4).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn hollow_glass_shell_reports_bubble_as_back_face() {
+        let glass: Arc<dyn Material + Send + Sync> = Arc::new(Dielectric::new(1.5));
+        let mut world = HittableList::default();
+        world.add(Arc::new(Sphere::new(Point3::default(), 0.5, Arc::clone(&glass))));
+        world.add(Arc::new(Sphere::new(Point3::default(), -0.4, glass)));
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+
+        // 从外部射入：先命中外球正面
+        let mut rec = HitRecord::default();
+        assert!(world.hit(&Ray::new(Point3::new(0.0, 0.0, -2.0), Vec3::new(0.0, 0.0, 1.0)), &ray_t, &mut rec));
+        assert!(rec.front_face);
+        assert!((rec.t - 1.5).abs() < 1e-6);
+
+        // 在玻璃壳内前进：命中气泡表面时视为离开玻璃(背面)，法线朝向光线来的一侧
+        let mut rec = HitRecord::default();
+        assert!(world.hit(&Ray::new(Point3::new(0.0, 0.0, -0.45), Vec3::new(0.0, 0.0, 1.0)), &ray_t, &mut rec));
+        assert!(!rec.front_face);
+        assert!((rec.p.z() + 0.4).abs() < 1e-6);
+        assert!(rec.normal.z() < 0.0);
+    }
+
+    #[test]
+    fn negative_radius_keeps_texture_coordinates() {
+        let glass: Arc<dyn Material + Send + Sync> = Arc::new(Dielectric::new(1.5));
+        let positive = Sphere::new(Point3::new(1.0, 2.0, 3.0), 0.4, Arc::clone(&glass));
+        let negative = Sphere::new(Point3::new(1.0, 2.0, 3.0), -0.4, glass);
+        let r = Ray::new(Point3::new(-1.0, 2.3, 2.5), Vec3::new(2.0, -0.3, 0.5));
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+
+        let (mut a, mut b) = (HitRecord::default(), HitRecord::default());
+        assert!(positive.hit(&r, &ray_t, &mut a));
+        assert!(negative.hit(&r, &ray_t, &mut b));
+        assert!((a.u - b.u).abs() < 1e-6 && (a.v - b.v).abs() < 1e-6);
+        assert!((a.tangent - b.tangent).near_zero());
+    }
+
+    #[test]
+    fn solid_angle_pdf_integrates_to_one() {
+        rtweekend::seed_random(811);
+        let light = Sphere::new(Point3::new(0.0, 3.0, 0.0), 1.0, Arc::new(Dielectric::new(1.5)));
+        let origin = Point3::new(0.5, 0.0, -0.5);
+
+        // 在整个球面上均匀采样方向，∫pdf dω ≈ 4π · mean(pdf)
+        let n = 200_000;
+        let sum: Real = (0..n).map(|_| light.pdf_value(origin, vec3::random_unit_vector())).sum();
+        let integral = 4.0 * rtweekend::PI * sum / n as Real;
+        assert!((integral - 1.0).abs() < 0.03, "integral = {}", integral);
+    }
+
+    #[test]
+    fn sampled_directions_hit_the_sphere() {
+        rtweekend::seed_random(811);
+        let light = Sphere::new(Point3::new(0.0, 3.0, 0.0), 1.0, Arc::new(Dielectric::new(1.5)));
+        let origin = Point3::new(0.5, 0.0, -0.5);
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+
+        for _ in 0..1000 {
+            let direction = light.random(origin);
+            assert!(light.hit(&Ray::new(origin, direction), &ray_t, &mut HitRecord::default()));
+            assert!(light.pdf_value(origin, direction) > 0.0);
+        }
+    }
+
+    #[test]
+    fn origin_inside_the_sphere_samples_and_weights_uniformly() {
+        rtweekend::seed_random(811);
+        let light = Sphere::new(Point3::new(0.0, 3.0, 0.0), 1.0, Arc::new(Dielectric::new(1.5)));
+        let ray_t = Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY);
+
+        for origin in [Point3::new(0.0, 3.0, 0.0), Point3::new(0.2, 3.5, -0.3)] {
+            for _ in 0..1000 {
+                let direction = light.random(origin);
+                assert!((direction.length() - 1.0).abs() < 1e-4, "direction = {:?}", direction);
+                assert!(light.hit(&Ray::new(origin, direction), &ray_t, &mut HitRecord::default()));
+                let pdf = light.pdf_value(origin, direction);
+                assert!((pdf - 1.0 / (4.0 * rtweekend::PI)).abs() < 1e-6, "pdf = {}", pdf);
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! 渲染统计模块
+//!
+//! 提供RenderStats结构体，以及渲染过程中使用的线程局部计数器。
+//! 计数器只在当前线程内累加，渲染器在每个像素结束时取出并随像素结果一起汇总，
+//! 因此多线程渲染时不需要加锁，也不会在热路径上争用原子变量
+
+use std::cell::Cell;
+use std::ops::AddAssign;
+
+use super::rtweekend::Real;
+
+/// 一次渲染的统计信息
+///
+/// # Fields
+/// - noise: 均方根噪声估计(所有像素均值标准误差的平均)
+/// - primary_rays: 从相机发出的主光线数量
+/// - rays_cast: 实际追踪的光线总数(主光线加所有散射光线)
+/// - background_hits: 未命中任何物体、取背景颜色的光线数量
+/// - intersection_tests: 物体求交测试次数(包围盒剔除后实际调用`hit`的次数)
+/// - nodes_visited: 加速结构(BVH、kd树)遍历时访问的节点数
+/// - discarded_samples: 含NaN或无穷大分量、被置零的采样数量
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    pub noise: Real,
+    pub primary_rays: u64,
+    pub rays_cast: u64,
+    pub background_hits: u64,
+    pub intersection_tests: u64,
+    pub nodes_visited: u64,
+    pub discarded_samples: u64,
+}
+
+impl RenderStats {
+    /// 平均路径长度，即每条主光线平均产生的光线段数
+    ///
+    /// # Returns
+    /// 返回 rays_cast / primary_rays，没有主光线时返回0
+    pub fn average_path_length(&self) -> Real {
+        if self.primary_rays == 0 {
+            0.0
+        } else {
+            self.rays_cast as Real / self.primary_rays as Real
+        }
+    }
+
+    /// 每条光线平均访问的加速结构节点数
+    ///
+    /// # Returns
+    /// 返回 nodes_visited / rays_cast，没有光线时返回0
+    pub fn nodes_per_ray(&self) -> Real {
+        if self.rays_cast == 0 {
+            0.0
+        } else {
+            self.nodes_visited as Real / self.rays_cast as Real
+        }
+    }
+}
+
+impl AddAssign for RenderStats {
+    /// 累加计数，噪声字段直接相加(由调用方负责求平均)
+    fn add_assign(&mut self, other: Self) {
+        self.noise += other.noise;
+        self.primary_rays += other.primary_rays;
+        self.rays_cast += other.rays_cast;
+        self.background_hits += other.background_hits;
+        self.intersection_tests += other.intersection_tests;
+        self.nodes_visited += other.nodes_visited;
+        self.discarded_samples += other.discarded_samples;
+    }
+}
+
+thread_local! {
+    /// 当前线程自上次`take`以来的计数
+    static COUNTERS: Cell<RenderStats> = Cell::new(RenderStats::default());
+}
+
+/// 修改当前线程的计数
+fn update(f: impl FnOnce(&mut RenderStats)) {
+    COUNTERS.with(|counters| {
+        let mut stats = counters.get();
+        f(&mut stats);
+        counters.set(stats);
+    });
+}
+
+/// 记录一条主光线
+pub fn count_primary_ray() {
+    update(|s| s.primary_rays += 1);
+}
+
+/// 记录一条被追踪的光线
+pub fn count_ray() {
+    update(|s| s.rays_cast += 1);
+}
+
+/// 记录一条命中背景的光线
+pub fn count_background_hit() {
+    update(|s| s.background_hits += 1);
+}
+
+/// 记录一次物体求交测试
+pub fn count_intersection_test() {
+    update(|s| s.intersection_tests += 1);
+}
+
+/// 记录一次加速结构节点访问
+pub fn count_node_visit() {
+    update(|s| s.nodes_visited += 1);
+}
+
+/// 记录一个因NaN或无穷大被置零的采样
+pub fn count_discarded_sample() {
+    update(|s| s.discarded_samples += 1);
+}
+
+/// 取出当前线程的计数并清零
+///
+/// # Returns
+/// 返回自上次调用以来累积的计数，noise字段为0
+pub fn take() -> RenderStats {
+    COUNTERS.with(|counters| counters.take())
+}
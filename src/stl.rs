@@ -0,0 +1,230 @@
+//! STL模型加载模块
+//!
+//! 提供STL格式(ASCII和二进制)三角网格的加载函数，CAD和3D打印模型大多以此格式保存
+
+use super::vec3::{self, Point3, Vec3};
+use super::material::Material;
+use super::mesh::{MeshFace, TriangleMesh};
+use super::rtweekend::Real;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 从STL文件加载三角网格
+///
+/// 文件长度恰好等于 84 + 50 × 三角形数 时按二进制格式解析，否则按ASCII格式解析。
+/// STL的每个三角形各自保存三个顶点，加载时把坐标完全相同的顶点合并为一个，
+/// 使网格具有共享顶点的拓扑(可用于细分等操作)。
+/// 文件中保存的面法线经常为零或与顶点顺序不符，因此被忽略，
+/// 改为按顶点逆时针顺序重新计算每个面的法线
+///
+/// # Arguments
+/// * `path` - STL文件路径
+/// * `material` - 整个网格使用的材质
+///
+/// # Returns
+/// 返回加载的网格，文件无法读取或格式错误时返回错误
+pub fn load_stl(path: impl AsRef<Path>, material: Arc<dyn Material + Send + Sync>) -> io::Result<TriangleMesh> {
+    let triangles = parse_stl(&fs::read(path)?)?;
+    Ok(build_mesh(&triangles, material))
+}
+
+/// 按文件内容选择二进制或ASCII格式解析出所有三角形
+fn parse_stl(data: &[u8]) -> io::Result<Vec<[Point3; 3]>> {
+    if is_binary(data) { Ok(parse_binary(data)) } else { parse_ascii(data) }
+}
+
+/// 根据文件头声明的三角形数和文件长度判断是否为二进制格式
+///
+/// 不能只看文件开头是否为"solid"，很多二进制文件的头部也以它开头
+fn is_binary(data: &[u8]) -> bool {
+    if data.len() < 84 {
+        return false;
+    }
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+    data.len() == 84 + 50 * count
+}
+
+/// 解析二进制STL：80字节文件头、三角形数，每个三角形为法线、三个顶点(均为3个f32)和2字节属性
+fn parse_binary(data: &[u8]) -> Vec<[Point3; 3]> {
+    let f32_at = |at: usize| f32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]) as Real;
+    let count = (data.len() - 84) / 50;
+    (0..count)
+        .map(|i| {
+            let base = 84 + 50 * i;
+            // 跳过开头的12字节法线
+            std::array::from_fn(|k| {
+                let at = base + 12 + 12 * k;
+                Point3::new(f32_at(at), f32_at(at + 4), f32_at(at + 8))
+            })
+        })
+        .collect()
+}
+
+/// 解析ASCII STL
+///
+/// 每个facet的outer loop中可以有多于三个顶点，此时按扇形拆分为三角形
+fn parse_ascii(data: &[u8]) -> io::Result<Vec<[Point3; 3]>> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("stl: {}", msg));
+    let text = std::str::from_utf8(data).map_err(|_| invalid("file is neither binary nor ASCII"))?;
+    let mut tokens = text.split_whitespace();
+    if tokens.next() != Some("solid") {
+        return Err(invalid("missing solid header"));
+    }
+
+    let mut triangles = Vec::new();
+    let mut polygon: Vec<Point3> = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            "vertex" => {
+                let mut coord = || -> io::Result<Real> {
+                    tokens
+                        .next()
+                        .and_then(|t| t.parse::<Real>().ok())
+                        .ok_or_else(|| invalid("invalid vertex coordinate"))
+                };
+                polygon.push(Point3::new(coord()?, coord()?, coord()?));
+            }
+            "endloop" => {
+                if polygon.len() < 3 {
+                    return Err(invalid("facet has fewer than 3 vertices"));
+                }
+                for i in 1..polygon.len() - 1 {
+                    triangles.push([polygon[0], polygon[i], polygon[i + 1]]);
+                }
+                polygon.clear();
+            }
+            _ => {}
+        }
+    }
+    Ok(triangles)
+}
+
+/// 合并重复顶点并计算面法线，构建网格
+fn build_mesh(triangles: &[[Point3; 3]], material: Arc<dyn Material + Send + Sync>) -> TriangleMesh {
+    let (positions, normals, faces) = weld(triangles);
+    TriangleMesh::new(positions, normals, Vec::new(), faces, material)
+}
+
+/// 合并坐标完全相同的顶点，并丢弃退化三角形
+///
+/// # Returns
+/// 返回(顶点位置, 每个面一条的单位法线, 三角面)
+fn weld(triangles: &[[Point3; 3]]) -> (Vec<Point3>, Vec<Vec3>, Vec<MeshFace>) {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut faces = Vec::with_capacity(triangles.len());
+    let mut lookup = HashMap::new();
+
+    for tri in triangles {
+        let normal = vec3::cross(tri[1] - tri[0], tri[2] - tri[0]);
+        // 退化三角形没有确定的法线，也不可能被光线命中
+        if normal.squared_length() == 0.0 {
+            continue;
+        }
+
+        let indices = tri.map(|p| {
+            // 以坐标的位模式作为键；+0.0和-0.0视为同一坐标
+            let key = p.to_array().map(|x| (x + 0.0).to_bits());
+            *lookup.entry(key).or_insert_with(|| {
+                positions.push(p);
+                positions.len() - 1
+            })
+        });
+
+        normals.push(vec3::unit_vector(normal));
+        let n = normals.len() - 1;
+        faces.push(MeshFace {
+            positions: indices,
+            normals: Some([n, n, n]),
+            uvs: None,
+        });
+    }
+
+    (positions, normals, faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 二进制STL，文件头以"solid"开头
+    fn binary(triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        let mut data = b"solid exported by a CAD tool".to_vec();
+        data.resize(80, b' ');
+        data.extend((triangles.len() as u32).to_le_bytes());
+        for tri in triangles {
+            data.extend([0u8; 12]);
+            for value in tri.iter().flatten() {
+                data.extend(value.to_le_bytes());
+            }
+            data.extend([0u8; 2]);
+        }
+        data
+    }
+
+    const ASCII: &str = "solid square
+  facet normal 0 0 0
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 0.5 0.5 0
+      vertex 1 1 0
+    endloop
+  endfacet
+endsolid square
+";
+
+    #[test]
+    fn format_is_chosen_by_length_not_by_the_solid_prefix() {
+        let data = binary(&[[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]]);
+        assert!(data.starts_with(b"solid"));
+        assert!(is_binary(&data));
+        let triangles = parse_stl(&data).unwrap();
+        assert_eq!(triangles.len(), 1);
+        assert_eq!(triangles[0][1].to_array(), [1.0, 0.0, 0.0]);
+
+        // 长度与声明的三角形数不符时按ASCII解析，二进制内容不是合法的ASCII STL
+        assert!(parse_stl(&data[..data.len() - 1]).is_err());
+
+        assert!(!is_binary(ASCII.as_bytes()));
+        let triangles = parse_stl(ASCII.as_bytes()).unwrap();
+        // 四边形拆成两个三角形，再加上一个退化三角形
+        assert_eq!(triangles.len(), 3);
+        assert!(parse_stl(b"solid bad\nouter loop\nvertex 0 0\nendloop\n").is_err());
+        assert!(parse_stl(b"facet normal 0 0 1\n").is_err());
+    }
+
+    #[test]
+    fn shared_vertices_are_welded_and_degenerate_triangles_dropped() {
+        let triangles = parse_stl(ASCII.as_bytes()).unwrap();
+        let (positions, normals, faces) = weld(&triangles);
+        assert_eq!(faces.len(), 2);
+        assert_eq!(positions.len(), 4);
+        for (face, normal) in faces.iter().zip(&normals) {
+            assert!(face.normals.is_some_and(|[a, b, c]| a == b && b == c));
+            assert!((*normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-6);
+        }
+
+        // +0.0与-0.0视为同一坐标
+        let p = Point3::new(0.0, 0.0, 0.0);
+        let q = Point3::new(-0.0, 0.0, -0.0);
+        let (positions, _, faces) = weld(&[
+            [p, Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0)],
+            [q, Point3::new(0.0, 1.0, 0.0), Point3::new(-1.0, 0.0, 1e-6)],
+        ]);
+        assert_eq!(faces.len(), 2);
+        assert_eq!(faces[0].positions[0], faces[1].positions[0]);
+        assert_eq!(faces[0].positions[2], faces[1].positions[1]);
+        assert_eq!(positions.len(), 4);
+    }
+}
@@ -0,0 +1,299 @@
+//! 次表面散射模块
+//!
+//! 以随机游走模拟光线进入半透明物体(皮肤、蜡、大理石)内部后多次散射再射出的过程，
+//! 物体内部视为各通道平均自由程不同的均匀参与介质
+
+use super::vec3;
+use super::ray::Ray;
+use super::color::Color;
+use super::material::{Dielectric, Material, ScatterRecord};
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 一次随机游走最多的散射次数，超过后视为光线被吸收
+const MAX_STEPS: usize = 4096;
+
+/// 次表面散射物体
+///
+/// 从外部射来的光线在边界上交给界面材质处理(默认为光滑电介质，按菲涅尔反射或折射进入内部)。
+/// 起点在内部的光线命中边界背面时，命中记录与普通物体一样停在边界上，材质为内部游走材质；
+/// 随机游走在该材质散射时才进行：反复抽取自由程，在边界内则各向同性散射，否则到达边界，
+/// 由界面材质在射出点折射出去或反射回内部。命中检测因此是确定的，阴影光线也不会触发游走
+///
+/// 每一步按当前各通道的吞吐量选取一个通道抽取自由程，各通道的权重除以三个通道的混合抽样概率，
+/// 因此平均自由程随通道不同时也是无偏的，且权重不会随步数发散
+///
+/// # Fields
+/// - surface: 边界上的界面材质
+/// - interior: 边界背面的内部游走材质，构造时创建，各次命中共享
+pub struct Subsurface {
+    surface: Arc<dyn Material + Send + Sync>,
+    interior: Arc<RandomWalk>,
+}
+
+impl Subsurface {
+    /// 创建次表面散射物体，界面为给定折射率的光滑电介质
+    ///
+    /// # Arguments
+    /// * `boundary` - 物体边界，必须是封闭物体，内部不能有其它物体
+    /// * `albedo` - 单次散射反照率，决定物体的颜色，越接近1越通透
+    /// * `mean_free_path` - 各通道的平均自由程(世界单位)，红色通道较长时光线在内部渗透更远，如皮肤
+    /// * `ior` - 界面折射率
+    pub fn new(boundary: Arc<dyn Hittable>, albedo: Color, mean_free_path: Color, ior: Real) -> Self {
+        let inverse = |d: Real| if d > 0.0 { 1.0 / d } else { 0.0 };
+        let surface: Arc<dyn Material + Send + Sync> = Arc::new(Dielectric::new(ior));
+        Self {
+            surface: Arc::clone(&surface),
+            interior: Arc::new(RandomWalk {
+                boundary,
+                albedo,
+                sigma_t: Color::new(inverse(mean_free_path.x()), inverse(mean_free_path.y()), inverse(mean_free_path.z())),
+                surface,
+            }),
+        }
+    }
+
+    /// 替换界面材质，如用透射度为1的原则化材质得到粗糙界面
+    ///
+    /// # Note
+    /// 界面材质应能把光线折射进出物体，否则光线无法进入内部
+    pub fn with_surface(mut self, surface: Arc<dyn Material + Send + Sync>) -> Self {
+        let interior = &self.interior;
+        self.interior = Arc::new(RandomWalk {
+            boundary: Arc::clone(&interior.boundary),
+            albedo: interior.albedo,
+            sigma_t: interior.sigma_t,
+            surface: Arc::clone(&surface),
+        });
+        self.surface = surface;
+        self
+    }
+}
+
+impl Hittable for Subsurface {
+    /// 实现次表面散射物体的光线命中检测
+    ///
+    /// 返回边界本身的命中：正面(从外部射来)使用界面材质，背面(在内部传播)使用内部游走材质
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        if !self.interior.boundary.hit(r, ray_t, hit_record) {
+            return false;
+        }
+        hit_record.mat = Some(if hit_record.front_face {
+            Arc::clone(&self.surface)
+        } else {
+            Arc::clone(&self.interior) as Arc<dyn Material + Send + Sync>
+        });
+        true
+    }
+
+    fn hit_any(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.interior.boundary.hit_any(r, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.interior.boundary.bounding_box()
+    }
+}
+
+/// 次表面散射物体内部的随机游走材质
+///
+/// 散射时从命中点沿入射方向继续在内部随机游走，以游走的最后一段光线作为入射光线
+/// 交给射出点的界面材质，并把衰减乘以游走累积的吞吐量。散射光线的起点是射出点而不是命中点
+///
+/// # Fields
+/// - boundary: 物体边界
+/// - albedo: 各通道的单次散射反照率
+/// - sigma_t: 各通道的消光系数，即平均自由程的倒数
+/// - surface: 边界上的界面材质
+struct RandomWalk {
+    boundary: Arc<dyn Hittable>,
+    albedo: Color,
+    sigma_t: Color,
+    surface: Arc<dyn Material + Send + Sync>,
+}
+
+impl RandomWalk {
+    /// 各通道沿距离d的透射率 exp(-σ_j d)
+    fn transmittance(&self, d: Real) -> Color {
+        Color::new((-self.sigma_t.x() * d).exp(), (-self.sigma_t.y() * d).exp(), (-self.sigma_t.z() * d).exp())
+    }
+
+    /// 从内部出发随机游走直到射出边界
+    ///
+    /// # Arguments
+    /// * `r` - 起点在内部的光线
+    /// * `ray_t` - 光线参数有效范围
+    ///
+    /// # Returns
+    /// 返回(射出点的边界命中记录, 射出前最后一段光线, 累积吞吐量)；
+    /// 超过最大步数或因数值误差找不到边界时返回None，视为光线被吸收
+    fn walk(&self, r: &Ray, ray_t: &Interval) -> Option<(HitRecord, Ray, Color)> {
+        let mut ray = *r;
+        let mut interval = *ray_t;
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        for _ in 0..MAX_STEPS {
+            let mut boundary = HitRecord::default();
+            if !self.boundary.hit(&ray, &interval, &mut boundary) {
+                return None;
+            }
+
+            // 按吞吐量选择通道，吞吐量全为0时游走已无贡献
+            let total = throughput.x() + throughput.y() + throughput.z();
+            if total <= 0.0 {
+                return None;
+            }
+            let probability = throughput / total;
+            let x = rtweekend::random_double();
+            let channel = if x < probability.x() { 0 } else if x < probability.x() + probability.y() { 1 } else { 2 };
+            let sigma = self.sigma_t[channel];
+            // 1 - random_double() 落在(0, 1]内，避免对0取对数
+            let distance = if sigma > 0.0 { -(1.0 - rtweekend::random_double()).ln() / sigma } else { rtweekend::INFINITY };
+
+            let ray_length = ray.direction().length();
+            let distance_inside = boundary.t * ray_length;
+            if distance >= distance_inside {
+                // 到达边界：各通道贡献 exp(-σ_j L)，混合概率为 Σ p_j exp(-σ_j L)
+                let transmittance = self.transmittance(distance_inside);
+                throughput = throughput * transmittance / vec3::dot(probability, transmittance);
+                return Some((boundary, ray, throughput));
+            }
+
+            // 散射：各通道贡献 α_j σ_j exp(-σ_j d)，混合概率密度为 Σ p_j σ_j exp(-σ_j d)
+            let density = self.sigma_t * self.transmittance(distance);
+            throughput = throughput * self.albedo * density / vec3::dot(probability, density);
+            // 散射点在内部而不在表面上，不会自相交，新光线从0开始求交
+            ray = Ray::with_time(ray.at(distance / ray_length), vec3::random_unit_vector(), ray.time());
+            interval = Interval::new(0.0, rtweekend::INFINITY);
+        }
+        None
+    }
+}
+
+impl Material for RandomWalk {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color, scattered: &mut Ray) -> bool {
+        ScatterRecord::write_out(self.scatter2(r_in, rec), attenuation, scattered)
+    }
+
+    /// 从命中点所在光线继续随机游走，在射出点由界面材质散射
+    ///
+    /// 散射光线的起点已沿射出点法线偏移。游走路径的概率已计入吞吐量，
+    /// 命中点处没有对应的散射概率密度，因此结果按镜面散射返回
+    fn scatter2(&self, r_in: &Ray, _rec: &HitRecord) -> Option<ScatterRecord> {
+        let (exit, incoming, throughput) = self.walk(r_in, &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY))?;
+        let srec = self.surface.scatter2(&incoming, &exit)?;
+        let direction = srec.scattered.direction();
+        Some(ScatterRecord {
+            attenuation: throughput * srec.attenuation,
+            scattered: Ray::with_time(exit.spawn_origin(direction), direction, srec.scattered.time()),
+            pdf: None,
+            is_specular: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+    use crate::material::Lambertian;
+    use crate::vec3::{Point3, Vec3};
+
+    /// 以原点为球心的单位球边界
+    fn ball(albedo: Color, mean_free_path: Color) -> Subsurface {
+        let boundary = Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, Arc::new(Lambertian::new(albedo))));
+        Subsurface::new(boundary, albedo, mean_free_path, 1.5)
+    }
+
+    fn ray_t() -> Interval {
+        Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY)
+    }
+
+    /// 从球心向外的光线，命中边界背面
+    fn from_center() -> Ray {
+        Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.3, 0.4, 0.5))
+    }
+
+    #[test]
+    fn hits_from_inside_are_deterministic_and_stay_on_the_ray() {
+        let ball = ball(Color::new(0.8, 0.8, 0.8), Color::new(0.1, 0.2, 0.3));
+        let r = from_center();
+        let (mut first, mut second) = (HitRecord::default(), HitRecord::default());
+        assert!(ball.hit(&r, &ray_t(), &mut first));
+        assert!(ball.hit(&r, &ray_t(), &mut second));
+
+        assert!(!first.front_face);
+        assert_eq!(first.t, second.t);
+        assert!((first.p - r.at(first.t)).length() < 1e-6);
+        assert!((first.p.length() - 1.0).abs() < 1e-4);
+        // 内部材质在构造时创建，命中时不再分配
+        assert!(Arc::ptr_eq(first.mat.as_ref().unwrap(), second.mat.as_ref().unwrap()));
+        assert!(ball.hit_any(&r, &ray_t()));
+    }
+
+    #[test]
+    fn hits_from_outside_use_the_surface_material() {
+        let ball = ball(Color::new(0.8, 0.8, 0.8), Color::new(0.1, 0.2, 0.3));
+        let r = Ray::new(Point3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0));
+        let mut rec = HitRecord::default();
+        assert!(ball.hit(&r, &ray_t(), &mut rec));
+        assert!(rec.front_face);
+        assert!((rec.t - 2.0).abs() < 1e-4);
+        assert!(Arc::ptr_eq(rec.mat.as_ref().unwrap(), &ball.surface));
+    }
+
+    #[test]
+    fn scattering_walks_to_an_exit_point_on_the_boundary() {
+        rtweekend::seed_random(1060);
+        let ball = ball(Color::new(0.9, 0.7, 0.5), Color::new(0.2, 0.1, 0.05));
+        let r = from_center();
+        let mut rec = HitRecord::default();
+        assert!(ball.hit(&r, &ray_t(), &mut rec));
+        let mat = rec.mat.clone().unwrap();
+
+        let mut scattered = 0;
+        for _ in 0..200 {
+            let Some(srec) = mat.scatter2(&r, &rec) else { continue };
+            scattered += 1;
+            assert!(srec.is_specular && srec.pdf.is_none());
+            // 起点在射出点附近，已偏移，不再是命中点
+            assert!((srec.scattered.origin().length() - 1.0).abs() < 1e-3);
+            let a = srec.attenuation;
+            assert!(a.to_array().iter().all(|c| c.is_finite() && *c >= 0.0), "attenuation = {a:?}");
+        }
+        assert!(scattered > 100, "only {scattered} of 200 walks left the ball");
+    }
+
+    #[test]
+    fn black_albedo_absorbs_every_walk() {
+        rtweekend::seed_random(1060);
+        let ball = ball(Color::new(0.0, 0.0, 0.0), Color::new(0.01, 0.01, 0.01));
+        let r = from_center();
+        let mut rec = HitRecord::default();
+        assert!(ball.hit(&r, &ray_t(), &mut rec));
+        let mat = rec.mat.clone().unwrap();
+        assert!((0..100).all(|_| mat.scatter2(&r, &rec).is_none()));
+    }
+
+    #[test]
+    fn white_albedo_conserves_energy_on_average() {
+        rtweekend::seed_random(1060);
+        // 各通道平均自由程不同，检验混合通道抽样的权重无偏
+        let ball = ball(Color::new(1.0, 1.0, 1.0), Color::new(0.1, 0.3, 0.5));
+        let r = from_center();
+        let mut rec = HitRecord::default();
+        assert!(ball.hit(&r, &ray_t(), &mut rec));
+        let mat = rec.mat.clone().unwrap();
+
+        let n = 2000;
+        let total = (0..n)
+            .filter_map(|_| mat.scatter2(&r, &rec))
+            .fold(Color::default(), |sum, srec| sum + srec.attenuation);
+        let mean = total / n as Real;
+        for c in mean.to_array() {
+            assert!((c - 1.0).abs() < 0.1, "mean attenuation = {mean:?}");
+        }
+    }
+}
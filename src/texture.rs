@@ -0,0 +1,206 @@
+//! 纹理模块
+//!
+//! 提供纹理抽象，根据表面参数坐标(u, v)和命中点位置返回颜色
+
+use super::color::{self, Color};
+use super::image::{self, Image};
+use super::rtweekend::Real;
+use super::vec3::Point3;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 纹理抽象接口
+///
+/// 所有纹理类型都应实现此trait
+pub trait Texture: Send + Sync {
+    /// 计算纹理在表面某点的颜色
+    ///
+    /// # Arguments
+    /// * `u` - 表面参数坐标u
+    /// * `v` - 表面参数坐标v
+    /// * `p` - 命中点位置
+    fn value(&self, u: Real, v: Real, p: Point3) -> Color;
+}
+
+/// 纯色纹理
+///
+/// # Fields
+/// - albedo: 纹理颜色
+pub struct SolidColor {
+    pub albedo: Color,
+}
+
+impl SolidColor {
+    /// 创建纯色纹理
+    ///
+    /// # Arguments
+    /// * `albedo` - 纹理颜色
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+
+    /// 由RGB分量创建纯色纹理
+    pub fn from_rgb(red: Real, green: Real, blue: Real) -> Self {
+        Self::new(Color::new(red, green, blue))
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: Real, _v: Real, _p: Point3) -> Color {
+        self.albedo
+    }
+}
+
+/// 棋盘格的取格方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CheckerMapping {
+    /// 按命中点的三维坐标划分立方体格子，与表面参数化无关，格子穿透物体
+    #[default]
+    Spatial,
+    /// 按纹理坐标(u, v)划分方格，格子随表面参数化弯曲(如球面上的经纬格)
+    Uv,
+}
+
+/// 棋盘格纹理
+///
+/// 相邻格子交替取两个子纹理的颜色，子纹理本身也可以是棋盘格等任意纹理
+///
+/// # Fields
+/// - inv_scale: 格子边长的倒数
+/// - even: 坐标取整之和为偶数的格子使用的纹理
+/// - odd: 坐标取整之和为奇数的格子使用的纹理
+/// - mapping: 取格方式，默认按三维坐标
+pub struct CheckerTexture {
+    pub inv_scale: Real,
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub mapping: CheckerMapping,
+}
+
+impl CheckerTexture {
+    /// 创建按三维坐标取格的棋盘格纹理
+    ///
+    /// # Arguments
+    /// * `scale` - 格子边长，按纹理坐标取格时为(u, v)空间中的边长
+    /// * `even` - 偶数格的纹理
+    /// * `odd` - 奇数格的纹理
+    pub fn new(scale: Real, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self { inv_scale: 1.0 / scale, even, odd, mapping: CheckerMapping::default() }
+    }
+
+    /// 由两种颜色创建按三维坐标取格的棋盘格纹理
+    pub fn from_colors(scale: Real, even: Color, odd: Color) -> Self {
+        Self::new(scale, Arc::new(SolidColor::new(even)), Arc::new(SolidColor::new(odd)))
+    }
+
+    /// 设置取格方式
+    pub fn with_mapping(mut self, mapping: CheckerMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: Real, v: Real, p: Point3) -> Color {
+        let cell = |x: Real| (self.inv_scale * x).floor() as i64;
+        let sum = match self.mapping {
+            CheckerMapping::Spatial => cell(p.x()) + cell(p.y()) + cell(p.z()),
+            CheckerMapping::Uv => cell(u) + cell(v),
+        };
+        if sum % 2 == 0 { self.even.value(u, v, p) } else { self.odd.value(u, v, p) }
+    }
+}
+
+/// 纹理坐标超出[0,1]时的处理方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WrapMode {
+    /// 截断到边缘像素
+    #[default]
+    Clamp,
+    /// 平铺重复
+    Repeat,
+}
+
+/// 图像纹理
+///
+/// 按纹理坐标(u, v)取最近的像素，u从左到右、v从下到上，
+/// 因此球体的经纬参数化可以直接贴上等距柱状投影的地图
+///
+/// # Fields
+/// - width, height: 图像尺寸
+/// - pixels: 线性空间的像素颜色，第一行为图像顶部
+/// - wrap: 纹理坐标超出[0,1]时的处理方式
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    pub wrap: WrapMode,
+}
+
+impl ImageTexture {
+    /// 由已解码的图像创建纹理
+    ///
+    /// # Note
+    /// 图像按与输出一致的gamma 2编码，像素值会转换回线性空间
+    pub fn new(image: &Image) -> Self {
+        Self::from_image(image, |c| color::gamma_to_linear(c as Real / 255.0))
+    }
+
+    /// 由已解码的图像创建不做gamma转换的纹理
+    ///
+    /// 像素值直接映射到[0,1]，用于法线贴图等存放非颜色数据的图像
+    pub fn new_linear(image: &Image) -> Self {
+        Self::from_image(image, |c| c as Real / 255.0)
+    }
+
+    /// 从PNG、JPEG或PPM/PGM文件加载纹理
+    ///
+    /// # Returns
+    /// 文件无法读取或解码失败时返回错误
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(&image::load_image(path)?))
+    }
+
+    /// 从文件加载不做gamma转换的纹理，见`new_linear`
+    pub fn load_linear(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new_linear(&image::load_image(path)?))
+    }
+
+    /// 按给定的分量转换函数把8位像素转换为颜色
+    fn from_image(image: &Image, convert: impl Fn(u8) -> Real) -> Self {
+        let pixels = image.pixels.iter().map(|&[r, g, b]| Color::new(convert(r), convert(g), convert(b))).collect();
+        Self { width: image.width, height: image.height, pixels, wrap: WrapMode::default() }
+    }
+
+    /// 设置纹理坐标超出[0,1]时的处理方式
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// 纹理坐标转换为像素索引
+    ///
+    /// # Arguments
+    /// * `t` - 纹理坐标分量
+    /// * `size` - 该方向的像素数
+    fn texel(&self, t: Real, size: usize) -> usize {
+        let t = match self.wrap {
+            WrapMode::Clamp => t.clamp(0.0, 1.0),
+            WrapMode::Repeat => t - t.floor(),
+        };
+        ((t * size as Real) as usize).min(size - 1)
+    }
+}
+
+impl Texture for ImageTexture {
+    /// 没有像素数据时返回青色，便于发现纹理缺失
+    fn value(&self, u: Real, v: Real, _p: Point3) -> Color {
+        if self.pixels.is_empty() {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        let i = self.texel(u, self.width);
+        let j = self.texel(1.0 - v, self.height);
+        self.pixels[j * self.width + i]
+    }
+}
@@ -0,0 +1,367 @@
+//! 圆环模块
+//!
+//! 提供圆环(甜甜圈形)几何形状的实现，通过求解四次方程计算光线交点
+
+use super::vec3::{self, Point3, Vec3};
+use super::ray::Ray;
+use super::material::Material;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::Aabb;
+use super::onb::Onb;
+use super::rtweekend::{self, Real};
+use std::sync::Arc;
+
+/// 判断系数是否可视为0的阈值
+const EQN_EPSILON: Real = 1e-9;
+
+/// 圆环几何形状
+///
+/// 由半径为`minor_radius`的圆绕轴线旋转一周得到，圆心轨迹是半径为`major_radius`的圆
+///
+/// # Fields
+/// - center: 圆环中心
+/// - major_radius: 主半径(中心到管道中心线的距离)
+/// - minor_radius: 次半径(管道半径)
+/// - mat: 圆环材质
+/// - frame: 局部坐标系，w轴为旋转轴
+/// - bbox: 包围盒
+pub struct Torus {
+    center: Point3,
+    major_radius: Real,
+    minor_radius: Real,
+    mat: Arc<dyn Material + Send + Sync>,
+    frame: Onb,
+    bbox: Aabb,
+}
+
+impl Torus {
+    /// 创建新的圆环
+    ///
+    /// # Arguments
+    /// * `center` - 圆环中心
+    /// * `axis` - 旋转轴方向(无需归一化)
+    /// * `major_radius` - 主半径
+    /// * `minor_radius` - 次半径
+    /// * `material` - 圆环材质
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: Real,
+        minor_radius: Real,
+        material: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        let axis = vec3::unit_vector(axis);
+
+        // 包围盒：中心线圆盘的包围盒向各方向扩展次半径
+        let extent = Vec3::new(
+            major_radius * (1.0 - axis.x() * axis.x()).max(0.0).sqrt() + minor_radius,
+            major_radius * (1.0 - axis.y() * axis.y()).max(0.0).sqrt() + minor_radius,
+            major_radius * (1.0 - axis.z() * axis.z()).max(0.0).sqrt() + minor_radius,
+        );
+
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            mat: material,
+            frame: Onb::new(axis),
+            bbox: Aabb::from_points(center - extent, center + extent),
+        }
+    }
+
+    /// 将世界坐标系中的向量变换到局部坐标系(分量依次对应u, v, w)
+    fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            vec3::dot(v, self.frame.u()),
+            vec3::dot(v, self.frame.v()),
+            vec3::dot(v, self.frame.w()),
+        )
+    }
+}
+
+impl Hittable for Torus {
+    /// 实现圆环的光线命中检测
+    ///
+    /// 在局部坐标系中圆环满足 (|P|² + R² - r²)² = 4R²(x² + y²)，
+    /// 代入光线方程得到关于t的四次方程。为减小数值误差，
+    /// 先把光线起点移到离圆环中心最近的位置并按主半径缩放后再求解，
+    /// 最后对每个根做牛顿迭代修正
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let dir_len = r.direction().length();
+        if dir_len == 0.0 {
+            return false;
+        }
+
+        // 局部坐标系下的单位方向，以及按主半径缩放后的起点
+        let d = self.to_local(r.direction()) / dir_len;
+        let o = self.to_local(r.origin() - self.center);
+        let shift = -vec3::dot(o, d);
+        let scale = self.major_radius;
+        let o = (o + shift * d) / scale;
+        let minor = self.minor_radius / scale;
+
+        // 四次方程系数(主半径已缩放为1，且 o·d = 0)
+        let k = o.squared_length() + 1.0 - minor * minor;
+        let coeffs = [
+            k * k - 4.0 * (o.x() * o.x() + o.y() * o.y()),
+            -8.0 * (o.x() * d.x() + o.y() * d.y()),
+            2.0 * k - 4.0 * (d.x() * d.x() + d.y() * d.y()),
+            0.0,
+            1.0,
+        ];
+
+        // 局部参数s与光线参数t的换算: t = (s * scale + shift) / |dir|
+        let mut closest = None;
+        for s in solve_quartic(coeffs) {
+            let s = polish_root(&coeffs, s);
+            let t = (s * scale + shift) / dir_len;
+            if ray_t.surrounds(t) && closest.is_none_or(|c| t < c) {
+                closest = Some(t);
+            }
+        }
+        let Some(t) = closest else {
+            return false;
+        };
+
+        hit_record.t = t;
+        hit_record.p = r.at(t);
+
+        // 法线：从管道中心线上最近点指向命中点
+        let local = self.to_local(hit_record.p - self.center);
+        let rho = (local.x() * local.x() + local.y() * local.y()).sqrt().max(1e-12);
+        let ring = Vec3::new(local.x() / rho, local.y() / rho, 0.0) * self.major_radius;
+        let outward_normal = self.frame.transform(vec3::unit_vector(local - ring));
+
+        // u沿主圆方向，v沿管道截面方向
+        let phi = local.y().atan2(local.x());
+        let theta = local.z().atan2(rho - self.major_radius);
+        hit_record.u = (phi + rtweekend::PI) / (2.0 * rtweekend::PI);
+        hit_record.v = (theta + rtweekend::PI) / (2.0 * rtweekend::PI);
+        hit_record.tangent = self.frame.transform(Vec3::new(-phi.sin(), phi.cos(), 0.0));
+
+        hit_record.set_face_normal(r, outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// 用牛顿迭代修正多项式的根
+///
+/// # Arguments
+/// * `c` - 多项式系数，c[i]为i次项系数
+/// * `x` - 初始根
+fn polish_root(c: &[Real; 5], mut x: Real) -> Real {
+    for _ in 0..2 {
+        let f = (((c[4] * x + c[3]) * x + c[2]) * x + c[1]) * x + c[0];
+        let df = ((4.0 * c[4] * x + 3.0 * c[3]) * x + 2.0 * c[2]) * x + c[1];
+        if df.abs() < EQN_EPSILON {
+            break;
+        }
+        x -= f / df;
+    }
+    x
+}
+
+/// 求解二次方程 c[2]x² + c[1]x + c[0] = 0 的实根
+fn solve_quadratic(c: [Real; 3]) -> Vec<Real> {
+    // 化为标准形式 x² + 2px + q = 0
+    let p = c[1] / (2.0 * c[2]);
+    let q = c[0] / c[2];
+    let d = p * p - q;
+
+    if d.abs() < EQN_EPSILON {
+        vec![-p]
+    } else if d < 0.0 {
+        Vec::new()
+    } else {
+        let sqrt_d = d.sqrt();
+        vec![sqrt_d - p, -sqrt_d - p]
+    }
+}
+
+/// 求解三次方程 c[3]x³ + c[2]x² + c[1]x + c[0] = 0 的实根(卡尔达诺公式)
+fn solve_cubic(c: [Real; 4]) -> Vec<Real> {
+    // 化为标准形式 x³ + Ax² + Bx + C = 0
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let cc = c[0] / c[3];
+
+    // 代换 x = y - A/3 消去二次项: y³ + 3py + 2q = 0
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + cc) / 2.0;
+    let cb_p = p * p * p;
+    let d = q * q + cb_p;
+
+    let roots = if d.abs() < EQN_EPSILON {
+        if q.abs() < EQN_EPSILON {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if d < 0.0 {
+        // 三个不同实根(三角函数解法)
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + rtweekend::PI / 3.0).cos(),
+            -t * (phi - rtweekend::PI / 3.0).cos(),
+        ]
+    } else {
+        let sqrt_d = d.sqrt();
+        vec![(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt()]
+    };
+
+    roots.into_iter().map(|y| y - a / 3.0).collect()
+}
+
+/// 求解四次方程 c[4]x⁴ + c[3]x³ + c[2]x² + c[1]x + c[0] = 0 的实根(费拉里方法)
+fn solve_quartic(c: [Real; 5]) -> Vec<Real> {
+    // 化为标准形式 x⁴ + Ax³ + Bx² + Cx + D = 0
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let cc = c[1] / c[4];
+    let dd = c[0] / c[4];
+
+    // 代换 x = y - A/4 消去三次项: y⁴ + py² + qy + r = 0
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + cc;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * cc / 4.0 + dd;
+
+    let roots = if r.abs() < EQN_EPSILON {
+        // 无常数项: y(y³ + py + q) = 0
+        let mut roots = solve_cubic([q, p, 0.0, 1.0]);
+        roots.push(0.0);
+        roots
+    } else {
+        // 先求预解三次方程的一个实根
+        let z = solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0])[0];
+
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if u.abs() < EQN_EPSILON {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return Vec::new();
+        };
+        let v = if v.abs() < EQN_EPSILON {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return Vec::new();
+        };
+
+        // 分解为两个二次方程
+        let mut roots = solve_quadratic([z - u, if q < 0.0 { -v } else { v }, 1.0]);
+        roots.extend(solve_quadratic([z + u, if q < 0.0 { v } else { -v }, 1.0]));
+        roots
+    };
+
+    roots.into_iter().map(|y| y - a / 4.0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    /// 中心在原点、旋转轴为y轴的圆环
+    fn torus(major_radius: Real, minor_radius: Real) -> Torus {
+        Torus::new(
+            Point3::default(),
+            Vec3::new(0.0, 1.0, 0.0),
+            major_radius,
+            minor_radius,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    fn cast(torus: &Torus, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        torus
+            .hit(&Ray::new(origin, direction), &Interval::new(rtweekend::HIT_EPSILON, rtweekend::INFINITY), &mut rec)
+            .then_some(rec)
+    }
+
+    fn assert_hit(rec: Option<HitRecord>, t: Real, front_face: bool, normal: Vec3) {
+        let rec = rec.expect("应当命中");
+        assert!((rec.t - t).abs() < 1e-3, "t = {}", rec.t);
+        assert_eq!(rec.front_face, front_face);
+        assert!((rec.normal.length() - 1.0).abs() < 1e-4);
+        assert!((rec.normal - normal).length() < 1e-3, "normal = {:?}", rec.normal);
+    }
+
+    #[test]
+    fn quartic_solver_finds_four_distinct_roots() {
+        // (x-1)(x-2)(x-3)(x-4)
+        let mut roots = solve_quartic([24.0, -50.0, 35.0, -10.0, 1.0]);
+        roots.sort_by(Real::total_cmp);
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0, 4.0]) {
+            assert!((root - expected).abs() < 1e-3, "roots = {:?}", roots);
+        }
+        // x⁴ + 1 没有实根
+        assert!(solve_quartic([1.0, 0.0, 0.0, 0.0, 1.0]).is_empty());
+    }
+
+    #[test]
+    fn axis_aligned_rays_hit_the_tube_walls() {
+        let torus = torus(2.0, 0.5);
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        // 从中心向外：先命中距中心R - r处的内壁
+        assert_hit(cast(&torus, Point3::default(), x), 1.5, true, -x);
+        // 从外部沿x轴射入：命中外壁
+        assert_hit(cast(&torus, Point3::new(-5.0, 0.0, 0.0), x), 2.5, true, -x);
+        // 平行于旋转轴射向管道中心线：命中管道顶部
+        assert_hit(cast(&torus, Point3::new(2.0, 5.0, 0.0), down), 4.5, true, -down);
+        // 沿旋转轴穿过中间的孔
+        assert!(cast(&torus, Point3::new(0.0, 5.0, 0.0), down).is_none());
+    }
+
+    #[test]
+    fn grazing_rays_just_above_the_tube_miss() {
+        let torus = torus(2.0, 0.5);
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        // 管道顶部在y = 0.5处，与之相切的光线两侧
+        assert!(cast(&torus, Point3::new(-5.0, 0.501, 0.0), x).is_none());
+        let rec = cast(&torus, Point3::new(-5.0, 0.49, 0.0), x).unwrap();
+        assert!((rec.p.x() + 2.0).abs() < 0.15);
+        assert!(rec.front_face);
+        // 从侧面擦过整个圆环
+        assert!(cast(&torus, Point3::new(-5.0, 0.0, 2.51), x).is_none());
+    }
+
+    #[test]
+    fn thin_torus_is_hit_at_the_expected_distance() {
+        let torus = torus(3.0, 0.01);
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        assert_hit(cast(&torus, Point3::new(-5.0, 0.0, 0.0), x), 5.0 - 3.01, true, -x);
+        assert_hit(cast(&torus, Point3::new(0.0, 5.0, 3.0), down), 5.0 - 0.01, true, -down);
+        assert!(cast(&torus, Point3::new(0.0, 5.0, 3.02), down).is_none());
+    }
+
+    #[test]
+    fn ray_from_inside_the_tube_hits_the_back_face() {
+        let torus = torus(2.0, 0.5);
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        // 起点在管道中心线上，向外和向内都在r处离开管道
+        assert_hit(cast(&torus, Point3::new(2.0, 0.0, 0.0), x), 0.5, false, -x);
+        assert_hit(cast(&torus, Point3::new(2.0, 0.0, 0.0), -x), 0.5, false, x);
+        assert_hit(cast(&torus, Point3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)), 0.5, false, Vec3::new(0.0, -1.0, 0.0));
+    }
+}
@@ -0,0 +1,273 @@
+//! 仿射变换模块
+//!
+//! 提供由3×3线性部分和平移部分组成的仿射变换，同时保存其逆变换，
+//! 用于在物体空间和世界空间之间变换点、向量和法线
+
+use super::vec3::{self, Point3, Vec3};
+use super::aabb::Aabb;
+use super::rtweekend::{self, Real};
+
+/// 仿射变换 p' = M p + t
+///
+/// # Fields
+/// - m: 线性部分，按行存储
+/// - t: 平移部分
+/// - inv_m: 线性部分的逆矩阵
+/// - inv_t: 逆变换的平移部分(-M⁻¹t)
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    m: [Vec3; 3],
+    t: Vec3,
+    inv_m: [Vec3; 3],
+    inv_t: Vec3,
+}
+
+impl Default for Transform {
+    /// 默认为恒等变换
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    /// 恒等变换
+    pub fn identity() -> Self {
+        let m = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        Self { m, t: Vec3::default(), inv_m: m, inv_t: Vec3::default() }
+    }
+
+    /// 由线性部分和平移部分创建变换
+    ///
+    /// # Arguments
+    /// * `m` - 3×3线性部分，按行给出
+    /// * `t` - 平移部分
+    ///
+    /// # Returns
+    /// 线性部分不可逆时返回None
+    pub fn new(m: [[Real; 3]; 3], t: Vec3) -> Option<Self> {
+        let m = m.map(Vec3::from_array);
+        let inv_m = invert(&m)?;
+        let inv_t = -mul(&inv_m, t);
+        Some(Self { m, t, inv_m, inv_t })
+    }
+
+    /// 平移变换
+    pub fn translate(offset: Vec3) -> Self {
+        let mut transform = Self::identity();
+        transform.t = offset;
+        transform.inv_t = -offset;
+        transform
+    }
+
+    /// 沿坐标轴的缩放变换
+    ///
+    /// # Note
+    /// 任一缩放因子为0时会panic
+    pub fn scale(factors: Vec3) -> Self {
+        assert!(
+            factors.x() != 0.0 && factors.y() != 0.0 && factors.z() != 0.0,
+            "缩放因子不能为0"
+        );
+        Self {
+            m: [
+                Vec3::new(factors.x(), 0.0, 0.0),
+                Vec3::new(0.0, factors.y(), 0.0),
+                Vec3::new(0.0, 0.0, factors.z()),
+            ],
+            t: Vec3::default(),
+            inv_m: [
+                Vec3::new(1.0 / factors.x(), 0.0, 0.0),
+                Vec3::new(0.0, 1.0 / factors.y(), 0.0),
+                Vec3::new(0.0, 0.0, 1.0 / factors.z()),
+            ],
+            inv_t: Vec3::default(),
+        }
+    }
+
+    /// 绕过原点的任意轴旋转
+    ///
+    /// # Arguments
+    /// * `axis` - 旋转轴(无需归一化)
+    /// * `degrees` - 旋转角度(角度制，沿轴方向看去逆时针为正)
+    pub fn rotate(axis: Vec3, degrees: Real) -> Self {
+        let angle = degrees.to_radians();
+        // 旋转矩阵的各列即三个基向量旋转后的结果
+        let cols = [
+            vec3::rotate_around_axis(Vec3::new(1.0, 0.0, 0.0), axis, angle),
+            vec3::rotate_around_axis(Vec3::new(0.0, 1.0, 0.0), axis, angle),
+            vec3::rotate_around_axis(Vec3::new(0.0, 0.0, 1.0), axis, angle),
+        ];
+        let m = transpose(&cols);
+        // 旋转矩阵的逆即其转置
+        Self { m, t: Vec3::default(), inv_m: cols, inv_t: Vec3::default() }
+    }
+
+    /// 组合变换：先应用`self`，再应用`next`
+    pub fn then(&self, next: &Transform) -> Self {
+        Self {
+            m: mat_mul(&next.m, &self.m),
+            t: mul(&next.m, self.t) + next.t,
+            inv_m: mat_mul(&self.inv_m, &next.inv_m),
+            inv_t: mul(&self.inv_m, next.inv_t) + self.inv_t,
+        }
+    }
+
+    /// 逆变换
+    pub fn inverse(&self) -> Self {
+        Self { m: self.inv_m, t: self.inv_t, inv_m: self.m, inv_t: self.t }
+    }
+
+    /// 变换点
+    pub fn point(&self, p: Point3) -> Point3 {
+        mul(&self.m, p) + self.t
+    }
+
+    /// 变换方向向量(不受平移影响)
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        mul(&self.m, v)
+    }
+
+    /// 变换法线：乘以线性部分逆矩阵的转置，结果未归一化
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        mul(&transpose(&self.inv_m), n)
+    }
+
+    /// 用逆变换变换点
+    pub fn inverse_point(&self, p: Point3) -> Point3 {
+        mul(&self.inv_m, p) + self.inv_t
+    }
+
+    /// 用逆变换变换方向向量
+    pub fn inverse_vector(&self, v: Vec3) -> Vec3 {
+        mul(&self.inv_m, v)
+    }
+
+    /// 变换包围盒，返回包含变换后八个角点的轴对齐包围盒
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        let mut min = Point3::new(rtweekend::INFINITY, rtweekend::INFINITY, rtweekend::INFINITY);
+        let mut max = -min;
+        for i in 0..8 {
+            let corner = Point3::new(
+                if i & 1 == 0 { bbox.x.min } else { bbox.x.max },
+                if i & 2 == 0 { bbox.y.min } else { bbox.y.max },
+                if i & 4 == 0 { bbox.z.min } else { bbox.z.max },
+            );
+            let p = self.point(corner);
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        Aabb::from_points(min, max)
+    }
+}
+
+/// 矩阵(按行存储)乘向量
+fn mul(m: &[Vec3; 3], v: Vec3) -> Vec3 {
+    Vec3::new(vec3::dot(m[0], v), vec3::dot(m[1], v), vec3::dot(m[2], v))
+}
+
+/// 矩阵转置
+fn transpose(m: &[Vec3; 3]) -> [Vec3; 3] {
+    [
+        Vec3::new(m[0].x(), m[1].x(), m[2].x()),
+        Vec3::new(m[0].y(), m[1].y(), m[2].y()),
+        Vec3::new(m[0].z(), m[1].z(), m[2].z()),
+    ]
+}
+
+/// 矩阵乘法 a·b
+fn mat_mul(a: &[Vec3; 3], b: &[Vec3; 3]) -> [Vec3; 3] {
+    let bt = transpose(b);
+    a.map(|row| mul(&bt, row))
+}
+
+/// 3×3矩阵求逆(伴随矩阵法)，行列式接近0时返回None
+fn invert(m: &[Vec3; 3]) -> Option<[Vec3; 3]> {
+    // 伴随矩阵的列是行向量两两的叉积
+    let c0 = vec3::cross(m[1], m[2]);
+    let c1 = vec3::cross(m[2], m[0]);
+    let c2 = vec3::cross(m[0], m[1]);
+    let det = vec3::dot(m[0], c0);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    Some(transpose(&[c0 / det, c1 / det, c2 / det]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    /// 包含镜像、非均匀缩放、旋转和平移的组合变换
+    fn skewed() -> Transform {
+        Transform::scale(Vec3::new(2.0, -3.0, 0.5))
+            .then(&Transform::rotate(Vec3::new(1.0, 2.0, 3.0), 37.0))
+            .then(&Transform::translate(Vec3::new(4.0, -1.0, 2.0)))
+    }
+
+    #[test]
+    fn then_applies_self_first() {
+        let rotate = Transform::rotate(Vec3::new(0.0, 0.0, 1.0), 90.0);
+        let shift = Transform::translate(Vec3::new(1.0, 0.0, 0.0));
+        let p = Point3::new(1.0, 0.0, 0.0);
+        // 先旋转到(0,1,0)再平移
+        assert_close(rotate.then(&shift).point(p), Point3::new(1.0, 1.0, 0.0));
+        // 先平移到(2,0,0)再旋转
+        assert_close(shift.then(&rotate).point(p), Point3::new(0.0, 2.0, 0.0));
+        // 方向向量不受平移影响
+        assert_close(rotate.then(&shift).vector(p), Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn inverse_round_trips_points_and_vectors() {
+        let transform = skewed();
+        let inverse = transform.inverse();
+        for p in [Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, -2.0, 3.0), Point3::new(-7.5, 0.25, 11.0)] {
+            assert_close(inverse.point(transform.point(p)), p);
+            assert_close(transform.inverse_point(transform.point(p)), p);
+            assert_close(transform.then(&inverse).point(p), p);
+            assert_close(inverse.then(&transform).point(p), p);
+            assert_close(transform.inverse_vector(transform.vector(p)), p);
+        }
+
+        // 由同一矩阵直接创建的变换与组合结果相同
+        let columns = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)].map(|e| transform.vector(e));
+        let m = [0, 1, 2].map(|row| columns.map(|column| column[row]));
+        let direct = Transform::new(m, transform.point(Point3::default())).unwrap();
+        let p = Point3::new(1.0, 2.0, 3.0);
+        assert_close(direct.point(p), transform.point(p));
+        assert_close(direct.inverse_point(p), transform.inverse_point(p));
+
+        assert!(Transform::new([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 0.0, 1.0]], Vec3::default()).is_none());
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_to_transformed_surfaces() {
+        let transform = skewed();
+        let n = vec3::unit_vector(Vec3::new(1.0, 1.0, -0.5));
+        // 与n垂直的两个切向量变换后仍与变换后的法线垂直
+        let u = vec3::cross(n, Vec3::new(0.0, 0.0, 1.0));
+        let v = vec3::cross(n, u);
+        let normal = transform.normal(n);
+        assert!(vec3::dot(normal, transform.vector(u)).abs() < 1e-4);
+        assert!(vec3::dot(normal, transform.vector(v)).abs() < 1e-4);
+        // 仍在切平面的同一侧
+        assert!(vec3::dot(normal, transform.vector(n)) > 0.0);
+
+        // 非均匀缩放下法线按因子的倒数缩放，而不是跟着表面一起拉伸
+        let stretch = Transform::scale(Vec3::new(4.0, 1.0, 1.0));
+        assert_close(vec3::unit_vector(stretch.normal(Vec3::new(1.0, 1.0, 0.0))), vec3::unit_vector(Vec3::new(0.25, 1.0, 0.0)));
+        // 镜像翻转对应轴上的分量
+        let mirror = Transform::scale(Vec3::new(-1.0, 1.0, 1.0));
+        assert_close(mirror.normal(Vec3::new(1.0, 0.0, 0.0)), Vec3::new(-1.0, 0.0, 0.0));
+    }
+}
@@ -2,7 +2,8 @@
 //!
 //! 提供三维向量和点运算的基本实现
 
-use super::rtweekend;
+use super::rtweekend::{self, Real};
+use rand::Rng;
 use std::ops::{
     Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub
 };
@@ -13,7 +14,7 @@ use std::ops::{
 /// - e: 包含x,y,z三个分量的数组
 #[derive(Clone, Copy, Debug)]
 pub struct Vec3 {
-    pub e: [f64; 3]
+    pub e: [Real; 3]
 }
 
 impl Default for Vec3 {
@@ -32,7 +33,7 @@ impl Neg for Vec3 {
 }
 
 impl Index<usize> for Vec3 {
-    type Output = f64;
+    type Output = Real;
 
     fn index(&self, i: usize) -> &Self::Output {
         &self.e[i]
@@ -53,16 +54,16 @@ impl AddAssign for Vec3 {
     }
 }
 
-impl MulAssign<f64> for Vec3 {
-    fn mul_assign(&mut self, t: f64) {
+impl MulAssign<Real> for Vec3 {
+    fn mul_assign(&mut self, t: Real) {
         self.e[0] *= t;
         self.e[1] *= t;
         self.e[2] *= t;
     }
 }
 
-impl DivAssign<f64> for Vec3 {
-    fn div_assign(&mut self, t: f64) {
+impl DivAssign<Real> for Vec3 {
+    fn div_assign(&mut self, t: Real) {
         *self *= 1.0 / t;
     }
 }
@@ -91,15 +92,15 @@ impl Mul for Vec3 {
     }
 }
 
-impl Mul<f64> for Vec3 {
+impl Mul<Real> for Vec3 {
     type Output = Self;
 
-    fn mul(self, t: f64) -> Self::Output {
+    fn mul(self, t: Real) -> Self::Output {
         Vec3 { e: [self.e[0] * t, self.e[1] * t, self.e[2] * t] }
     }
 }
 
-impl Mul<Vec3> for f64 {
+impl Mul<Vec3> for Real {
     type Output = Vec3;
 
     fn mul(self, v: Vec3) -> Self::Output {
@@ -107,11 +108,29 @@ impl Mul<Vec3> for f64 {
     }
 }
 
-impl Div<f64> for Vec3 {
+impl Div<Real> for Vec3 {
     type Output = Self;
 
-    fn div(self, t: f64) -> Self::Output {
-        (1 as f64 / t) * self
+    fn div(self, t: Real) -> Self::Output {
+        (1.0 / t) * self
+    }
+}
+
+impl From<[Real; 3]> for Vec3 {
+    fn from(e: [Real; 3]) -> Self {
+        Vec3 { e }
+    }
+}
+
+impl From<(Real, Real, Real)> for Vec3 {
+    fn from((x, y, z): (Real, Real, Real)) -> Self {
+        Vec3 { e: [x, y, z] }
+    }
+}
+
+impl From<Vec3> for [Real; 3] {
+    fn from(v: Vec3) -> Self {
+        v.e
     }
 }
 
@@ -128,25 +147,50 @@ impl Vec3 {
     /// * `e0` - x分量
     /// * `e1` - y分量
     /// * `e2` - z分量
-    pub fn new(e0: f64, e1: f64, e2: f64) -> Self {
+    pub fn new(e0: Real, e1: Real, e2: Real) -> Self {
         Self { e: [e0, e1, e2] }
     }
 
     /// 获取x分量
-    pub fn x(&self) -> f64 { self.e[0] }
+    pub fn x(&self) -> Real { self.e[0] }
     /// 获取y分量
-    pub fn y(&self) -> f64 { self.e[1] }
+    pub fn y(&self) -> Real { self.e[1] }
     /// 获取z分量
-    pub fn z(&self) -> f64 { self.e[2] }
+    pub fn z(&self) -> Real { self.e[2] }
+
+    /// 设置x分量
+    pub fn set_x(&mut self, x: Real) { self.e[0] = x; }
+    /// 设置y分量
+    pub fn set_y(&mut self, y: Real) { self.e[1] = y; }
+    /// 设置z分量
+    pub fn set_z(&mut self, z: Real) { self.e[2] = z; }
+
+    /// 由数组创建向量
+    pub fn from_array(e: [Real; 3]) -> Self {
+        Self { e }
+    }
+
+    /// 转换为数组
+    pub fn to_array(&self) -> [Real; 3] {
+        self.e
+    }
+
+    /// 由切片创建向量
+    /// 
+    /// # Returns
+    /// 切片长度恰好为3时返回Some，否则返回None
+    pub fn from_slice(s: &[Real]) -> Option<Self> {
+        <[Real; 3]>::try_from(s).ok().map(Self::from_array)
+    }
 
     /// 计算向量长度
-    pub fn length(&self) -> f64 {
+    pub fn length(&self) -> Real {
         (self.e[0] * self.e[0] + self.e[1] * self.e[1] + self.e[2] * self.e[2]).sqrt()
     }
 
     /// 计算向量长度的平方
-    pub fn squared_length(&self) -> f64 {
-        self.e[0] * self.e[0] + self.e[1] * self.e[1] + self.e[2] * self.e[2]
+    pub fn squared_length(&self) -> Real {
+        dot(*self, *self)
     }
 
     /// 生成随机向量，各分量在[0,1)范围内
@@ -159,13 +203,13 @@ impl Vec3 {
     /// # Arguments
     /// * `min` - 分量最小值
     /// * `max` - 分量最大值
-    pub fn random_range(min: f64, max: f64) -> Self {
+    pub fn random_range(min: Real, max: Real) -> Self {
         Self { e: [rtweekend::random_double_range(min, max), rtweekend::random_double_range(min, max), rtweekend::random_double_range(min, max)] }
     }
 
     /// 检查向量是否接近零
     pub fn near_zero(&self) -> bool {
-        let s = 1e-8;
+        let s = rtweekend::NEAR_ZERO_EPSILON;
         self.e[0].abs() < s && self.e[1].abs() < s && self.e[2].abs() < s
     }
 }
@@ -178,8 +222,12 @@ pub type Point3 = Vec3;
 /// # Arguments
 /// * `u` - 第一个向量
 /// * `v` - 第二个向量
-pub fn dot(u: Vec3, v: Vec3) -> f64 {
-    u.e[0] * v.e[0] + u.e[1] * v.e[1] + u.e[2] * v.e[2]
+#[inline]
+pub fn dot(u: Vec3, v: Vec3) -> Real {
+    #[cfg(feature = "simd")]
+    return simd::dot(u, v);
+    #[cfg(not(feature = "simd"))]
+    return scalar_dot(u, v);
 }
 
 /// 计算向量叉积
@@ -187,7 +235,27 @@ pub fn dot(u: Vec3, v: Vec3) -> f64 {
 /// # Arguments
 /// * `u` - 第一个向量
 /// * `v` - 第二个向量
+#[inline]
 pub fn cross(u: Vec3, v: Vec3) -> Vec3 {
+    #[cfg(feature = "simd")]
+    return simd::cross(u, v);
+    #[cfg(not(feature = "simd"))]
+    return scalar_cross(u, v);
+}
+
+/// 点积的标量实现
+///
+/// 启用`simd`时只在测试中用作对照
+#[cfg(any(test, not(feature = "simd")))]
+#[inline]
+pub(crate) fn scalar_dot(u: Vec3, v: Vec3) -> Real {
+    u.e[0] * v.e[0] + u.e[1] * v.e[1] + u.e[2] * v.e[2]
+}
+
+/// 叉积的标量实现
+#[cfg(any(test, not(feature = "simd")))]
+#[inline]
+pub(crate) fn scalar_cross(u: Vec3, v: Vec3) -> Vec3 {
     Vec3 { e: [
         u.e[1] * v.e[2] - u.e[2] * v.e[1],
         u.e[2] * v.e[0] - u.e[0] * v.e[2],
@@ -195,6 +263,44 @@ pub fn cross(u: Vec3, v: Vec3) -> Vec3 {
     ]}
 }
 
+/// Vec3运算的SIMD实现
+/// 
+/// 三个分量装入4通道寄存器，第4通道补0，因此点积可直接对全部通道求和
+#[cfg(feature = "simd")]
+pub mod simd {
+    use super::{Real, Vec3};
+
+    #[cfg(not(feature = "f32"))]
+    type Lanes = wide::f64x4;
+    #[cfg(feature = "f32")]
+    type Lanes = wide::f32x4;
+
+    /// 将向量装入SIMD寄存器，第4通道补0
+    #[inline]
+    fn load(v: Vec3) -> Lanes {
+        Lanes::from([v.e[0], v.e[1], v.e[2], 0.0])
+    }
+
+    /// 计算向量点积
+    #[inline]
+    pub fn dot(u: Vec3, v: Vec3) -> Real {
+        (load(u) * load(v)).reduce_add()
+    }
+
+    /// 计算向量叉积
+    /// 
+    /// cross(u, v) = u.yzx * v.zxy - u.zxy * v.yzx
+    #[inline]
+    pub fn cross(u: Vec3, v: Vec3) -> Vec3 {
+        let u_yzx = Lanes::from([u.e[1], u.e[2], u.e[0], 0.0]);
+        let u_zxy = Lanes::from([u.e[2], u.e[0], u.e[1], 0.0]);
+        let v_yzx = Lanes::from([v.e[1], v.e[2], v.e[0], 0.0]);
+        let v_zxy = Lanes::from([v.e[2], v.e[0], v.e[1], 0.0]);
+        let r = (u_yzx * v_zxy - u_zxy * v_yzx).to_array();
+        Vec3 { e: [r[0], r[1], r[2]] }
+    }
+}
+
 /// 计算单位向量
 /// 
 /// # Arguments
@@ -203,23 +309,86 @@ pub fn unit_vector(v: Vec3) -> Vec3 {
     v / v.length()
 }
 
+/// 拒绝采样的最大尝试次数，超过后改用归一化方向直接构造样本以保证终止
+const MAX_REJECTIONS: usize = 64;
+
+/// 直接构造样本时半径的收缩系数，避免舍入后半径恰好为1而落到边界上
+const FALLBACK_SHRINK: Real = 1.0 - 4.0 * Real::EPSILON;
+
+/// 生成单位圆盘内的随机向量(z分量为0)
 pub fn random_in_unit_disk() -> Vec3 {
-    loop {
-        let p = Vec3::new(rtweekend::random_double_range(-1.0, 1.0), rtweekend::random_double_range(-1.0, 1.0), 0.0);
+    random_in_unit_disk_with(&mut rtweekend::LocalRng)
+}
+
+/// 使用指定的随机数生成器生成单位圆盘内的随机向量(z分量为0)
+/// 
+/// 拒绝采样最多尝试`MAX_REJECTIONS`次，之后把最后一个样本归一化并乘以
+/// 按面积均匀分布的半径，结果仍严格位于圆盘内
+/// 
+/// # Arguments
+/// * `rng` - 随机数生成器，多线程渲染时各线程传入自己的生成器
+pub fn random_in_unit_disk_with(rng: &mut impl Rng) -> Vec3 {
+    let mut p = Vec3::default();
+    for _ in 0..MAX_REJECTIONS {
+        p = Vec3::new(
+            -1.0 + 2.0 * rtweekend::random_double_with(rng),
+            -1.0 + 2.0 * rtweekend::random_double_with(rng),
+            0.0,
+        );
         if p.squared_length() < 1.0 {
             return p;
         }
     }
-}   
+    unit_vector(p) * (rtweekend::random_double_with(rng).sqrt() * FALLBACK_SHRINK)
+}
+
+/// 在内接于单位圆的正多边形内均匀生成随机向量(z分量为0)
+/// 
+/// 正多边形由中心和各边组成的全等三角形拼成，先等概率选一个三角形，
+/// 再在三角形内均匀采样
+/// 
+/// # Arguments
+/// * `sides` - 边数(至少为3)
+/// * `rotation` - 第一个顶点的角度(弧度)
+pub fn random_in_regular_polygon(sides: u32, rotation: Real) -> Vec3 {
+    let step = 2.0 * rtweekend::PI / sides as Real;
+    let k = ((rtweekend::random_double() * sides as Real) as u32).min(sides - 1);
+    let a0 = rotation + k as Real * step;
+    let a1 = a0 + step;
+
+    // 三角形(原点, v0, v1)内的均匀采样
+    let r1 = rtweekend::random_double().sqrt();
+    let r2 = rtweekend::random_double();
+    let x = r1 * ((1.0 - r2) * a0.cos() + r2 * a1.cos());
+    let y = r1 * ((1.0 - r2) * a0.sin() + r2 * a1.sin());
+    Vec3::new(x, y, 0.0)
+}
 
 /// 生成单位球体内的随机向量
 pub fn random_in_unit_sphere() -> Vec3 {
-    loop {
-        let p = Vec3::random_range(-1.0, 1.0);
+    random_in_unit_sphere_with(&mut rtweekend::LocalRng)
+}
+
+/// 使用指定的随机数生成器生成单位球体内的随机向量
+/// 
+/// 拒绝采样最多尝试`MAX_REJECTIONS`次，之后把最后一个样本归一化并乘以
+/// 按体积均匀分布的半径，结果仍严格位于球内
+/// 
+/// # Arguments
+/// * `rng` - 随机数生成器，多线程渲染时各线程传入自己的生成器
+pub fn random_in_unit_sphere_with(rng: &mut impl Rng) -> Vec3 {
+    let mut p = Vec3::default();
+    for _ in 0..MAX_REJECTIONS {
+        p = Vec3::new(
+            -1.0 + 2.0 * rtweekend::random_double_with(rng),
+            -1.0 + 2.0 * rtweekend::random_double_with(rng),
+            -1.0 + 2.0 * rtweekend::random_double_with(rng),
+        );
         if p.squared_length() < 1.0 {
             return p;
         }
     }
+    unit_vector(p) * (rtweekend::random_double_with(rng).cbrt() * FALLBACK_SHRINK)
 }
 
 /// 生成单位球面上的随机向量(已归一化)
@@ -263,7 +432,7 @@ pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
 /// 根据斯涅尔定律将入射向量分解为垂直和平行分量：
 /// 1. 垂直分量按折射率比例缩放
 /// 2. 平行分量保持能量守恒
-pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: f64) -> Vec3 {
+pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: Real) -> Vec3 {
     // 计算入射角余弦（限制在[0,1]范围避免数值误差）
     let cos_theta = dot(-uv, n).min(1.0);
     
@@ -277,3 +446,170 @@ pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: f64) -> Vec3 {
     r_out_perp + r_out_parallel
 }
 
+
+/// 两个向量之间的线性插值
+/// 
+/// # Arguments
+/// * `a` - t=0时的向量
+/// * `b` - t=1时的向量
+/// * `t` - 插值参数，不限制在[0,1]内(超出范围时为外插)
+pub fn lerp(a: Vec3, b: Vec3, t: Real) -> Vec3 {
+    (1.0 - t) * a + t * b
+}
+
+/// 绕任意轴旋转向量(罗德里格斯公式)
+/// 
+/// v' = v cosθ + (k × v) sinθ + k (k·v)(1 - cosθ)，k为归一化后的轴
+/// 
+/// # Arguments
+/// * `v` - 要旋转的向量
+/// * `axis` - 旋转轴(无需归一化)
+/// * `angle` - 旋转角度(弧度)，沿轴方向看去为逆时针
+pub fn rotate_around_axis(v: Vec3, axis: Vec3, angle: Real) -> Vec3 {
+    let k = unit_vector(axis);
+    let (sin_theta, cos_theta) = angle.sin_cos();
+    v * cos_theta + cross(k, v) * sin_theta + k * dot(k, v) * (1.0 - cos_theta)
+}
+
+/// 计算向量在另一向量方向上的投影
+/// 
+/// # Arguments
+/// * `v` - 要投影的向量
+/// * `onto` - 投影方向(无需归一化)
+/// 
+/// # Returns
+/// 返回v平行于onto的分量；onto为零向量时返回零向量
+pub fn project_onto(v: Vec3, onto: Vec3) -> Vec3 {
+    let len2 = onto.squared_length();
+    if len2 == 0.0 {
+        return Vec3::default();
+    }
+    onto * (dot(v, onto) / len2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn array_tuple_and_slice_conversions_round_trip() {
+        let v = Vec3::new(1.0, -2.0, 3.5);
+        let arr: [Real; 3] = v.into();
+        assert_eq!(arr, [1.0, -2.0, 3.5]);
+        assert_eq!(Vec3::from(arr).e, v.e);
+        assert_eq!(Vec3::from_array(v.to_array()).e, v.e);
+        assert_eq!(Vec3::from((1.0, -2.0, 3.5)).e, v.e);
+
+        assert_eq!(Vec3::from_slice(&[1.0, -2.0, 3.5]).map(|u| u.e), Some(v.e));
+        assert!(Vec3::from_slice(&[1.0, 2.0]).is_none());
+        assert!(Vec3::from_slice(&[1.0, 2.0, 3.0, 4.0]).is_none());
+    }
+
+    #[test]
+    fn lerp_and_rotation_hit_known_values() {
+        let (a, b) = (Vec3::new(0.0, 2.0, -4.0), Vec3::new(2.0, 4.0, 4.0));
+        assert_eq!(lerp(a, b, 0.5).e, [1.0, 3.0, 0.0]);
+        assert_eq!(lerp(a, b, 0.0).e, a.e);
+        assert_eq!(lerp(a, b, 1.0).e, b.e);
+
+        let rotated = rotate_around_axis(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0), rtweekend::PI / 2.0);
+        assert!((rotated - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-6, "{}", rotated);
+        // 平行于轴的分量不变
+        let along = rotate_around_axis(Vec3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 0.0, 1.0), 1.0);
+        assert!((along - Vec3::new(0.0, 0.0, 3.0)).length() < 1e-6);
+
+        assert_eq!(project_onto(Vec3::new(3.0, 4.0, 0.0), Vec3::new(2.0, 0.0, 0.0)).e, [3.0, 0.0, 0.0]);
+        assert_eq!(project_onto(Vec3::new(3.0, 4.0, 0.0), Vec3::default()).e, [0.0; 3]);
+    }
+
+    #[test]
+    fn polygon_samples_stay_inside_the_polygon() {
+        rtweekend::seed_random(819);
+        for (sides, rotation) in [(3u32, 0.0), (6, 0.3), (7, 1.0)] {
+            let step = 2.0 * rtweekend::PI / sides as Real;
+            // 正多边形的边心距：外接圆半径为1
+            let apothem = (0.5 * step).cos();
+            let mut per_sector = vec![0usize; sides as usize];
+            let n = 20_000;
+            for _ in 0..n {
+                let p = random_in_regular_polygon(sides, rotation);
+                assert_eq!(p.z(), 0.0);
+                assert!(p.length() <= 1.0 + 1e-6);
+
+                // 在每条边的内侧：沿边中点方向的投影不超过边心距
+                for k in 0..sides {
+                    let mid = rotation + (k as Real + 0.5) * step;
+                    assert!(p.x() * mid.cos() + p.y() * mid.sin() <= apothem + 1e-6);
+                }
+
+                let angle = (p.y().atan2(p.x()) - rotation).rem_euclid(2.0 * rtweekend::PI);
+                per_sector[((angle / step) as usize).min(sides as usize - 1)] += 1;
+            }
+
+            // 各扇区面积相同，样本数应大致相等
+            let expected = n as Real / sides as Real;
+            for count in per_sector {
+                assert!((count as Real - expected).abs() < 0.1 * expected, "sides = {}, count = {}", sides, count);
+            }
+        }
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn simd_matches_scalar() {
+        rtweekend::seed_random(826);
+        for _ in 0..1000 {
+            let (u, v) = (Vec3::random_range(-10.0, 10.0), Vec3::random_range(-10.0, 10.0));
+            let tolerance = 1e-4 * u.length() * v.length();
+            assert!((simd::dot(u, v) - scalar_dot(u, v)).abs() <= tolerance);
+            assert!((simd::cross(u, v) - scalar_cross(u, v)).length() <= tolerance);
+        }
+        // 第4通道补0不影响结果
+        let (x, y) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(simd::dot(x, y), 0.0);
+        assert_eq!(simd::cross(x, y).e, [0.0, 0.0, 1.0]);
+    }
+
+    /// 总是返回最大值的生成器，使拒绝采样每次都失败
+    struct Stuck;
+
+    impl rand::RngCore for Stuck {
+        fn next_u32(&mut self) -> u32 {
+            u32::MAX
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            u64::MAX
+        }
+
+        fn fill_bytes(&mut self, dst: &mut [u8]) {
+            dst.fill(0xff);
+        }
+    }
+
+    #[test]
+    fn unit_sphere_and_disk_samples_stay_inside_and_are_reproducible() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let (mut a, mut b) = (StdRng::seed_from_u64(849), StdRng::seed_from_u64(849));
+        let mut other = StdRng::seed_from_u64(850);
+        let mut differs = false;
+        for _ in 0..10_000 {
+            let p = random_in_unit_sphere_with(&mut a);
+            assert!(p.squared_length() < 1.0);
+            assert_eq!(p.e, random_in_unit_sphere_with(&mut b).e);
+            differs |= p.e != random_in_unit_sphere_with(&mut other).e;
+
+            let d = random_in_unit_disk_with(&mut a);
+            assert!(d.squared_length() < 1.0 && d.z() == 0.0);
+            assert_eq!(d.e, random_in_unit_disk_with(&mut b).e);
+        }
+        assert!(differs);
+
+        // 拒绝采样始终失败时退回归一化构造，仍然终止且位于内部
+        assert!(random_in_unit_sphere_with(&mut Stuck).squared_length() < 1.0);
+        let d = random_in_unit_disk_with(&mut Stuck);
+        assert!(d.squared_length() < 1.0 && d.z() == 0.0);
+    }
+}
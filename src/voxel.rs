@@ -0,0 +1,436 @@
+//! 稀疏体素网格模块
+//!
+//! 提供仿照OpenVDB叶节点组织的稀疏体素网格：空间按8×8×8体素分块，只为含有非零值的块分配存储。
+//! 网格既可以作为非均匀介质的密度场渲染烟雾、云，也可以把超过阈值的体素当作实心方块渲染，
+//! 并提供一种简单文本格式的加载函数
+
+use super::vec3::{Point3, Vec3};
+use super::ray::Ray;
+use super::color::Color;
+use super::material::{Isotropic, Material};
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::aabb::{self, Aabb};
+use super::box3::Box3;
+use super::heterogeneous_medium::HeterogeneousMedium;
+use super::rtweekend::{self, Real};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 每个块在每个轴上的体素数
+const BLOCK_SIZE: i32 = 8;
+
+/// 每个块的体素总数
+const BLOCK_VOXELS: usize = (BLOCK_SIZE * BLOCK_SIZE * BLOCK_SIZE) as usize;
+
+/// 稀疏体素网格
+///
+/// 体素(i, j, k)占据 origin + [i, i+1) × [j, j+1) × [k, k+1) × voxel_size，
+/// 其值位于体素中心。未分配的体素值为0
+///
+/// # Fields
+/// - origin: 体素(0, 0, 0)的最小角点
+/// - voxel_size: 体素边长
+/// - blocks: 块坐标到块内体素值的映射，块内按x最快、z最慢的顺序排列
+/// - max_value: 所有写入过的值的最大值
+/// - min_index, max_index: 非零体素的索引范围(含两端)
+pub struct VoxelGrid {
+    origin: Point3,
+    voxel_size: Real,
+    blocks: HashMap<[i32; 3], Box<[f32; BLOCK_VOXELS]>>,
+    max_value: Real,
+    min_index: [i32; 3],
+    max_index: [i32; 3],
+}
+
+impl VoxelGrid {
+    /// 创建空网格
+    ///
+    /// # Arguments
+    /// * `origin` - 体素(0, 0, 0)的最小角点
+    /// * `voxel_size` - 体素边长，必须为正
+    pub fn new(origin: Point3, voxel_size: Real) -> Self {
+        assert!(voxel_size > 0.0, "体素边长必须为正");
+        Self {
+            origin,
+            voxel_size,
+            blocks: HashMap::new(),
+            max_value: 0.0,
+            min_index: [i32::MAX; 3],
+            max_index: [i32::MIN; 3],
+        }
+    }
+
+    /// 体素所在的块坐标和在块内的下标
+    fn locate(index: [i32; 3]) -> ([i32; 3], usize) {
+        let block = index.map(|i| i.div_euclid(BLOCK_SIZE));
+        let [x, y, z] = index.map(|i| i.rem_euclid(BLOCK_SIZE) as usize);
+        let b = BLOCK_SIZE as usize;
+        (block, x + b * (y + b * z))
+    }
+
+    /// 设置体素的值
+    ///
+    /// 写入0不会为空块分配存储
+    #[allow(clippy::unnecessary_cast)] // 单精度模式下Real为f32
+    pub fn set(&mut self, index: [i32; 3], value: Real) {
+        let (block, offset) = Self::locate(index);
+        if value == 0.0 {
+            if let Some(data) = self.blocks.get_mut(&block) {
+                data[offset] = 0.0;
+            }
+            return;
+        }
+
+        self.blocks.entry(block).or_insert_with(|| Box::new([0.0; BLOCK_VOXELS]))[offset] = value as f32;
+        self.max_value = self.max_value.max(value);
+        for (axis, &i) in index.iter().enumerate() {
+            self.min_index[axis] = self.min_index[axis].min(i);
+            self.max_index[axis] = self.max_index[axis].max(i);
+        }
+    }
+
+    /// 读取体素的值，未分配的体素为0
+    pub fn get(&self, index: [i32; 3]) -> Real {
+        let (block, offset) = Self::locate(index);
+        self.blocks.get(&block).map_or(0.0, |data| data[offset] as Real)
+    }
+
+    /// 在世界坐标p处对体素值做三线性插值
+    pub fn sample(&self, p: Point3) -> Real {
+        // 体素中心位于整数坐标 + 0.5 处
+        let g = (p - self.origin) / self.voxel_size - Vec3::new(0.5, 0.5, 0.5);
+        let base = [g.x().floor(), g.y().floor(), g.z().floor()];
+        let f = [g.x() - base[0], g.y() - base[1], g.z() - base[2]];
+        let base = base.map(|x| x as i32);
+
+        let mut value = 0.0;
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let weight: Real = (0..3)
+                .map(|axis| if offset[axis] == 1 { f[axis] } else { 1.0 - f[axis] })
+                .product();
+            if weight > 0.0 {
+                value += weight * self.get([base[0] + offset[0], base[1] + offset[1], base[2] + offset[2]]);
+            }
+        }
+        value
+    }
+
+    /// 写入过的最大值，可作为密度上界
+    pub fn max_value(&self) -> Real {
+        self.max_value
+    }
+
+    /// 已分配的块数
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// 包含所有非零体素的包围盒，网格为空时为空包围盒
+    pub fn bounding_box(&self) -> Aabb {
+        if self.min_index[0] > self.max_index[0] {
+            return aabb::EMPTY;
+        }
+        let corner = |index: [i32; 3]| {
+            self.origin + self.voxel_size * Vec3::new(index[0] as Real, index[1] as Real, index[2] as Real)
+        };
+        Aabb::from_points(corner(self.min_index), corner(self.max_index.map(|i| i + 1)))
+    }
+
+    /// 把网格作为密度场创建非均匀介质
+    ///
+    /// 介质边界是非零体素包围盒向外扩展一个体素的长方体，覆盖插值后密度非零的全部区域
+    ///
+    /// # Arguments
+    /// * `density_scale` - 体素值到密度的比例
+    /// * `albedo` - 散射反照率
+    pub fn medium(self: Arc<Self>, density_scale: Real, albedo: Color) -> HeterogeneousMedium {
+        let bbox = self.bounding_box();
+        let pad = Vec3::new(self.voxel_size, self.voxel_size, self.voxel_size);
+        let min = Point3::new(bbox.x.min, bbox.y.min, bbox.z.min) - pad;
+        let max = Point3::new(bbox.x.max, bbox.y.max, bbox.z.max) + pad;
+        // 边界只用于求进出距离，材质不会被使用
+        let boundary = Arc::new(Box3::new(min, max, Arc::new(Isotropic::new(albedo))));
+
+        let max_density = density_scale * self.max_value;
+        HeterogeneousMedium::new(boundary, move |p| density_scale * self.sample(p), max_density, albedo)
+    }
+}
+
+/// 实心体素
+///
+/// 值大于阈值的体素渲染为实心立方体，求交时先以块为单元做三维DDA遍历，
+/// 只在已分配的块内再以体素为单元遍历
+///
+/// # Fields
+/// - grid: 体素网格
+/// - threshold: 实心阈值
+/// - mat: 材质
+/// - bbox: 包围盒
+pub struct VoxelSolid {
+    grid: Arc<VoxelGrid>,
+    threshold: Real,
+    mat: Arc<dyn Material + Send + Sync>,
+    bbox: Aabb,
+}
+
+impl VoxelSolid {
+    /// 创建实心体素物体
+    ///
+    /// # Arguments
+    /// * `grid` - 体素网格
+    /// * `threshold` - 值大于此阈值的体素为实心
+    /// * `material` - 材质
+    pub fn new(grid: Arc<VoxelGrid>, threshold: Real, material: Arc<dyn Material + Send + Sync>) -> Self {
+        let bbox = grid.bounding_box();
+        Self {
+            grid,
+            threshold,
+            mat: material,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for VoxelSolid {
+    /// 实现实心体素的光线命中检测
+    ///
+    /// 光线进入第一个实心体素的那个面即为命中面，法线为该面的轴向法线；
+    /// 纹理坐标为命中点在该面内的相对位置。起点位于实心体素内部时该体素被忽略
+    fn hit(&self, r: &Ray, ray_t: &Interval, hit_record: &mut HitRecord) -> bool {
+        let Some((span, entry_axis)) = slab_entry(&self.bbox, r, ray_t) else {
+            return false;
+        };
+
+        let grid = &self.grid;
+        let block_size = grid.voxel_size * BLOCK_SIZE as Real;
+        let mut found = None;
+        dda(grid.origin, block_size, r, span, entry_axis, |block, block_span, block_axis| {
+            if !grid.blocks.contains_key(&block) {
+                return false;
+            }
+            let block_origin = grid.origin
+                + block_size * Vec3::new(block[0] as Real, block[1] as Real, block[2] as Real);
+            dda(block_origin, grid.voxel_size, r, block_span, block_axis, |voxel, voxel_span, axis| {
+                let index = [0, 1, 2].map(|a| block[a] * BLOCK_SIZE + voxel[a]);
+                let inside_block = voxel.iter().all(|&v| (0..BLOCK_SIZE).contains(&v));
+                if !inside_block || grid.get(index) <= self.threshold {
+                    return false;
+                }
+                match axis {
+                    Some(axis) if ray_t.surrounds(voxel_span.min) => {
+                        found = Some((voxel_span.min, axis, index));
+                        true
+                    }
+                    _ => false,
+                }
+            })
+        });
+        let Some((t, axis, index)) = found else {
+            return false;
+        };
+
+        hit_record.t = t;
+        hit_record.p = r.at(t);
+
+        let mut outward_normal = Vec3::default();
+        outward_normal[axis] = if r.direction()[axis] > 0.0 { -1.0 } else { 1.0 };
+        // 命中面内的两个轴
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let local = (hit_record.p - grid.origin) / grid.voxel_size;
+        hit_record.u = (local[a] - index[a] as Real).clamp(0.0, 1.0);
+        hit_record.v = (local[b] - index[b] as Real).clamp(0.0, 1.0);
+        let mut tangent = Vec3::default();
+        tangent[a] = 1.0;
+        hit_record.tangent = tangent;
+
+        hit_record.set_face_normal(r, outward_normal);
+        hit_record.mat = Some(Arc::clone(&self.mat));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// 求光线与包围盒的参数区间，以及光线从哪个轴的面进入包围盒
+///
+/// # Returns
+/// 返回(区间, 进入轴)，起点已在包围盒内部时进入轴为None
+fn slab_entry(bbox: &Aabb, r: &Ray, ray_t: &Interval) -> Option<(Interval, Option<usize>)> {
+    let mut t_min = ray_t.min;
+    let mut t_max = ray_t.max;
+    let mut entry_axis = None;
+    for axis in 0..3 {
+        let ax = bbox.axis_interval(axis);
+        let inv = 1.0 / r.direction()[axis];
+        let t0 = (ax.min - r.origin()[axis]) * inv;
+        let t1 = (ax.max - r.origin()[axis]) * inv;
+        let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+        if t0 > t_min {
+            t_min = t0;
+            entry_axis = Some(axis);
+        }
+        t_max = t_max.min(t1);
+        if t_max <= t_min {
+            return None;
+        }
+    }
+    Some((Interval::new(t_min, t_max), entry_axis))
+}
+
+/// 三维DDA(Amanatides–Woo)，按光线经过的顺序访问规则网格中的单元
+///
+/// 单元(i, j, k)占据 origin + [i, i+1) × [j, j+1) × [k, k+1) × cell
+///
+/// # Arguments
+/// * `origin` - 网格原点
+/// * `cell` - 单元边长
+/// * `r` - 光线
+/// * `span` - 遍历的参数区间
+/// * `entry_axis` - 光线在区间起点穿过的面所在的轴
+/// * `visit` - 访问函数，参数为(单元坐标, 光线在单元内的参数区间, 进入单元时穿过的面所在的轴)，
+///   返回true时停止遍历
+///
+/// # Returns
+/// 访问函数要求停止时返回true
+fn dda(
+    origin: Point3,
+    cell: Real,
+    r: &Ray,
+    span: Interval,
+    entry_axis: Option<usize>,
+    mut visit: impl FnMut([i32; 3], Interval, Option<usize>) -> bool,
+) -> bool {
+    let d = r.direction();
+    // 用略微进入区间的点定位起始单元，避免起点恰好落在单元边界上时取错单元
+    let t_start = span.min;
+    let probe = r.at(t_start + 1e-6 * (span.max - span.min));
+    let mut cell_index = [0; 3];
+    let mut step = [0; 3];
+    let mut t_next = [rtweekend::INFINITY; 3];
+    let mut t_delta = [rtweekend::INFINITY; 3];
+    for axis in 0..3 {
+        cell_index[axis] = ((probe[axis] - origin[axis]) / cell).floor() as i32;
+        if d[axis] > 0.0 {
+            step[axis] = 1;
+            let boundary = origin[axis] + (cell_index[axis] + 1) as Real * cell;
+            t_next[axis] = (boundary - r.origin()[axis]) / d[axis];
+            t_delta[axis] = cell / d[axis];
+        } else if d[axis] < 0.0 {
+            step[axis] = -1;
+            let boundary = origin[axis] + cell_index[axis] as Real * cell;
+            t_next[axis] = (boundary - r.origin()[axis]) / d[axis];
+            t_delta[axis] = -cell / d[axis];
+        }
+    }
+
+    let mut t = t_start;
+    let mut axis_in = entry_axis;
+    loop {
+        let next_axis = if t_next[0] < t_next[1] {
+            if t_next[0] < t_next[2] { 0 } else { 2 }
+        } else if t_next[1] < t_next[2] {
+            1
+        } else {
+            2
+        };
+        let t_exit = t_next[next_axis].min(span.max);
+        if visit(cell_index, Interval::new(t, t_exit), axis_in) {
+            return true;
+        }
+        if t_next[next_axis] >= span.max {
+            return false;
+        }
+        t = t_next[next_axis];
+        cell_index[next_axis] += step[next_axis];
+        t_next[next_axis] += t_delta[next_axis];
+        axis_in = Some(next_axis);
+    }
+}
+
+/// 从文本文件加载体素网格
+///
+/// 文件由若干行组成，`#`之后为注释：
+/// - `origin x y z`：体素(0, 0, 0)的最小角点，默认为原点
+/// - `voxel_size s`：体素边长，默认为1
+/// - `dense nx ny nz`：之后依次给出nx × ny × nz个值，x最快、z最慢，适合模拟程序直接导出
+/// - `sparse`：之后每行为`i j k value`，适合大部分为空的网格
+///
+/// # Arguments
+/// * `path` - 文件路径
+///
+/// # Returns
+/// 返回加载的网格，文件无法读取或格式错误时返回错误
+pub fn load_voxels(path: impl AsRef<Path>) -> io::Result<VoxelGrid> {
+    let text = fs::read_to_string(path)?;
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("voxels: {}", msg));
+
+    let mut tokens = text
+        .lines()
+        .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace());
+    let number = |tokens: &mut dyn Iterator<Item = &str>| -> io::Result<Real> {
+        tokens
+            .next()
+            .ok_or_else(|| invalid("unexpected end of file"))?
+            .parse::<Real>()
+            .map_err(|_| invalid("invalid number"))
+    };
+
+    let mut origin = Point3::default();
+    let mut voxel_size = 1.0;
+    let mut entries: Vec<([i32; 3], Real)> = Vec::new();
+    while let Some(keyword) = tokens.next() {
+        match keyword {
+            "origin" => origin = Point3::new(number(&mut tokens)?, number(&mut tokens)?, number(&mut tokens)?),
+            "voxel_size" => {
+                voxel_size = number(&mut tokens)?;
+                if voxel_size <= 0.0 {
+                    return Err(invalid("voxel_size must be positive"));
+                }
+            }
+            "dense" => {
+                let mut dim = || -> io::Result<i32> {
+                    let n = number(&mut tokens)?;
+                    (n >= 0.0 && n.fract() == 0.0).then_some(n as i32).ok_or_else(|| invalid("invalid dimension"))
+                };
+                let (nx, ny, nz) = (dim()?, dim()?, dim()?);
+                for k in 0..nz {
+                    for j in 0..ny {
+                        for i in 0..nx {
+                            let value = number(&mut tokens)?;
+                            if value != 0.0 {
+                                entries.push(([i, j, k], value));
+                            }
+                        }
+                    }
+                }
+            }
+            "sparse" => {
+                // 剩余内容全部为 i j k value 四元组
+                let rest: Vec<&str> = tokens.by_ref().collect();
+                if !rest.len().is_multiple_of(4) {
+                    return Err(invalid("incomplete sparse entry"));
+                }
+                for entry in rest.chunks_exact(4) {
+                    let index = |s: &str| s.parse::<i32>().map_err(|_| invalid("invalid voxel index"));
+                    let value = entry[3].parse::<Real>().map_err(|_| invalid("invalid number"))?;
+                    entries.push(([index(entry[0])?, index(entry[1])?, index(entry[2])?], value));
+                }
+            }
+            _ => return Err(invalid("unknown keyword")),
+        }
+    }
+
+    let mut grid = VoxelGrid::new(origin, voxel_size);
+    for (index, value) in entries {
+        grid.set(index, value);
+    }
+    Ok(grid)
+}